@@ -0,0 +1,614 @@
+//! Generates the typed syntax tree and its visitor from `language_nodes.txt`
+//!
+//! Every line of the spec declares one of
+//!
+//! - a token, `Name` or `Name = Tag`, wrapping a token of that tag
+//! - a node, `Name { field: Type, ... }`, whose children are its fields in order
+//! - a union, `Name <Variant: Type, ...>`, where a variant without a type is
+//!   named after it
+//!
+//! optionally followed by `as name`, for the `Visitor` method to be called
+//! `visit_name` instead of `visit_` and the name in snake case. A field is
+//! `Token`, a declared type, `Type?` for a child that may be a null node or
+//! an empty token, or `[Type]` for a node whose children are all of a type.
+//!
+//! `ast_nodes.rs` holds `NodeType` and the wrappers, included by `ast2.rs`.
+//! `visitor.rs` holds the `Visitor` trait with a `walk_*` function for every
+//! node and union, and the `Fold` trait, included by `visitor.rs`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+const SPEC: &str = "language_nodes.txt";
+
+struct Decl {
+    name: String,
+    kind: DeclKind,
+    /// Name of the visitor method without the `visit_` prefix
+    visit: String,
+}
+
+enum DeclKind {
+    /// Tag of the token
+    Token(String),
+    Node(Vec<(String, String)>),
+    /// Variant names and types
+    Union(Vec<(String, String)>),
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", SPEC);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let spec = std::fs::read_to_string(SPEC).expect("the node spec should be readable");
+    let decls: Vec<Decl> = spec.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .map(parse_decl)
+        .collect();
+    let spec = Spec::new(&decls);
+
+    let out = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out).join("ast_nodes.rs"), spec.ast_nodes()).unwrap();
+    std::fs::write(Path::new(&out).join("visitor.rs"), spec.visitor()).unwrap();
+}
+
+fn parse_decl(line: &str) -> Decl {
+    let (line, visit) = match line.rsplit_once(" as ") {
+        Some((line, visit)) => (line.trim(), Some(visit.trim().to_string())),
+        None => (line, None),
+    };
+    let (name, kind) = if let Some((name, fields)) = line.split_once('{') {
+        let fields = fields.strip_suffix('}').unwrap_or_else(|| panic!("`{}` should end with `}}`", line));
+        (name, DeclKind::Node(pairs(fields, line)))
+    } else if let Some((name, variants)) = line.split_once('<') {
+        let variants = variants.strip_suffix('>').unwrap_or_else(|| panic!("`{}` should end with `>`", line));
+        (name, DeclKind::Union(pairs(variants, line)))
+    } else if let Some((name, tag)) = line.split_once('=') {
+        (name, DeclKind::Token(tag.trim().to_string()))
+    } else {
+        (line, DeclKind::Token(line.to_string()))
+    };
+    let name = name.trim().to_string();
+    let visit = visit.unwrap_or_else(|| snake_case(&name));
+    Decl { name, kind, visit }
+}
+
+/// Comma separated `name: Type` pairs, where the type defaults to the name
+fn pairs(list: &str, line: &str) -> Vec<(String, String)> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once(':') {
+            Some((name, typ)) => (name.trim().to_string(), typ.trim().to_string()),
+            None if !pair.contains(char::is_whitespace) => (pair.to_string(), pair.to_string()),
+            None => panic!("malformed field `{}` in `{}`", pair, line),
+        })
+        .collect()
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+struct Spec<'d> {
+    decls: &'d [Decl],
+    by_name: HashMap<&'d str, &'d Decl>,
+}
+
+/// What a child of a node is cast to
+#[derive(Clone, Copy, PartialEq)]
+enum Child {
+    /// A raw token
+    Token,
+    /// A wrapper of tokens
+    AstToken,
+    /// A wrapper of nodes
+    AstNode,
+    /// A union of tokens and nodes
+    Both,
+}
+
+impl<'d> Spec<'d> {
+    fn new(decls: &'d [Decl]) -> Self {
+        let by_name: HashMap<_, _> = decls.iter().map(|decl| (decl.name.as_str(), decl)).collect();
+        assert_eq!(by_name.len(), decls.len(), "declarations should have distinct names");
+        let spec = Self { decls, by_name };
+        for decl in decls {
+            match &decl.kind {
+                DeclKind::Token(_) => {}
+                DeclKind::Node(fields) => {
+                    for (field, typ) in fields {
+                        let inner = typ.trim_end_matches('?').trim_start_matches('[').trim_end_matches(']');
+                        assert!(inner == "Token" || spec.by_name.contains_key(inner), "unknown type `{}` of `{}.{}`", typ, decl.name, field);
+                        assert!(!typ.starts_with('[') || fields.len() == 1, "the list `{}.{}` should be the only field", decl.name, field);
+                    }
+                }
+                DeclKind::Union(variants) => {
+                    for (_, typ) in variants {
+                        assert!(
+                            matches!(spec.by_name.get(typ.as_str()), Some(Decl { kind: DeclKind::Token(_) | DeclKind::Node(_), .. })),
+                            "variant `{}` of `{}` should be a token or a node", typ, decl.name,
+                        );
+                    }
+                }
+            }
+        }
+        spec
+    }
+
+    fn is_token(&self, name: &str) -> bool {
+        matches!(self.by_name[name].kind, DeclKind::Token(_))
+    }
+
+    fn child(&self, typ: &str) -> Child {
+        if typ == "Token" {
+            return Child::Token;
+        }
+        match &self.by_name[typ].kind {
+            DeclKind::Token(_) => Child::AstToken,
+            DeclKind::Node(_) => Child::AstNode,
+            DeclKind::Union(variants) if variants.iter().any(|(_, typ)| self.is_token(typ)) => Child::Both,
+            DeclKind::Union(_) => Child::AstNode,
+        }
+    }
+
+    fn ast_nodes(&self) -> String {
+        let mut out = String::from("// Generated by build.rs from language_nodes.txt, do not edit\n\n");
+        out += "#[derive(PartialEq, Eq, Debug, Clone, Copy)]\n#[repr(u8)]\npub enum NodeType {\n";
+        out += "    // Should never exceed 127 variants as NodeType <-> NodeKind\n    // uses the MSB as a null bit\n";
+        for decl in self.decls.iter().filter(|decl| matches!(decl.kind, DeclKind::Node(_))) {
+            writeln!(out, "    {},", decl.name).unwrap();
+        }
+        out += "\n    // Generic nodetype for any one of the above types.\n";
+        out += "    // Typically used as the default node type before a concrete one is assigned.\n";
+        out += "    Any,\n}\n\n";
+        self.node_type_names(&mut out);
+
+        for decl in self.decls {
+            match &decl.kind {
+                DeclKind::Token(tag) => self.token(&mut out, &decl.name, tag),
+                DeclKind::Node(fields) => self.node(&mut out, &decl.name, fields),
+                DeclKind::Union(variants) => self.union(&mut out, &decl.name, variants),
+            }
+        }
+        out
+    }
+
+    /// Lookups by the names of the spec, for tools that take them as text
+    fn node_type_names(&self, out: &mut String) {
+        let nodes: Vec<_> = self.decls.iter()
+            .filter_map(|decl| match &decl.kind {
+                DeclKind::Node(fields) => Some((decl, fields)),
+                _ => None,
+            })
+            .collect();
+        out.push_str("impl NodeType {\n    /// The node type declared with a name, not counting unions\n");
+        out.push_str("    pub fn from_name(name: &str) -> Option<NodeType> {\n        match name {\n");
+        for (decl, _) in nodes.iter() {
+            writeln!(out, "            \"{0}\" => Some(NodeType::{0}),", decl.name).unwrap();
+        }
+        out.push_str("            _ => None,\n        }\n    }\n\n");
+
+        out.push_str("    /// Names of the fields, in the order of the children\n");
+        out.push_str("    pub fn fields(self) -> &'static [&'static str] {\n        match self {\n");
+        for (decl, fields) in nodes.iter() {
+            let names: Vec<_> = fields.iter().map(|(name, _)| format!("\"{}\"", name)).collect();
+            writeln!(out, "            NodeType::{} => &[{}],", decl.name, names.join(", ")).unwrap();
+        }
+        out.push_str("            NodeType::Any => &[],\n        }\n    }\n\n");
+
+        out.push_str("    /// Whether the children are the items of a list, its only field\n");
+        out.push_str("    pub fn is_list(self) -> bool {\n");
+        let lists: Vec<_> = nodes.iter()
+            .filter(|(_, fields)| fields.iter().any(|(_, typ)| typ.starts_with('[')))
+            .map(|(decl, _)| format!("NodeType::{}", decl.name))
+            .collect();
+        writeln!(out, "        matches!(self, {})\n    }}\n", lists.join(" | ")).unwrap();
+
+        out.push_str("    /// Whether `child` fits the field at `index`, any item for a list\n");
+        out.push_str("    pub fn accepts(self, index: usize, child: &NodeChild) -> bool {\n        match (self, index) {\n");
+        for (decl, fields) in nodes.iter() {
+            for (i, (_, typ)) in fields.iter().enumerate() {
+                let index = match typ.starts_with('[') {
+                    true => "_".to_string(),
+                    false => i.to_string(),
+                };
+                writeln!(out, "            (NodeType::{}, {}) => {},", decl.name, index, self.accepts(typ)).unwrap();
+            }
+        }
+        out.push_str("            _ => false,\n        }\n    }\n\n");
+
+        out.push_str("    /// Node types of the union declared with a name, leaving out its tokens\n");
+        out.push_str("    pub fn union_members(name: &str) -> Option<&'static [NodeType]> {\n        match name {\n");
+        for decl in self.decls {
+            if let DeclKind::Union(variants) = &decl.kind {
+                let members: Vec<_> = variants.iter()
+                    .filter(|(_, typ)| !self.is_token(typ))
+                    .map(|(_, typ)| format!("NodeType::{}", typ))
+                    .collect();
+                writeln!(out, "            \"{}\" => Some(&[{}]),", decl.name, members.join(", ")).unwrap();
+            }
+        }
+        out.push_str("            _ => None,\n        }\n    }\n}\n\n");
+    }
+
+    /// A match on `child` for whether it fits a field of type `typ`
+    fn accepts(&self, typ: &str) -> String {
+        let optional = typ.ends_with('?');
+        let inner = typ.trim_end_matches('?').trim_start_matches('[').trim_end_matches(']');
+        let members: Vec<&str> = match &self.by_name.get(inner).map(|decl| &decl.kind) {
+            Some(DeclKind::Union(variants)) => variants.iter().map(|(_, typ)| typ.as_str()).collect(),
+            _ => vec![inner],
+        };
+        let mut tags = Vec::new();
+        let mut nodes = Vec::new();
+        for member in members {
+            match self.by_name.get(member).map(|decl| &decl.kind) {
+                Some(DeclKind::Token(tag)) => tags.push(format!("Tag::{}", tag)),
+                Some(_) => nodes.push(format!("NodeType::{}", member)),
+                None => {}
+            }
+        }
+
+        let mut token = match (inner == "Token", tags.is_empty()) {
+            (true, _) => vec!["true".to_string()],
+            (false, true) => Vec::new(),
+            (false, false) => vec![format!("matches!(token.tag, {})", tags.join(" | "))],
+        };
+        let mut node = match nodes.is_empty() {
+            true => Vec::new(),
+            false => vec![format!("matches!(node.kind.0, {})", nodes.join(" | "))],
+        };
+        // Where the parser failed, an invalid node or an absent token takes
+        // the place of the child
+        if !node.is_empty() {
+            node.push("node.is_invalid()".to_string());
+        }
+        if !token.is_empty() || optional {
+            token.push("token.is_empty()".to_string());
+        }
+        if optional {
+            node.push("node.is_null()".to_string());
+        }
+        let arm = |conditions: Vec<String>| match conditions.iter().any(|condition| condition == "true") {
+            true => "true".to_string(),
+            false if conditions.is_empty() => "false".to_string(),
+            false => conditions.join(" || "),
+        };
+        let node_arm = arm(node);
+        let token_arm = arm(token);
+        let binding = |name: &str, arm: &str| match arm.contains(name) {
+            true => name.to_string(),
+            false => "_".to_string(),
+        };
+        format!(
+            "match child {{ NodeChild::Node({}) => {}, NodeChild::Token({}) => {} }}",
+            binding("node", &node_arm), node_arm, binding("token", &token_arm), token_arm,
+        )
+    }
+
+    fn token(&self, out: &mut String, name: &str, tag: &str) {
+        writeln!(out, "\
+#[derive(Debug, Clone)]
+pub struct {name}<'s, 'b> {{
+    token: &'b Token<'s>,
+}}
+
+impl<'s, 'b> AstToken<'s, 'b> for {name}<'s, 'b> {{
+    fn cast(token: &'b Token<'s>) -> Self
+    where
+        Self: Sized,
+    {{
+        debug_assert!(token.tag == Tag::{tag} || token.is_empty());
+
+        Self {{ token }}
+    }}
+
+    fn token(&self) -> &'b Token<'s> {{
+        self.token
+    }}
+}}
+").unwrap();
+    }
+
+    /// The wrapper of a node, with an accessor for every field whether or not
+    /// anything reads it yet
+    fn node(&self, out: &mut String, name: &str, fields: &[(String, String)]) {
+        writeln!(out, "\
+#[derive(Debug, Clone)]
+pub struct {name}<'s, 'b> {{
+    node: &'b Node<'s, 'b>,
+}}
+
+impl<'s, 'b> AstNode<'s, 'b> for {name}<'s, 'b> {{
+    fn cast(node: &'b Node<'s, 'b>) -> Self
+    where
+        Self: Sized,
+    {{
+        debug_assert_eq!(node.kind.0, NodeType::{name});
+
+        Self {{ node }}
+    }}
+
+    fn node(&self) -> &'b Node<'s, 'b> {{
+        self.node
+    }}
+}}
+
+#[allow(dead_code)]
+impl<'s, 'b> {name}<'s, 'b> {{").unwrap();
+        for (i, (field, typ)) in fields.iter().enumerate() {
+            if !typ.starts_with('[') {
+                writeln!(out, "    const {}: usize = {};", field.to_uppercase(), i).unwrap();
+            }
+        }
+        for (field, typ) in fields {
+            self.field(out, field, typ);
+        }
+        out.push_str("}\n\n");
+    }
+
+    fn field(&self, out: &mut String, field: &str, typ: &str) {
+        let optional = typ.ends_with('?');
+        let list = typ.starts_with('[');
+        let inner = typ.trim_end_matches('?').trim_start_matches('[').trim_end_matches(']');
+        let child = self.child(inner);
+
+        let wrap = |cast: String, check: &str| match optional {
+            true => format!("({}).then(|| {})", check, cast),
+            false => cast,
+        };
+        let node_arm = match child {
+            Child::AstNode | Child::Both => wrap(format!("<{} as AstNode>::cast(node)", inner), "!node.is_null()"),
+            Child::Token | Child::AstToken => String::new(),
+        };
+        let token_arm = match child {
+            Child::Token => "token".to_string(),
+            Child::AstToken | Child::Both => wrap(format!("<{} as AstToken>::cast(token)", inner), "!token.is_empty()"),
+            Child::AstNode => String::new(),
+        };
+        let mut arms = String::new();
+        if !node_arm.is_empty() {
+            writeln!(arms, "            NodeChild::Node(node) => {},", node_arm).unwrap();
+        }
+        if !token_arm.is_empty() {
+            writeln!(arms, "            NodeChild::Token(token) => {},", token_arm).unwrap();
+        }
+        if child != Child::Both {
+            arms.push_str("            _ => unreachable!(),\n");
+        }
+
+        let item = match child {
+            Child::Token => "&'b Token<'s>".to_string(),
+            _ => format!("{}<'s, 'b>", inner),
+        };
+        if list {
+            // Whatever the parser failed on is kept in the tree, but isn't an item
+            let filter = match child {
+                Child::Token | Child::AstToken => "",
+                _ => ".filter(|x| !matches!(x, NodeChild::Node(node) if node.is_invalid()))",
+            };
+            writeln!(out, "    pub fn {field}(&self) -> impl Iterator<Item = {item}> {{
+        let list = self.node.children();
+        list.iter(){filter}.map(|x| match x {{
+{arms}        }})
+    }}").unwrap();
+        } else {
+            let ret = match optional {
+                true => format!("Option<{}>", item),
+                false => item,
+            };
+            let index = field.to_uppercase();
+            writeln!(out, "    pub fn {field}(&self) -> {ret} {{
+        let elem = &self.node.children()[Self::{index}];
+
+        match elem {{
+{arms}        }}
+    }}").unwrap();
+        }
+    }
+
+    fn union(&self, out: &mut String, name: &str, variants: &[(String, String)]) {
+        writeln!(out, "#[derive(Debug, Clone)]\npub enum {}<'s, 'b> {{", name).unwrap();
+        for (variant, typ) in variants {
+            writeln!(out, "    {}({}<'s, 'b>),", variant, typ).unwrap();
+        }
+        out.push_str("}\n\n");
+
+        let (tokens, nodes): (Vec<_>, Vec<_>) = variants.iter().partition(|(_, typ)| self.is_token(typ));
+        // The other kind of variant is unreachable when there is one
+        let rest = |other: &[&(String, String)]| match other.is_empty() {
+            true => "",
+            false => "            _ => unreachable!(),\n",
+        };
+        if !nodes.is_empty() {
+            writeln!(out, "\
+impl<'s, 'b> AstNode<'s, 'b> for {name}<'s, 'b> {{
+    fn cast(node: &'b Node<'s, 'b>) -> Self
+    where
+        Self: Sized,
+    {{
+        match node.kind.0 {{").unwrap();
+            for (variant, typ) in &nodes {
+                writeln!(out, "            NodeType::{typ} => {name}::{variant}(<{typ} as AstNode>::cast(node)),").unwrap();
+            }
+            writeln!(out, "            _ => unreachable!(),
+        }}
+    }}
+
+    fn node(&self) -> &'b Node<'s, 'b> {{
+        match self {{").unwrap();
+            for (variant, _) in &nodes {
+                writeln!(out, "            {name}::{variant}(inner) => inner.node(),").unwrap();
+            }
+            writeln!(out, "{}        }}\n    }}\n}}\n", rest(&tokens)).unwrap();
+        }
+        if !tokens.is_empty() {
+            writeln!(out, "\
+impl<'s, 'b> AstToken<'s, 'b> for {name}<'s, 'b> {{
+    fn cast(token: &'b Token<'s>) -> Self
+    where
+        Self: Sized,
+    {{
+        match token.tag {{").unwrap();
+            for (variant, typ) in &tokens {
+                let DeclKind::Token(tag) = &self.by_name[typ.as_str()].kind else { unreachable!() };
+                writeln!(out, "            Tag::{tag} => {name}::{variant}(<{typ} as AstToken>::cast(token)),").unwrap();
+            }
+            writeln!(out, "            _ => unreachable!(),
+        }}
+    }}
+
+    fn token(&self) -> &'b Token<'s> {{
+        match self {{").unwrap();
+            for (variant, _) in &tokens {
+                writeln!(out, "            {name}::{variant}(inner) => inner.token(),").unwrap();
+            }
+            writeln!(out, "{}        }}\n    }}\n}}\n", rest(&nodes)).unwrap();
+        }
+    }
+
+    /// The traversals are there for every node, used or not, so the generated
+    /// items allow dead code
+    fn visitor(&self) -> String {
+        let mut out = String::from("// Generated by build.rs from language_nodes.txt, do not edit\n\n");
+        out += "/// Visits the typed syntax tree, by default recursing into every child
+///
+/// Overriding a method stops the recursion below that node unless it calls
+/// the matching `walk_*` function. Null and invalid nodes and empty tokens
+/// are never visited.
+#[allow(dead_code, unused_variables)]
+pub trait Visitor<'s, 'b> {
+    fn visit_program(&mut self, node: &'b ast2::Node<'s, 'b>) {
+        self.visit_top_level_declarations(ast2::TopDeclList::cast(node));
+    }
+";
+        for decl in self.decls {
+            match decl.kind {
+                DeclKind::Token(_) => writeln!(out, "    fn visit_{}(&mut self, node: ast2::{}<'s, 'b>) {{}}", decl.visit, decl.name),
+                _ => writeln!(out, "    fn visit_{0}(&mut self, node: ast2::{1}<'s, 'b>) {{\n        walk_{0}(self, node)\n    }}", decl.visit, decl.name),
+            }
+            .unwrap();
+        }
+        out += "}\n\n";
+
+        for decl in self.decls {
+            match &decl.kind {
+                DeclKind::Token(_) => {}
+                DeclKind::Node(fields) => self.walk_node(&mut out, decl, fields),
+                DeclKind::Union(variants) => self.walk_union(&mut out, decl, variants),
+            }
+        }
+
+        out += "/// Rewrites the syntax tree in place, by default keeping every node
+///
+/// Nodes are taken by value and whatever is returned takes their place in
+/// the parent, so a subtree can be replaced by another node or a token.
+/// Overriding a method stops the recursion below that node unless it calls
+/// `fold_children`. Null and invalid nodes are kept as they are.
+#[allow(dead_code)]
+pub trait Fold<'s, 'b> {
+    fn fold_program(&mut self, node: ast2::Node<'s, 'b>) -> ast2::Node<'s, 'b> {
+        fold_children(self, node)
+    }
+";
+        for decl in self.decls {
+            match decl.kind {
+                DeclKind::Token(_) => writeln!(out, "    fn fold_{}(&mut self, token: crate::token::Token<'s>) -> ast2::NodeChild<'s, 'b> {{\n        token.into()\n    }}", decl.visit),
+                DeclKind::Node(_) => writeln!(out, "    fn fold_{}(&mut self, node: ast2::Node<'s, 'b>) -> ast2::NodeChild<'s, 'b> {{\n        fold_children(self, node).into()\n    }}", decl.visit),
+                DeclKind::Union(_) => Ok(()),
+            }
+            .unwrap();
+        }
+        out += "}
+
+/// Folds every child of a node, putting the results in place of the children
+#[allow(dead_code)]
+pub fn fold_children<'s, 'b, F: Fold<'s, 'b> + ?Sized>(folder: &mut F, mut node: ast2::Node<'s, 'b>) -> ast2::Node<'s, 'b> {
+    if let Some(children) = &mut node.children {
+        for child in children.0.iter_mut() {
+            let taken = std::mem::replace(child, ast2::NodeChild::Node(ast2::Node::null(ast2::NodeType::Any)));
+            *child = fold_child(folder, taken);
+        }
+    }
+    node
+}
+
+/// Calls the fold method of a child's type
+#[allow(dead_code)]
+fn fold_child<'s, 'b, F: Fold<'s, 'b> + ?Sized>(folder: &mut F, child: ast2::NodeChild<'s, 'b>) -> ast2::NodeChild<'s, 'b> {
+    match child {
+        ast2::NodeChild::Node(node) if node.is_null() || node.is_invalid() => node.into(),
+        ast2::NodeChild::Node(node) => match node.kind.0 {
+";
+        for decl in self.decls.iter().filter(|decl| matches!(decl.kind, DeclKind::Node(_))) {
+            writeln!(out, "            ast2::NodeType::{} => folder.fold_{}(node),", decl.name, decl.visit).unwrap();
+        }
+        out += "            ast2::NodeType::Any => node.into(),
+        },
+        ast2::NodeChild::Token(token) => match token.tag {
+";
+        for decl in self.decls {
+            if let DeclKind::Token(tag) = &decl.kind {
+                writeln!(out, "            crate::token::Tag::{} => folder.fold_{}(token),", tag, decl.visit).unwrap();
+            }
+        }
+        out += "            _ => token.into(),\n        },\n    }\n}\n";
+        out
+    }
+
+    fn walk_node(&self, out: &mut String, decl: &Decl, fields: &[(String, String)]) {
+        writeln!(out, "#[allow(dead_code)]\npub fn walk_{}<'s, 'b, V: Visitor<'s, 'b> + ?Sized>(visitor: &mut V, node: ast2::{}<'s, 'b>) {{", decl.visit, decl.name).unwrap();
+        out.push_str("    let children = node.node().children();\n");
+        for (index, (_, typ)) in fields.iter().enumerate() {
+            let list = typ.starts_with('[');
+            let inner = typ.trim_end_matches('?').trim_start_matches('[').trim_end_matches(']');
+            // Raw tokens have no visitor method
+            if inner == "Token" {
+                continue;
+            }
+            let visit = &self.by_name[inner].visit;
+            let child = self.child(inner);
+
+            // Lists match each of the children, other fields the one at their index
+            let (pattern, indent) = match list {
+                true => ("{}", "            "),
+                false => ("Some({})", "        "),
+            };
+            let mut arms = String::new();
+            if matches!(child, Child::AstNode | Child::Both) {
+                let node = pattern.replace("{}", "ast2::NodeChild::Node(child)");
+                writeln!(arms, "{indent}{node} if !child.is_null() && !child.is_invalid() => visitor.visit_{visit}(<ast2::{inner} as AstNode>::cast(child)),").unwrap();
+            }
+            if matches!(child, Child::AstToken | Child::Both) {
+                let token = pattern.replace("{}", "ast2::NodeChild::Token(child)");
+                writeln!(arms, "{indent}{token} if !child.is_empty() => visitor.visit_{visit}(<ast2::{inner} as AstToken>::cast(child)),").unwrap();
+            }
+            writeln!(arms, "{indent}_ => {{}}").unwrap();
+            match list {
+                true => writeln!(out, "    for child in children {{\n        match child {{\n{arms}        }}\n    }}"),
+                false if index == 0 => writeln!(out, "    match children.first() {{\n{arms}    }}"),
+                false => writeln!(out, "    match children.get({index}) {{\n{arms}    }}"),
+            }
+            .unwrap();
+        }
+        out.push_str("}\n\n");
+    }
+
+    fn walk_union(&self, out: &mut String, decl: &Decl, variants: &[(String, String)]) {
+        writeln!(out, "#[allow(dead_code)]\npub fn walk_{}<'s, 'b, V: Visitor<'s, 'b> + ?Sized>(visitor: &mut V, node: ast2::{}<'s, 'b>) {{\n    match node {{", decl.visit, decl.name).unwrap();
+        for (variant, typ) in variants {
+            writeln!(out, "        ast2::{}::{}(inner) => visitor.visit_{}(inner),", decl.name, variant, self.by_name[typ.as_str()].visit).unwrap();
+        }
+        out.push_str("    }\n}\n\n");
+    }
+}
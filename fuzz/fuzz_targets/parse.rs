@@ -0,0 +1,27 @@
+//! Runs the lexer and parser checks of `grammar::fuzz` on fuzzer inputs
+//!
+//! The first byte picks what the rest is: text as it is, or the choices a
+//! program is generated from, which keeps most inputs close to the grammar
+//! while the fuzzer mutates them. Run with `cargo +nightly fuzz run parse`.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use haze_lang::grammar::ebnf::Grammar;
+use haze_lang::grammar::fuzz::{check, program};
+use haze_lang::utils::choices::Bytes;
+use libfuzzer_sys::fuzz_target;
+
+static GRAMMAR: OnceLock<Grammar> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&kind, rest)) = data.split_first() else { return };
+    let source = match kind % 2 {
+        0 => String::from_utf8_lossy(rest).into_owned(),
+        _ => program(GRAMMAR.get_or_init(Grammar::v1), &mut Bytes::new(rest)),
+    };
+    if let Err(message) = check(&source) {
+        panic!("{}\n{:?}", message, source);
+    }
+});
@@ -6,7 +6,8 @@ use serde::Serializer;
 use crate::bumping::*;
 
 pub fn tag_is_binop(tag: Tag) -> bool {
-    match tag {
+    matches!(
+        tag,
         Tag::Plus
         | Tag::Minus
         | Tag::Slash
@@ -19,25 +20,16 @@ pub fn tag_is_binop(tag: Tag) -> bool {
         | Tag::Greater
         | Tag::GreaterEqual
         | Tag::Less
-        | Tag::LessEqual => true,
-
-        _ => false,
-    }
+        | Tag::LessEqual
+    )
 }
 
 pub fn tag_is_unaryop(tag: Tag) -> bool {
-    match tag {
-        Tag::Minus | Tag::Bang => true,
-        _ => false,
-    }
+    matches!(tag, Tag::Minus | Tag::Bang)
 }
 
 pub fn tag_is_literal(tag: Tag) -> bool {
-
-    match tag {
-        Tag::Ident | Tag::String | Tag::Number | Tag::Bool => true,
-        _ => false,
-    }
+    matches!(tag, Tag::Ident | Tag::String | Tag::Number | Tag::Bool)
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,526 @@
+//! Command line interface
+//!
+//! Every command takes its arguments after the command name, e.g.
+//! `haze disasm main.hz`. Diagnostics are written to stderr as
+//! `file:line:column: error[code]: message`, or to stdout as JSON by
+//! `haze check --format json`.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use bumpalo::Bump;
+
+use crate::ast2::{AstNode, TopDeclList};
+use crate::errors::{self, Loc};
+use crate::explain;
+use crate::highlight;
+use crate::ir;
+use crate::lsp;
+use crate::repl;
+use crate::ir::opt::PassManager;
+use crate::loader::{ModuleGraph, ModuleId};
+use crate::parser3::{Parsed, Parser};
+use crate::codegen;
+use crate::dump;
+use crate::query::Query;
+use crate::tree_json;
+use crate::typecheck::check::TypeChecker;
+use crate::typecheck::resolve::resolve_graph;
+use crate::vm::{bytecode, compiler, Io, Vm};
+use crate::wasm;
+
+const USAGE: &str = "\
+usage: haze <command> [args]
+
+commands:
+    check [--format human|json] <file>
+                     report the errors of a program
+    run <file>       check a program and run it
+    disasm <file>    print the bytecode of a program
+    ir [-O<n>] <file>  print the SSA form of a program, optimized at level n
+    wasm [--wat] <file> [-o <out>]
+                     compile a program to WebAssembly, or print it as text
+    c <file> [-o <out>]
+                     compile a program to C, printed unless written to a file
+    lsp              start a language server on stdin and stdout
+    cat <file>       print a program with syntax highlighting
+    parse [--format json|sexpr|dot] <file>
+                     print the syntax tree of a program
+    fix <file>       apply the suggested fixes for syntax errors to a file
+    explain <code>   print the explanation of an error code, e.g. E0012
+    query <pattern> <files>
+                     print the syntax tree nodes matching a pattern, e.g.
+                     'FnDef > ParamList > Param[name=radius]'
+    repl             start an interactive session
+    help             print this message
+";
+
+pub fn main(args: Vec<String>) -> ExitCode {
+    let Some(command) = args.first() else {
+        eprint!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let args = &args[1..];
+
+    let result = match command.as_str() {
+        "check" => check(args),
+        "run" => run(args),
+        "disasm" => disasm(args),
+        "ir" => ir(args),
+        "wasm" => wasm(args),
+        "c" => c(args),
+        "lsp" => lsp::run(std::io::stdin().lock(), std::io::stdout().lock()).map_err(|err| err.to_string()),
+        "cat" => cat(args),
+        "parse" => parse(args),
+        "fix" => fix(args),
+        "explain" => explain(args),
+        "query" => query(args),
+        "repl" => repl::run(std::io::stdin().lock(), std::io::stdout().lock()).map_err(|err| err.to_string()),
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("unknown command `{}`\n\n{}", command, USAGE)),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// The single file argument of a command
+fn file_arg(args: &[String]) -> Result<&str, String> {
+    match args {
+        [path] => Ok(path),
+        _ => Err("expected a single file argument".into()),
+    }
+}
+
+/// An error with its code, located in a file
+pub(crate) struct Diagnostic {
+    pub path: String,
+    pub line: u32,
+    pub column: usize,
+    pub code: &'static str,
+    pub message: String,
+    /// File, line and column of the declaration of a private item or field
+    pub declared_at: Option<(String, u32, usize)>,
+}
+
+impl Diagnostic {
+    pub fn new(path: &str, source: &str, location: Loc, code: &'static str, message: impl std::fmt::Display) -> Self {
+        let (line, column) = location.line_column(source);
+        Self { path: path.into(), line, column, code, message: message.to_string(), declared_at: None }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "file": self.path,
+            "line": self.line,
+            "column": self.column,
+            "code": self.code,
+            "message": self.message,
+        });
+        if let Some((file, line, column)) = &self.declared_at {
+            json["declared_at"] = serde_json::json!({ "file": file, "line": line, "column": column });
+        }
+        json
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}: error[{}]: {}", self.path, self.line, self.column, self.code, self.message)?;
+        if let Some((file, line, column)) = &self.declared_at {
+            write!(f, "\n{}:{}:{}: note: declared here", file, line, column)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a diagnostic with its code, pointing into a source file
+pub(crate) fn report(path: &str, source: &str, location: Loc, code: &'static str, message: impl std::fmt::Display) {
+    eprintln!("{}", Diagnostic::new(path, source, location, code, message));
+}
+
+/// Parses and type checks a source, the type checker only runs on a tree
+/// without syntax errors
+fn diagnose<'s, 'b>(path: &str, source: &'s str, bump: &'b Bump) -> (TopDeclList<'s, 'b>, TypeChecker<'s>, Vec<Diagnostic>) {
+    let mut parser = Parser::new(source, bump);
+    let root = bump.alloc(parser.parse().tree);
+    let mut diagnostics: Vec<_> = parser.errors.iter()
+        .map(|err| Diagnostic::new(path, source, err.location, err.kind.code(), &err.kind))
+        .collect();
+
+    let mut checker = TypeChecker::new();
+    if diagnostics.is_empty() {
+        checker.check_program(TopDeclList::cast(root));
+        diagnostics.extend(checker.errors.iter().map(|err| Diagnostic::new(path, source, err.location, err.kind.code(), &err.kind)));
+    }
+    (TopDeclList::cast(root), checker, diagnostics)
+}
+
+/// Loads a file along with the modules it imports, from the directory of the
+/// file, and reports the errors of all of them
+///
+/// Names are only resolved once every file parsed and every import was found.
+/// Types are only checked once names resolve, and for a program of a single
+/// module, as the type checker doesn't follow imports.
+fn diagnose_project(path: &str) -> Result<Vec<Diagnostic>, String> {
+    let path = Path::new(path);
+    let bump = Bump::new();
+    let mut graph = ModuleGraph::new(path.parent().unwrap_or(Path::new("")), &bump);
+    let entry = path.file_name().and_then(|name| graph.load_entry(name));
+    let Some(entry) = entry else {
+        return Err(graph.errors.first().map_or(format!("cannot read `{}`", path.display()), |err| err.kind.to_string()));
+    };
+
+    let in_module = |module: ModuleId, location: Loc, code, message: &dyn std::fmt::Display| {
+        let file = graph.file(graph.module(module).file);
+        Diagnostic::new(&file.path.display().to_string(), file.source, location, code, message)
+    };
+    let declared_at = |module: ModuleId, location: Loc| {
+        let file = graph.file(graph.module(module).file);
+        let (line, column) = location.line_column(file.source);
+        (file.path.display().to_string(), line, column)
+    };
+    let mut diagnostics = Vec::new();
+    for file in graph.files() {
+        let path = file.path.display().to_string();
+        diagnostics.extend(file.errors.iter().map(|err| Diagnostic::new(&path, file.source, err.location, err.kind.code(), &err.kind)));
+    }
+    for err in graph.errors.iter() {
+        match (err.module, err.location) {
+            (Some(module), Some(location)) => diagnostics.push(in_module(module, location, err.kind.code(), &err.kind)),
+            _ => return Err(err.kind.to_string()),
+        }
+    }
+    if !diagnostics.is_empty() {
+        return Ok(diagnostics);
+    }
+
+    diagnostics.extend(resolve_graph(&graph).iter().map(|err| Diagnostic {
+        declared_at: err.declared_at.map(|(module, location)| declared_at(module, location)),
+        ..in_module(err.module, err.location, err.kind.code(), &err.kind)
+    }));
+    if diagnostics.is_empty() && graph.modules().count() == 1 {
+        let mut checker = TypeChecker::new();
+        checker.check_program(graph.module(entry).decls.clone());
+        diagnostics.extend(checker.errors.iter().map(|err| in_module(entry, err.location, err.kind.code(), &err.kind)));
+    }
+    Ok(diagnostics)
+}
+
+/// Reads, parses and type checks a file, reporting every diagnostic
+fn check_file<R>(
+    path: &str,
+    then: impl for<'s, 'b> FnOnce(TopDeclList<'s, 'b>, &TypeChecker<'s>) -> Result<R, String>,
+) -> Result<R, String> {
+    let source = std::fs::read_to_string(path).map_err(|err| format!("cannot read `{}`: {}", path, err))?;
+    let bump = Bump::new();
+    let (decls, checker, diagnostics) = diagnose(path, &source, &bump);
+    for diagnostic in diagnostics.iter() {
+        eprintln!("{}", diagnostic);
+    }
+    if !diagnostics.is_empty() {
+        return Err(format!("could not compile `{}`", path));
+    }
+    then(decls, &checker)
+}
+
+/// Reports the errors of a file and the modules it imports without compiling them
+///
+/// As JSON, the diagnostics are an array written to stdout, which is empty
+/// when there are none.
+fn check(args: &[String]) -> Result<(), String> {
+    let mut format = "human";
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next().ok_or("expected a format after `--format`")?,
+            _ => files.push(arg.clone()),
+        }
+    }
+    let path = file_arg(&files)?;
+    let diagnostics = diagnose_project(path)?;
+    match format {
+        "human" => diagnostics.iter().for_each(|diagnostic| eprintln!("{}", diagnostic)),
+        "json" => println!("{:#}", serde_json::Value::Array(diagnostics.iter().map(Diagnostic::to_json).collect())),
+        _ => return Err(format!("unknown format `{}`, expected `human` or `json`", format)),
+    }
+    match diagnostics.is_empty() {
+        true => Ok(()),
+        false => Err(format!("could not compile `{}`", path)),
+    }
+}
+
+fn compile<'s>(path: &str, decls: TopDeclList<'s, '_>, types: &TypeChecker<'s>) -> Result<bytecode::Program, String> {
+    compiler::compile(decls, types).map_err(|errors| {
+        for err in errors {
+            eprintln!("{}:{}: error: {} are not supported by the VM", path, err.location.line, err.message);
+        }
+        format!("could not compile `{}`", path)
+    })
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let path = file_arg(args)?;
+    check_file(path, |decls, types| {
+        let program = compile(path, decls, types)?;
+        let mut io = Io { input: &mut std::io::stdin().lock(), output: &mut std::io::stdout() };
+        Vm::new(&program).run_main(&mut io)
+            .map(|_| ())
+            .map_err(|err| format!("{}:{}: {}", path, err.line, err.kind))
+    })
+}
+
+fn disasm(args: &[String]) -> Result<(), String> {
+    let path = file_arg(args)?;
+    check_file(path, |decls, types| {
+        let program = compile(path, decls, types)?;
+        print!("{}", bytecode::disassemble(&program));
+        Ok(())
+    })
+}
+
+fn ir(args: &[String]) -> Result<(), String> {
+    let (flags, args): (Vec<String>, Vec<String>) = args.iter().cloned().partition(|arg| arg.starts_with("-O"));
+    let level = match flags.last().map(|flag| &flag[2..]) {
+        None => 0,
+        Some(level) => level.parse().map_err(|_| format!("invalid optimization level `{}`", level))?,
+    };
+    let path = file_arg(&args)?;
+    check_file(path, |decls, types| {
+        let mut module = ir::lower::lower(decls, types).map_err(|errors| {
+            for err in errors {
+                eprintln!("{}:{}: error: {} are not supported by the IR", path, err.location.line, err.message);
+            }
+            format!("could not compile `{}`", path)
+        })?;
+        PassManager::new(level).run(&mut module);
+        print!("{}", module);
+        Ok(())
+    })
+}
+
+fn wasm(args: &[String]) -> Result<(), String> {
+    let mut wat = false;
+    let mut output = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--wat" => wat = true,
+            "-o" => output = Some(args.next().ok_or("expected a file after `-o`")?.clone()),
+            _ => files.push(arg.clone()),
+        }
+    }
+    let path = file_arg(&files)?;
+    check_file(path, |decls, types| {
+        let module = wasm::compiler::compile(decls, types).map_err(|errors| {
+            for err in errors {
+                eprintln!("{}:{}: error: {} are not supported by WebAssembly", path, err.location.line, err.message);
+            }
+            format!("could not compile `{}`", path)
+        })?;
+        if wat && output.is_none() {
+            print!("{}", module);
+            return Ok(());
+        }
+        let output = output.unwrap_or_else(|| {
+            let extension = if wat { "wat" } else { "wasm" };
+            std::path::Path::new(path).with_extension(extension).to_string_lossy().into_owned()
+        });
+        let bytes = if wat { module.to_string().into_bytes() } else { wasm::encode::encode(&module) };
+        std::fs::write(&output, bytes).map_err(|err| format!("cannot write `{}`: {}", output, err))
+    })
+}
+
+fn c(args: &[String]) -> Result<(), String> {
+    let mut output = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => output = Some(args.next().ok_or("expected a file after `-o`")?.clone()),
+            _ => files.push(arg.clone()),
+        }
+    }
+    let path = file_arg(&files)?;
+    check_file(path, |decls, types| {
+        let c = codegen::generate_c(decls, types).map_err(|errors| {
+            for err in errors {
+                eprintln!("{}:{}: error: {} are not supported by C", path, err.location.line, err.message);
+            }
+            format!("could not compile `{}`", path)
+        })?;
+        match output {
+            Some(output) => std::fs::write(&output, c).map_err(|err| format!("cannot write `{}`: {}", output, err)),
+            None => {
+                print!("{}", c);
+                Ok(())
+            }
+        }
+    })
+}
+
+fn cat(args: &[String]) -> Result<(), String> {
+    let path = file_arg(args)?;
+    let source = std::fs::read_to_string(path).map_err(|err| format!("cannot read `{}`: {}", path, err))?;
+    print!("{}", highlight::ansi(&source, &highlight::highlight(&source)));
+    Ok(())
+}
+
+/// Prints the tree even if the program has syntax errors, as it keeps
+/// whatever the parser failed on
+fn parse(args: &[String]) -> Result<(), String> {
+    let mut format = "json";
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next().ok_or("expected a format after `--format`")?,
+            _ => files.push(arg.clone()),
+        }
+    }
+    let path = file_arg(&files)?;
+    let source = std::fs::read_to_string(path).map_err(|err| format!("cannot read `{}`: {}", path, err))?;
+    let bump = Bump::new();
+    let mut parser = Parser::new(&source, &bump);
+    let Parsed { tree, spans } = parser.parse();
+    match format {
+        "json" => println!("{:#}", tree_json::to_json(&tree, spans)),
+        "sexpr" => print!("{}", dump::sexpr(&tree)),
+        "dot" => print!("{}", dump::dot(&tree)),
+        _ => return Err(format!("unknown format `{}`, expected `json`, `sexpr` or `dot`", format)),
+    }
+    for err in parser.errors.iter() {
+        report(path, &source, err.location, err.kind.code(), &err.kind);
+    }
+    match parser.errors.is_empty() {
+        true => Ok(()),
+        false => Err(format!("`{}` has syntax errors", path)),
+    }
+}
+
+/// Applying a fix can reveal another, so files are reparsed a few times
+const FIX_PASSES: usize = 8;
+
+fn fix(args: &[String]) -> Result<(), String> {
+    let path = file_arg(args)?;
+    let mut source = std::fs::read_to_string(path).map_err(|err| format!("cannot read `{}`: {}", path, err))?;
+    let mut applied = 0;
+    for _ in 0..FIX_PASSES {
+        let bump = Bump::new();
+        let mut parser = Parser::new(&source, &bump);
+        parser.parse();
+        let (fixed, count) = errors::apply_fixes(&source, &parser.errors);
+        drop(parser);
+        if count == 0 {
+            break;
+        }
+        source = fixed;
+        applied += count;
+    }
+    if applied > 0 {
+        std::fs::write(path, &source).map_err(|err| format!("cannot write `{}`: {}", path, err))?;
+    }
+    println!("{}: applied {} fixes", path, applied);
+
+    let bump = Bump::new();
+    let mut parser = Parser::new(&source, &bump);
+    parser.parse();
+    for err in parser.errors.iter() {
+        report(path, &source, err.location, err.kind.code(), &err.kind);
+    }
+    match parser.errors.is_empty() {
+        true => Ok(()),
+        false => Err(format!("`{}` has errors without a fix", path)),
+    }
+}
+
+fn explain(args: &[String]) -> Result<(), String> {
+    let [code] = args else { return Err("expected a single error code".into()) };
+    match explain::lookup(code) {
+        Some(explanation) => {
+            print!("{}", explanation);
+            Ok(())
+        }
+        None => Err(format!("no error has the code `{}`", code)),
+    }
+}
+
+/// Prints `file:line:column: NodeType: text` for every match, with the
+/// first line of its text
+fn query(args: &[String]) -> Result<(), String> {
+    let [pattern, paths @ ..] = args else { return Err("expected a pattern".into()) };
+    if paths.is_empty() {
+        return Err("expected at least one file".into());
+    }
+    let query = Query::parse(pattern).map_err(|err| err.to_string())?;
+    for path in paths {
+        let source = std::fs::read_to_string(path).map_err(|err| format!("cannot read `{}`: {}", path, err))?;
+        let bump = Bump::new();
+        let mut parser = Parser::new(&source, &bump);
+        let parsed = bump.alloc(parser.parse());
+        for found in query.run(&parsed.tree, parsed.spans) {
+            let start = found.span.start as usize;
+            let (line, column) = Loc::new(found.span.start, 0, 0).line_column(&source);
+            let text = source[start..found.span.end as usize].lines().next().unwrap_or("");
+            println!("{}:{}:{}: {:?}: {}", path, line, column, found.node.kind.0, text);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+    use serde_json::json;
+
+    use super::*;
+    use crate::utils::temp_project::TempProject;
+
+    #[test]
+    fn test_diagnostics() {
+        let bump = Bump::new();
+        let source = "fn main() {\n    let x: i64 = true;\n    print(1 2);\n}";
+        let (_, _, diagnostics) = diagnose("main.hz", source, &bump);
+        let json: Vec<_> = diagnostics.iter().map(Diagnostic::to_json).collect();
+        assert_eq!(json, [json!({ "file": "main.hz", "line": 3, "column": 13, "code": "E0016", "message": "expected an operator" })]);
+        assert_eq!(diagnostics[0].to_string(), "main.hz:3:13: error[E0016]: expected an operator");
+
+        // Types are only checked without syntax errors
+        let (_, _, diagnostics) = diagnose("main.hz", "fn main() {\n    let x: i64 = true;\n}", &bump);
+        assert_eq!(diagnostics[0].to_string(), "main.hz:2:18: error[E0100]: expected `i64`, found `bool`");
+    }
+
+    #[test]
+    fn test_project_diagnostics() {
+        let project = TempProject::new(&[
+            ("main.hz", "import geo.shapes;\nfn main() {\n    let s = secret();\n}\n"),
+            ("geo/shapes.hz", "fn secret() -> i64 { return 1; }\npub fn area() -> i64 { return 2; }\n"),
+            ("lost.hz", "import nowhere;\nfn main() {}\n"),
+            ("broken.hz", "import geo.broken;\n"),
+            ("geo/broken.hz", "fn f( {}\n"),
+            ("types.hz", "fn main() {\n    let x: i64 = true;\n}\n"),
+        ]);
+        let messages = |file: &str| -> Vec<String> {
+            let path = project.root().join(file);
+            let diagnostics = diagnose_project(path.to_str().unwrap()).unwrap();
+            let prefix = format!("{}{}", project.root().display(), std::path::MAIN_SEPARATOR);
+            diagnostics.iter().map(|diagnostic| diagnostic.to_string().replace(&prefix, "")).collect()
+        };
+
+        assert_eq!(messages("main.hz"), ["main.hz:3:13: error[E0200]: item is private to its module\ngeo/shapes.hz:1:4: note: declared here"]);
+        assert_eq!(messages("lost.hz"), ["lost.hz:1:8: error[E0300]: no file or module `nowhere`"]);
+        assert!(messages("broken.hz")[0].starts_with("geo/broken.hz:1:"), "{:?}", messages("broken.hz"));
+        assert_eq!(messages("types.hz"), ["types.hz:2:18: error[E0100]: expected `i64`, found `bool`"]);
+        assert!(diagnose_project(project.root().join("missing.hz").to_str().unwrap()).is_err());
+    }
+}
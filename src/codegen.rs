@@ -1,66 +1,679 @@
-// use crate::ast2::{Expr, FnDef, Node, NodeBuilder, NodeKind, NodeType::*, Stmt, TopDeclList, VarDecl};
-// use crate::errors::Loc;
-// use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
-// use crate::token::Tag;
-// pub use crate::parser3::Parser;
-// use crate::bumping::Box;
-// use crate::typecheck::Type;
-// use core::fmt::Write;
-
-// use crate::{ast2::AstToken, visitor::{Visitor, Walker}};
-
-// struct Generator<'s, 'b> {
-//     ast: TopDeclList<'s, 'b>,
-//     output: String
-// }
-
-// impl<'s, 'b> Generator<'s, 'b> {
-//     pub fn generate(&mut self) {
-//         for decl in self.ast.items() {
-//             match decl {
-//                 crate::ast2::TopLevelDecl::Mod(_) => unimplemented!(),
-//                 crate::ast2::TopLevelDecl::Import(_) => unimplemented!(),
-//                 crate::ast2::TopLevelDecl::Enum(_) => unimplemented!(),
-//                 crate::ast2::TopLevelDecl::Fn(fn_def) => self.generate_func(fn_def),
-//                 crate::ast2::TopLevelDecl::Struct(_) => todo!(),
-//                 crate::ast2::TopLevelDecl::Type(_) => todo!(),
-//                 crate::ast2::TopLevelDecl::Const(_) => todo!()
-//             }
-//         }
-//     }
-
-//     pub fn generate_func(&mut self, fn_def: FnDef<'s, 'b>) {
-//         let return_type = fn_def.return_type().map(|ret| ret.token().value).unwrap_or("void");
-
-//         let name = fn_def.name().token().value;
-
-//         write!(self.output, "{} {}(", return_type, name);
-//         fn_def.params().items().map(|param| {
-//             let param_type = Type::from_type_expr(&param.param_type());
-//             // write!(self.output, "{} {}", param.param_type().)
-//         });
-//         for stmt in fn_def.body().body().items() {
-            
-//         }
-//     }
-
-//     fn generate_statement(&mut self, stmt: Stmt<'s, 'b>) {
-//         match stmt {
-//             Stmt::VarDecl(node) => self.generate_var_decl(node),
-//             Stmt::ExprStmt(_) => todo!(),
-//         }
-//     }
-
-//     fn generate_var_decl(&mut self, var: VarDecl<'s, 'b>) {
-//         write!(self.output, "{} {} = ", 
-//             var.var_type().unwrap().token().value,
-//             var.name().token().value
-//         )?;
-//         self.generate_expr(var.value().unwrap());
-//         self.output.push(';');
-//     }
-
-//     fn generate_expr(&mut self, expr: Expr<'s, 'b>) {
-
-//     }
-// }
\ No newline at end of file
+//! C backend
+//!
+//! Emits a single C99 translation unit for a type checked program. Haze names
+//! are prefixed with `hz_` so they never collide with the C library, and the
+//! helpers the generated code relies on are prefixed with `haze_`.
+//!
+//! Arrays are wrapped in structs so they keep value semantics when assigned,
+//! passed or returned, just like in Haze.
+
+use core::fmt::Write;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::ast2::{
+    AstToken, BlockExpr, Expr, FnDef, IfAlt, IfExpr, Stmt, TopDeclList, TopLevelDecl,
+    VarDecl,
+};
+use crate::errors::Loc;
+use crate::token::Tag;
+use crate::typecheck::check::{expr_loc, TypeChecker};
+use crate::typecheck::Type;
+
+/// Functions every generated program may call
+const RUNTIME: &str = r#"static void haze_print_string(const char* value) { printf("%s\n", value); }
+static void haze_print_bool(bool value) { puts(value ? "true" : "false"); }
+static void haze_print_i64(int64_t value) { printf("%" PRId64 "\n", value); }
+static void haze_print_u64(uint64_t value) { printf("%" PRIu64 "\n", value); }
+
+/* Prints the shortest digits which read back as the same value, without an
+   exponent, the way Rust formats an f64 */
+static void haze_print_f64(double value) {
+    if (isnan(value)) { puts("NaN"); return; }
+    if (isinf(value)) { puts(value < 0 ? "-inf" : "inf"); return; }
+    char buffer[32];
+    int precision = 1;
+    for (; precision < 17; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*e", precision - 1, value);
+        if (strtod(buffer, NULL) == value) break;
+    }
+    snprintf(buffer, sizeof buffer, "%.*e", precision - 1, value);
+
+    /* buffer is [-]d[.ddd]e[+-]xx */
+    const char* c = buffer;
+    if (*c == '-') { putchar('-'); c++; }
+    char digits[24];
+    int count = 0;
+    for (; *c != 'e'; c++) {
+        if (*c != '.') digits[count++] = *c;
+    }
+    int exponent = atoi(c + 1);
+    while (count > 1 && digits[count - 1] == '0') count--;
+    if (exponent < 0) {
+        fputs("0.", stdout);
+        for (int i = 1; i < -exponent; i++) putchar('0');
+        fwrite(digits, 1, count, stdout);
+    } else if (exponent + 1 >= count) {
+        fwrite(digits, 1, count, stdout);
+        for (int i = count; i <= exponent; i++) putchar('0');
+    } else {
+        fwrite(digits, 1, exponent + 1, stdout);
+        putchar('.');
+        fwrite(digits + exponent + 1, 1, count - exponent - 1, stdout);
+    }
+    putchar('\n');
+}
+
+static const char* haze_input(const char* prompt) {
+    static char buffer[1024];
+    fputs(prompt, stdout);
+    fflush(stdout);
+    if (!fgets(buffer, sizeof buffer, stdin)) buffer[0] = '\0';
+    buffer[strcspn(buffer, "\n")] = '\0';
+    char* line = malloc(strlen(buffer) + 1);
+    strcpy(line, buffer);
+    return line;
+}
+
+static int64_t haze_int(const char* text) { return strtoll(text, NULL, 10); }
+
+static int64_t haze_index(int64_t index, int64_t len) {
+    if (index < 0 || index >= len) {
+        fprintf(stderr, "index out of bounds: the len is %" PRId64 " but the index is %" PRId64 "\n", len, index);
+        exit(101);
+    }
+    return index;
+}
+"#;
+
+#[derive(Debug)]
+pub struct CodegenError {
+    pub message: &'static str,
+    pub location: Loc,
+}
+
+/// Generates C source for a program which passed type checking
+pub fn generate_c<'s, 'b>(ast: TopDeclList<'s, 'b>, types: &TypeChecker<'s>) -> Result<String, Vec<CodegenError>> {
+    let mut generator = Generator::new(ast, types);
+    generator.generate();
+    if generator.errors.is_empty() {
+        Ok(generator.finish())
+    } else {
+        Err(generator.errors)
+    }
+}
+
+pub struct Generator<'s, 'b, 't> {
+    ast: TopDeclList<'s, 'b>,
+    types: &'t TypeChecker<'s>,
+    /// Struct and array definitions, each after the types it contains
+    type_defs: String,
+    defined: HashSet<String>,
+    prototypes: String,
+    output: String,
+    indent: usize,
+    /// C names of the locals in scope
+    locals: Vec<HashMap<&'s str, String>>,
+    /// C names already used in the current function
+    taken: HashSet<String>,
+    has_main: Option<Type>,
+    pub errors: Vec<CodegenError>,
+}
+
+impl<'s, 'b, 't> Generator<'s, 'b, 't> {
+    pub fn new(ast: TopDeclList<'s, 'b>, types: &'t TypeChecker<'s>) -> Self {
+        Self {
+            ast,
+            types,
+            type_defs: String::new(),
+            defined: HashSet::new(),
+            prototypes: String::new(),
+            output: String::new(),
+            indent: 0,
+            locals: Vec::new(),
+            taken: HashSet::new(),
+            has_main: None,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn generate(&mut self) {
+        for decl in self.ast.items() {
+            match decl {
+                TopLevelDecl::Fn(fn_def) => self.generate_func(fn_def),
+                TopLevelDecl::Struct(struct_decl) => {
+                    let typ = self.types.global(struct_decl.name().token().value).cloned();
+                    if let Some(typ) = typ { self.define(&typ); }
+                }
+                TopLevelDecl::Const(const_decl) => {
+                    let name = global_name(const_decl.name().token().value);
+                    let const_type = match self.types.global(const_decl.name().token().value) {
+                        Some(Type::Const(inner)) => (**inner).clone(),
+                        _ => Type::Error,
+                    };
+                    let c_type = self.c_type(&const_type, Loc::from_token(*const_decl.name().token()));
+                    // C only allows literals in static initializers, so constants are
+                    // computed by a function which may refer to other constants
+                    let _ = writeln!(self.prototypes, "static {} {}(void);", c_type, name);
+                    self.begin_function();
+                    let value = self.expr(const_decl.value());
+                    let _ = writeln!(self.output, "static {} {}(void) {{ return {}; }}\n", c_type, name, value);
+                }
+                // Aliases are replaced by the types they name
+                TopLevelDecl::Type(_) => {}
+                TopLevelDecl::Enum(enum_decl) => {
+                    self.unsupported("enums", Loc::from_token(*enum_decl.name().token()));
+                }
+                TopLevelDecl::Mod(_) | TopLevelDecl::Import(_) => {}
+            }
+        }
+    }
+
+    /// Assembles the translation unit
+    pub fn finish(self) -> String {
+        let mut c = String::from("/* Generated by the Haze compiler */\n");
+        for header in ["inttypes.h", "math.h", "stdbool.h", "stdint.h", "stdio.h", "stdlib.h", "string.h"] {
+            let _ = writeln!(c, "#include <{}>", header);
+        }
+        c.push('\n');
+        c.push_str(RUNTIME);
+        c.push('\n');
+        c.push_str(&self.type_defs);
+        c.push('\n');
+        c.push_str(&self.prototypes);
+        c.push('\n');
+        c.push_str(&self.output);
+
+        match self.has_main {
+            Some(Type::Unit) => c.push_str("int main(void) {\n    hz_main();\n    return 0;\n}\n"),
+            Some(_) => c.push_str("int main(void) {\n    return (int)hz_main();\n}\n"),
+            None => {}
+        }
+        c
+    }
+
+    fn unsupported(&mut self, message: &'static str, location: Loc) {
+        self.errors.push(CodegenError { message, location });
+    }
+
+    fn line(&mut self, text: impl AsRef<str>) {
+        for _ in 0..self.indent {
+            self.output.push_str("    ");
+        }
+        self.output.push_str(text.as_ref());
+        self.output.push('\n');
+    }
+
+    /// Resets the local names before generating a new function
+    fn begin_function(&mut self) {
+        self.locals.clear();
+        self.taken.clear();
+        for decl in self.ast.items() {
+            let name = match decl {
+                TopLevelDecl::Fn(node) => node.name(),
+                TopLevelDecl::Struct(node) => node.name(),
+                TopLevelDecl::Const(node) => node.name(),
+                TopLevelDecl::Type(node) => node.name(),
+                TopLevelDecl::Enum(node) => node.name(),
+                TopLevelDecl::Mod(_) | TopLevelDecl::Import(_) => continue,
+            };
+            self.taken.insert(global_name(name.token().value));
+        }
+    }
+
+    /// Picks a unique C name for a local, since Haze allows shadowing within a scope
+    fn bind(&mut self, name: &'s str) -> String {
+        let mut c_name = global_name(name);
+        let mut suffix = 0;
+        while self.taken.contains(&c_name) {
+            suffix += 1;
+            c_name = format!("hz_{}_{}", name, suffix);
+        }
+        self.taken.insert(c_name.clone());
+        self.locals.last_mut()
+            .expect("locals are bound inside a scope")
+            .insert(name, c_name.clone());
+        c_name
+    }
+
+    fn local(&self, name: &str) -> Option<&String> {
+        self.locals.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Emits the definition of a struct or array type and every type it contains
+    fn define(&mut self, typ: &Type) {
+        match typ {
+            Type::Array(elem, len) => {
+                let name = typ.as_c_literal();
+                if !self.defined.insert(name.clone()) { return; }
+                self.define(elem);
+                let _ = writeln!(self.type_defs, "typedef struct {{ {} items[{}]; }} {};", elem.as_c_literal(), len, name);
+            }
+            Type::Struct(_, fields) => {
+                let name = typ.as_c_literal();
+                if !self.defined.insert(name.clone()) { return; }
+                for (_, field) in fields.iter() {
+                    self.define(field);
+                }
+                let _ = writeln!(self.type_defs, "typedef struct {} {{", name);
+                for (field, field_type) in fields.iter() {
+                    let _ = writeln!(self.type_defs, "    {} {};", field_type.as_c_literal(), global_name(field));
+                }
+                let _ = writeln!(self.type_defs, "}} {};", name);
+            }
+            Type::Const(inner) | Type::Alias(inner) => self.define(inner),
+            _ => {}
+        }
+    }
+
+    fn c_type(&mut self, typ: &Type, location: Loc) -> String {
+        match typ {
+            Type::Fn(..) => {
+                self.unsupported("function values", location);
+                "void".into()
+            }
+            typ if typ.is_error() => {
+                self.unsupported("code with type errors", location);
+                "void".into()
+            }
+            typ => {
+                self.define(typ);
+                typ.as_c_literal()
+            }
+        }
+    }
+
+    pub fn generate_func(&mut self, fn_def: FnDef<'s, 'b>) {
+        let name = fn_def.name().token().value;
+        let location = Loc::from_token(*fn_def.name().token());
+        let Some(Type::Fn(params, ret)) = self.types.global(name).cloned() else { return };
+        let Some(body) = fn_def.body() else { return };
+
+        self.begin_function();
+        self.locals.push(HashMap::new());
+
+        let return_type = self.c_type(&ret, location);
+        let mut signature = Vec::new();
+        let mut param_types = Vec::new();
+        for (param, param_type) in fn_def.params().items().zip(params.iter()) {
+            let c_type = self.c_type(param_type, Loc::from_token(*param.ident().token()));
+            let c_name = self.bind(param.ident().token().value);
+            signature.push(format!("{} {}", c_type, c_name));
+            param_types.push(c_type);
+        }
+        let join = |list: Vec<String>| if list.is_empty() { "void".to_string() } else { list.join(", ") };
+
+        if name == "main" {
+            self.has_main = Some((*ret).clone());
+        }
+        let _ = writeln!(self.prototypes, "static {} {}({});", return_type, global_name(name), join(param_types));
+        self.line(format!("static {} {}({}) {{", return_type, global_name(name), join(signature)));
+        self.block_body(body);
+        self.line("}");
+        self.output.push('\n');
+        self.locals.pop();
+    }
+
+    /// Statements of a block, indented, without the braces
+    fn block_body(&mut self, block: BlockExpr<'s, 'b>) {
+        self.indent += 1;
+        self.locals.push(HashMap::new());
+        for stmt in block.body().items() {
+            self.generate_statement(stmt);
+        }
+        self.locals.pop();
+        self.indent -= 1;
+    }
+
+    fn generate_statement(&mut self, stmt: Stmt<'s, 'b>) {
+        match stmt {
+            Stmt::VarDecl(node) => self.generate_var_decl(node),
+            Stmt::ExprStmt(node) => self.generate_expr_stmt(node.expr()),
+            Stmt::EmptyStmt(_) => {}
+        }
+    }
+
+    fn generate_var_decl(&mut self, var: VarDecl<'s, 'b>) {
+        let location = Loc::from_token(*var.name().token());
+        let var_type = self.types.type_of_var(&var).cloned().unwrap_or(Type::Error);
+
+        if matches!(var_type, Type::Unit | Type::Never) {
+            // Nothing to store, only the side effects of the initializer remain
+            if let Some(value) = var.value() { self.generate_expr_stmt(value); }
+            self.bind(var.name().token().value);
+            return;
+        }
+
+        let c_type = self.c_type(&var_type, location);
+        // The initializer is generated before binding so `let x = x;` reads the outer `x`
+        let value = match var.value() {
+            Some(value) => self.expr(value),
+            None => "{0}".into(),
+        };
+        let name = self.bind(var.name().token().value);
+        self.line(format!("{} {} = {};", c_type, name, value));
+    }
+
+    /// Expressions in statement position, where control flow maps to C statements
+    fn generate_expr_stmt(&mut self, expr: Expr<'s, 'b>) {
+        match expr {
+            Expr::IfExpr(if_expr) => self.generate_if(if_expr),
+            Expr::WhileExpr(while_expr) => {
+                let condition = self.expr(while_expr.condition());
+                self.line(format!("while ({}) {{", condition));
+                self.block_body(while_expr.consequence());
+                self.line("}");
+            }
+            Expr::BlockExpr(block) => {
+                self.line("{");
+                self.block_body(block);
+                self.line("}");
+            }
+            Expr::ReturnExpr(return_expr) => match return_expr.value() {
+                Some(value) => {
+                    let value = self.expr(value);
+                    self.line(format!("return {};", value));
+                }
+                None => self.line("return;"),
+            },
+            Expr::BreakExpr(_) => self.line("break;"),
+            Expr::ContinueExpr(_) => self.line("continue;"),
+            Expr::AssignExpr(assign) => {
+                let value = self.expr(assign.value());
+                match assign.target() {
+                    Expr::Ident(name) => match self.local(name.token().value).cloned() {
+                        Some(local) => self.line(format!("{} = {};", local, value)),
+                        None => self.unsupported("assignment to a global", Loc::from_token(*name.token())),
+                    },
+                    target => self.unsupported("assignment to a field or element", expr_loc(&target)),
+                }
+            }
+            expr => {
+                let value = self.expr(expr);
+                self.line(format!("{};", value));
+            }
+        }
+    }
+
+    fn generate_if(&mut self, if_expr: IfExpr<'s, 'b>) {
+        let condition = self.expr(if_expr.condition());
+        self.line(format!("if ({}) {{", condition));
+        self.block_body(if_expr.consequence());
+        match if_expr.alternate() {
+            Some(IfAlt::Else(block)) => {
+                self.line("} else {");
+                self.block_body(block);
+            }
+            Some(IfAlt::ElseIf(else_if)) => {
+                self.line("} else {");
+                self.indent += 1;
+                self.generate_if(else_if);
+                self.indent -= 1;
+            }
+            None => {}
+        }
+        self.line("}");
+    }
+
+    fn type_of(&self, expr: &Expr<'s, 'b>) -> Type {
+        self.types.type_of(expr).cloned().unwrap_or(Type::Error)
+    }
+
+    /// Expressions in value position
+    fn expr(&mut self, expr: Expr<'s, 'b>) -> String {
+        let location = expr_loc(&expr);
+        let typ = self.type_of(&expr);
+        match expr {
+            Expr::Int(int) => int_literal(int.token().value, &typ),
+            Expr::Str(string) => string.token().value.replace('\n', "\\n").replace('\r', "\\r"),
+            Expr::Bool(boolean) => boolean.token().value.to_string(),
+            Expr::Ident(ident) => {
+                let name = ident.token().value;
+                if let Some(local) = self.local(name) {
+                    return local.clone();
+                }
+                match self.types.global(name) {
+                    Some(Type::Const(_)) => format!("{}()", global_name(name)),
+                    _ => {
+                        self.unsupported("function values", location);
+                        String::new()
+                    }
+                }
+            }
+            Expr::Group(group) => format!("({})", self.expr(group.expr())),
+            Expr::Infix(infix) => {
+                let operand_type = self.type_of(&infix.left());
+                let left = self.expr(infix.left());
+                let right = self.expr(infix.right());
+                let op = infix.op();
+                if operand_type == Type::String && matches!(op.tag, Tag::EqualEqual | Tag::BangEqual) {
+                    format!("(strcmp({}, {}) {} 0)", left, right, op.value)
+                } else if let (Some((signed, unsigned)), Tag::Plus | Tag::Minus | Tag::Asterisk) = (wrapping(&typ), op.tag) {
+                    format!("(({})(({}){} {} ({}){}))", signed, unsigned, left, op.value, unsigned, right)
+                } else {
+                    format!("({} {} {})", left, op.value, right)
+                }
+            }
+            Expr::Prefix(prefix) => {
+                let op = prefix.op();
+                let right = self.expr(prefix.right());
+                match (wrapping(&typ), op.tag) {
+                    (Some((signed, unsigned)), Tag::Minus) => format!("(({})(0u - ({}){}))", signed, unsigned, right),
+                    _ => format!("({}{})", op.value, right),
+                }
+            }
+            Expr::CallExpr(call) => {
+                let name = call.name().token().value;
+                let args: Vec<_> = call.args().args().collect();
+                let is_user_defined = self.local(name).is_some() || self.types.global(name).is_some();
+                if !is_user_defined {
+                    let arg_type = args.first().map(|arg| self.type_of(arg)).unwrap_or(Type::Error);
+                    let arg = args.into_iter().next().map(|arg| self.expr(arg)).unwrap_or_default();
+                    return match name {
+                        "print" => match arg_type {
+                            Type::String => format!("haze_print_string({})", arg),
+                            Type::Bool => format!("haze_print_bool({})", arg),
+                            Type::U32 | Type::U64 => format!("haze_print_u64({})", arg),
+                            Type::F32 | Type::F64 => format!("haze_print_f64({})", arg),
+                            _ => format!("haze_print_i64({})", arg),
+                        },
+                        _ => format!("haze_{}({})", name, arg),
+                    };
+                }
+                if self.local(name).is_some() {
+                    self.unsupported("function values", location);
+                }
+                let args: Vec<String> = args.into_iter().map(|arg| self.expr(arg)).collect();
+                format!("{}({})", global_name(name), args.join(", "))
+            }
+            Expr::ArrayExpr(array) => {
+                let c_type = self.c_type(&typ, location);
+                let items: Vec<String> = array.items().map(|item| self.expr(item)).collect();
+                format!("(({}){{ {{ {} }} }})", c_type, items.join(", "))
+            }
+            Expr::IndexExpr(index) => {
+                let len = match self.type_of(&index.container()) {
+                    Type::Array(_, len) => len,
+                    _ => 0,
+                };
+                let container = self.expr(index.container());
+                let index = self.expr(index.index());
+                format!("{}.items[haze_index((int64_t)({}), {})]", container, index, len)
+            }
+            Expr::FieldAccessExpr(access) => {
+                let parent = self.expr(access.parent());
+                format!("{}.{}", parent, global_name(access.field_name().token().value))
+            }
+            Expr::StructExpr(struct_expr) => {
+                let c_type = self.c_type(&typ, location);
+                let fields: Vec<String> = struct_expr.fields().items()
+                    .map(|field| {
+                        let value = self.expr(field.value());
+                        format!(".{} = {}", global_name(field.name().token().value), value)
+                    })
+                    .collect();
+                format!("(({}){{ {} }})", c_type, fields.join(", "))
+            }
+            Expr::IfExpr(_) | Expr::WhileExpr(_) | Expr::BlockExpr(_) | Expr::ReturnExpr(_)
+            | Expr::BreakExpr(_) | Expr::ContinueExpr(_) | Expr::AssignExpr(_) => {
+                self.unsupported("control flow inside an expression", location);
+                String::new()
+            }
+            Expr::MethodCall(_) | Expr::TupleExpr(_) => {
+                self.unsupported("methods and tuples", location);
+                String::new()
+            }
+        }
+    }
+}
+
+fn global_name(name: &str) -> String {
+    format!("hz_{}", name)
+}
+
+/// The C types signed arithmetic of a type is done in, so that it wraps like
+/// in the VM instead of overflowing, which C leaves undefined
+fn wrapping(typ: &Type) -> Option<(&'static str, &'static str)> {
+    match typ {
+        Type::I32 => Some(("int32_t", "uint32_t")),
+        Type::I64 => Some(("int64_t", "uint64_t")),
+        _ => None,
+    }
+}
+
+/// A number literal in C syntax for the given type
+fn int_literal(value: &str, typ: &Type) -> String {
+    if value.contains('.') || typ.is_float() {
+        let mut literal = value.to_string();
+        if !literal.contains('.') { literal.push_str(".0"); }
+        if *typ == Type::F32 { literal.push('f'); }
+        return literal;
+    }
+    // A leading zero would make C read the literal as octal
+    let digits = value.trim_start_matches('0');
+    let digits = if digits.is_empty() { "0" } else { digits };
+    match typ {
+        Type::U32 => format!("{}u", digits),
+        Type::U64 => format!("{}ull", digits),
+        Type::I64 => format!("{}ll", digits),
+        _ => digits.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::ast2::AstNode;
+    use crate::parser3::Parser;
+    use crate::utils::temp_project::TempProject;
+
+    /// Compiles a program with the system C compiler and returns what it prints
+    fn run(source: &str) -> String {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        assert!(checker.errors.is_empty(), "{:?}", checker.errors);
+
+        let c = generate_c(TopDeclList::cast(root), &checker).unwrap();
+
+        let project = TempProject::new(&[("main.c", &c)]);
+        let c_path = project.root().join("main.c");
+        let exe_path = project.root().join("main");
+
+        let compile = Command::new("cc")
+            .args(["-std=c99", "-Wall", "-Werror", "-Wno-unused-function", "-o"])
+            .arg(&exe_path)
+            .arg(&c_path)
+            .output()
+            .expect("a C compiler is installed");
+        assert!(compile.status.success(), "{}\n{}", String::from_utf8_lossy(&compile.stderr), c);
+
+        let output = Command::new(&exe_path).output().unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_compile_and_run() {
+        let output = run(r#"
+struct Point { x: i64, y: i64 }
+type Path = [Point; 3];
+const SCALE: i64 = 10;
+const LABEL: string = "total";
+
+fn fib(n: u32) -> u32 {
+    if n < 2 { return n; }
+    return fib(n - 1) + fib(n - 2);
+}
+
+fn length(path: Path) -> i64 {
+    let total: i64 = 0;
+    let i: u64 = 0;
+    while true {
+        if i == 3 { break; }
+        let p = path[i];
+        total = total + p.x * SCALE + p.y;
+        i = i + 1;
+    }
+    return total;
+}
+
+fn main() {
+    let path: Path = [.Point { x: 1, y: 2 }, .Point { x: 3, y: 4 }, .Point { x: 5, y: 6 }];
+    print(LABEL);
+    print(length(path));
+    print(fib(10));
+
+    let x = 1;
+    let x = x + 1;
+    { let x = 40; print(x + 2); }
+    print(x);
+
+    let half: f64 = 7 / 2.0;
+    print(half);
+    print(!(x == 2));
+    if "a" == "b" { print("equal"); } else if x > 1 { print("else if"); } else { print("else"); }
+}
+"#);
+        assert_eq!(output, "total\n102\n55\n42\n2\n3.5\nfalse\nelse if\n");
+    }
+
+    #[test]
+    fn test_wrapping_and_floats() {
+        let output = run(r#"
+fn main() {
+    let x: i32 = 2147483647;
+    let y: i32 = x + 1;
+    print(y);
+    print(-y);
+    let z: u32 = 0;
+    print(z - 1);
+    print(10.0 / 3.0);
+    print(0.1 + 0.2);
+    print(-89.7 - 0.00000000000001);
+    print(1.0 / 0.0);
+    print(100000000000000000000.0);
+    print(0.000001);
+}
+"#);
+        assert_eq!(output, "-2147483648\n-2147483648\n4294967295\n3.3333333333333335\n0.30000000000000004\n-89.70000000000002\ninf\n100000000000000000000\n0.000001\n");
+    }
+
+    #[test]
+    fn test_main_exit_code() {
+        let bump = Bump::new();
+        let mut parser = Parser::new("fn main() -> i32 { return 3; }", &bump);
+        let root = bump.alloc(parser.parse().tree);
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        let c = generate_c(TopDeclList::cast(root), &checker).unwrap();
+        assert!(c.contains("return (int)hz_main();"));
+    }
+}
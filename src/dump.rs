@@ -0,0 +1,157 @@
+//! Readable dumps of the syntax tree, for reviewing what the parser built
+//!
+//! The S-expression form writes a node as `(Kind children...)`, an invalid
+//! one as `(Kind! ...)`, a token as `Tag:"value"` and a null node or an
+//! empty token as `_`. The Graphviz form draws the same tree top down.
+
+use std::fmt::Write;
+
+use crate::ast2::{Node, NodeChild};
+use crate::token::Token;
+
+/// Nodes that fit in this many columns are written on a single line
+const WIDTH: usize = 80;
+
+pub fn sexpr(node: &Node) -> String {
+    let mut out = String::new();
+    write_sexpr(&mut out, node, 0);
+    out.push('\n');
+    out
+}
+
+fn write_sexpr(out: &mut String, node: &Node, indent: usize) {
+    let flat = flat_sexpr(node);
+    if indent + flat.len() <= WIDTH || node.children().is_empty() {
+        out.push_str(&flat);
+        return;
+    }
+    write!(out, "({}", kind(node)).unwrap();
+    for child in node.children() {
+        write!(out, "\n{:indent$}", "", indent = indent + 2).unwrap();
+        match child {
+            NodeChild::Node(child) => write_sexpr(out, child, indent + 2),
+            NodeChild::Token(token) => out.push_str(&token_sexpr(token)),
+        }
+    }
+    out.push(')');
+}
+
+fn flat_sexpr(node: &Node) -> String {
+    if node.is_null() {
+        return "_".to_string();
+    }
+    let mut out = format!("({}", kind(node));
+    for child in node.children() {
+        out.push(' ');
+        match child {
+            NodeChild::Node(child) => out += &flat_sexpr(child),
+            NodeChild::Token(token) => out += &token_sexpr(token),
+        }
+    }
+    out.push(')');
+    out
+}
+
+fn token_sexpr(token: &Token) -> String {
+    match token.is_empty() {
+        true => "_".to_string(),
+        false => format!("{:?}:{:?}", token.tag, token.value),
+    }
+}
+
+fn kind(node: &Node) -> String {
+    format!("{:?}{}", node.kind.0, if node.is_invalid() { "!" } else { "" })
+}
+
+/// A Graphviz digraph of the tree, with invalid nodes filled in red, tokens
+/// in boxes and null nodes and empty tokens dashed
+pub fn dot(node: &Node) -> String {
+    let mut out = String::from("digraph tree {\n    node [fontname=\"monospace\"];\n");
+    let mut next = 0;
+    write_dot(&mut out, node, &mut next);
+    out.push_str("}\n");
+    out
+}
+
+/// Writes the node and its subtree, returning the id of the node
+fn write_dot(out: &mut String, node: &Node, next: &mut usize) -> usize {
+    let id = *next;
+    *next += 1;
+    let style = match () {
+        _ if node.is_invalid() => ", style=filled, fillcolor=\"#f4b6b6\", color=red",
+        _ if node.is_null() => ", style=dashed",
+        _ => "",
+    };
+    writeln!(out, "    n{} [label=\"{}\"{}];", id, escape(&format!("{:?}", node.kind.0)), style).unwrap();
+    for child in node.children() {
+        let child = match child {
+            NodeChild::Node(child) => write_dot(out, child, next),
+            NodeChild::Token(token) => {
+                let child = *next;
+                *next += 1;
+                match token.is_empty() {
+                    true => writeln!(out, "    n{} [label=\"\", shape=box, style=dashed];", child),
+                    false => writeln!(out, "    n{} [label=\"{:?}\\n{}\", shape=box];", child, token.tag, escape(token.value)),
+                }
+                .unwrap();
+                child
+            }
+        };
+        writeln!(out, "    n{} -> n{};", id, child).unwrap();
+    }
+    id
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::{dot, sexpr};
+    use crate::parser3::Parser;
+
+    #[test]
+    fn test_sexpr() {
+        let bump = Bump::new();
+        let tree = Parser::new("fn f(a: i64) { let b = a + 1; print(\"b is\", b); }\nfn g() { h(1 +); }", &bump).parse().tree;
+        assert_eq!(sexpr(&tree), r#"(TopDeclList
+  (FnDef
+    Ident:"f"
+    (ParamList (Param Ident:"a" Ident:"i64"))
+    (BlockExpr
+      (StmtList
+        (VarDecl Ident:"b" _ (Infix Ident:"a" Plus:"+" Number:"1"))
+        (ExprStmt
+          (CallExpr Ident:"print" (ArgList String:"\"b is\"" Ident:"b")))))
+    _
+    _)
+  (FnDef
+    Ident:"g"
+    _
+    (BlockExpr
+      (StmtList
+        (ExprStmt!
+          (CallExpr! Ident:"h" (ArgList! (Infix! Number:"1" Plus:"+")))
+          RParen:")"
+          Semicolon:";")))
+    _
+    _))
+"#);
+    }
+
+    #[test]
+    fn test_dot() {
+        let bump = Bump::new();
+        let tree = Parser::new("fn f() { print(\"a\\n\"); 1 +; }", &bump).parse().tree;
+        let dot = dot(&tree);
+        assert!(dot.starts_with("digraph tree {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("[label=\"String\\n\\\"a\\\\n\\\"\", shape=box];"));
+        assert!(dot.contains("[label=\"ExprStmt\", style=filled, fillcolor=\"#f4b6b6\", color=red];"));
+        assert!(dot.contains("[label=\"\", shape=box, style=dashed];"));
+        assert_eq!(dot.matches(" -> ").count() + 1, dot.matches("[label=").count());
+    }
+}
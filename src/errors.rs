@@ -1,24 +1,39 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::ast2::Node;
 use crate::token::{Tag, Token};
 
 #[derive(Debug, PartialEq)]
 pub enum ParseErrorKind {
     ExpectedSemi,
-    ExpectedLBrace,
-    ExpectedBlock,
     UnexpectedEOF,
-    ExpectedRBrace,
 
     Expected(Tag),
 
     // Import related errors
 
     MalformedImportPath,
-    MissingImportDelimeter,
 
     // Struct related errors
 
     MissingFieldDelimeter,
 
+    // Enum related errors
+
+    MissingVariantDelimeter,
+
+    /// A token that cannot start a declaration at the top level
+    ExpectedDecl,
+
+    /// An opening delimiter that is never closed
+    UnclosedDelimiter,
+    /// A closing delimiter without an opening one
+    UnmatchedDelimiter,
+
+    /// `pub` before a declaration that cannot be exported
+    MisplacedVisibility,
+
     // Expr related errors
 
     ExpectedExpr,
@@ -34,14 +49,128 @@ pub enum ParseErrorKind {
     ExpectedFunctionParameters,
     ParamIncomplete,
     ExpectedColon,
-    ExpectedReturnType,
-
-    ExpectedVarDeclSeparator,
 
     ExpectedType,
 }
 
-#[derive(Debug)]
+impl ParseErrorKind {
+    /// Stable code of the error, explained by `haze explain`
+    ///
+    /// Codes are never reused or renumbered, those of removed variants are
+    /// left unused. Type errors start at `E0100`.
+    pub fn code(&self) -> &'static str {
+        use ParseErrorKind::*;
+        match self {
+            ExpectedSemi => "E0001",
+            UnexpectedEOF => "E0004",
+            Expected(_) => "E0006",
+            MalformedImportPath => "E0007",
+            MissingFieldDelimeter => "E0009",
+            MissingVariantDelimeter => "E0010",
+            ExpectedDecl => "E0011",
+            UnclosedDelimiter => "E0012",
+            UnmatchedDelimiter => "E0013",
+            MisplacedVisibility => "E0014",
+            ExpectedExpr => "E0015",
+            ExpectedOperator => "E0016",
+            BlockExprDisallowed => "E0017",
+            ExpectedArrayDelimeter => "E0018",
+            ExpectedIfOrBlock => "E0019",
+            ExpectedExprOrSemi => "E0020",
+            ExpectedFunctionParameters => "E0021",
+            ParamIncomplete => "E0022",
+            ExpectedColon => "E0023",
+            ExpectedType => "E0026",
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseErrorKind::*;
+        match self {
+            ExpectedSemi => write!(f, "expected `;`"),
+            UnexpectedEOF => write!(f, "unexpected end of file"),
+            Expected(tag) => write!(f, "expected {}", describe(*tag)),
+            MalformedImportPath => write!(f, "malformed import path"),
+            MissingFieldDelimeter => write!(f, "missing `,` between fields"),
+            MissingVariantDelimeter => write!(f, "missing `,` between variants"),
+            ExpectedDecl => write!(f, "expected a declaration"),
+            UnclosedDelimiter => write!(f, "unclosed delimiter"),
+            UnmatchedDelimiter => write!(f, "unmatched delimiter"),
+            MisplacedVisibility => write!(f, "only declarations that can be exported may be `pub`"),
+            ExpectedExpr => write!(f, "expected an expression"),
+            ExpectedOperator => write!(f, "expected an operator"),
+            BlockExprDisallowed => write!(f, "block not allowed here"),
+            ExpectedArrayDelimeter => write!(f, "expected `,` or `]`"),
+            ExpectedIfOrBlock => write!(f, "expected `if` or a block after `else`"),
+            ExpectedExprOrSemi => write!(f, "expected an expression or `;`"),
+            ExpectedFunctionParameters => write!(f, "expected function parameters"),
+            ParamIncomplete => write!(f, "parameter is missing its type"),
+            ExpectedColon => write!(f, "expected `:`"),
+            ExpectedType => write!(f, "expected a type"),
+        }
+    }
+}
+
+/// How a message names a token of the kind
+fn describe(tag: Tag) -> &'static str {
+    use Tag::*;
+    match tag {
+        Ident => "a name",
+        String => "a string",
+        Bool => "`true` or `false`",
+        Number => "a number",
+        UnexpectedEof => "the end of the file",
+        Invalid => "a valid token",
+        Plus => "`+`",
+        PlusEqual => "`+=`",
+        Minus => "`-`",
+        MinusEqual => "`-=`",
+        Slash => "`/`",
+        SlashEqual => "`/=`",
+        Asterisk => "`*`",
+        AsteriskEqual => "`*=`",
+        Dot => "`.`",
+        DotDot => "`..`",
+        Bang => "`!`",
+        BangEqual => "`!=`",
+        Equal => "`=`",
+        EqualEqual => "`==`",
+        Greater => "`>`",
+        GreaterEqual => "`>=`",
+        Less => "`<`",
+        LessEqual => "`<=`",
+        LParen => "`(`",
+        RParen => "`)`",
+        LBrace => "`{`",
+        RBrace => "`}`",
+        LBracket => "`[`",
+        RBracket => "`]`",
+        Semicolon => "`;`",
+        Colon => "`:`",
+        Comma => "`,`",
+        Arrow => "`->`",
+        Fn => "`fn`",
+        If => "`if`",
+        Else => "`else`",
+        Return => "`return`",
+        While => "`while`",
+        For => "`for`",
+        Let => "`let`",
+        Break => "`break`",
+        Continue => "`continue`",
+        Module => "`module`",
+        Struct => "`struct`",
+        Enum => "`enum`",
+        Import => "`import`",
+        Type => "`type`",
+        Const => "`const`",
+        Pub => "`pub`",
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct Loc {
     pub start: u32,
     pub len: u32,
@@ -54,12 +183,60 @@ impl Loc {
     }
 
     pub fn from_token<'s>(token: Token<'s>) -> Loc { Loc::new(token.pos, token.value.len() as u32, token.line) }
+
+    /// Line and column of the start in `source`, both from 1, the column
+    /// counted in characters
+    pub fn line_column(&self, source: &str) -> (u32, usize) {
+        let start = (self.start as usize).min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[..line_start].matches('\n').count() + 1;
+        (line as u32, source[line_start..start].chars().count() + 1)
+    }
+
+    /// Location spanning every token of a node
+    pub fn from_node(node: &Node) -> Loc {
+        match (node.first_token(), node.last_token()) {
+            (Some(first), Some(last)) => {
+                let end = last.pos + last.value.len() as u32;
+                Loc::new(first.pos, end - first.pos, first.line)
+            }
+            _ => Loc::new(0, 0, 0),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct ParseError {
     pub kind: ParseErrorKind,
-    pub location: Loc
+    pub location: Loc,
+    /// A machine-applicable edit that resolves the error
+    pub fix: Option<TextEdit>,
+}
+
+/// Applies the fixes of `errors` in source order, returning the fixed source
+/// and how many were applied
+///
+/// A fix overlapping or touching one before it is left out, as errors
+/// cascading from the same spot all suggest something there. Parsing the
+/// fixed source again suggests it anew if it still applies.
+pub fn apply_fixes<'e>(source: &str, errors: impl IntoIterator<Item = &'e ParseError>) -> (String, usize) {
+    let mut fixes: Vec<&TextEdit> = errors.into_iter().filter_map(|err| err.fix.as_ref()).collect();
+    fixes.sort_by_key(|fix| fix.range.start);
+
+    let mut fixed = String::with_capacity(source.len());
+    let mut last = None;
+    let mut applied = 0;
+    for fix in fixes {
+        if last.is_some_and(|last| fix.range.start <= last) {
+            continue;
+        }
+        fixed.push_str(&source[last.unwrap_or(0) as usize..fix.range.start as usize]);
+        fixed.push_str(&fix.text);
+        last = Some(fix.range.end);
+        applied += 1;
+    }
+    fixed.push_str(&source[last.unwrap_or(0) as usize..]);
+    (fixed, applied)
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -84,4 +261,23 @@ pub enum ParsingError {
     Terminal,
 
 
-}
\ No newline at end of file
+}
+
+/// Replacement of a byte range of the source
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub range: Range<u32>,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<u32>, text: impl Into<String>) -> Self {
+        Self { range, text: text.into() }
+    }
+
+    /// The source after the edit
+    pub fn apply(&self, source: &str) -> String {
+        let Range { start, end } = self.range;
+        format!("{}{}{}", &source[..start as usize], self.text, &source[end as usize..])
+    }
+}
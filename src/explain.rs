@@ -0,0 +1,724 @@
+//! Long-form explanations of diagnostic codes
+//!
+//! Every parse, type, name resolution and module loading error has a stable
+//! code, see [`ParseErrorKind::code`](crate::errors::ParseErrorKind::code),
+//! [`TypeErrorKind::code`](crate::typecheck::check::TypeErrorKind::code),
+//! [`ResolveErrorKind::code`](crate::typecheck::resolve::ResolveErrorKind::code)
+//! and [`LoadErrorKind::code`](crate::loader::LoadErrorKind::code).
+//! `haze explain <code>` prints the entry for one of them. The examples are
+//! checked by the tests: the erroneous one must report the code and the
+//! corrected one must compile without errors.
+//!
+//! Examples of errors between modules are projects, written as a `// path`
+//! line before the contents of each file. The first file is the entry.
+
+use std::fmt::{self, Display};
+
+pub struct Explanation {
+    pub code: &'static str,
+    /// One line summary of the error
+    pub title: &'static str,
+    pub text: &'static str,
+    /// Code reporting the error, empty for errors no source can cause
+    pub wrong: &'static str,
+    pub correct: &'static str,
+}
+
+/// Finds the explanation of a code, ignoring its case
+pub fn lookup(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS.iter().find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {}\n", self.code, self.title)?;
+        writeln!(f, "{}", self.text)?;
+        if !self.wrong.is_empty() {
+            writeln!(f, "\nErroneous code example:\n")?;
+            write_code(f, self.wrong)?;
+            writeln!(f, "\nCorrected:\n")?;
+            write_code(f, self.correct)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_code(f: &mut fmt::Formatter<'_>, code: &str) -> fmt::Result {
+    for line in code.lines() {
+        match line.is_empty() {
+            true => writeln!(f)?,
+            false => writeln!(f, "    {}", line)?,
+        }
+    }
+    Ok(())
+}
+
+/// Every explanation, ordered by code
+pub static EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0001",
+        title: "expected `;`",
+        text: "A statement or declaration was not terminated with a semicolon. Statements
+other than `if`, `while` and block expressions end with `;`, as do constant,
+type alias and import declarations.",
+        wrong: "fn main() {
+    let x = 1
+    print(x);
+}",
+        correct: "fn main() {
+    let x = 1;
+    print(x);
+}",
+    },
+    Explanation {
+        code: "E0004",
+        title: "unexpected end of file",
+        text: "The file ended in the middle of a construct that needs more tokens to be
+complete, e.g. a declaration that was cut off.",
+        wrong: "struct Point { x: f64",
+        correct: "struct Point { x: f64 }",
+    },
+    Explanation {
+        code: "E0006",
+        title: "expected a specific token",
+        text: "The grammar only allows one token at this point, e.g. the `:` between
+the name and the type of a constant. The message names the expected token.",
+        wrong: "const LIMIT i64 = 10;",
+        correct: "const LIMIT: i64 = 10;",
+    },
+    Explanation {
+        code: "E0007",
+        title: "malformed import path",
+        text: "An import path is a list of names separated by `.` and ends with `;`.",
+        wrong: "import std..io;",
+        correct: "import std.io;",
+    },
+    Explanation {
+        code: "E0009",
+        title: "missing `,` between fields",
+        text: "The fields of a struct declaration or a struct literal are separated by
+commas.",
+        wrong: "struct Point { x: f64 y: f64 }",
+        correct: "struct Point { x: f64, y: f64 }",
+    },
+    Explanation {
+        code: "E0010",
+        title: "missing `,` between variants",
+        text: "The variants of an enum declaration are separated by commas.",
+        wrong: "enum Shape { Circle(f64) Square(f64) }",
+        correct: "enum Shape { Circle(f64), Square(f64) }",
+    },
+    Explanation {
+        code: "E0011",
+        title: "expected a declaration",
+        text: "Only declarations can appear at the top level of a file: functions,
+structs, enums, type aliases, constants, modules and imports. Statements go
+inside of a function.",
+        wrong: "let limit = 10;",
+        correct: "const LIMIT: i64 = 10;",
+    },
+    Explanation {
+        code: "E0012",
+        title: "unclosed delimiter",
+        text: "A `(`, `[` or `{` is never closed. The error points at the opening
+delimiter. A declaration inside of a block also closes it, as declarations
+can't be nested.",
+        wrong: "fn main() {
+    print((1 + 2);
+}",
+        correct: "fn main() {
+    print((1 + 2));
+}",
+    },
+    Explanation {
+        code: "E0013",
+        title: "unmatched delimiter",
+        text: "A `)`, `]` or `}` doesn't close any open delimiter, either because there
+is one too many or because the innermost open one is of a different kind.",
+        wrong: "fn main() {
+    print(1));
+}",
+        correct: "fn main() {
+    print(1);
+}",
+    },
+    Explanation {
+        code: "E0014",
+        title: "misplaced `pub`",
+        text: "Modules and imports can't be exported, so they can't be marked `pub`.",
+        wrong: "pub import std.io;",
+        correct: "import std.io;",
+    },
+    Explanation {
+        code: "E0015",
+        title: "expected an expression",
+        text: "The token can't start an expression, e.g. a binary operator without a
+left operand.",
+        wrong: "fn main() {
+    let x = * 2;
+}",
+        correct: "fn main() {
+    let x = 1 * 2;
+}",
+    },
+    Explanation {
+        code: "E0016",
+        title: "expected an operator",
+        text: "Two expressions follow each other without an operator between them.",
+        wrong: "fn main() {
+    let x = 1 2;
+}",
+        correct: "fn main() {
+    let x = 1 + 2;
+}",
+    },
+    Explanation {
+        code: "E0017",
+        title: "block not allowed here",
+        text: "The condition of an `if` or `while` can't start with a block, the block
+would be taken as the body.",
+        wrong: "fn main() {
+    if { true } { print(1); }
+}",
+        correct: "fn main() {
+    if true { print(1); }
+}",
+    },
+    Explanation {
+        code: "E0018",
+        title: "missing `,` between elements",
+        text: "The elements of an array literal are separated by commas. Array types
+use `;` between the element type and the length, literals don't.",
+        wrong: "fn main() {
+    let a = [1; 2];
+}",
+        correct: "fn main() {
+    let a = [1, 2];
+}",
+    },
+    Explanation {
+        code: "E0019",
+        title: "expected `if` or a block after `else`",
+        text: "`else` is followed either by another `if` or by a block.",
+        wrong: "fn main() {
+    if true { print(1); } else print(2);
+}",
+        correct: "fn main() {
+    if true { print(1); } else { print(2); }
+}",
+    },
+    Explanation {
+        code: "E0020",
+        title: "expected an expression or `;`",
+        text: "The file ended after `return` or `break`, which are followed by either
+a value or the end of the statement.",
+        wrong: "fn main() {
+    return",
+        correct: "fn main() {
+    return;
+}",
+    },
+    Explanation {
+        code: "E0021",
+        title: "expected function parameters",
+        text: "A function name is followed by its parameter list in parentheses, which
+may be empty.",
+        wrong: "fn main {
+    print(1);
+}",
+        correct: "fn main() {
+    print(1);
+}",
+    },
+    Explanation {
+        code: "E0022",
+        title: "incomplete parameter",
+        text: "Every parameter of a function has a type, given after its name and a
+`:`.",
+        wrong: "fn add(a, b: i64) -> i64 {
+    return a + b;
+}",
+        correct: "fn add(a: i64, b: i64) -> i64 {
+    return a + b;
+}",
+    },
+    Explanation {
+        code: "E0023",
+        title: "expected `:`",
+        text: "The name of a parameter is separated from its type by a `:`.",
+        wrong: "fn add(a i64, b: i64) -> i64 {
+    return a + b;
+}",
+        correct: "fn add(a: i64, b: i64) -> i64 {
+    return a + b;
+}",
+    },
+    Explanation {
+        code: "E0026",
+        title: "expected a type",
+        text: "A type is a name such as `i64`, an array type such as `[f64; 3]`, or a
+type in parentheses.",
+        wrong: "fn square(n: 1) -> i64 {
+    return n * n;
+}",
+        correct: "fn square(n: i64) -> i64 {
+    return n * n;
+}",
+    },
+    Explanation {
+        code: "E0100",
+        title: "mismatched types",
+        text: "An expression doesn't have the type its context requires, e.g. the
+declared type of a variable or the type of a parameter. Integers aren't
+converted implicitly between `i32`, `i64` and `f64`.",
+        wrong: "fn main() {
+    let x: i64 = \"one\";
+}",
+        correct: "fn main() {
+    let x: i64 = 1;
+}",
+    },
+    Explanation {
+        code: "E0101",
+        title: "undefined name",
+        text: "The name is not a variable in scope or a declaration of the program.
+Variables are only visible after their `let` and inside of the block that
+declares them.",
+        wrong: "fn main() {
+    print(count);
+}",
+        correct: "fn main() {
+    let count = 1;
+    print(count);
+}",
+    },
+    Explanation {
+        code: "E0102",
+        title: "unknown type",
+        text: "The name is not a builtin type, struct or type alias. The integer types
+are `i32` and `i64`.",
+        wrong: "fn main() {
+    let x: int = 1;
+}",
+        correct: "fn main() {
+    let x: i64 = 1;
+}",
+    },
+    Explanation {
+        code: "E0103",
+        title: "struct contains itself",
+        text: "A struct can't contain a field of its own type, directly or through
+other structs or arrays, as it would have no finite size. Refer to other
+values by their index in an array instead.",
+        wrong: "struct List { value: i64, next: List }",
+        correct: "struct List { value: i64, next: i64 }",
+    },
+    Explanation {
+        code: "E0104",
+        title: "name is already defined",
+        text: "Two declarations of a program, or two fields of a struct, have the same
+name.",
+        wrong: "fn f() {}
+fn f() {}",
+        correct: "fn f() {}
+fn g() {}",
+    },
+    Explanation {
+        code: "E0105",
+        title: "expected a value",
+        text: "A type or a builtin function was used as a value. Builtins can only be
+called.",
+        wrong: "struct Point { x: f64 }
+
+fn main() {
+    let p = Point;
+}",
+        correct: "struct Point { x: f64 }
+
+fn main() {
+    let p = .Point { x: 0.0 };
+}",
+    },
+    Explanation {
+        code: "E0106",
+        title: "not a function",
+        text: "Only functions and builtins can be called.",
+        wrong: "fn main() {
+    let x = 1;
+    x();
+}",
+        correct: "fn x() {}
+
+fn main() {
+    x();
+}",
+    },
+    Explanation {
+        code: "E0107",
+        title: "wrong number of arguments",
+        text: "A call passes a different number of arguments than the function has
+parameters. There are no default arguments.",
+        wrong: "fn add(a: i64, b: i64) -> i64 {
+    return a + b;
+}
+
+fn main() {
+    print(add(1));
+}",
+        correct: "fn add(a: i64, b: i64) -> i64 {
+    return a + b;
+}
+
+fn main() {
+    print(add(1, 2));
+}",
+    },
+    Explanation {
+        code: "E0108",
+        title: "invalid operand",
+        text: "The operator is not defined for the type of its operand, e.g. arithmetic
+on booleans or strings. Array indices are integers.",
+        wrong: "fn main() {
+    print(true + 1);
+}",
+        correct: "fn main() {
+    print(1 + 1);
+}",
+    },
+    Explanation {
+        code: "E0109",
+        title: "value cannot be indexed",
+        text: "Only arrays can be indexed.",
+        wrong: "fn main() {
+    let x = 1;
+    print(x[0]);
+}",
+        correct: "fn main() {
+    let x = [1];
+    print(x[0]);
+}",
+    },
+    Explanation {
+        code: "E0110",
+        title: "no such field",
+        text: "The struct has no field of that name, or the value accessed isn't a
+struct.",
+        wrong: "struct Point { x: f64, y: f64 }
+
+fn main() {
+    let p = .Point { x: 1.0, y: 2.0 };
+    print(p.z);
+}",
+        correct: "struct Point { x: f64, y: f64 }
+
+fn main() {
+    let p = .Point { x: 1.0, y: 2.0 };
+    print(p.y);
+}",
+    },
+    Explanation {
+        code: "E0111",
+        title: "missing field",
+        text: "A struct literal gives a value to every field of the struct.",
+        wrong: "struct Point { x: f64, y: f64 }
+
+fn main() {
+    let p = .Point { x: 1.0 };
+}",
+        correct: "struct Point { x: f64, y: f64 }
+
+fn main() {
+    let p = .Point { x: 1.0, y: 0.0 };
+}",
+    },
+    Explanation {
+        code: "E0112",
+        title: "cannot assign",
+        text: "Only variables can be assigned to, constants and functions can't. Neither
+can fields of structs or elements of arrays one at a time, the whole value
+is built again instead.",
+        wrong: "const LIMIT: i64 = 10;
+
+fn main() {
+    LIMIT = 20;
+}",
+        correct: "fn main() {
+    let limit = 10;
+    limit = 20;
+}",
+    },
+    Explanation {
+        code: "E0113",
+        title: "`break` or `continue` outside of a loop",
+        text: "`break` and `continue` can only be used inside of a `while` loop.",
+        wrong: "fn main() {
+    break;
+}",
+        correct: "fn main() {
+    while true {
+        break;
+    }
+}",
+    },
+    Explanation {
+        code: "E0114",
+        title: "missing return",
+        text: "A function with a return type must return a value on every path through
+its body. An `if` without an `else` may not run at all.",
+        wrong: "fn sign(n: i64) -> i64 {
+    if n < 0 { return -1; }
+}",
+        correct: "fn sign(n: i64) -> i64 {
+    if n < 0 { return -1; }
+    return 1;
+}",
+    },
+    Explanation {
+        code: "E0115",
+        title: "not supported by the type checker yet",
+        text: "The construct parses, but the type checker doesn't handle it yet, e.g.
+enums.",
+        wrong: "enum Shape { Circle(f64), Square(f64) }",
+        correct: "struct Circle { radius: f64 }",
+    },
+    Explanation {
+        code: "E0116",
+        title: "literal out of range",
+        text: "A number literal must fit the type it takes, which is `i32` for an integer
+without an annotation.",
+        wrong: "fn main() {
+    let big = 3000000000;
+}",
+        correct: "fn main() {
+    let big: i64 = 3000000000;
+}",
+    },
+    Explanation {
+        code: "E0200",
+        title: "private item",
+        text: "An item of another module can only be used if it is declared `pub`. The
+error points at the use, and the note at the declaration.",
+        wrong: "// main.hz
+import shapes;
+
+fn main() {
+    print(secret());
+}
+
+// shapes.hz
+fn secret() -> i64 {
+    return 42;
+}",
+        correct: "// main.hz
+import shapes;
+
+fn main() {
+    print(secret());
+}
+
+// shapes.hz
+pub fn secret() -> i64 {
+    return 42;
+}",
+    },
+    Explanation {
+        code: "E0201",
+        title: "private field",
+        text: "A field of a struct declared in another module can only be read, or given
+in a struct literal, if the field itself is `pub`. A `pub` struct doesn't make
+its fields public.",
+        wrong: "// main.hz
+import shapes;
+
+fn main() {
+    let s = .Square { side: 2 };
+    print(s.side);
+}
+
+// shapes.hz
+pub struct Square { side: i64 }",
+        correct: "// main.hz
+import shapes;
+
+fn main() {
+    let s = .Square { side: 2 };
+    print(s.side);
+}
+
+// shapes.hz
+pub struct Square { pub side: i64 }",
+    },
+    Explanation {
+        code: "E0300",
+        title: "module not found",
+        text: "An import path names a file relative to the project root, `a.b` being
+`a/b.hz`, or else an inline `module b` declared in `a.hz`. Neither exists.",
+        wrong: "// main.hz
+import geometry.shapes;
+
+fn main() {}",
+        correct: "// main.hz
+import geometry.shapes;
+
+fn main() {}
+
+// geometry/shapes.hz
+pub struct Square { pub side: i64 }",
+    },
+    Explanation {
+        code: "E0301",
+        title: "cannot read a file",
+        text: "The file of a module exists but could not be read, e.g. because of its
+permissions or because it is a directory.",
+        wrong: "",
+        correct: "",
+    },
+    Explanation {
+        code: "E0302",
+        title: "import cycle",
+        text: "Modules import each other, directly or through other modules. The items
+they share go in a module that both import instead.",
+        wrong: "// main.hz
+import shapes;
+
+pub const UNIT: i64 = 1;
+
+fn main() {}
+
+// shapes.hz
+import main;
+
+pub struct Square { pub side: i64 }",
+        correct: "// main.hz
+import shapes;
+import units;
+
+fn main() {}
+
+// shapes.hz
+import units;
+
+pub struct Square { pub side: i64 }
+
+// units.hz
+pub const UNIT: i64 = 1;",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::{lookup, EXPLANATIONS};
+    use crate::ast2::{AstNode, TopDeclList};
+    use crate::loader::ModuleGraph;
+    use crate::parser3::Parser;
+    use crate::token::Tag;
+    use crate::typecheck::check::TypeChecker;
+    use crate::typecheck::resolve::resolve_graph;
+    use crate::utils::temp_project::TempProject;
+
+    /// Codes of the errors reported for a project, written as `// path`
+    /// lines each followed by the contents of the file
+    fn project_codes(example: &str) -> Vec<&'static str> {
+        let mut files: Vec<(&str, String)> = Vec::new();
+        for line in example.lines() {
+            match (line.strip_prefix("// "), files.last_mut()) {
+                (Some(path), _) => files.push((path, String::new())),
+                (None, Some((_, contents))) => {
+                    contents.push_str(line);
+                    contents.push('\n');
+                }
+                (None, None) => panic!("expected a `// path` line first"),
+            }
+        }
+        let files: Vec<_> = files.iter().map(|(path, contents)| (*path, contents.as_str())).collect();
+        let project = TempProject::new(&files);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        graph.load_entry(files[0].0).unwrap();
+
+        let mut codes: Vec<_> = graph.files().flat_map(|file| file.errors.iter().map(|err| err.kind.code())).collect();
+        codes.extend(graph.errors.iter().map(|err| err.kind.code()));
+        if codes.is_empty() {
+            codes.extend(resolve_graph(&graph).iter().map(|err| err.kind.code()));
+        }
+        codes
+    }
+
+    /// Codes of the errors reported for a program, parse errors first
+    fn codes(source: &str) -> Vec<&'static str> {
+        if source.starts_with("// ") {
+            return project_codes(source);
+        }
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        let mut codes: Vec<_> = parser.errors.iter().map(|err| err.kind.code()).collect();
+        if codes.is_empty() {
+            let mut checker = TypeChecker::new();
+            checker.check_program(TopDeclList::cast(root));
+            codes.extend(checker.errors.iter().map(|err| err.kind.code()));
+        }
+        codes
+    }
+
+    #[test]
+    fn test_codes_are_ordered() {
+        for pair in EXPLANATIONS.windows(2) {
+            assert!(pair[0].code < pair[1].code, "{} before {}", pair[0].code, pair[1].code);
+        }
+        assert_eq!(lookup("e0012").map(|explanation| explanation.code), Some("E0012"));
+        assert!(lookup("E9999").is_none());
+    }
+
+    #[test]
+    fn test_every_error_is_explained() {
+        use crate::errors::ParseErrorKind::*;
+        use crate::typecheck::check::TypeErrorKind::*;
+        use crate::typecheck::Type;
+        use crate::loader::LoadErrorKind::*;
+        use crate::typecheck::resolve::ResolveErrorKind;
+        let parse = [
+            ExpectedSemi, UnexpectedEOF, Expected(Tag::Ident), MalformedImportPath, MissingFieldDelimeter,
+            MissingVariantDelimeter, ExpectedDecl, UnclosedDelimiter, UnmatchedDelimiter, MisplacedVisibility,
+            ExpectedExpr, ExpectedOperator, BlockExprDisallowed, ExpectedArrayDelimeter, ExpectedIfOrBlock,
+            ExpectedExprOrSemi, ExpectedFunctionParameters, ParamIncomplete, ExpectedColon, ExpectedType,
+        ];
+        let types = [
+            Mismatch { expected: Type::I32, found: Type::Bool }, UndefinedName, UnknownType, RecursiveType,
+            DuplicateDefinition, NotAValue, NotCallable, ArgumentCount { expected: 1, found: 2 },
+            InvalidOperand(Type::Bool), NotIndexable(Type::I32), UnknownField, MissingField("x".into()),
+            NotAssignable, BreakOutsideLoop, MissingReturn, Unsupported, LiteralOutOfRange(Type::I32),
+        ];
+        let resolve = [ResolveErrorKind::UndefinedName, ResolveErrorKind::PrivateItem, ResolveErrorKind::PrivateField];
+        let load = [
+            ModuleNotFound("a".into()),
+            Io("a.hz".into(), std::io::ErrorKind::NotFound.into()),
+            ImportCycle(Box::new(["a".into()])),
+        ];
+        let mut codes: Vec<_> = parse.iter().map(|kind| kind.code())
+            .chain(types.iter().map(|kind| kind.code()))
+            .chain(resolve.iter().map(|kind| kind.code()))
+            .chain(load.iter().map(|kind| kind.code()))
+            .collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes, EXPLANATIONS.iter().map(|explanation| explanation.code).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_examples() {
+        for explanation in EXPLANATIONS.iter().filter(|explanation| !explanation.wrong.is_empty()) {
+            let code = explanation.code;
+            assert!(codes(explanation.wrong).contains(&code), "{}: {:?}", code, codes(explanation.wrong));
+            // Parse errors are only looked for in the corrected code of parse errors
+            let correct = codes(explanation.correct);
+            if code < "E0100" {
+                assert!(correct.iter().all(|found| *found >= "E0100"), "{}: {:?}", code, correct);
+            } else {
+                assert!(correct.is_empty(), "{}: {:?}", code, correct);
+            }
+        }
+    }
+}
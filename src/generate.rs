@@ -0,0 +1,665 @@
+//! Random well-typed programs
+//!
+//! Statements, expressions and types take their shape from the productions
+//! of `spec/grammar_v1.ebnf`: each one picks among the alternatives of its
+//! rule, leaving out those that can't have the type the context expects.
+//! Programs only use types of [`Type`] the backends agree on: `bool`, `i32`,
+//! `i64`, `f64`, `string`, arrays and structs. They always type check and run
+//! to completion without a runtime error: functions only call the ones
+//! declared before them, loops count up to a small bound, divisors are
+//! non-zero literals and indexes are within bounds. Integer arithmetic may
+//! overflow, which every backend wraps. `main` prints its variables at the
+//! end, so running a program with two backends and comparing the output tests
+//! one against the other, as the tests below do for the VM, the tree walking
+//! evaluator and the C backend.
+//!
+//! The same [`Config`] always generates the same program.
+
+use std::fmt::Write;
+
+use crate::grammar::ebnf::Grammar;
+use crate::typecheck::Type;
+use crate::utils::{choices::Choices, rng::Rng};
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub seed: u64,
+    /// Functions besides `main`
+    pub functions: usize,
+    /// Most statements in a block
+    pub statements: usize,
+    /// Most nesting of blocks, of expressions and of types
+    pub depth: usize,
+    pub structs: bool,
+    pub arrays: bool,
+    pub loops: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { seed: 0, functions: 3, statements: 6, depth: 3, structs: true, arrays: true, loops: true }
+    }
+}
+
+/// Most times a loop runs
+const MAX_ITERATIONS: usize = 4;
+const STRINGS: &[&str] = &["\"\"", "\"a\"", "\"haze\"", "\"x y\""];
+
+struct Function {
+    name: String,
+    params: Vec<Type>,
+    ret: Type,
+}
+
+struct Local {
+    name: String,
+    typ: Type,
+    /// Loop counters are only assigned to by their loop
+    assignable: bool,
+}
+
+/// The alternatives the generator picks from, by the rule or terminal they
+/// start with
+struct Productions<'g> {
+    statements: Vec<&'g str>,
+    exprs: Vec<&'g str>,
+    types: Vec<&'g str>,
+    binary: Vec<&'g str>,
+    unary: Vec<&'g str>,
+}
+
+impl<'g> Productions<'g> {
+    fn new(grammar: &'g Grammar) -> Self {
+        let expressions = ["ExprStmt", "ExprMaybeSemi", "ExprSemi"];
+        Self {
+            statements: grammar.heads("Statement", &expressions),
+            exprs: grammar.heads("Expr", &expressions),
+            types: grammar.heads("Type", &[]),
+            binary: grammar.heads("BinOp", &[]),
+            unary: grammar.heads("UnaryOp", &[]),
+        }
+    }
+}
+
+/// The text of an expression, with what it takes to use it as an operand
+struct Expr {
+    text: String,
+    /// Made of number literals only, so it takes whatever numeric type its
+    /// context expects and defaults to `i32` without one
+    untyped: bool,
+    /// A literal, name, call, access or group, which needs no parentheses
+    atom: bool,
+}
+
+impl Expr {
+    fn atom(text: String) -> Expr {
+        Expr { text, untyped: false, atom: true }
+    }
+
+    fn operand(&self) -> String {
+        match self.atom {
+            true => self.text.clone(),
+            false => format!("({})", self.text),
+        }
+    }
+}
+
+pub fn generate(config: &Config) -> String {
+    let grammar = Grammar::v1();
+    let mut generator = Generator {
+        config,
+        productions: Productions::new(&grammar),
+        rng: Rng::new(config.seed),
+        structs: Vec::new(),
+        functions: Vec::new(),
+        scopes: Vec::new(),
+        locals: 0,
+        loop_depth: 0,
+        after_block: false,
+        ret: Type::Unit,
+        out: String::new(),
+        indent: 0,
+    };
+    generator.program();
+    generator.out
+}
+
+struct Generator<'c> {
+    config: &'c Config,
+    productions: Productions<'c>,
+    rng: Rng,
+    /// Declared structs, as `Type::Struct`
+    structs: Vec<Type>,
+    /// Declared functions, which the next ones may call
+    functions: Vec<Function>,
+    scopes: Vec<Vec<Local>>,
+    /// Locals named so far in the function, for fresh names
+    locals: usize,
+    loop_depth: usize,
+    /// The last statement ended with a block, which the parser would take
+    /// a block right after for the rest of an expression
+    after_block: bool,
+    /// Return type of the function being generated
+    ret: Type,
+    out: String,
+    indent: usize,
+}
+
+impl Generator<'_> {
+    fn program(&mut self) {
+        if self.config.structs {
+            for i in 0..1 + self.rng.below(3) {
+                self.struct_decl(format!("S{}", i));
+            }
+        }
+        for i in 0..self.config.functions {
+            self.function(format!("f{}", i));
+        }
+        self.main();
+    }
+
+    fn struct_decl(&mut self, name: String) {
+        let fields: Vec<(Box<str>, Type)> = (0..1 + self.rng.below(3))
+            .map(|i| (["a", "b", "c"][i].into(), self.any_type(self.config.depth.saturating_sub(1))))
+            .collect();
+        write!(self.out, "struct {} {{ ", name).unwrap();
+        for (i, (field, typ)) in fields.iter().enumerate() {
+            write!(self.out, "{}{}: {}", if i > 0 { ", " } else { "" }, field, typ).unwrap();
+        }
+        self.out.push_str(" }\n\n");
+        self.structs.push(Type::Struct(name.into(), fields.into()));
+    }
+
+    fn function(&mut self, name: String) {
+        let params: Vec<Type> = (0..self.rng.below(4)).map(|_| self.any_type(self.config.depth)).collect();
+        let ret = match self.rng.one_in(4) {
+            true => Type::Unit,
+            false => self.any_type(self.config.depth),
+        };
+
+        write!(self.out, "fn {}(", name).unwrap();
+        let mut scope = Vec::new();
+        for (i, typ) in params.iter().enumerate() {
+            write!(self.out, "{}p{}: {}", if i > 0 { ", " } else { "" }, i, typ).unwrap();
+            scope.push(Local { name: format!("p{}", i), typ: typ.clone(), assignable: true });
+        }
+        self.out.push(')');
+        if ret != Type::Unit {
+            write!(self.out, " -> {}", ret).unwrap();
+        }
+        self.out.push_str(" {\n");
+
+        self.scopes.push(scope);
+        self.locals = 0;
+        self.ret = ret.clone();
+        self.indent += 1;
+        self.statements(self.config.depth);
+        if ret != Type::Unit {
+            let value = self.expr(&ret, self.config.depth);
+            self.line(format!("return {};", value.text));
+        }
+        self.indent -= 1;
+        self.scopes.pop();
+        self.out.push_str("}\n\n");
+        self.functions.push(Function { name, params, ret });
+    }
+
+    fn main(&mut self) {
+        self.out.push_str("fn main() {\n");
+        self.scopes.push(Vec::new());
+        self.locals = 0;
+        self.ret = Type::Unit;
+        self.indent += 1;
+        for _ in 0..1 + self.rng.below(self.config.statements.max(1)) {
+            self.statement(self.config.depth);
+        }
+        let printable: Vec<String> = self.scopes.last().unwrap()
+            .iter()
+            .filter(|local| local.typ.is_primitive())
+            .map(|local| local.name.clone())
+            .collect();
+        for name in printable {
+            self.line(format!("print({});", name));
+        }
+        self.indent -= 1;
+        self.scopes.pop();
+        self.out.push_str("}\n");
+    }
+
+    fn line(&mut self, text: String) {
+        writeln!(self.out, "{:indent$}{}", "", text, indent = self.indent * 4).unwrap();
+    }
+
+    fn statements(&mut self, depth: usize) {
+        for _ in 0..self.rng.below(self.config.statements + 1) {
+            self.statement(depth);
+        }
+    }
+
+    /// A block of statements in a new scope, ending its line with `}`
+    fn block(&mut self, depth: usize, first: Option<String>) {
+        self.scopes.push(Vec::new());
+        self.indent += 1;
+        self.after_block = false;
+        if let Some(first) = first {
+            self.line(first);
+        }
+        self.statements(depth);
+        self.indent -= 1;
+        self.scopes.pop();
+        write!(self.out, "{:indent$}}}", "", indent = self.indent * 4).unwrap();
+    }
+
+    fn statement(&mut self, depth: usize) {
+        let productions: Vec<&str> = self.productions.statements
+            .iter()
+            .copied()
+            .filter(|production| self.is_statement(production, depth))
+            .collect();
+        let production = *self.rng.pick(&productions);
+        match production {
+            "VarDecl" => {
+                let typ = self.any_type(self.config.depth);
+                let value = self.expr(&typ, depth);
+                let name = self.fresh();
+                // Integers take their type from an annotation
+                match mentions_integer(&typ) || self.rng.one_in(2) {
+                    true => self.line(format!("let {}: {} = {};", name, typ, value.text)),
+                    false => self.line(format!("let {} = {};", name, value.text)),
+                }
+                self.scopes.last_mut().unwrap().push(Local { name, typ, assignable: true });
+            }
+            // The one infix operator of a statement is `=`
+            "InfixExpr" => {
+                let locals: Vec<(String, Type)> =
+                    self.assignable().map(|local| (local.name.clone(), local.typ.clone())).collect();
+                let (name, typ) = self.rng.pick(&locals).clone();
+                let mut value = self.expr(&typ, depth);
+                if value.text == name {
+                    value = self.literal(&typ, depth);
+                }
+                self.line(format!("{} = {};", name, value.text));
+            }
+            "CallExpr" => match self.functions.is_empty() || self.rng.one_in(2) {
+                true => {
+                    let typ = self.scalar_type();
+                    let value = self.expr(&typ, depth);
+                    self.line(format!("print({});", value.text));
+                }
+                false => {
+                    let index = self.rng.below(self.functions.len());
+                    let call = self.call(index, depth);
+                    self.line(format!("{};", call));
+                }
+            },
+            "IfExpr" => {
+                let condition = self.expr(&Type::Bool, depth - 1);
+                writeln!(self.out, "{:indent$}if {} {{", "", condition.text, indent = self.indent * 4).unwrap();
+                self.block(depth - 1, None);
+                if self.rng.one_in(2) {
+                    self.out.push_str(" else {\n");
+                    self.block(depth - 1, None);
+                }
+                self.out.push('\n');
+            }
+            "WhileExpr" => {
+                let counter = self.fresh();
+                let bound = 1 + self.rng.below(MAX_ITERATIONS);
+                self.line(format!("let {}: i64 = 0;", counter));
+                self.line(format!("while {} < {} {{", counter, bound));
+                self.scopes.last_mut().unwrap().push(Local { name: counter.clone(), typ: Type::I64, assignable: false });
+                // Counting first lets `continue` skip the rest of the body
+                self.loop_depth += 1;
+                self.block(depth - 1, Some(format!("{0} = {0} + 1;", counter)));
+                self.loop_depth -= 1;
+                self.out.push('\n');
+            }
+            "BlockExpr" => {
+                self.line("{".to_string());
+                self.block(depth - 1, None);
+                self.out.push('\n');
+            }
+            // Jumps and returns are conditional, so the statements after
+            // them still run some of the time
+            jump @ ("break" | "continue") => {
+                let condition = self.expr(&Type::Bool, depth - 1);
+                self.line(format!("if {} {{ {}; }}", condition.text, jump));
+            }
+            "ReturnExpr" => {
+                let condition = self.expr(&Type::Bool, depth - 1);
+                let value = self.expr(&self.ret.clone(), depth - 1);
+                self.line(format!("if {} {{ return {}; }}", condition.text, value.text));
+            }
+            production => unreachable!("`{}` isn't a statement", production),
+        }
+        // Jumps and returns are `if`s too
+        self.after_block = !matches!(production, "VarDecl" | "InfixExpr" | "CallExpr");
+    }
+
+    /// Whether `production` can make a statement that has an effect and
+    /// lets the program run to its end
+    fn is_statement(&self, production: &str, depth: usize) -> bool {
+        match production {
+            "VarDecl" | "CallExpr" => true,
+            "InfixExpr" => self.assignable().next().is_some(),
+            "IfExpr" => depth > 0,
+            "BlockExpr" => depth > 0 && !self.after_block,
+            "WhileExpr" => depth > 0 && self.config.loops,
+            "break" | "continue" => depth > 0 && self.loop_depth > 0,
+            "ReturnExpr" => depth > 0 && self.ret != Type::Unit,
+            _ => false,
+        }
+    }
+
+    fn fresh(&mut self) -> String {
+        self.locals += 1;
+        format!("v{}", self.locals - 1)
+    }
+
+    fn visible(&self) -> impl Iterator<Item = &Local> {
+        self.scopes.iter().flatten()
+    }
+
+    fn assignable(&self) -> impl Iterator<Item = &Local> {
+        self.visible().filter(|local| local.assignable)
+    }
+
+    fn scalar_type(&mut self) -> Type {
+        self.rng.pick(&[Type::Bool, Type::I32, Type::I64, Type::F64, Type::String]).clone()
+    }
+
+    /// A type nested at most `depth` times
+    fn any_type(&mut self, depth: usize) -> Type {
+        let arrays = self.config.arrays && depth > 0;
+        let shapes: Vec<&str> = self.productions.types.iter().copied().filter(|&shape| shape != "[" || arrays).collect();
+        match *self.rng.pick(&shapes) {
+            "[" => {
+                let elem = self.any_type(depth - 1);
+                Type::Array(Box::new(elem), 1 + self.rng.below(3) as u32)
+            }
+            _ if !self.structs.is_empty() && self.rng.one_in(5) => self.rng.pick(&self.structs).clone(),
+            _ => self.scalar_type(),
+        }
+    }
+
+    fn call(&mut self, index: usize, depth: usize) -> String {
+        let params = self.functions[index].params.clone();
+        let args: Vec<String> = params.iter().map(|param| self.expr(param, depth.saturating_sub(1)).text).collect();
+        format!("{}({})", self.functions[index].name, args.join(", "))
+    }
+
+    /// An expression of type `typ`, nested at most `depth` times
+    fn expr(&mut self, typ: &Type, depth: usize) -> Expr {
+        let productions: Vec<&str> = self.productions.exprs
+            .iter()
+            .copied()
+            .filter(|production| self.is_expr(production, typ, depth))
+            .collect();
+        match *self.rng.pick(&productions) {
+            "bool" | "number" | "string" | "ArrayExpr" | "StructExpr" => self.literal(typ, depth),
+            "ident" => {
+                let names: Vec<String> =
+                    self.visible().filter(|local| local.typ == *typ).map(|local| local.name.clone()).collect();
+                Expr::atom(self.rng.pick(&names).clone())
+            }
+            "FieldExpr" => {
+                let fields: Vec<String> = self.visible()
+                    .flat_map(|local| match &local.typ {
+                        Type::Struct(_, fields) => fields.iter()
+                            .filter(|(_, field)| field == typ)
+                            .map(|(field, _)| format!("{}.{}", local.name, field))
+                            .collect(),
+                        _ => Vec::new(),
+                    })
+                    .collect();
+                Expr::atom(self.rng.pick(&fields).clone())
+            }
+            "IndexExpr" => {
+                let arrays: Vec<(String, u32)> = self.visible()
+                    .filter_map(|local| match &local.typ {
+                        Type::Array(elem, len) if **elem == *typ => Some((local.name.clone(), *len)),
+                        _ => None,
+                    })
+                    .collect();
+                let (name, len) = self.rng.pick(&arrays).clone();
+                Expr::atom(format!("{}[{}]", name, self.rng.below(len as usize)))
+            }
+            "CallExpr" => {
+                let candidates: Vec<usize> =
+                    (0..self.functions.len()).filter(|&i| self.functions[i].ret == *typ).collect();
+                let index = *self.rng.pick(&candidates);
+                Expr::atom(self.call(index, depth))
+            }
+            "GroupExpr" => {
+                let inner = self.expr(typ, depth - 1);
+                Expr { text: format!("({})", inner.text), untyped: inner.untyped, atom: true }
+            }
+            "PrefixExpr" => self.prefix(typ, depth - 1),
+            "InfixExpr" => self.infix(typ, depth - 1),
+            production => unreachable!("`{}` can't be an expression of type `{}`", production, typ),
+        }
+    }
+
+    /// Whether `production` can make an expression of type `typ` nested at
+    /// most `depth` times
+    fn is_expr(&self, production: &str, typ: &Type, depth: usize) -> bool {
+        let operable = matches!(typ, Type::Bool | Type::I32 | Type::I64 | Type::F64);
+        match production {
+            "bool" => *typ == Type::Bool,
+            "number" => matches!(typ, Type::I32 | Type::I64 | Type::F64),
+            "string" => *typ == Type::String,
+            "ArrayExpr" => matches!(typ, Type::Array(..)),
+            "StructExpr" => matches!(typ, Type::Struct(..)),
+            "ident" => self.visible().any(|local| local.typ == *typ),
+            "FieldExpr" => self.visible().any(|local| has_field(&local.typ, typ)),
+            "IndexExpr" => self.visible().any(|local| matches!(&local.typ, Type::Array(elem, _) if **elem == *typ)),
+            "CallExpr" => depth > 0 && self.functions.iter().any(|function| function.ret == *typ),
+            "GroupExpr" | "PrefixExpr" | "InfixExpr" => depth > 0 && operable,
+            _ => false,
+        }
+    }
+
+    fn literal(&mut self, typ: &Type, depth: usize) -> Expr {
+        let text = match typ {
+            Type::Bool => self.rng.pick(&["true", "false"]).to_string(),
+            Type::I32 | Type::I64 => return Expr { text: self.rng.below(100).to_string(), untyped: true, atom: true },
+            Type::F64 => format!("{}.{}", self.rng.below(100), self.rng.below(10)),
+            Type::String => self.rng.pick(STRINGS).to_string(),
+            Type::Array(elem, len) => {
+                let items: Vec<String> =
+                    (0..*len).map(|_| self.expr(elem, depth.saturating_sub(1)).text).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Type::Struct(name, fields) => {
+                let inits: Vec<String> = fields.iter()
+                    .map(|(field, typ)| format!("{}: {}", field, self.expr(typ, depth.saturating_sub(1)).text))
+                    .collect();
+                format!(".{} {{ {} }}", name, inits.join(", "))
+            }
+            typ => unreachable!("no literal of type `{}`", typ),
+        };
+        Expr::atom(text)
+    }
+
+    /// A unary operator applied to an operand nested at most `depth` times
+    fn prefix(&mut self, typ: &Type, depth: usize) -> Expr {
+        let negates = *typ != Type::Bool;
+        let ops: Vec<&str> = self.productions.unary.iter().copied().filter(|&op| (op == "-") == negates).collect();
+        let op = *self.rng.pick(&ops);
+        let operand = self.expr(typ, depth);
+        Expr { text: format!("{}{}", op, operand.operand()), untyped: operand.untyped, atom: false }
+    }
+
+    /// A binary operator applied to operands nested at most `depth` times
+    fn infix(&mut self, typ: &Type, depth: usize) -> Expr {
+        let arithmetic = |op: &str| matches!(op, "+" | "-" | "*" | "/");
+        let ops: Vec<&str> = self.productions.binary
+            .iter()
+            .copied()
+            .filter(|&op| op != "=" && arithmetic(op) == (*typ != Type::Bool))
+            .collect();
+        let op = *self.rng.pick(&ops);
+        let (text, untyped) = match op {
+            // Dividing by a literal can't divide by zero
+            "/" => {
+                let left = self.expr(typ, depth);
+                match typ {
+                    Type::F64 => (format!("{} / {}.5", left.operand(), self.rng.below(9)), false),
+                    _ => (format!("{} / {}", left.operand(), 1 + self.rng.below(9)), left.untyped),
+                }
+            }
+            _ => {
+                let operands = match op {
+                    _ if arithmetic(op) => typ.clone(),
+                    "==" | "!=" => self.rng.pick(&[Type::Bool, Type::I32, Type::I64, Type::F64]).clone(),
+                    _ => self.rng.pick(&[Type::I32, Type::I64, Type::F64]).clone(),
+                };
+                let (left, right) = typed_first(self.expr(&operands, depth), self.expr(&operands, depth));
+                let untyped = arithmetic(op) && left.untyped && right.untyped;
+                (format!("{} {} {}", left.operand(), op, right.operand()), untyped)
+            }
+        };
+        Expr { text, untyped, atom: false }
+    }
+}
+
+/// Swaps the operands of a binary operator if the left one has no type of its
+/// own while the right one has, as the left one would then be an `i32`,
+/// unless it is a single literal
+fn typed_first(left: Expr, right: Expr) -> (Expr, Expr) {
+    let literal = left.text.bytes().all(|byte| byte.is_ascii_digit());
+    match left.untyped && !literal && !right.untyped {
+        true => (right, left),
+        false => (left, right),
+    }
+}
+
+fn mentions_integer(typ: &Type) -> bool {
+    match typ {
+        Type::I32 | Type::I64 => true,
+        Type::Array(elem, _) => mentions_integer(elem),
+        _ => false,
+    }
+}
+
+fn has_field(container: &Type, typ: &Type) -> bool {
+    matches!(container, Type::Struct(_, fields) if fields.iter().any(|(_, field)| field == typ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use bumpalo::Bump;
+
+    use super::{generate, Config, Productions};
+    use crate::grammar::ebnf::Grammar;
+    use crate::ast2::{AstNode, TopDeclList};
+    use crate::codegen::generate_c;
+    use crate::parser3::Parser;
+    use crate::typecheck::check::TypeChecker;
+    use crate::utils::temp_project::TempProject;
+    use crate::vm::{compiler, eval, Io, Vm};
+
+    /// Output of the VM and of the tree walking evaluator
+    fn run(source: &str) -> (String, String) {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}\n{}", parser.errors, source);
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        assert!(checker.errors.is_empty(), "{:?}\n{}", checker.errors, source);
+
+        let decls = TopDeclList::cast(root);
+        let program = compiler::compile(decls.clone(), &checker).unwrap();
+        let mut vm = Vec::new();
+        let mut io = Io { input: &mut std::io::empty(), output: &mut vm };
+        Vm::new(&program).run_main(&mut io).unwrap_or_else(|err| panic!("{:?}\n{}", err, source));
+        let mut tree = Vec::new();
+        let mut io = Io { input: &mut std::io::empty(), output: &mut tree };
+        eval::Evaluator::new(decls, &checker).run_main(&mut io).unwrap();
+        (String::from_utf8(vm).unwrap(), String::from_utf8(tree).unwrap())
+    }
+
+    /// Output of the program compiled with the C backend and the system C compiler
+    fn run_c(source: &str) -> String {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        let c = generate_c(TopDeclList::cast(root), &checker).unwrap_or_else(|errors| panic!("{:?}\n{}", errors, source));
+
+        let project = TempProject::new(&[("main.c", &c)]);
+        let exe_path = project.root().join("main");
+        // Generated programs have unused variables and compare values with themselves
+        let compile = Command::new("cc")
+            .args(["-std=c99", "-w", "-o"])
+            .arg(&exe_path)
+            .arg(project.root().join("main.c"))
+            .output()
+            .expect("a C compiler is installed");
+        assert!(compile.status.success(), "{}\n{}", String::from_utf8_lossy(&compile.stderr), c);
+        String::from_utf8(Command::new(&exe_path).output().unwrap().stdout).unwrap()
+    }
+
+    #[test]
+    fn test_generated_programs_run() {
+        for seed in 0..200 {
+            let config = Config {
+                seed,
+                functions: seed as usize % 5,
+                depth: 1 + seed as usize % 4,
+                structs: seed % 3 != 0,
+                arrays: seed % 4 != 0,
+                loops: seed % 5 != 0,
+                ..Config::default()
+            };
+            let source = generate(&config);
+            let (vm, tree) = run(&source);
+            assert_eq!(vm, tree, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_generated_programs_compile_to_c() {
+        // Fewer programs, each one goes through the C compiler
+        for seed in 0..40 {
+            let config = Config { seed, functions: seed as usize % 4, depth: 1 + seed as usize % 3, ..Config::default() };
+            let source = generate(&config);
+            let (vm, _) = run(&source);
+            assert_eq!(run_c(&source), vm, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_productions() {
+        // A production the spec renames or drops would silently go unused
+        let grammar = Grammar::v1();
+        let productions = Productions::new(&grammar);
+        for statement in ["VarDecl", "InfixExpr", "CallExpr", "IfExpr", "WhileExpr", "BlockExpr", "break", "continue", "ReturnExpr"] {
+            assert!(productions.statements.contains(&statement), "{}", statement);
+        }
+        for expr in ["ident", "bool", "number", "string", "InfixExpr", "PrefixExpr", "GroupExpr", "CallExpr", "IndexExpr", "FieldExpr", "ArrayExpr", "StructExpr"] {
+            assert!(productions.exprs.contains(&expr), "{}", expr);
+        }
+        assert_eq!(productions.types, ["ident", "["]);
+        assert_eq!(productions.unary, ["-", "!"]);
+        assert!(productions.binary.contains(&"="));
+    }
+
+    #[test]
+    fn test_config() {
+        let config = Config { seed: 7, ..Config::default() };
+        assert_eq!(generate(&config), generate(&config));
+        assert_ne!(generate(&config), generate(&Config { seed: 8, ..config.clone() }));
+
+        for seed in 0..50 {
+            let config = Config { seed, functions: 4, structs: false, arrays: false, loops: false, ..Config::default() };
+            let source = generate(&config);
+            assert_eq!(source.matches("fn ").count(), 5, "{}", source);
+            assert!(!source.contains("struct") && !source.contains('[') && !source.contains("while"), "{}", source);
+        }
+    }
+}
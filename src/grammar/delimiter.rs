@@ -0,0 +1,149 @@
+//! Delimiter matching for error recovery
+//!
+//! The parser keeps every `(`, `[` and `{` it consumed until its closer is
+//! consumed too. Synchronization uses them to skip balanced groups whole
+//! and to stop at the closer of a group it is inside of, and an unmatched
+//! delimiter is reported once instead of through the errors it cascades into.
+
+use crate::errors::{Loc, ParseErrorKind, ParseErrorKind::*};
+use crate::token::{Tag, Token};
+pub use super::Parser;
+
+/// The delimiter closing `tag`, if it opens a group
+pub(crate) fn closer(tag: Tag) -> Option<Tag> {
+    match tag {
+        Tag::LParen => Some(Tag::RParen),
+        Tag::LBracket => Some(Tag::RBracket),
+        Tag::LBrace => Some(Tag::RBrace),
+        _ => None,
+    }
+}
+
+/// The closer an error reports missing, as the end of a list or group
+fn closed_by(kind: &ParseErrorKind) -> Option<Tag> {
+    match kind {
+        Expected(tag) if is_closer(*tag) => Some(*tag),
+        ExpectedArrayDelimeter => Some(Tag::RBracket),
+        _ => None,
+    }
+}
+
+fn is_closer(tag: Tag) -> bool {
+    matches!(tag, Tag::RParen | Tag::RBracket | Tag::RBrace)
+}
+
+/// Whether `tag` can only start a top-level declaration
+pub(crate) fn is_decl_leader(tag: Tag) -> bool {
+    use Tag::*;
+    matches!(tag, Module | Import | Enum | Fn | Struct | Type | Const | Pub)
+}
+
+impl<'s, 'b> Parser<'s, 'b> {
+    /// Updates the open delimiters with a consumed token
+    pub(crate) fn track_delimiter(&mut self, tok: Token<'s>) {
+        if closer(tok.tag).is_some() {
+            self.delims.push(tok);
+        } else if is_closer(tok.tag) {
+            match self.enclosing(tok.tag) {
+                // Whatever was opened inside the group is never closed
+                Some(depth) => {
+                    self.unclosed(depth + 1);
+                    self.delims.pop();
+                }
+                None => {
+                    // Errors the declaration reported at a stray closer are all due to it
+                    let mut index = 0;
+                    let first = self.decl_errors;
+                    self.errors.retain(|err| {
+                        index += 1;
+                        index <= first || err.location.start != tok.pos
+                    });
+                    self.add_error(UnmatchedDelimiter, Loc::from_token(tok));
+                }
+            }
+        }
+    }
+
+    /// Index of the innermost open delimiter `tag` closes
+    pub(crate) fn enclosing(&self, tag: Tag) -> Option<usize> {
+        self.delims.iter().rposition(|open| closer(open.tag) == Some(tag))
+    }
+
+    /// Reports the delimiters opened past `depth` as unclosed and forgets them
+    ///
+    /// Errors that reported their closers missing are dropped, they are the
+    /// same error.
+    pub(crate) fn unclosed(&mut self, depth: usize) {
+        if depth >= self.delims.len() {
+            return;
+        }
+        for open in self.delims.split_off(depth) {
+            let missing = &mut self.missing_closers;
+            self.errors.retain(|err| {
+                closed_by(&err.kind).is_none() || !missing.contains(&(open.pos, err.location.start))
+            });
+            missing.retain(|&(pos, _)| pos != open.pos);
+            self.add_error(UnclosedDelimiter, Loc::from_token(open));
+        }
+    }
+
+    /// Remembers an error reporting the closer of the innermost group missing
+    pub(crate) fn track_missing_closer(&mut self, kind: &ParseErrorKind, loc: Loc) {
+        let Some(open) = self.delims.last() else { return };
+        if closed_by(kind).is_some() && closed_by(kind) == closer(open.tag) {
+            self.missing_closers.push((open.pos, loc.start));
+        }
+    }
+
+    /// Skips the next token, or the whole group it opens if that is balanced
+    ///
+    /// An unbalanced opener is skipped alone, it would take the rest of the
+    /// file with it otherwise.
+    pub(crate) fn skip(&mut self) {
+        let depth = self.delims.len();
+        let balanced = self.opens_balanced_group();
+        self.next();
+        while balanced && self.delims.len() > depth {
+            self.next();
+        }
+    }
+
+    /// Whether the next token opens a group closed before anything unmatched
+    fn opens_balanced_group(&mut self) -> bool {
+        let Some(open) = self.peek().filter(|tok| closer(tok.tag).is_some()) else { return false };
+        if !self.groups.contains_key(&open.pos) {
+            self.scan_groups(open);
+        }
+        let (balanced, decided) = self.groups[&open.pos];
+        self.lookahead = self.lookahead.max(decided);
+        balanced
+    }
+
+    /// Matches every opener from `open` to the end of the file at once
+    ///
+    /// A group is decided at its closer, or at the first closer it cannot
+    /// match, which decides every group still open too. Skipping would lex
+    /// to the end of an unbalanced group for each of its openers otherwise.
+    fn scan_groups(&mut self, open: Token<'s>) {
+        let mut open = vec![open];
+        // The lexer is already past the peeked token
+        let mut tokens = self.tokens;
+        while let Some(tok) = tokens.next() {
+            if closer(tok.tag).is_some() {
+                open.push(tok);
+            } else if is_closer(tok.tag) {
+                match open.pop() {
+                    Some(group) if closer(group.tag) == Some(tok.tag) => {
+                        self.groups.insert(group.pos, (true, tokens.offset));
+                    }
+                    unmatched => for group in unmatched.into_iter().chain(open.drain(..)) {
+                        self.groups.insert(group.pos, (false, tokens.offset));
+                    },
+                }
+            }
+        }
+        for group in open {
+            self.groups.insert(group.pos, (false, tokens.offset));
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! The grammar of `spec/grammar_v1.ebnf`, for generating programs from it
+//!
+//! Rules expand through [`Choices`], with the lexical classes the spec leaves
+//! undefined, such as `ident` and `number`, filled in by the caller. Past a
+//! given depth every choice goes to the cheapest alternative, so expansion
+//! always ends.
+
+use hashbrown::HashMap;
+
+use crate::utils::choices::Choices;
+
+#[derive(Debug)]
+pub enum Expr {
+    /// A quoted terminal
+    Literal(String),
+    /// A rule, or a lexical class such as `ident` if it has no definition
+    Rule(String),
+    Seq(Vec<Expr>),
+    Alt(Vec<Expr>),
+    /// Repeated at least `min` times
+    Repeat(Box<Expr>, usize),
+    Optional(Box<Expr>),
+}
+
+pub struct Grammar {
+    rules: HashMap<String, Expr>,
+    /// Fewest tokens each rule can expand to, for cutting the recursion
+    costs: HashMap<String, usize>,
+}
+
+impl Grammar {
+    /// The grammar of `spec/grammar_v1.ebnf`
+    pub fn v1() -> Grammar {
+        Grammar::parse(&[include_str!("../../spec/grammar_v1.ebnf")])
+    }
+
+    /// Rules of later sources replace the ones of the same name before them
+    pub fn parse(sources: &[&str]) -> Grammar {
+        let mut rules = HashMap::new();
+        for source in sources {
+            let mut parser = EbnfParser { tokens: ebnf_tokens(source), pos: 0 };
+            while parser.pos < parser.tokens.len() {
+                let name = parser.bump();
+                assert_eq!(parser.bump(), "=", "after `{}`", name);
+                let expr = parser.alt();
+                assert_eq!(parser.bump(), ";", "after the rule `{}`", name);
+                rules.insert(name, expr);
+            }
+        }
+        let mut grammar = Grammar { rules, costs: HashMap::new() };
+        loop {
+            let costs: HashMap<String, usize> =
+                grammar.rules.iter().map(|(name, expr)| (name.clone(), grammar.cost(expr))).collect();
+            if costs == grammar.costs {
+                break;
+            }
+            grammar.costs = costs;
+        }
+        grammar
+    }
+
+    pub fn rule(&self, name: &str) -> Option<&Expr> {
+        self.rules.get(name)
+    }
+
+    /// The alternatives of the rule `name` by the rule or terminal each one
+    /// starts with, those starting with a rule of `inline` replaced by the
+    /// alternatives of that rule
+    pub fn heads(&self, name: &str, inline: &[&str]) -> Vec<&str> {
+        fn head(expr: &Expr) -> Option<&str> {
+            match expr {
+                Expr::Literal(text) | Expr::Rule(text) => Some(text),
+                Expr::Seq(items) => items.first().and_then(head),
+                Expr::Repeat(item, _) | Expr::Optional(item) => head(item),
+                Expr::Alt(_) => None,
+            }
+        }
+        let alternatives = match self.rules.get(name) {
+            Some(Expr::Alt(alternatives)) => alternatives.iter().collect(),
+            Some(expr) => vec![expr],
+            None => Vec::new(),
+        };
+        let mut heads = Vec::new();
+        for head in alternatives.into_iter().filter_map(head) {
+            match inline.contains(&head) {
+                true => heads.extend(self.heads(head, inline)),
+                false => heads.push(head),
+            }
+        }
+        heads
+    }
+
+    pub fn cost(&self, expr: &Expr) -> usize {
+        match expr {
+            Expr::Literal(_) => 1,
+            Expr::Rule(name) => match self.rules.contains_key(name) {
+                true => self.costs.get(name).copied().unwrap_or(usize::MAX),
+                false => 1,
+            },
+            Expr::Seq(items) => items.iter().fold(0, |sum, item| sum.saturating_add(self.cost(item))),
+            Expr::Alt(choices) => choices.iter().map(|choice| self.cost(choice)).min().unwrap(),
+            Expr::Repeat(item, min) => self.cost(item).saturating_mul(*min),
+            Expr::Optional(_) => 0,
+        }
+    }
+
+    /// Expands `expr` into tokens, taking the cheapest way out past `depth`
+    /// 0. `lexeme` spells the lexical classes.
+    pub fn generate<C: Choices>(
+        &self,
+        expr: &Expr,
+        depth: usize,
+        choices: &mut C,
+        lexeme: &mut impl FnMut(&str, &mut C) -> String,
+        out: &mut Vec<String>,
+    ) {
+        match expr {
+            Expr::Literal(text) => out.push(text.clone()),
+            Expr::Rule(name) => match self.rules.get(name) {
+                Some(expr) => self.generate(expr, depth.saturating_sub(1), choices, lexeme, out),
+                None => out.push(lexeme(name, choices)),
+            },
+            Expr::Seq(items) => items.iter().for_each(|item| self.generate(item, depth, choices, lexeme, out)),
+            Expr::Alt(alternatives) => {
+                let alternative = match depth {
+                    0 => alternatives.iter().min_by_key(|alternative| self.cost(alternative)).unwrap(),
+                    _ => choices.pick(alternatives),
+                };
+                self.generate(alternative, depth, choices, lexeme, out);
+            }
+            Expr::Repeat(item, min) => {
+                let count = min + if depth == 0 { 0 } else { choices.below(3) };
+                (0..count).for_each(|_| self.generate(item, depth, choices, lexeme, out));
+            }
+            Expr::Optional(item) => {
+                if depth > 0 && choices.one_in(2) {
+                    self.generate(item, depth, choices, lexeme, out);
+                }
+            }
+        }
+    }
+
+    /// Every quoted terminal, sorted
+    pub fn literals(&self) -> Vec<&str> {
+        fn collect<'g>(expr: &'g Expr, out: &mut Vec<&'g str>) {
+            match expr {
+                Expr::Literal(text) => out.push(text),
+                Expr::Rule(_) => {}
+                Expr::Seq(items) | Expr::Alt(items) => items.iter().for_each(|item| collect(item, out)),
+                Expr::Repeat(item, _) | Expr::Optional(item) => collect(item, out),
+            }
+        }
+        let mut literals = Vec::new();
+        self.rules.values().for_each(|expr| collect(expr, &mut literals));
+        literals.sort();
+        literals.dedup();
+        literals
+    }
+}
+
+/// Names, quoted terminals and punctuation, without `#` comments
+fn ebnf_tokens(source: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '\'' | '"' => {
+                let mut text = String::new();
+                while let Some(next) = chars.next().filter(|&next| next != c) {
+                    text.push(next);
+                }
+                tokens.push(format!("{}{}", c, text));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some(next) = chars.next_if(|&c| c.is_alphanumeric() || c == '_') {
+                    name.push(next);
+                }
+                tokens.push(name);
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push(c.to_string()),
+        }
+    }
+    tokens
+}
+
+struct EbnfParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl EbnfParser {
+    fn bump(&mut self) -> String {
+        self.pos += 1;
+        self.tokens[self.pos - 1].clone()
+    }
+
+    fn peek(&self) -> &str {
+        self.tokens.get(self.pos).map_or("", String::as_str)
+    }
+
+    fn alt(&mut self) -> Expr {
+        let mut choices = vec![self.seq()];
+        while self.peek() == "|" {
+            self.pos += 1;
+            choices.push(self.seq());
+        }
+        match choices.len() {
+            1 => choices.pop().unwrap(),
+            _ => Expr::Alt(choices),
+        }
+    }
+
+    fn seq(&mut self) -> Expr {
+        let mut items = Vec::new();
+        while !matches!(self.peek(), "|" | ";" | ")" | "") {
+            let mut item = self.atom();
+            loop {
+                item = match self.peek() {
+                    "*" => Expr::Repeat(Box::new(item), 0),
+                    "+" => Expr::Repeat(Box::new(item), 1),
+                    "?" => Expr::Optional(Box::new(item)),
+                    _ => break,
+                };
+                self.pos += 1;
+            }
+            items.push(item);
+        }
+        Expr::Seq(items)
+    }
+
+    fn atom(&mut self) -> Expr {
+        let token = self.bump();
+        match token.chars().next().unwrap() {
+            '(' => {
+                let expr = self.alt();
+                assert_eq!(self.bump(), ")");
+                expr
+            }
+            '\'' | '"' => Expr::Literal(token[1..].to_string()),
+            _ => Expr::Rule(token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Grammar;
+
+    #[test]
+    fn test_heads() {
+        let grammar = Grammar::parse(&["A = B ';' | 'x' C ; B = 'y'? | C ; C = 'z' 'z' ;"]);
+        assert_eq!(grammar.heads("A", &[]), ["B", "x"]);
+        assert_eq!(grammar.heads("A", &["B"]), ["y", "C", "x"]);
+        assert_eq!(grammar.heads("C", &[]), ["z"]);
+        assert!(grammar.heads("D", &[]).is_empty());
+    }
+}
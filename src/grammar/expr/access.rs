@@ -1,32 +1,31 @@
-use crate::ast2::{tag_is_binop, Node, NodeBuilder, NodeChild, NodeKind, NodeType::*};
-use crate::errors::Loc;
-use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
-use crate::peek_matches;
+use crate::ast2::{Node, NodeType::*};
+use crate::errors::ParsingError::{*, self};
 use crate::token::{Tag, Token};
+use crate::attempt;
 pub use super::Parser;
 
 impl<'s, 'b> Parser<'s, 'b> {
     pub fn index_expr(&mut self, ident: Token<'s>) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut node = NodeBuilder::from_type(IndexExpr, self.bump);
+        let mut node = self.builder_from(IndexExpr, ident);
         node.add(ident);
-        let index = self.expr_with_allower(|tok| tok.tag == Tag::RBracket)?;
+        let index = attempt!(self, node, self.expr_with_allower(|tok| tok.tag == Tag::RBracket));
         node.add(index);
 
         if self.expect_token(Tag::RBracket).is_empty() {
-            self.expr_synchronize(Tag::RBracket);
-            return Err(Failed)
+            self.skip_into(&mut node, |parser| parser.expr_synchronize(Tag::RBracket));
+            self.fail(node, Failed)
         } else {
-            return Ok(node.finish(false));
+            Ok(self.finish(node))
         }
     }
 
     pub fn field_access_expr(&mut self, ident: Token<'s>) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut node = NodeBuilder::from_type(FieldAccessExpr, self.bump);
+        let mut node = self.builder_from(FieldAccessExpr, ident);
         node.add(ident);
         let field_name = self.expect_token(Tag::Ident);
-        if field_name.is_empty() { return Err(Failed); }
+        if field_name.is_empty() { return self.fail(node, Failed); }
 
         node.add(field_name);
-        Ok(node.finish(false))
+        Ok(self.finish(node))
     }
 }
\ No newline at end of file
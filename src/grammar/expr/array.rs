@@ -1,15 +1,16 @@
-use crate::ast2::{tag_is_binop, Node, NodeBuilder, NodeChild, NodeKind, NodeType::*};
+use crate::ast2::{Node, NodeType::*};
 use crate::errors::Loc;
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
-use crate::peek_matches;
 use crate::token::Tag;
+use crate::attempt;
 pub use super::Parser;
 
 impl<'s, 'b> Parser<'s, 'b> {
     pub fn array_expr(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut array = NodeBuilder::from_type(ArrayExpr, self.bump);
+        let mut array = self.builder_from_last(ArrayExpr);
+        if self.eat_token(Tag::RBracket).is_some() { return Ok(self.finish(array)); }
         loop {
-            let expr = self.expr()?;
+            let expr = attempt!(self, array, self.expr_with_allower(|tok| matches!(tok.tag, Tag::Comma | Tag::RBracket)));
             array.add(expr);
 
             match self.peek() {
@@ -18,15 +19,15 @@ impl<'s, 'b> Parser<'s, 'b> {
                     Tag::RBracket => { self.next(); break; },
                     _ => {
                         self.add_error(ExpectedArrayDelimeter, Loc::from_token(tok));
-                        return Err(Failed);
+                        return self.fail(array, Failed);
                     }
                 }
                 _ => {
                     self.add_error(ExpectedArrayDelimeter, self.loc(0));
-                    return Err(Failed);
+                    return self.fail(array, Failed);
                 }
             }
         }
-        Ok(array.finish(false))
+        Ok(self.finish(array))
     }
 }
\ No newline at end of file
@@ -1,37 +1,80 @@
-use crate::ast2::{Node, NodeBuilder, NodeChild, NodeKind, NodeType::*};
+use crate::ast2::{Node, NodeType::*};
 use crate::errors::Loc;
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
+use crate::errors::TextEdit;
+use crate::parser3::BlockSpan;
 use crate::token::Tag;
+use crate::grammar::delimiter::is_decl_leader;
 pub use super::Parser;
 
 impl<'s, 'b> Parser<'s, 'b> {
     pub(crate) fn block_expr(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut node = NodeBuilder::from_type(BlockExpr, self.bump);
-        let mut stmts = NodeBuilder::from_type(StmtList, self.bump);
+        let open = self.last.filter(|tok| tok.tag == Tag::LBrace);
+        let mut node = match open {
+            Some(open) => self.builder_at(BlockExpr, open.pos),
+            None => self.builder(BlockExpr),
+        };
+        let mut stmts = self.builder(StmtList);
+        let mut close = None;
+        let outer = std::mem::replace(&mut self.block_depth, self.delims.len());
 
         loop {
             match self.peek() {
                 Some(tok) if tok.tag == Tag::RBrace => {
-                    self.next();
+                    close = Some(tok);
                     break;
                 }
-                Some(_) => {
-                    let statement = self.statement();
+                // Declarations can't be nested, so the brace was never closed
+                Some(tok) if is_decl_leader(tok.tag) && open.is_some() => {
+                    self.unclosed(self.block_depth - 1);
+                    break;
+                }
+                Some(start) => {
+                    debug_assert!(self.partial.is_none(), "a failed parse was left unrecovered");
+                    let statement = self.statement(start);
                     match statement { 
                         Ok(stmt) => stmts.add(stmt),
-                        Err(Failed) => continue,
-                        Err(err) => return Err(err)
+                        Err(Failed) => {
+                            // Keep the failed statement in place, always making progress
+                            let node_type = if start.tag == Tag::Let { VarDecl } else { ExprStmt };
+                            let mut failed = self.recovered(node_type, start.pos);
+                            if self.peek().map(|tok| tok.pos) == Some(start.pos) {
+                                self.skip_into(&mut failed, |parser| parser.next());
+                            }
+                            stmts.add(self.finish_invalid(failed));
+                        }
+                        Err(err) => {
+                            self.block_depth = outer;
+                            return self.fail_nested(node, stmts, err);
+                        }
                     }
                 }
                 None => {
-                    self.add_error(ExpectedRBrace, self.loc(0));
-                    self.add_error(UnexpectedEOF, self.loc(0));
+                    let end = self.loc(0).start;
+                    let fix = TextEdit::new(end..end, "}");
+                    match open {
+                        // Reported at the brace, the same as one closed by a declaration
+                        Some(open) => {
+                            self.unclosed(self.block_depth);
+                            self.delims.truncate(self.block_depth - 1);
+                            self.add_error_fix(UnclosedDelimiter, Loc::from_token(open), fix);
+                        }
+                        None => self.add_error_fix(Expected(Tag::RBrace), self.loc(0), fix),
+                    }
                     break;
                 }
             }
         }
-        node.add(stmts.finish(false));
+        self.block_depth = outer;
+        // The statements end before the closing brace, the block after it
+        node.add(self.finish(stmts));
+        if let Some(close) = close {
+            self.next();
+            if let (Some(open), Some(blocks)) = (open, self.blocks.as_mut()) {
+                blocks.push(BlockSpan { open: open.pos + 1, line: open.line, close: close.pos });
+            }
+        }
 
-        Ok(node.finish(false))
+        Ok(self.finish(node))
     }
 }
\ No newline at end of file
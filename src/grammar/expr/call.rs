@@ -1,18 +1,27 @@
-use crate::ast2::{tag_is_binop, Node, NodeBuilder, NodeChild, NodeKind, NodeType::*};
+use crate::ast2::{Node, NodeType::*};
 use crate::errors::Loc;
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
-use crate::peek_matches;
 use crate::token::{Tag, Token};
 pub use super::Parser;
 
 impl<'s, 'b> Parser<'s, 'b> {
     pub fn call_expr(&mut self, ident: Token<'s>) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut node = NodeBuilder::from_type(CallExpr, self.bump);
-        let mut args = NodeBuilder::from_type(ArgList, self.bump);
+        let mut node = self.builder_from(CallExpr, ident);
+        let mut args = self.builder_from_last(ArgList);
 
         node.add(ident);
+
+        if self.peek_is(Tag::RParen) {
+            self.next();
+            node.add(self.finish(args));
+            return Ok(self.finish(node));
+        }
+
         loop {
-            let arg = self.expr_with_allower(|tok| matches!(tok.tag, Tag::RParen | Tag::Comma))?;
+            let arg = match self.expr_with_allower(|tok| matches!(tok.tag, Tag::RParen | Tag::Comma)) {
+                Ok(arg) => arg,
+                Err(err) => return self.fail_nested(node, args, err),
+            };
             args.add(arg);
 
             match self.peek() {
@@ -21,16 +30,16 @@ impl<'s, 'b> Parser<'s, 'b> {
                     Tag::RParen => { self.next(); break; },
                     _ => {
                         self.add_error(Expected(Tag::RParen), Loc::from_token(tok));
-                        return Err(Failed);
+                        return self.fail_nested(node, args, Failed);
                     }
                 }
                 _ => {
                     self.add_error(Expected(Tag::RParen), self.loc(0));
-                    return Err(Failed);
+                    return self.fail_nested(node, args, Failed);
                 }
             }
         }
-        node.add(args.finish(false));
-        Ok(node.finish(false))
+        node.add(self.finish(args));
+        Ok(self.finish(node))
     }
 }
\ No newline at end of file
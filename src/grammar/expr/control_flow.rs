@@ -1,94 +1,97 @@
-use crate::ast2::{tag_is_binop, Node, NodeBuilder, NodeChild, NodeKind, NodeType::*};
+use crate::ast2::{Node, NodeType::*};
 use crate::errors::Loc;
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
-use crate::token::Tag;
-use crate::grammar::types::*;
+use crate::token::{Tag, Token};
+use crate::attempt;
 pub use super::{Parser, Restrictions};
 
 impl<'s, 'b> Parser<'s, 'b> {
     pub(crate) fn if_expr(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
         // ATM, this method assumes the if token is consumed
-        let mut node = NodeBuilder::from_type(IfExpr, self.bump);
+        let mut node = self.builder_from_last(IfExpr);
 
-        let condition = self.expr_bp(0, Restrictions::BLOCK, |tok| tok.tag == Tag::LBrace)?;
+        let condition = attempt!(self, node, self.expr_bp(0, Restrictions::BLOCK, |tok| tok.tag == Tag::LBrace));
         node.add(condition);
 
         if self.expect_token(Tag::LBrace).is_empty() {
-            return Err(Failed);
+            return self.fail(node, Failed);
         }
 
-        let consequence = self.block_expr()?;
+        let consequence = attempt!(self, node, self.block_expr());
         node.add(consequence);
 
-        let mut alternate = Node::null(Any);
-
         if self.peek_is(Tag::Else) { self.next(); }
-        else { node.add(alternate); return Ok(node.finish(false)); }
+        else { node.add(self.null_node()); return Ok(self.finish(node)); }
 
-        match self.peek() {
-            Some(tok) if tok.tag == Tag::If => { self.next(); alternate = self.if_expr()?; },
-            Some(tok) if tok.tag == Tag::LBrace => { self.next(); alternate = self.block_expr()?; },
+        let alternate = match self.peek() {
+            Some(tok) if tok.tag == Tag::If => { self.next(); attempt!(self, node, self.if_expr()) },
+            Some(tok) if tok.tag == Tag::LBrace => { self.next(); attempt!(self, node, self.block_expr()) },
             Some(tok) => {
                 self.add_error(ExpectedIfOrBlock, Loc::from_token(tok));
-                return Err(Failed);
+                return self.fail(node, Failed);
             }
             None => {
                 // self.add_error(ExpectedIfOrBlock, self.loc(0));
                 self.add_error(UnexpectedEOF, self.loc(0));
-                return Err(Failed);
+                return self.fail(node, Failed);
             }
-        }
+        };
         node.add(alternate);
 
-        Ok(node.finish(false))
+        Ok(self.finish(node))
     }
 
     pub(crate) fn while_expr(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
         // ATM, this method assumes the while token is consumed
-        let mut node = NodeBuilder::from_type(WhileExpr, self.bump);
+        let mut node = self.builder_from_last(WhileExpr);
 
-        let condition = self.expr_bp(0, Restrictions::BLOCK, |tok| tok.tag == Tag::LBrace)?;
+        let condition = attempt!(self, node, self.expr_bp(0, Restrictions::BLOCK, |tok| tok.tag == Tag::LBrace));
         node.add(condition);
 
         if self.expect_token(Tag::LBrace).is_empty() {
-            return Err(Failed);
+            return self.fail(node, Failed);
         }
 
-        let consequence = self.block_expr()?;
+        let consequence = attempt!(self, node, self.block_expr());
         node.add(consequence);
 
-        Ok(node.finish(false))
+        Ok(self.finish(node))
     }
 
     pub(crate) fn return_expr(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut node = NodeBuilder::from_type(ReturnExpr, self.bump);
+        let mut node = self.builder_from_last(ReturnExpr);
         match self.peek() {
             Some(tok) if matches!(tok.tag, Tag::Semicolon | Tag::RBrace) => {
-                node.add(Node::null(Any));
-                Ok(node.finish(false))
+                node.add(self.null_node());
+                Ok(self.finish(node))
             }
-            Some(tok) => { node.add(self.expr()?); Ok(node.finish(false)) }
+            Some(_) => { node.add(attempt!(self, node, self.expr())); Ok(self.finish(node)) }
             None => {
                 self.add_error(ExpectedExprOrSemi, self.loc(0));
-                return Err(Failed);
+                self.fail(node, Failed)
             }
         } 
     }
 
     pub(crate) fn break_expr(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut node = NodeBuilder::from_type(BreakExpr, self.bump);
+        let mut node = self.builder_from_last(BreakExpr);
         match self.peek() {
             Some(tok) if matches!(tok.tag, Tag::Semicolon | Tag::RBrace) => {
-                node.add(Node::null(Any));
-                Ok(node.finish(false))
+                node.add(self.null_node());
+                Ok(self.finish(node))
             }
-            Some(tok) => { node.add(self.expr()?); Ok(node.finish(false)) }
+            Some(_) => { node.add(attempt!(self, node, self.expr())); Ok(self.finish(node)) }
             None => {
                 self.add_error(ExpectedExprOrSemi, self.loc(0));
-                return Err(Failed);
+                self.fail(node, Failed)
             }
         } 
     }
 
-    pub(crate) fn continue_expr(&mut self) -> Result<Node<'s, 'b>, ParsingError> { todo!() }
+    pub(crate) fn continue_expr(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
+        let mut node = self.builder_from_last(ContinueExpr);
+        // Loop labels are not part of the syntax yet
+        node.add(Token::empty());
+        Ok(self.finish(node))
+    }
 }
\ No newline at end of file
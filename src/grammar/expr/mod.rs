@@ -1,20 +1,21 @@
 use std::ops::BitOr;
 
-use crate::{ast::Expr, ast2::{tag_is_binop, tag_is_unaryop, Node, NodeBuilder, NodeChild, NodeKind, NodeType::{*, self}}, token::Token};
+use crate::{ast2::{tag_is_binop, tag_is_unaryop, NodeChild, NodeType::*}, token::Token};
 use crate::errors::Loc;
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
 use crate::token::Tag;
+use crate::grammar::delimiter::is_decl_leader;
+use crate::attempt;
 pub use super::Parser;
 
 pub mod control_flow;
 pub mod block;
 pub mod array;
 pub mod access;
-pub mod assign;
 pub mod call;
 pub mod r#struct;
 
-fn noop_allow<'s>(tok: Token<'s>) -> bool { false }
+fn noop_allow(_: Token) -> bool { false }
 
 impl<'s, 'b> Parser<'s, 'b> {
     pub(crate) fn expr(&mut self) -> Result<NodeChild<'s, 'b>, ParsingError> {
@@ -25,10 +26,6 @@ impl<'s, 'b> Parser<'s, 'b> {
         self.expr_bp(0, Restrictions::NONE, allower)
     }
 
-    pub(crate) fn expr_with_restrictions(&mut self, restrictions: Restrictions) -> Result<NodeChild<'s, 'b>, ParsingError> {
-        self.expr_bp(0, restrictions, noop_allow)
-    }
-
     fn expr_bp(&mut self, min_bp: u8, restrictions: Restrictions, allower: fn(Token<'s>) -> bool) -> Result<NodeChild<'s, 'b>, ParsingError> {
         let mut lhs = self.prefix(restrictions, allower)?;
     
@@ -36,22 +33,37 @@ impl<'s, 'b> Parser<'s, 'b> {
             use Tag::*;
             let op = match self.peek() {
                 Some(token) if tag_is_binop(token.tag) => token,
+                // A block or a declaration can't continue an expression, an
+                // unclosed delimiter before it is reported by the enclosing group
                 Some(token) if matches!(token.tag,
                     | Let | Break | Return
                     | If | Semicolon | While
-                    | Ident | RBrace
-                ) || allower(token) => break,
+                    | Ident | RBrace | LBrace
+                ) || is_decl_leader(token.tag) || allower(token) => break,
                 Some(token)=> {
                     self.add_error(ExpectedOperator, Loc::from_token(token));
+                    self.orphan(lhs);
                     return Err(Failed);
                 },
-                None => {
-                    self.add_error(UnexpectedEOF, self.loc(0));
-                    // `Fatal` would work here too
-                    return Err(Failed);
-                }
+                // Whatever encloses the expression reports what is missing
+                None => break,
             };
 
+            // Assignment binds the loosest and to the right. Any expression
+            // parses as its target, the type checker rejects what isn't a variable
+            if op.tag == Equal {
+                if min_bp > 0 {
+                    break;
+                }
+                self.next();
+                let mut node = self.builder_at(AssignExpr, self.start_of(&lhs));
+                node.add(lhs);
+                let value = attempt!(self, node, self.expr_bp(0, restrictions, allower));
+                node.add(value);
+                lhs = self.finish(node).into();
+                continue;
+            }
+
             let (lbp, rbp) = bp::infix(op.tag);
 
             if lbp < min_bp {
@@ -60,23 +72,31 @@ impl<'s, 'b> Parser<'s, 'b> {
 
             self.next(); // consume op
 
-            // Parse the next expression preceding an operator
-            // whose bp < rbp
-            let rhs = self.expr_bp(rbp, restrictions, allower)?;
-
-            let mut lhs_builder = NodeBuilder::from_type(Infix, self.bump);
+            let mut lhs_builder = self.builder_at(Infix, self.start_of(&lhs));
             lhs_builder.add(lhs);
             lhs_builder.add(op);
+
+            // Parse the next expression preceding an operator
+            // whose bp < rbp
+            let rhs = attempt!(self, lhs_builder, self.expr_bp(rbp, restrictions, allower));
             lhs_builder.add(rhs);
 
-            lhs = lhs_builder.finish(false).into();
+            lhs = self.finish(lhs_builder).into();
         }
 
         Ok(lhs)
     }
 
     fn prefix(&mut self, restrictions: Restrictions, allower: fn(Token<'s>) -> bool) -> Result<NodeChild<'s, 'b>, ParsingError> {
-        let tok = self.next().expect("caller should ensure a token is available");
+        let Some(tok) = self.peek()
+            else { self.add_error(ExpectedExpr, self.loc(0)); return Err(Failed); };
+        // A closer or `;` ends what encloses the expression, taking it would
+        // leave that to report it missing as well
+        if matches!(tok.tag, Tag::Semicolon | Tag::RParen | Tag::RBracket | Tag::RBrace) {
+            self.add_error(ExpectedExpr, Loc::from_token(tok));
+            return Err(Failed);
+        }
+        self.next();
 
         match tok.tag {
             Tag::Ident => {
@@ -84,10 +104,9 @@ impl<'s, 'b> Parser<'s, 'b> {
                 // token -> whatever comes after
                 // Easy to confuse them
                 let Some(token) = self.peek() 
-                    else { self.add_error(UnexpectedEOF, self.loc(0)); return Err(Failed); };
+                    else { self.add_error(UnexpectedEOF, self.loc(0)); self.orphan(tok); return Err(Failed); };
                 match token.tag {
                     Tag::LParen => { self.next(); self.call_expr(tok).map(NodeChild::Node) }
-                    Tag::Equal => { self.next(); self.assign_expr(tok).map(NodeChild::Node)  }
                     Tag::LBracket => { self.next(); self.index_expr(tok).map(NodeChild::Node) }
                     Tag::Dot => { self.next(); self.field_access_expr(tok).map(NodeChild::Node) }
                     _ => Ok(tok.into())
@@ -101,31 +120,35 @@ impl<'s, 'b> Parser<'s, 'b> {
                         Expected(Tag::Ident), 
                         self.peek().map(Loc::from_token).unwrap_or(self.loc(0))
                     );
-                    return Err(Failed);
+                    self.orphan(tok);
+                    Err(Failed)
                 }
             },
             tag if tag_is_unaryop(tag) => {
                 let ((), rbp) = bp::prefix(tag);
-                let rhs = self.expr_bp(rbp, restrictions, allower)?;
-                let mut node = NodeBuilder::from_type(Prefix, self.bump);
-                node.add(tok); node.add(rhs);
-                Ok(node.finish(false).into())
+                let mut node = self.builder_from(Prefix, tok);
+                node.add(tok);
+                let rhs = attempt!(self, node, self.expr_bp(rbp, restrictions, allower));
+                node.add(rhs);
+                Ok(self.finish(node).into())
             }
             Tag::If => self.if_expr().map(NodeChild::Node),
             Tag::While => self.while_expr().map(NodeChild::Node),
             Tag::Return => self.return_expr().map(NodeChild::Node),
             Tag::Break => self.break_expr().map(NodeChild::Node),
+            Tag::Continue => self.continue_expr().map(NodeChild::Node),
             Tag::LBracket => self.array_expr().map(NodeChild::Node),
             Tag::LParen => {
-                let mut node = NodeBuilder::from_type(Group, self.bump);
-                let inner = self.expr_bp(0, restrictions, |tok| tok.tag == Tag::RParen)?;
-                if self.expect_token(Tag::RParen).is_empty() { return Err(Failed); }
+                let mut node = self.builder_from(Group, tok);
+                let inner = attempt!(self, node, self.expr_bp(0, restrictions, |tok| tok.tag == Tag::RParen));
                 node.add(inner);
-                Ok(node.finish(false).into())
+                if self.expect_token(Tag::RParen).is_empty() { return self.fail(node, Failed); }
+                Ok(self.finish(node).into())
             }
             Tag::LBrace => if restrictions.has(Restrictions::BLOCK) {
                 self.add_error(BlockExprDisallowed, Loc::from_token(tok));
-                return Err(Failed);
+                self.orphan(tok);
+                Err(Failed)
             } else {
                 self.block_expr().map(NodeChild::Node)
             }
@@ -133,8 +156,9 @@ impl<'s, 'b> Parser<'s, 'b> {
                 // We generally don't recover to keep parsing expressions due to
                 // the risk of cascading. Some common mistakes may be recovered from.
                 self.add_error(ExpectedExpr, Loc::from_token(tok));
+                self.orphan(tok);
                 // We task the caller with synchronization.
-                return Err(Failed)
+                Err(Failed)
             }
         }
     }
@@ -150,8 +174,8 @@ impl<'s, 'b> Parser<'s, 'b> {
                 Some(
                     | Let | String | Number | Bool | Minus 
                     | Bang | Break | Return | LParen | LBrace 
-                    | LParen | If) => { return; }
-                Some(_) => { self.next(); continue; }, 
+                    | If) => { return; }
+                Some(_) => { self.skip(); continue; }, 
                 None => return // End of file
             }
         }
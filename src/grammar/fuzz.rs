@@ -0,0 +1,244 @@
+//! Fuzzing of the lexer and parser
+//!
+//! Inputs are programs generated from `spec/grammar_v1.ebnf`, then mutated
+//! a token at a time to reach the recovery paths, and random text. For each
+//! of them the lexer and the parser must not panic, the tokens must tile
+//! the input with only whitespace and comments between them, and the error
+//! locations and node spans must lie within the input.
+//!
+//! The tests run seeded rounds, `HAZE_FUZZ_SEED` and `HAZE_FUZZ_ROUNDS` run
+//! other or more of them, e.g. `HAZE_FUZZ_ROUNDS=100000 cargo test fuzz`,
+//! and shrink a failing input before reporting it. The cargo-fuzz target in
+//! `fuzz/` runs the same checks on inputs a coverage guided fuzzer steers.
+
+use bumpalo::Bump;
+
+use super::ebnf::{Expr, Grammar};
+use crate::ast2::{Node, NodeChild, NodeId, SpanTable};
+use crate::lexer::Lexer;
+use crate::parser3::Parser;
+use crate::utils::choices::Choices;
+
+const IDENTS: &[&str] = &["a", "b", "x", "count", "main", "Point", "i64", "f64", "_t", "été"];
+const NUMBERS: &[&str] = &["0", "1", "42", "3.14", "2.", "007"];
+const STRINGS: &[&str] = &["\"\"", "\"hi\"", "\"a \\\" b\"", "\"multi\nline\""];
+const SEPARATORS: &[&str] = &[" ", " ", " ", "\n", "", "\t", " // note\n", "\r\n"];
+/// Characters random text is made of, weighted towards the ones the lexer
+/// gives a meaning to
+const CHARS: &[char] = &[
+    'a', 'z', 'X', '_', '0', '7', '.', '"', '\\', '/', '-', '>', '=', '!', '<', '+', '*', ',', ';', ':',
+    '(', ')', '[', ']', '{', '}', ' ', ' ', '\n', '\t', '\r', '#', '@', '\0', 'é', 'ß', '日', '😀',
+    '\u{301}', '\u{200b}', '\u{fffd}',
+];
+
+/// A program of a few items, possibly broken by token mutations
+pub fn program(grammar: &Grammar, choices: &mut impl Choices) -> String {
+    let mut tokens = Vec::new();
+    let item = Expr::Rule("Item".to_string());
+    for _ in 0..1 + choices.below(3) {
+        let depth = 4 + choices.below(8);
+        grammar.generate(&item, depth, choices, &mut lexeme, &mut tokens);
+    }
+    let literals = grammar.literals();
+    for _ in 0..choices.below(4) {
+        if tokens.is_empty() {
+            break;
+        }
+        let at = choices.below(tokens.len());
+        match choices.below(5) {
+            0 => drop(tokens.remove(at)),
+            1 => tokens.insert(at, tokens[at].clone()),
+            2 => tokens.insert(at, choices.pick(&literals).to_string()),
+            3 => {
+                let other = choices.below(tokens.len());
+                tokens.swap(at, other);
+            }
+            _ => tokens.truncate(at),
+        }
+    }
+    let mut source = String::new();
+    for token in tokens {
+        source += &token;
+        source += choices.pick(SEPARATORS);
+    }
+    source
+}
+
+fn lexeme(class: &str, choices: &mut impl Choices) -> String {
+    match class {
+        "ident" => choices.pick(IDENTS),
+        "number" => choices.pick(NUMBERS),
+        "string" => choices.pick(STRINGS),
+        "bool" => choices.pick(&["true", "false"]),
+        _ => panic!("`{}` is neither a rule nor a lexical class", class),
+    }
+    .to_string()
+}
+
+/// Text of the characters the lexer gives a meaning to, or of random bytes
+pub fn random_text(choices: &mut impl Choices) -> String {
+    match choices.one_in(2) {
+        true => (0..choices.below(64)).map(|_| *choices.pick(CHARS)).collect(),
+        false => {
+            let bytes: Vec<u8> = (0..choices.below(64)).map(|_| choices.below(256) as u8).collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+    }
+}
+
+/// Whether `text` is only whitespace and line comments
+fn is_trivia(mut text: &str) -> bool {
+    loop {
+        text = text.trim_start_matches([' ', '\t', '\r', '\n']);
+        match text.strip_prefix("//") {
+            Some(comment) => text = comment.find('\n').map_or("", |end| &comment[end..]),
+            None => return text.is_empty(),
+        }
+    }
+}
+
+fn check_tokens(source: &str) -> Result<(), String> {
+    let mut end = 0;
+    let mut line = 1;
+    for token in Lexer::from(source) {
+        let start = token.pos as usize;
+        let gap = source.get(end..start).ok_or_else(|| format!("{:?} overlaps the token before", token))?;
+        if !is_trivia(gap) {
+            return Err(format!("{:?} skipped over {:?}", token, gap));
+        }
+        line += gap.matches('\n').count();
+        if token.line as usize != line {
+            return Err(format!("{:?} isn't on line {}", token, line));
+        }
+        end = start + token.value.len();
+        if source.get(start..end).map(str::as_ptr) != Some(token.value.as_ptr()) {
+            return Err(format!("{:?} doesn't slice the source at its position", token));
+        }
+        line += token.value.matches('\n').count();
+    }
+    match is_trivia(&source[end..]) {
+        true => Ok(()),
+        false => Err(format!("the tokens stop at {}", end)),
+    }
+}
+
+fn check_parse(source: &str) -> Result<(), String> {
+    let bump = Bump::new();
+    let mut parser = Parser::new(source, &bump);
+    let parsed = parser.parse();
+    let lines = source.matches('\n').count() + 1;
+    for err in parser.errors.iter() {
+        let location = err.location;
+        if location.start as usize + location.len as usize > source.len() || !(1..=lines).contains(&(location.line as usize)) {
+            return Err(format!("{:?} at {:?} is out of bounds", err.kind, location));
+        }
+    }
+    fn check_spans(node: &Node, spans: &SpanTable, len: usize) -> Result<(), String> {
+        if node.id() != NodeId::DETACHED {
+            let span = spans.span(node.id()).ok_or_else(|| format!("{:?} has no span", node.kind))?;
+            if span.start > span.end || span.end as usize > len {
+                return Err(format!("{:?} has the span {:?}", node.kind, span));
+            }
+        }
+        node.children().iter().try_for_each(|child| match child {
+            NodeChild::Node(child) => check_spans(child, spans, len),
+            NodeChild::Token(_) => Ok(()),
+        })
+    }
+    check_spans(&parsed.tree, parsed.spans, source.len())
+}
+
+/// Lexes and parses `source`, an error if the tokens or the tree break
+/// one of the rules above. A panic is left to the caller, which is how a
+/// fuzzer tells it apart.
+pub fn check(source: &str) -> Result<(), String> {
+    check_tokens(source)?;
+    check_parse(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::{check, program, random_text};
+    use crate::grammar::ebnf::Grammar;
+    use crate::utils::rng::Rng;
+
+    /// [`check`], with a panic as one more error
+    fn check_caught(source: &str) -> Result<(), String> {
+        catch_unwind(AssertUnwindSafe(|| check(source))).unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(format!("panicked: {}", message))
+        })
+    }
+
+    /// Removes characters from a failing input for as long as it still fails
+    fn shrink(source: &str) -> String {
+        let mut chars: Vec<char> = source.chars().collect();
+        let mut chunk = chars.len().max(1);
+        while chunk > 0 {
+            let mut start = 0;
+            while start < chars.len() {
+                let end = (start + chunk).min(chars.len());
+                let candidate: String = chars[..start].iter().chain(&chars[end..]).collect();
+                match check_caught(&candidate) {
+                    Err(_) => chars.drain(start..end).for_each(drop),
+                    Ok(()) => start += chunk,
+                }
+            }
+            chunk /= 2;
+        }
+        chars.into_iter().collect()
+    }
+
+    fn env_or(name: &str, default: u64) -> u64 {
+        std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+    }
+
+    #[test]
+    fn test_fuzz() {
+        let grammar = Grammar::v1();
+        let seed = env_or("HAZE_FUZZ_SEED", 0xf022);
+        let mut rng = Rng::new(seed);
+        for round in 0..env_or("HAZE_FUZZ_ROUNDS", 2000) {
+            let source = match round % 2 {
+                0 => program(&grammar, &mut rng),
+                _ => random_text(&mut rng),
+            };
+            if let Err(message) = check_caught(&source) {
+                let shrunk = shrink(&source);
+                panic!(
+                    "round {} of seed {:#x}: {}\n{:?}\nshrunk to {:?}: {}",
+                    round, seed, message, source, shrunk, check_caught(&shrunk).unwrap_err()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fuzz_regressions() {
+        // Shrunk inputs the fuzzer found, then half written code as the
+        // language server and the REPL get it
+        for source in [
+            "enum//",
+            "const[t",
+            "const[t[]=",
+            "fn f() { p.x = 1; }",
+            "fn main() { let x = ",
+            "fn main() { foo(1, ",
+            "fn f(a: i64, ) -> { a[ }",
+            "struct P { x: i64, y",
+            "fn main() { .P { x: 1, ",
+            "fn main() { if x { } else",
+            "1 + ;",
+            "a.b = ;",
+            "}",
+        ] {
+            assert_eq!(check(source), Ok(()), "{:?}", source);
+        }
+    }
+}
@@ -2,6 +2,12 @@ pub mod top_level;
 pub mod expr;
 pub mod stmt;
 pub mod types;
+pub mod delimiter;
+pub mod ebnf;
+pub mod fuzz;
 
 // Re-exported for use in child modules.
-pub use crate::parser3::Parser;
\ No newline at end of file
+pub use crate::parser3::Parser;
+
+#[cfg(test)]
+mod snapshots;
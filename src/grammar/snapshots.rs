@@ -0,0 +1,73 @@
+//! Snapshot tests for the parser
+//!
+//! Every `tests/parser/<name>.hz` is parsed, and its tree, as printed by
+//! `haze parse --format sexpr`, and its diagnostics are compared with
+//! `<name>.tree` and `<name>.errors` next to it. A program without errors
+//! has no `.errors` file. Run with `HAZE_BLESS=1` to write the snapshots
+//! from the current output instead, then review the diff.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use bumpalo::Bump;
+
+use crate::dump;
+use crate::parser3::Parser;
+
+const BLESS: &str = "HAZE_BLESS";
+
+/// The diagnostics as `line:column: error[code]: kind`, one per line
+fn diagnostics(source: &str, parser: &Parser) -> String {
+    let mut out = String::new();
+    for err in parser.errors.iter() {
+        let (line, column) = err.location.line_column(source);
+        writeln!(out, "{}:{}: error[{}]: {:?}", line, column, err.kind.code(), err.kind).unwrap();
+    }
+    out
+}
+
+/// Compares `actual` with the snapshot at `path`, or writes it there when
+/// blessing, with an empty snapshot standing for a missing file
+fn check(path: &Path, actual: &str, bless: bool, failures: &mut Vec<String>) {
+    let expected = std::fs::read_to_string(path).unwrap_or_default();
+    if expected == actual {
+        return;
+    }
+    if !bless {
+        failures.push(format!("{}\n--- expected\n{}--- actual\n{}", path.display(), expected, actual));
+    } else if actual.is_empty() {
+        std::fs::remove_file(path).unwrap();
+    } else {
+        std::fs::write(path, actual).unwrap();
+    }
+}
+
+#[test]
+fn test_parser_snapshots() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/parser");
+    let bless = std::env::var_os(BLESS).is_some();
+    let mut programs: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "hz"))
+        .collect();
+    programs.sort();
+    assert!(!programs.is_empty(), "no programs in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for path in &programs {
+        let source = std::fs::read_to_string(path).unwrap();
+        let bump = Bump::new();
+        let mut parser = Parser::new(&source, &bump);
+        let tree = parser.parse().tree;
+        check(&path.with_extension("tree"), &dump::sexpr(&tree), bless, &mut failures);
+        check(&path.with_extension("errors"), &diagnostics(&source, &parser), bless, &mut failures);
+    }
+    assert!(
+        failures.is_empty(),
+        "{} snapshot(s) differ, rerun with {}=1 to update them\n\n{}",
+        failures.len(),
+        BLESS,
+        failures.join("\n")
+    );
+}
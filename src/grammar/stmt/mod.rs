@@ -1,14 +1,13 @@
-use crate::ast2::{tag_is_binop, Node, NodeBuilder, NodeChild, NodeKind, NodeType::*};
-use crate::errors::Loc;
+use crate::ast2::{Node, NodeChild, NodeType::*};
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
-use crate::token::Tag;
-use crate::grammar::types::*;
+use crate::token::{Tag, Token};
+use crate::grammar::delimiter::is_decl_leader;
+use crate::attempt;
 pub use super::Parser;
 
 impl<'s, 'b> Parser<'s, 'b> {
-    pub(crate) fn statement(&mut self) -> Result<NodeChild<'s, 'b>, ParsingError> {
-        let tok = self.peek().unwrap();
-
+    /// Parses the statement starting at `tok`, the next token
+    pub(crate) fn statement(&mut self, tok: Token<'s>) -> Result<NodeChild<'s, 'b>, ParsingError> {
         match tok.tag {
             Tag::Let => self.var_decl().map(NodeChild::Node),
             Tag::Semicolon => {
@@ -20,12 +19,15 @@ impl<'s, 'b> Parser<'s, 'b> {
     }
 
     fn expr_stmt(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut node = NodeBuilder::from_type(ExprStmt, self.bump);
+        let mut node = self.builder(ExprStmt);
 
         let expr = match self.expr() {
             Ok(expr) => expr,
-            Err(Failed) => { self.stmt_synchronize(); return Err(Failed); },
-            Err(err) => return Err(err)
+            Err(Failed) => {
+                self.skip_into(&mut node, |parser| parser.stmt_synchronize());
+                return self.fail(node, Failed);
+            },
+            Err(err) => return self.fail(node, err)
         };
 
         match &expr {
@@ -37,46 +39,49 @@ impl<'s, 'b> Parser<'s, 'b> {
         }
 
         node.add(expr);
-        Ok(node.finish(false))
+        Ok(self.finish(node))
     }
 
     fn var_decl(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        self.next(); // consume `let`` token
-        
-        let mut node = NodeBuilder::from_type(VarDecl, self.bump);
+        let mut node = self.builder(VarDecl);
+        self.next(); // consume `let` token
 
         let ident = self.expect_token(Tag::Ident);
         node.add(ident);
 
-        if self.peek_is(Tag::Colon) { self.next(); node.add(self.type_expr()?);
-        } else { node.add(Node::null(Any)); }
+        if self.peek_is(Tag::Colon) { self.next(); node.add(attempt!(self, node, self.type_expr()));
+        } else { node.add(self.null_node()); }
 
-        if !self.expect_token(Tag::Equal).is_empty() {
+        let has_value = !self.expect_token(Tag::Equal).is_empty();
+        if has_value {
             node.add(match self.expr() {
                 Ok(expr) => expr,
                 Err(Failed) => { 
-                    // lil' hack until I figure out how to handle this better
-                    //if matches!(self.errors.last(), Some(err) if err.kind == ExpectedOperator) { self.errors.pop(); }
-                    self.handle_synchronize(); 
-                    return Err(Failed); 
+                    self.skip_into(&mut node, |parser| parser.handle_synchronize());
+                    return self.fail(node, Failed); 
                 },
-                Err(err) => return Err(err)
+                Err(err) => return self.fail(node, err)
             });
-        } else { node.add(Node::null(Any)); }
+        } else { node.add(self.null_node()); }
 
-        if self.peek_is(Tag::Semicolon) { self.next(); return Ok(node.finish(false)) }
+        // A complete declaration only misses its semicolon, like an expression statement
+        if has_value || self.peek_is(Tag::Semicolon) {
+            self.expect_token(Tag::Semicolon);
+            return Ok(self.finish(node));
+        }
 
-        self.handle_synchronize();
-        Err(Failed)
+        self.skip_into(&mut node, |parser| parser.handle_synchronize());
+        self.fail(node, Failed)
     }
 
     fn handle_synchronize(&mut self) {
+        let last = self.last.map(|tok| tok.pos);
         match self.stmt_synchronize() {
             SyncStatus::FoundSemi => { }
-            SyncStatus::FoundLeading => {
-                self.add_error(ExpectedSemi, Loc::from_token(self.peek().unwrap()));
-            }
-            SyncStatus::EOF => {
+            // The semicolon only goes right after the statement if nothing was skipped
+            SyncStatus::FoundLeading if self.last.map(|tok| tok.pos) == last => { self.expect_token(Tag::Semicolon); }
+            SyncStatus::FoundLeading => self.add_error(ExpectedSemi, self.after_last()),
+            SyncStatus::Eof => {
                 self.add_error(UnexpectedEOF, self.loc(0));
             }
         }
@@ -85,22 +90,32 @@ impl<'s, 'b> Parser<'s, 'b> {
     fn stmt_synchronize(&mut self) -> SyncStatus {
         use Tag::*;
         use SyncStatus::*;
+        // Groups past the block were opened by the failed statement
+        let depth = self.block_depth;
         loop {
             let tag = self.peek().map(|tok| tok.tag); 
             match tag {
                 Some(Semicolon) => { self.next(); return FoundSemi; },
+                Some(tag) if self.delims.len() > depth => match self.enclosing(tag) {
+                    Some(open) if open < depth => return FoundLeading,
+                    Some(_) => { self.next(); continue; },
+                    None => { self.skip(); continue; },
+                },
                 // Leading tokens for some expressions as the next statement
                 // may well be an expression statement.
                 Some(
                     | Let | Break | Return
-                    | If | Semicolon | While
+                    | If | While
                     /*How many expression leads can we add here?
                     Likely leads should be dependent on where the failure occurred.
                      */
                     | Ident
                 ) => { return FoundLeading; }
-                Some(_) => { self.next(); continue; }, 
-                None => return EOF // End of file
+                // The end of the block is left to the block, as is a
+                // declaration after a block that was never closed
+                Some(tag) if self.enclosing(tag).is_some() || is_decl_leader(tag) => { return FoundLeading; }
+                Some(_) => { self.skip(); continue; }, 
+                None => return Eof // End of file
             }
         }
     }
@@ -109,5 +124,5 @@ impl<'s, 'b> Parser<'s, 'b> {
 enum SyncStatus {
     FoundSemi,
     FoundLeading,
-    EOF
+    Eof
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::ast2::{Node, NodeBuilder, NodeKind, NodeType::*};
+use crate::ast2::{Node, NodeType::*};
 use crate::errors::Loc;
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
 use crate::token::{Tag, Token};
@@ -6,34 +6,31 @@ pub use super::Parser;
 use crate::peek_matches;
 
 impl<'s, 'b> Parser<'s, 'b> {
-    pub fn struct_decl(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
+    pub fn struct_decl(&mut self, visibility: Token<'s>) -> Result<Node<'s, 'b>, ParsingError> {
+        let mut node = self.builder_from(StructDecl, visibility);
         self.next(); // consume struct token
 
-        let mut node = NodeBuilder::from_type(StructDecl, self.bump);
-        
         // Identifiers are required but report error and keep parsing
         let struct_name = self.expect_token(Tag::Ident); 
         node.add(struct_name);
 
+        let mut fields = self.builder(FieldList);
         self.expect_token(Tag::LBrace);
 
-        let mut fields = NodeBuilder::from_type(FieldList, self.bump);
-
-        // True if an error we don't want to recover from occurs
-        let mut failed = false;
-
         loop {
             match self.struct_field() {
                 Ok(node) => fields.add(node),
                 Err(Terminal) => { 
-                    self.decl_synchronize_generic(Tag::RBrace);
-                    return Err(Failed); 
+                    self.skip_into(&mut fields, |parser| parser.decl_synchronize_generic(Tag::RBrace));
+                    return self.fail_nested(node, fields, Failed);
                 },
-                Err(CouldRecover) => {}
-                err => return err
+                // The field is kept as an invalid node
+                Err(CouldRecover) => self.adopt(&mut fields),
+                Err(err) => return self.fail_nested(node, fields, err)
             }
             let Some(tok) = self.peek() else { 
-                self.add_error(UnexpectedEOF, self.loc(0)); return Err(Failed); 
+                self.add_error(UnexpectedEOF, self.loc(0));
+                return self.fail_nested(node, fields, Failed);
             };
             match tok.tag {
                 Tag::Comma => { 
@@ -43,35 +40,96 @@ impl<'s, 'b> Parser<'s, 'b> {
                 },
                 Tag::RBrace => { self.next(); break; },
                 _ => {
-                    self.add_error(MissingFieldDelimeter, Loc::from_token(tok));
-                    self.decl_synchronize_generic(Tag::RBrace);
-                    return Err(Failed);
+                    self.add_error_fix(MissingFieldDelimeter, Loc::from_token(tok), self.insert_after_last(","));
+                    self.skip_into(&mut fields, |parser| parser.decl_synchronize_generic(Tag::RBrace));
+                    return self.fail_nested(node, fields, Failed);
                 }
             }
         }
 
-        node.add(fields.finish(false));
+        node.add(self.finish(fields));
+        node.add(visibility);
 
-        return Ok(node.finish(false));
+        Ok(self.finish(node))
     }
 
     fn struct_field(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut field = NodeBuilder::from_type(Field, self.bump);
+        let mut field = self.builder(Field);
 
+        let visibility = self.eat_token(Tag::Pub).unwrap_or(Token::empty());
         let field_name = self.expect_token(Tag::Ident);
-        if field_name.is_empty() || 
-            self.expect_token(Tag::Colon).is_empty() { return Err(Terminal); }
+        if field_name.is_empty() { return self.fail(field, Terminal); }
+        field.add(field_name);
+        if !self.peek_is(Tag::Colon) {
+            // A type right after the name is taken as missing only the colon
+            self.add_error_fix(Expected(Tag::Colon), self.after_last(), self.insert_after_last(":"));
+            if !peek_matches!(self, Tag::Ident | Tag::LBracket | Tag::LParen) { return self.fail(field, Terminal); }
+        } else { self.next(); }
 
         let field_type = match self.type_expr() {
             Ok(expr) => expr,
             // `Parser::type_expr` would have already reported some errors
-            Err(Failed) => if peek_matches!(self, Tag::Comma | Tag::RBrace) { return Err(CouldRecover); }
-                else { return Err(Terminal); }
-            Err(err) => return Err(err)
+            Err(Failed) => if peek_matches!(self, Tag::Comma | Tag::RBrace) { return self.fail(field, CouldRecover); }
+                else { return self.fail(field, Terminal); }
+            Err(err) => return self.fail(field, err)
         };
 
-        field.add(field_name); field.add(field_type);
-        Ok(field.finish(false))
+        field.add(field_type); field.add(visibility);
+        Ok(self.finish(field))
+    }
+
+    pub fn enum_decl(&mut self, visibility: Token<'s>) -> Result<Node<'s, 'b>, ParsingError> {
+        let mut node = self.builder_from(EnumDecl, visibility);
+        self.next(); // consume enum token
+
+        // Identifiers are required but report error and keep parsing
+        let enum_name = self.expect_token(Tag::Ident);
+        node.add(enum_name);
+
+        let mut variants = self.builder(VariantList);
+        self.expect_token(Tag::LBrace);
+
+        loop {
+            if self.peek_is(Tag::RBrace) { self.next(); break; }
+
+            let mut variant = self.builder(Variant);
+            let tag = self.expect_token(Tag::Ident);
+            if tag.is_empty() {
+                let _ = self.fail::<()>(variant, Failed);
+                self.skip_into(&mut variants, |parser| parser.decl_synchronize_generic(Tag::RBrace));
+                return self.fail_nested(node, variants, Failed);
+            }
+            variant.add(tag);
+
+            // Payload type of a tuple variant, e.g. `Some(i32)`
+            let mut variant_type = Token::empty();
+            if self.peek_is(Tag::LParen) {
+                self.next();
+                variant_type = self.expect_token(Tag::Ident);
+                self.expect_token(Tag::RParen);
+            }
+            variant.add(variant_type);
+            variants.add(self.finish(variant));
+
+            let Some(tok) = self.peek() else {
+                self.add_error(UnexpectedEOF, self.loc(0));
+                return self.fail_nested(node, variants, Failed);
+            };
+            match tok.tag {
+                Tag::Comma => { self.next(); },
+                Tag::RBrace => { self.next(); break; },
+                _ => {
+                    self.add_error(MissingVariantDelimeter, Loc::from_token(tok));
+                    self.skip_into(&mut variants, |parser| parser.decl_synchronize_generic(Tag::RBrace));
+                    return self.fail_nested(node, variants, Failed);
+                }
+            }
+        }
+
+        node.add(self.finish(variants));
+        node.add(visibility);
+
+        Ok(self.finish(node))
     }
 }
 
@@ -80,7 +138,7 @@ mod tests {
     use bumpalo::Bump;
 
     use super::*;
-    use crate::ast2::{StructDecl, AstNode, AstToken, Field, Ident};
+    use crate::ast2::{StructDecl, AstNode, AstToken, TopDeclList, TopLevelDecl};
 
     #[test]
     fn test_struct_decl_valid() {
@@ -92,26 +150,51 @@ struct Flags {
 }
 ", &bump);
 
-        let result = parser.struct_decl();
+        let result = parser.struct_decl(Token::empty());
         assert!(result.is_ok());
         let node = bump.alloc(result.unwrap());
 
-        let result = StructDecl::cast(&node);
+        let result = StructDecl::cast(node);
         
         assert!(result.name().token().value == "Flags");
-        let mut fields = result.fields()
+        let fields = result.fields()
             .items()
             .map(|field| (field.name().token().value, field.field_type().token().value));
         assert!(fields.eq([("visible", "bool"), ("ready", "bool")]));
     }
 
+    #[test]
+    fn test_visibility() {
+        let bump = Bump::new();
+        let mut parser = Parser::new("
+pub struct Flags { pub visible: bool, ready: bool }
+enum Shape { Circle(f32), Empty }
+", &bump);
+
+        let tree = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty());
+
+        let mut decls = TopDeclList::cast(tree).items();
+        let Some(TopLevelDecl::Struct(flags)) = decls.next() else { panic!("expected struct") };
+        assert!(flags.visibility().is_some());
+        let fields: std::vec::Vec<_> = flags.fields().items().map(|f| f.visibility().is_some()).collect();
+        assert_eq!(fields, [true, false]);
+
+        let Some(TopLevelDecl::Enum(shape)) = decls.next() else { panic!("expected enum") };
+        assert!(shape.visibility().is_none());
+        let variants: std::vec::Vec<_> = shape.variants()
+            .items()
+            .map(|v| (v.tag().token().value, v.variant_type().map(|t| t.token().value)))
+            .collect();
+        assert_eq!(variants, [("Circle", Some("f32")), ("Empty", None)]);
+    }
+
     #[test]
     fn test_struct_parse_error() {
         let bump = Bump::new();
         let mut parser = Parser::new("struct Flags { : bool, ready: bool }", &bump);
 
-        let result = parser.struct_decl();
-        parser.errors.iter().for_each(|err| println!("{:?}", err));
-        
+        let _ = parser.struct_decl(Token::empty());
+        assert!(!parser.errors.is_empty());
     }
 }
\ No newline at end of file
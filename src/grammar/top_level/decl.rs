@@ -1,50 +1,47 @@
-use crate::ast2::{Node, NodeBuilder, NodeKind, NodeType::*};
+use crate::ast2::{Node, NodeType::*};
 use crate::errors::Loc;
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
-use crate::token::Tag;
+use crate::token::{Tag, Token};
+use crate::grammar::delimiter::is_decl_leader;
+use crate::attempt;
 pub use super::Parser;
 
 impl<'s, 'b> Parser<'s, 'b> {
     pub fn module(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
+        let mut node = self.builder(Module);
         self.next(); // consume module token
-        let mut node = NodeBuilder::from_type(Module, self.bump);
 
         // Identifiers are required but report error and keep parsing
         let ident = self.expect_token(Tag::Ident); 
         node.add(ident);
         
-        let mut decls = NodeBuilder::from_type(TopDeclList, self.bump);
+        let mut decls = self.builder(TopDeclList);
 
         self.expect_token(Tag::LBrace);
 
-        while let Some(_) = self.peek() {
-            match self.top_level_declaration() {
-                Ok(decl) => decls.add(decl),
-                // Fatal errors always bubble up
-                err @ Err(Fatal) => return err,
-                // Skip declaration if parsing fails at some point
-                Err(_) => continue
-            }
+        while let Some(tok) = self.peek() {
+            if tok.tag == Tag::RBrace { break; }
+            decls.add(self.declaration());
         }
 
         self.expect_token(Tag::RBrace);
 
-        node.add(decls.finish(false));
+        node.add(self.finish(decls));
     
-        Ok(node.finish(false))
+        Ok(self.finish(node))
     }
 
 
     pub fn import(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
+        let mut import_path = self.builder(ImportDecl);
         self.next(); // consume import token
-        let mut import_path = NodeBuilder::from_type(ImportDecl, self.bump);
 
         let mut needs_ident = true;
 
         loop {
             let Some(tok) = self.peek() else {
                 self.add_error(Expected(Tag::Ident), self.loc(0));
-                return Err(Failed);
+                return self.fail(import_path, Failed);
             };
             match tok.tag {
                 Tag::Ident if needs_ident => { 
@@ -56,8 +53,8 @@ impl<'s, 'b> Parser<'s, 'b> {
                 Tag::Semicolon if !needs_ident => { self.next(); break; }
                 _ => {
                     self.add_error(MalformedImportPath, Loc::from_token(tok));
-                    self.decl_synchronize();
-                    return Err(Failed);
+                    self.skip_into(&mut import_path, |parser| parser.decl_synchronize());
+                    return self.fail(import_path, Failed);
                 }
             }
         }
@@ -92,7 +89,7 @@ impl<'s, 'b> Parser<'s, 'b> {
         //     } else { import_path.add(ident); }
         // }
 
-        Ok(import_path.finish(false))
+        Ok(self.finish(import_path))
     }
 
     /// Skip tokens until the another declaration can likely be parsed
@@ -102,15 +99,26 @@ impl<'s, 'b> Parser<'s, 'b> {
     /// 
     /// This is a simple implementation of Panic Mode error recovery
     pub(crate) fn decl_synchronize_generic(&mut self, closer: Tag) {
-        use Tag::*;
+        self.synchronize_decl(closer, self.delims.len())
+    }
+
+    /// Panic mode recovery that closes the groups opened past `depth`
+    ///
+    /// The closer only stops it outside of those groups, and it never skips
+    /// past the closer of a group opened before `depth`.
+    pub(crate) fn synchronize_decl(&mut self, closer: Tag, depth: usize) {
         loop {
             let tag = self.peek().map(|tok| tok.tag); 
             // The `top_level_declaration` method must always peek
             // leading tokens so don't consume them
             match tag {
-                Some(t) if t == closer => { self.next(); },
-                Some(Module | Import | Enum | Fn | Struct | Type | Const) => { },
-                Some(_) => { self.next(); continue; }, 
+                Some(t) if t == closer && self.delims.len() <= depth => { self.next(); },
+                Some(t) if is_decl_leader(t) => { },
+                Some(t) => match self.enclosing(t) {
+                    Some(open) if open >= depth => { self.next(); continue; }
+                    Some(_) => { },
+                    None => { self.skip(); continue; }
+                },
                 None => return // End of file
             }
             break;
@@ -119,9 +127,9 @@ impl<'s, 'b> Parser<'s, 'b> {
 
     fn decl_synchronize(&mut self) { self.decl_synchronize_generic(Tag::Semicolon) }
 
-    pub(crate) fn type_alias(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
+    pub(crate) fn type_alias(&mut self, visibility: Token<'s>) -> Result<Node<'s, 'b>, ParsingError> {
+        let mut node = self.builder_from(TypeAlias, visibility);
         self.next(); // consume `type` token
-        let mut node = NodeBuilder::from_type(TypeAlias, self.bump);
 
         let ident = self.expect_token(Tag::Ident);
         node.add(ident);
@@ -129,38 +137,98 @@ impl<'s, 'b> Parser<'s, 'b> {
         self.expect_token(Tag::Equal);
         
         let type_value = match self.type_expr() {
+            Ok(type_expr) => type_expr,
             // Type errors would have already reported some errors
-            Err(Failed) => { self.decl_synchronize(); return Err(Failed); },
-            type_expr => type_expr 
-        }?; // <-- Propagate other errors if any
+            Err(Failed) => {
+                self.skip_into(&mut node, |parser| parser.decl_synchronize());
+                return self.fail(node, Failed);
+            },
+            Err(err) => return self.fail(node, err),
+        };
 
         node.add(type_value);
+        node.add(visibility);
+
+        self.expect_token(Tag::Semicolon);
 
-        Ok(node.finish(false))
+        Ok(self.finish(node))
     }
 
-    pub(crate) fn const_decl(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
+    pub(crate) fn const_decl(&mut self, visibility: Token<'s>) -> Result<Node<'s, 'b>, ParsingError> {
+        let mut node = self.builder_from(ConstDecl, visibility);
         self.next(); // consume `const` token
-        let mut node = NodeBuilder::from_type(ConstDecl, self.bump);
 
         let ident = self.expect_token(Tag::Ident);
         node.add(ident);
 
-        self.expect_token(Tag::Semicolon);
-        let const_type = self.type_expr()?;
+        self.expect_token(Tag::Colon);
+        let const_type = attempt!(self, node, self.type_expr());
 
         node.add(const_type);
 
         self.expect_token(Tag::Equal);
         
         let type_value = match self.expr() {
-            // Type errors would have already reported some errors
-            Err(Failed) => { self.decl_synchronize(); return Err(Failed); },
-            type_expr => type_expr 
-        }?; // <-- Propagate other errors if any
+            Ok(expr) => expr,
+            // Expression errors would have already reported some errors
+            Err(Failed) => {
+                self.skip_into(&mut node, |parser| parser.decl_synchronize());
+                return self.fail(node, Failed);
+            },
+            Err(err) => return self.fail(node, err),
+        };
 
         node.add(type_value);
+        node.add(visibility);
+
+        self.expect_token(Tag::Semicolon);
+
+        Ok(self.finish(node))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::ast2::{AstNode, AstToken, TopDeclList, TopLevelDecl};
+    use crate::errors::ParseErrorKind;
+
+    fn error_kinds(source: &str) -> std::vec::Vec<ParseErrorKind> {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        parser.parse();
+        std::mem::take(&mut parser.errors).into_iter().map(|err| err.kind).collect()
+    }
+
+    #[test]
+    fn test_type_alias_and_const() {
+        let bump = Bump::new();
+        let mut parser = Parser::new("type Meters = f64;\nconst LIMIT: i64 = 10;\nfn main() {}", &bump);
+        let tree = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+
+        let mut decls = TopDeclList::cast(tree).items();
+        let Some(TopLevelDecl::Type(alias)) = decls.next() else { panic!("expected type alias") };
+        assert_eq!(alias.name().token().value, "Meters");
+        let Some(TopLevelDecl::Const(limit)) = decls.next() else { panic!("expected const") };
+        assert_eq!(limit.name().token().value, "LIMIT");
+        assert!(matches!(decls.next(), Some(TopLevelDecl::Fn(_))));
+
+        // Both end with `;`, and the type of a constant follows a `:`
+        assert_eq!(error_kinds("type Meters = f64\nfn main() {}"), [ExpectedSemi]);
+        assert_eq!(error_kinds("const LIMIT: i64 = 10"), [ExpectedSemi]);
+        assert_eq!(error_kinds("const LIMIT i64 = 10;"), [Expected(Tag::Colon)]);
+    }
+
+    #[test]
+    fn test_module_ends_at_its_brace() {
+        let bump = Bump::new();
+        let mut parser = Parser::new("module math { fn one() {} }\nfn main() {}", &bump);
+        let tree = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
 
-        Ok(node.finish(false))
+        let decls: std::vec::Vec<_> = TopDeclList::cast(tree).items().collect();
+        assert!(matches!(decls[..], [TopLevelDecl::Mod(_), TopLevelDecl::Fn(_)]));
     }
-}
\ No newline at end of file
+}
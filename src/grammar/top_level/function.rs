@@ -1,13 +1,15 @@
-use crate::ast2::{Node, NodeBuilder, NodeKind, NodeType::*, NodeChild};
+use crate::ast2::{Node, NodeBuilder, NodeType::*, NodeChild};
 use crate::errors::Loc;
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
+use crate::errors::TextEdit;
 use crate::token::{Tag, Token};
+use crate::attempt;
 pub use super::Parser;
 
 impl<'s, 'b> Parser<'s, 'b> {
-    pub(crate) fn function_def(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
+    pub(crate) fn function_def(&mut self, visibility: Token<'s>) -> Result<Node<'s, 'b>, ParsingError> {
+        let mut node = self.builder_from(FnDef, visibility);
         self.next(); // consume fn token
-        let mut node = NodeBuilder::from_type(FnDef, self.bump);
 
         let ident = self.expect_token(Tag::Ident);
         node.add(ident);
@@ -16,73 +18,109 @@ impl<'s, 'b> Parser<'s, 'b> {
 
         match self.peek() {
             Some(tok) => match tok.tag {
-                Tag::LParen => { self.next(); parameters = self.function_params()? },
+                Tag::LParen => parameters = attempt!(self, node, self.function_params()),
                 _ => {
                     self.add_error(ExpectedFunctionParameters, Loc::from_token(tok));
-                    return Err(Failed);
+                    return self.fail(node, Failed);
                 }
             }
             None => {
                 self.add_error(ExpectedFunctionParameters, self.loc(0));
                 self.add_error(UnexpectedEOF, self.loc(0));
-                return Err(Failed); 
+                return self.fail(node, Failed);
             }
         }
 
         node.add(parameters);
 
-        let mut return_expr = NodeChild::Node(Node::null(Any));
-        if self.peek_is(Tag::Arrow) { 
+        let return_expr = if self.peek_is(Tag::Arrow) { 
             self.next(); 
-            return_expr = self.type_expr()?;
-        }
+            match self.type_expr() {
+                Ok(return_type) => return_type,
+                // The missing type is reported, and the body parsed all the same
+                Err(Failed) if self.peek_is(Tag::LBrace) => {
+                    let mut missing = self.builder(Any);
+                    self.adopt(&mut missing);
+                    self.finish_invalid(missing).into()
+                }
+                Err(err) => return self.fail(node, err),
+            }
+        } else { self.null_node().into() };
     
-        let mut body = NodeChild::Node(Node::null(Any));
-        if self.peek_is(Tag::LBrace) { self.next(); body = self.block_expr()?.into(); }
-        // Note to self: Look at the order of fields defined in 
-        // `language_nodes copy.txt`. Don't modify otherwise
+        let body: NodeChild = if self.peek_is(Tag::LBrace) { self.next(); attempt!(self, node, self.block_expr()).into() }
+        else { self.null_node().into() };
+        // Children go in the order of the fields in `language_nodes.txt`
 
-        node.add(body); node.add(return_expr);
+        node.add(body); node.add(return_expr); node.add(visibility);
 
-        Ok(node.finish(false))
+        Ok(self.finish(node))
     }
 
     fn function_params(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut params = NodeBuilder::from_type(ParamList, self.bump);
+        let mut params = self.builder(ParamList);
+        self.next(); // consume `(` token
 
-        if self.peek_is(Tag::RParen) { self.next(); return Ok(params.finish(false)); }
+        if self.peek_is(Tag::RParen) { self.next(); return Ok(self.finish(params)); }
  
         loop {
-            let mut param = NodeBuilder::from_type(Param, self.bump);
+            let mut param = self.builder(Param);
             let ident = self.expect_token(Tag::Ident);
             param.add(ident);
 
-            let tok = self.peek().ok_or(Failed)?;
+            let Some(tok) = self.peek() else { return self.fail_nested(params, param, Failed) };
             match tok.tag {
                 Tag::Colon => { 
                     self.next();
-                    let param_type = self.type_expr()?;
-                    param.add(param_type);
+                    match self.type_expr() {
+                        Ok(param_type) => param.add(param_type),
+                        Err(Failed) => {
+                            use SyncStatus::*;
+                            match self.param_recover(&mut params, param) {
+                                FoundComma => { self.next(); continue; },
+                                FoundClosingParen => { self.next(); return Ok(self.finish(params)) },
+                                Eof => return self.fail(params, Failed)
+                            }
+                        }
+                        Err(err) => return self.fail_nested(params, param, err),
+                    }
                 }
                 _ => {
                     self.add_error(ParamIncomplete, Loc::from_token(tok));
-                    self.add_error(ExpectedColon, Loc::from_token(tok));
+                    match ident.is_empty() {
+                        true => self.add_error(ExpectedColon, self.after_last()),
+                        false => {
+                            let end = ident.pos + ident.value.len() as u32;
+                            self.add_error_fix(ExpectedColon, self.after_last(), TextEdit::new(end..end, ":"));
+                        }
+                    }
                     use SyncStatus::*;
-                    match self.param_synchronize() {
+                    match self.param_recover(&mut params, param) {
                         FoundComma => { self.next(); continue; },
-                        FoundClosingParen => { self.next(); return Ok(params.finish(false)) },
-                        EOF => return Err(Failed)
+                        FoundClosingParen => { self.next(); return Ok(self.finish(params)) },
+                        Eof => return self.fail(params, Failed)
                     }
                 }
             }
 
-            params.add(param.finish(false));
+            params.add(self.finish(param));
 
             if self.peek_is(Tag::Comma) { self.next(); continue; }
             if self.peek_is(Tag::RParen) { self.next(); break; }
+            // Another parameter follows without a comma
+            if self.peek_is(Tag::Ident) {
+                self.add_error_fix(Expected(Tag::Comma), self.after_last(), self.insert_after_last(","));
+            }
         }
 
-        Ok(params.finish(false))
+        Ok(self.finish(params))
+    }
+
+    /// Keeps a failed parameter as an invalid node, along with the tokens
+    /// skipped up to the next one
+    fn param_recover(&mut self, params: &mut NodeBuilder<'s, 'b>, mut param: NodeBuilder<'s, 'b>) -> SyncStatus {
+        let status = self.skip_into(&mut param, |parser| parser.param_synchronize());
+        params.add(self.finish_invalid(param));
+        status
     }
 
     fn param_synchronize(&mut self) -> SyncStatus {
@@ -93,8 +131,8 @@ impl<'s, 'b> Parser<'s, 'b> {
             match tag {
                 Some(Comma) => { return FoundComma; },
                 Some(RParen) => { return FoundClosingParen; }
-                Some(_) => { self.next(); continue; }, 
-                None => return EOF // End of file
+                Some(_) => { self.skip(); continue; }, 
+                None => return Eof // End of file
             }
         }
     }
@@ -103,7 +141,7 @@ impl<'s, 'b> Parser<'s, 'b> {
 enum SyncStatus {
     FoundComma,
     FoundClosingParen,
-    EOF
+    Eof
 }
 
 #[cfg(test)]
@@ -111,7 +149,6 @@ mod tests {
     use bumpalo::Bump;
 
     use super::*;
-    use crate::ast2::{FnDef, AstNode, AstToken, Ident};
 
     #[test]
     fn test_fn() {
@@ -140,7 +177,7 @@ fn bring(num: int) {
 
 "#, &bump);
 
-        let result = parser.function_def();
+        let result = parser.function_def(Token::empty());
         println!("{:#}", if let Ok(node) = result { node } else { Node::null(Any) });
         parser.errors.iter().for_each(|err| println!("{:?}", err));
         
@@ -191,10 +228,10 @@ fn foo(a, b, c, d) {
 
 "#, &bump);
 
-        let result = parser.parse();
+        let result = parser.parse().tree;
         println!("{:#}", result);
 
-        parser.debug_errors();
+        assert!(!parser.errors.is_empty());
     }
 }
 
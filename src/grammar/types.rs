@@ -1,7 +1,7 @@
-use crate::ast2::{Node, NodeBuilder, NodeChild, NodeKind, NodeType::*};
-use crate::errors::Loc;
+use crate::ast2::{Node, NodeChild, NodeType::*};
 use crate::errors::{ParsingError::{*, self}, ParseErrorKind::*};
 use crate::token::Tag;
+use crate::attempt;
 pub use crate::parser3::Parser;
 
 impl<'s, 'b> Parser<'s, 'b> {
@@ -11,37 +11,37 @@ impl<'s, 'b> Parser<'s, 'b> {
                 Tag::LBracket => { self.next(); self.array_type().map(NodeChild::Node) }
                 Tag::LParen => { 
                     self.next(); 
-                    let mut node = NodeBuilder::from_type(GroupType, self.bump);
-                    let inner = self.type_expr()?;
+                    let mut node = self.builder_from_last(GroupType);
+                    let inner = attempt!(self, node, self.type_expr());
                     node.add(inner);
                     if self.expect_token(Tag::RParen).is_empty() { 
-                        return Err(Failed);
-                    } else { Ok(node.finish(false).into()) }
+                        self.fail(node, Failed)
+                    } else { Ok(self.finish(node).into()) }
 
                 }
                 Tag::Ident => { self.next(); Ok(NodeChild::Token(tok)) }
                 _ => {
-                    self.add_error(ExpectedType, self.loc(0));
-                    return Err(Failed);
+                    self.add_error(ExpectedType, self.after_last());
+                    Err(Failed)
                 }
 
             }
             None => {
-                self.add_error(ExpectedType, self.loc(0));
-                return Err(Failed);
+                self.add_error(ExpectedType, self.after_last());
+                Err(Failed)
             }
         }
     }
 
     pub(crate) fn array_type(&mut self) -> Result<Node<'s, 'b>, ParsingError> {
-        let mut node = NodeBuilder::from_type(ArrayType, self.bump);
+        let mut node = self.builder_from_last(ArrayType);
 
-        node.add(self.type_expr()?);
+        node.add(attempt!(self, node, self.type_expr()));
         self.expect_token(Tag::Semicolon);
         node.add(self.expect_token(Tag::Number));
 
         self.expect_token(Tag::RBracket);
 
-        return Ok(node.finish(false));
+        Ok(self.finish(node))
     }
 }
\ No newline at end of file
@@ -0,0 +1,194 @@
+//! Semantic highlighting
+//!
+//! Tokens are classified by their tag, except identifiers, which take the
+//! class of whatever `names` resolves them to. Comments never reach the
+//! parser, so they are found in the gaps between tokens.
+
+use hashbrown::HashMap;
+
+use crate::errors::Loc;
+use crate::lexer::Lexer;
+use crate::names::{Name, Names};
+use crate::token::Tag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Keyword,
+    Type,
+    Function,
+    Parameter,
+    Local,
+    Field,
+    String,
+    Number,
+    Comment,
+    Operator,
+}
+
+impl Class {
+    /// Every class, in the order of their discriminants
+    pub const ALL: [Class; 10] = [
+        Class::Keyword,
+        Class::Type,
+        Class::Function,
+        Class::Parameter,
+        Class::Local,
+        Class::Field,
+        Class::String,
+        Class::Number,
+        Class::Comment,
+        Class::Operator,
+    ];
+
+    /// Name of the class in the semantic tokens legend of LSP
+    pub fn lsp_name(self) -> &'static str {
+        match self {
+            Class::Keyword => "keyword",
+            Class::Type => "type",
+            Class::Function => "function",
+            Class::Parameter => "parameter",
+            Class::Local => "variable",
+            Class::Field => "property",
+            Class::String => "string",
+            Class::Number => "number",
+            Class::Comment => "comment",
+            Class::Operator => "operator",
+        }
+    }
+
+    /// SGR parameters of the class on a terminal
+    fn ansi(self) -> &'static str {
+        match self {
+            Class::Keyword => "35",
+            Class::Type => "33",
+            Class::Function => "34",
+            Class::Parameter => "3;36",
+            Class::Local => "36",
+            Class::Field => "96",
+            Class::String => "32",
+            Class::Number => "31",
+            Class::Comment => "90",
+            Class::Operator => "1",
+        }
+    }
+}
+
+/// A classified span of the source
+#[derive(Debug, Clone, Copy)]
+pub struct Highlight {
+    pub location: Loc,
+    pub class: Class,
+}
+
+/// Classifies every token and comment of a program
+pub fn highlight(source: &str) -> Vec<Highlight> {
+    classify(source, &Names::new(source).names)
+}
+
+/// Classifies a program whose names were already resolved
+///
+/// Identifiers that were never resolved, like those the parser skipped
+/// over, are left out.
+pub fn classify(source: &str, names: &[Name]) -> Vec<Highlight> {
+    let names: HashMap<u32, Class> = names.iter().map(|name| (name.location.start, name.class)).collect();
+    let mut highlights = Vec::new();
+    let mut end = (0, 1);
+
+    for token in Lexer::from(source) {
+        comments(source, end, token.pos, &mut highlights);
+        end = (token.pos + token.value.len() as u32, token.line + token.value.matches('\n').count() as u32);
+
+        let class = match token.tag {
+            Tag::Ident => names.get(&token.pos).copied(),
+            Tag::String | Tag::UnexpectedEof => Some(Class::String),
+            Tag::Number => Some(Class::Number),
+            Tag::Bool | Tag::Fn | Tag::If | Tag::Else | Tag::Return | Tag::While | Tag::For | Tag::Let
+            | Tag::Break | Tag::Continue | Tag::Module | Tag::Struct | Tag::Enum | Tag::Import
+            | Tag::Type | Tag::Const | Tag::Pub => Some(Class::Keyword),
+            Tag::Plus | Tag::PlusEqual | Tag::Minus | Tag::MinusEqual | Tag::Slash | Tag::SlashEqual
+            | Tag::Asterisk | Tag::AsteriskEqual | Tag::Dot | Tag::DotDot | Tag::Bang | Tag::BangEqual
+            | Tag::Equal | Tag::EqualEqual | Tag::Greater | Tag::GreaterEqual | Tag::Less | Tag::LessEqual
+            | Tag::Arrow => Some(Class::Operator),
+            _ => None,
+        };
+        if let Some(class) = class {
+            highlights.push(Highlight { location: Loc::from_token(token), class });
+        }
+    }
+    comments(source, end, source.len() as u32, &mut highlights);
+    highlights
+}
+
+/// Finds the comments between the end of a token and the next one, which
+/// is only whitespace otherwise
+fn comments(source: &str, (mut start, mut line): (u32, u32), end: u32, highlights: &mut Vec<Highlight>) {
+    let gap = &source[start as usize..end as usize];
+    let mut rest = gap;
+    while let Some(comment) = rest.find("//") {
+        line += rest[..comment].matches('\n').count() as u32;
+        start += comment as u32;
+        let len = rest[comment..].find('\n').unwrap_or(rest.len() - comment);
+        highlights.push(Highlight { location: Loc::new(start, len as u32, line), class: Class::Comment });
+        start += len as u32;
+        rest = &rest[comment + len..];
+    }
+}
+
+/// Renders a program with its highlights as ANSI escape codes
+pub fn ansi(source: &str, highlights: &[Highlight]) -> String {
+    let mut out = String::with_capacity(source.len() * 2);
+    let mut last = 0;
+    for highlight in highlights {
+        let start = highlight.location.start as usize;
+        let end = start + highlight.location.len as usize;
+        out.push_str(&source[last..start]);
+        out.push_str(&format!("\x1b[{}m{}\x1b[0m", highlight.class.ansi(), &source[start..end]));
+        last = end;
+    }
+    out.push_str(&source[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ansi, highlight, Class};
+
+    fn classes(source: &str) -> Vec<(&str, Class)> {
+        highlight(source).iter()
+            .map(|h| (&source[h.location.start as usize..(h.location.start + h.location.len) as usize], h.class))
+            .collect()
+    }
+
+    #[test]
+    fn test_classes() {
+        let source = "// points\nstruct P { x: int }\n\nfn f(p: P, n: int) -> int {\n    let y = p.x + n; // sum\n    return f(y, 1);\n}\n";
+        let expected = vec![
+            ("// points", Class::Comment),
+            ("struct", Class::Keyword), ("P", Class::Type), ("x", Class::Field), ("int", Class::Type),
+            ("fn", Class::Keyword), ("f", Class::Function), ("p", Class::Parameter), ("P", Class::Type),
+            ("n", Class::Parameter), ("int", Class::Type), ("->", Class::Operator), ("int", Class::Type),
+            ("let", Class::Keyword), ("y", Class::Local), ("=", Class::Operator), ("p", Class::Parameter),
+            (".", Class::Operator), ("x", Class::Field), ("+", Class::Operator), ("n", Class::Parameter),
+            ("// sum", Class::Comment),
+            ("return", Class::Keyword), ("f", Class::Function), ("y", Class::Local), ("1", Class::Number),
+        ];
+        assert_eq!(classes(source), expected);
+    }
+
+    #[test]
+    fn test_calls_and_locals() {
+        let source = "fn main() {\n    let print_it = \"hi\";\n    print(print_it);\n}";
+        assert_eq!(classes(source)[5..], [
+            ("\"hi\"", Class::String),
+            ("print", Class::Function),
+            ("print_it", Class::Local),
+        ]);
+    }
+
+    #[test]
+    fn test_ansi() {
+        let source = "const N: int = 1; // n\n";
+        let text = ansi(source, &highlight(source));
+        assert_eq!(text, "\x1b[35mconst\x1b[0m \x1b[36mN\x1b[0m: \x1b[33mint\x1b[0m \x1b[1m=\x1b[0m \x1b[31m1\x1b[0m; \x1b[90m// n\x1b[0m\n");
+    }
+}
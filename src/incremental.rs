@@ -0,0 +1,548 @@
+//! Incremental reparsing
+//!
+//! A [`Document`] keeps a parsed tree together with where each top-level
+//! declaration and block in it starts. After an edit, only the innermost
+//! block containing the edit is relexed and reparsed. When there is no such
+//! block, or the new text doesn't close it with the same brace, the
+//! declarations are reparsed from the one containing the edit until the
+//! parser reaches the (shifted) start of an old declaration again.
+//! Everything outside the reparsed window is moved into the new tree, with
+//! the positions of tokens after the edit shifted. The language server keeps
+//! one for each open document.
+//!
+//! The tree doesn't store braces, so the parser records block spans as it
+//! goes. They are only kept for declarations without errors, where recovery
+//! can't have thrown away a parsed block.
+
+use std::ops::Range;
+
+use bumpalo::Bump;
+
+use crate::ast2::{Node, NodeBuilder, NodeChild, NodeType, SpanTable};
+use crate::errors::{ParseError, TextEdit};
+use crate::lexer::Lexer;
+use crate::parser3::{BlockSpan, Parser};
+use crate::token::{Tag, Token};
+
+/// A parsed source that can be edited
+pub struct Document<'s, 'b> {
+    source: &'s str,
+    bump: &'b Bump,
+    tree: Node<'s, 'b>,
+    segments: Vec<Segment>,
+    /// Spans of the nodes of the tree, compacted after every edit
+    spans: &'b SpanTable<'b>,
+}
+
+/// A top-level declaration and what parsing it produced, one for each
+/// child of the tree
+#[derive(Debug)]
+struct Segment {
+    /// Offset and line of the first token
+    start: u32,
+    line: u32,
+    errors: Vec<ParseError>,
+    /// Blocks ordered by their opening brace, empty when they can't be trusted
+    blocks: Vec<BlockSpan>,
+    /// Furthest offset parsing it read ahead to, past its end
+    lookahead: u32,
+}
+
+/// Declarations parsed from some offset
+struct Parsed<'s, 'b> {
+    nodes: Vec<Node<'s, 'b>>,
+    segments: Vec<Segment>,
+    /// Whether parsing stopped at the start of an old declaration
+    resynced: bool,
+}
+
+impl<'s, 'b> Document<'s, 'b> {
+    pub fn parse(source: &'s str, bump: &'b Bump) -> Self {
+        let spans = bump.alloc(SpanTable::new_in(bump));
+        let parsed = parse_decls(source, bump, spans, 0, 1, |_| false);
+        let mut tree = NodeBuilder::new(NodeType::TopDeclList, bump, spans, 0);
+        for node in parsed.nodes {
+            tree.add(node);
+        }
+        Self { source, bump, tree: tree.finish(false), segments: parsed.segments, spans }
+    }
+
+    pub fn source(&self) -> &'s str {
+        self.source
+    }
+
+    pub fn tree(&self) -> &Node<'s, 'b> {
+        &self.tree
+    }
+
+    /// Byte ranges of the nodes of the tree, indexed by `Node::id`
+    ///
+    /// Edits renumber the nodes, so ids are only valid until the next one.
+    pub fn spans(&self) -> &'b SpanTable<'b> {
+        self.spans
+    }
+
+    /// Errors in source order, the same as `Parser::errors` after a full parse
+    pub fn errors(&self) -> impl Iterator<Item = &ParseError> {
+        self.segments.iter().flat_map(|segment| segment.errors.iter())
+    }
+
+    /// Updates the tree for `source`, which must be the previous source with
+    /// `edit` applied. Returns the range of the new source that was reparsed.
+    pub fn edit(&mut self, edit: &TextEdit, source: &'s str) -> Range<u32> {
+        debug_assert_eq!(edit.apply(self.source), source);
+        let shift = Shift::new(self.source, edit);
+        let index = self.segments.partition_point(|segment| segment.start < edit.range.start).saturating_sub(1);
+
+        let reparsed = match self.reparse_block(index, edit, shift, source) {
+            Some(reparsed) => reparsed,
+            None => self.reparse_decls(index, edit, shift, source),
+        };
+        // Replaced nodes leave their spans behind, and the ids in between
+        self.spans.compact(&mut self.tree);
+        self.source = source;
+        reparsed
+    }
+
+    /// Reparses the innermost block of the declaration at `index` that
+    /// contains the edit, if it still ends at the same brace
+    fn reparse_block(&mut self, index: usize, edit: &TextEdit, shift: Shift, source: &'s str) -> Option<Range<u32>> {
+        let segment = self.segments.get(index)?;
+        if !segment.errors.is_empty() {
+            return None;
+        }
+        let k = segment.blocks.iter()
+            .rposition(|block| block.open <= edit.range.start && edit.range.end <= block.close)?;
+        let span = segment.blocks[k];
+
+        let mut parser = Parser::at(source, self.bump, span.open, span.line);
+        parser.spans = self.spans;
+        parser.last = Some(Token::new(Tag::LBrace, "{", span.open - 1, span.line));
+        parser.delims.push(parser.last.unwrap());
+        parser.blocks = Some(Vec::new());
+        let block = parser.block_expr().ok()?;
+        // Errors depend on the delimiters open around the block, which it can't see
+        if !parser.errors.is_empty() {
+            return None;
+        }
+        let mut blocks = parser.blocks.take().unwrap_or_default();
+        // What follows the block is only unchanged if it ends at the old brace
+        let close = shift.pos(span.close);
+        if blocks.last() != Some(&BlockSpan { open: span.open, line: span.line, close }) {
+            return None;
+        }
+
+        let children = &mut self.tree.children.as_mut()?.0[index..];
+        for child in children.iter_mut() {
+            shift.child(child, self.spans);
+        }
+        let NodeChild::Node(decl) = &mut children[0] else { unreachable!() };
+        // The enclosing nodes span the braces around the edit, so shifting moved their ends
+        *nth_block(decl, &mut k.clone()).expect("block spans match the tree") = block;
+
+        blocks.sort_by_key(|block| block.open);
+        let segment = &mut self.segments[index];
+        let inner = segment.blocks[k + 1..].iter().take_while(|block| block.open < span.close).count();
+        let after = segment.blocks.split_off(k + 1 + inner);
+        segment.blocks.truncate(k);
+        for block in segment.blocks.iter_mut() {
+            shift.block(block);
+        }
+        segment.blocks.extend(blocks);
+        segment.blocks.extend(after.into_iter().map(|mut block| { shift.block(&mut block); block }));
+        for segment in self.segments[index + 1..].iter_mut() {
+            shift.segment(segment);
+        }
+        Some(span.open..close + 1)
+    }
+
+    /// Reparses declarations from the one at `index`, until an old one starts
+    fn reparse_decls(&mut self, index: usize, edit: &TextEdit, shift: Shift, source: &'s str) -> Range<u32> {
+        // Declarations peek at the first token of the next one, and failed
+        // ones skip up to it, so editing that token can change the one before
+        let mut index = match self.segments.get(index) {
+            Some(segment) if index > 0 && edit.range.start <= first_token_end(self.source, segment) => index - 1,
+            _ => index,
+        };
+        // Failed ones also look for the closers of groups they skip, however far
+        if let Some(first) = self.segments[..index].iter().position(|segment| segment.lookahead >= edit.range.start) {
+            index = first;
+        }
+        let (offset, line) = match self.segments.get(index) {
+            Some(segment) if index > 0 => (segment.start, segment.line),
+            _ => (0, 1),
+        };
+
+        let mut next = 0;
+        let segments = &self.segments;
+        let parsed = parse_decls(source, self.bump, self.spans, offset, line, |pos| {
+            while next < segments.len() && (segments[next].start < shift.end || shift.pos(segments[next].start) < pos) {
+                next += 1;
+            }
+            next < segments.len() && shift.pos(segments[next].start) == pos
+        });
+        let (kept, end) = match parsed.resynced {
+            true => (next, shift.pos(self.segments[next].start)),
+            false => (self.segments.len(), source.len() as u32),
+        };
+        let index = index.min(kept);
+
+        let mut old = Vec::new();
+        for child in self.tree.children.iter_mut().flat_map(|children| children.0.iter_mut()) {
+            if let NodeChild::Node(node) = std::mem::replace(child, NodeChild::Token(Token::empty())) {
+                old.push(node);
+            }
+        }
+        let mut tree = NodeBuilder::new(NodeType::TopDeclList, self.bump, self.spans, 0);
+        let mut old = old.into_iter();
+        for node in old.by_ref().take(index) {
+            tree.add(node);
+        }
+        for node in parsed.nodes {
+            tree.add(node);
+        }
+        for mut node in old.skip(kept - index) {
+            shift.node(&mut node, self.spans);
+            tree.add(node);
+        }
+        self.tree = tree.finish(false);
+
+        let count = parsed.segments.len();
+        self.segments.splice(index..kept, parsed.segments);
+        for segment in self.segments[index + count..].iter_mut() {
+            shift.segment(segment);
+        }
+        offset..end
+    }
+}
+
+fn parse_decls<'s, 'b>(
+    source: &'s str,
+    bump: &'b Bump,
+    spans: &'b SpanTable<'b>,
+    offset: u32,
+    line: u32,
+    mut resync: impl FnMut(u32) -> bool,
+) -> Parsed<'s, 'b> {
+    let mut parser = Parser::at(source, bump, offset, line);
+    parser.spans = spans;
+    let mut parsed = Parsed { nodes: Vec::new(), segments: Vec::new(), resynced: false };
+
+    while let Some(tok) = parser.peek() {
+        if resync(tok.pos) {
+            parsed.resynced = true;
+            break;
+        }
+        parser.blocks = Some(Vec::new());
+        let node = parser.declaration();
+        let mut segment = Segment {
+            start: tok.pos,
+            line: tok.line,
+            errors: std::mem::take(&mut parser.errors),
+            blocks: Vec::new(),
+            lookahead: std::mem::take(&mut parser.lookahead),
+        };
+        let mut blocks = parser.blocks.take().unwrap_or_default();
+        blocks.sort_by_key(|block| block.open);
+
+        if segment.errors.is_empty() && blocks_match(&node, &blocks) {
+            segment.blocks = blocks;
+        }
+        parsed.nodes.push(node);
+        parsed.segments.push(segment);
+    }
+    parsed
+}
+
+fn first_token_end(source: &str, segment: &Segment) -> u32 {
+    let mut tokens = Lexer::from(source);
+    tokens.offset = segment.start;
+    tokens.line = segment.line;
+    tokens.next().map_or(segment.start, |tok| tok.pos + tok.value.len() as u32)
+}
+
+/// Whether the blocks of `node` in preorder line up with the recorded spans
+fn blocks_match(node: &Node, spans: &[BlockSpan]) -> bool {
+    fn collect<'n, 's, 'b>(node: &'n Node<'s, 'b>, blocks: &mut Vec<&'n Node<'s, 'b>>) {
+        if node.kind.0 == NodeType::BlockExpr && node.children.is_some() {
+            blocks.push(node);
+        }
+        for child in node.children() {
+            if let NodeChild::Node(child) = child {
+                collect(child, blocks);
+            }
+        }
+    }
+    let mut blocks = Vec::new();
+    collect(node, &mut blocks);
+
+    blocks.len() == spans.len() && blocks.iter().zip(spans).all(|(block, span)| {
+        match (block.first_token(), block.last_token()) {
+            (Some(first), Some(last)) => span.open <= first.pos && last.pos < span.close,
+            _ => true,
+        }
+    })
+}
+
+fn nth_block<'n, 's, 'b>(node: &'n mut Node<'s, 'b>, n: &mut usize) -> Option<&'n mut Node<'s, 'b>> {
+    if node.kind.0 == NodeType::BlockExpr && node.children.is_some() {
+        if *n == 0 {
+            return Some(node);
+        }
+        *n -= 1;
+    }
+    for child in node.children.as_mut()?.0.iter_mut() {
+        if let NodeChild::Node(child) = child {
+            if let Some(block) = nth_block(child, n) {
+                return Some(block);
+            }
+        }
+    }
+    None
+}
+
+/// Moves positions at or after the end of the replaced range
+#[derive(Debug, Clone, Copy)]
+struct Shift {
+    /// End of the replaced range in the old source
+    end: u32,
+    delta: i64,
+    lines: i64,
+}
+
+impl Shift {
+    fn new(source: &str, edit: &TextEdit) -> Self {
+        let replaced = &source[edit.range.start as usize..edit.range.end as usize];
+        let newlines = |text: &str| text.bytes().filter(|&b| b == b'\n').count() as i64;
+        Self {
+            end: edit.range.end,
+            delta: edit.text.len() as i64 - replaced.len() as i64,
+            lines: newlines(&edit.text) - newlines(replaced),
+        }
+    }
+
+    fn pos(&self, pos: u32) -> u32 {
+        if pos >= self.end { (pos as i64 + self.delta) as u32 } else { pos }
+    }
+
+    fn line(&self, pos: u32, line: u32) -> u32 {
+        if pos >= self.end { (line as i64 + self.lines) as u32 } else { line }
+    }
+
+    fn node(&self, node: &mut Node, spans: &SpanTable) {
+        if let Some(span) = spans.span(node.id) {
+            spans.set(node.id, self.pos(span.start)..self.pos(span.end));
+        }
+        for child in node.children.iter_mut().flat_map(|children| children.0.iter_mut()) {
+            self.child(child, spans);
+        }
+    }
+
+    fn child(&self, child: &mut NodeChild, spans: &SpanTable) {
+        match child {
+            NodeChild::Node(node) => self.node(node, spans),
+            NodeChild::Token(token) if !token.is_empty() => {
+                token.line = self.line(token.pos, token.line);
+                token.pos = self.pos(token.pos);
+            }
+            NodeChild::Token(_) => {}
+        }
+    }
+
+    fn block(&self, block: &mut BlockSpan) {
+        // The opening brace sits just before `open`
+        if block.open > self.end {
+            block.line = self.line(block.open, block.line);
+            block.open = self.pos(block.open);
+        }
+        block.close = self.pos(block.close);
+    }
+
+    fn segment(&self, segment: &mut Segment) {
+        segment.line = self.line(segment.start, segment.line);
+        segment.start = self.pos(segment.start);
+        segment.lookahead = self.pos(segment.lookahead);
+        for error in segment.errors.iter_mut() {
+            error.location.line = self.line(error.location.start, error.location.line);
+            error.location.start = self.pos(error.location.start);
+            if let Some(fix) = error.fix.as_mut() {
+                fix.range = self.pos(fix.range.start)..self.pos(fix.range.end);
+            }
+        }
+        for block in segment.blocks.iter_mut() {
+            self.block(block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use bumpalo::Bump;
+
+    use super::Document;
+    use crate::ast2::{Node, NodeChild, SpanTable};
+    use crate::errors::TextEdit;
+    use crate::parser3::Parser;
+    use crate::utils::{choices::Choices, rng::Rng};
+
+    const SOURCE: &str = "struct Point { x: f64, y: f64 }
+
+fn main() {
+    let i = 0;
+    while i < 10 {
+        if i > 5 { print(i); }
+        i = i + 1;
+    }
+}
+
+fn other() -> int {
+    return 1 + 2;
+}
+";
+
+    /// Tree and errors of a full parse
+    fn full_parse(source: &str) -> String {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let parsed = parser.parse();
+        format!("{}\n{}\n{:?}", parsed.tree, spans(&parsed.tree, parsed.spans), parser.errors)
+    }
+
+    fn dump(document: &Document) -> String {
+        let tree = document.tree();
+        format!("{}\n{}\n{:?}", tree, spans(tree, document.spans()), document.errors().collect::<Vec<_>>())
+    }
+
+    /// Spans of the declarations and everything in them, in preorder
+    fn spans(tree: &Node, table: &SpanTable) -> String {
+        fn collect(node: &Node, table: &SpanTable, out: &mut Vec<Range<u32>>) {
+            out.push(table.span(node.id()).expect("nodes of a parse have spans"));
+            for child in node.children() {
+                if let NodeChild::Node(child) = child {
+                    collect(child, table, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        for child in tree.children() {
+            if let NodeChild::Node(child) = child {
+                collect(child, table, &mut out);
+            }
+        }
+        format!("{:?}", out)
+    }
+
+    fn edit<'s>(strings: &'s Bump, document: &mut Document<'s, '_>, edit: TextEdit) -> std::ops::Range<u32> {
+        let source = strings.alloc_str(&edit.apply(document.source()));
+        let reparsed = document.edit(&edit, source);
+        assert_eq!(dump(document), full_parse(source), "after {:?}", edit);
+        assert_eq!(ids(document.tree()), (0..document.spans().len() as u32).collect::<Vec<_>>());
+        reparsed
+    }
+
+    /// Ids of the nodes of the tree, sorted
+    fn ids(tree: &Node) -> Vec<u32> {
+        fn collect(node: &Node, out: &mut Vec<u32>) {
+            out.push(node.id().0);
+            for child in node.children() {
+                if let NodeChild::Node(child) = child {
+                    collect(child, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        collect(tree, &mut out);
+        out.sort();
+        out
+    }
+
+    fn at(source: &str, pattern: &str) -> u32 {
+        source.find(pattern).unwrap() as u32
+    }
+
+    #[test]
+    fn test_block_edit() {
+        let bump = Bump::new();
+        let strings = Bump::new();
+        let mut document = Document::parse(SOURCE, &bump);
+
+        let pos = at(SOURCE, "print(i)") + 6;
+        let reparsed = edit(&strings, &mut document, TextEdit::new(pos..pos + 1, "i * 2"));
+        let source = document.source();
+        assert_eq!(&source[reparsed.start as usize..reparsed.end as usize], " print(i * 2); }");
+
+        // Line changes move everything after the block
+        let pos = at(source, "i = i + 1;");
+        let reparsed = edit(&strings, &mut document, TextEdit::new(pos..pos, "let j = i;\n        "));
+        let source = document.source();
+        assert!(source[reparsed.start as usize..reparsed.end as usize].starts_with("\n        if i > 5"));
+        assert!(reparsed.end < at(source, "fn other"));
+    }
+
+    #[test]
+    fn test_declaration_edit() {
+        let bump = Bump::new();
+        let strings = Bump::new();
+        let mut document = Document::parse(SOURCE, &bump);
+
+        // Unbalancing a block falls back to its declaration
+        let pos = at(SOURCE, "print(i); }") + 10;
+        let reparsed = edit(&strings, &mut document, TextEdit::new(pos..pos + 1, ""));
+        assert_eq!(reparsed.start, at(SOURCE, "fn main"));
+
+        // Restoring it brings back the block spans of both functions
+        let reparsed = edit(&strings, &mut document, TextEdit::new(pos..pos, "}"));
+        assert_eq!(reparsed.start, at(SOURCE, "fn main"));
+        let pos = at(SOURCE, "1 + 2");
+        let reparsed = edit(&strings, &mut document, TextEdit::new(pos..pos + 1, "3"));
+        assert_eq!(reparsed, at(SOURCE, "return 1") - 5..at(SOURCE, "1 + 2;") + 8);
+
+        let pos = at(SOURCE, "struct");
+        let reparsed = edit(&strings, &mut document, TextEdit::new(pos..pos, "type Id = int;\n"));
+        assert_eq!(reparsed, 0..at(document.source(), "struct"));
+
+        let end = document.source().len() as u32;
+        edit(&strings, &mut document, TextEdit::new(end..end, "const N: int = 3;\n"));
+        let end = document.source().len() as u32;
+        edit(&strings, &mut document, TextEdit::new(0..end, ""));
+        assert!(document.tree().children().is_empty());
+    }
+
+    #[test]
+    fn test_random_edits() {
+        const SNIPPETS: &[&str] = &[
+            "", " ", "\n", "x", "1", ";", "{", "}", "(", ")", "[", "]", ",", ".", "=", "+ 1", "\"",
+            "let y = 2;", "if x { y; }", "{ z }", "fn g() {}", "pub ", "-> int", "return;", "while", "// c",
+        ];
+        let sources = [
+            SOURCE,
+            include_str!("../samples/fib.hz"),
+            include_str!("../samples/loops.hz"),
+            include_str!("../samples/points.hz"),
+        ];
+
+        let mut rng = Rng::new(0x5eed);
+        for round in 0..300 {
+            let bump = Bump::new();
+            let strings = Bump::new();
+            let original = sources[round % sources.len()];
+            let mut document = Document::parse(original, &bump);
+
+            for _ in 0..20 {
+                let source = document.source();
+                let mut start = rng.below(source.len() + 1);
+                while !source.is_char_boundary(start) { start -= 1; }
+                let mut end = (start + rng.below(8)).min(source.len());
+                while !source.is_char_boundary(end) { end -= 1; }
+
+                let edit = TextEdit::new(start as u32..end as u32, *rng.pick(SNIPPETS));
+                let text = strings.alloc_str(&edit.apply(source));
+                document.edit(&edit, text);
+                assert_eq!(dump(&document), full_parse(text), "round {} after {:?} on\n{}", round, edit, source);
+                assert_eq!(ids(document.tree()).len(), document.spans().len());
+            }
+        }
+    }
+}
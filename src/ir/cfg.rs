@@ -0,0 +1,200 @@
+//! Control flow graph queries and cleanups shared by lowering and the passes
+
+use hashbrown::{HashMap, HashSet};
+
+use super::{Block, Function, Inst, Value};
+
+/// Predecessors of every block, in block order and without duplicates
+pub fn predecessors(function: &Function) -> Vec<Vec<Block>> {
+    let mut preds = vec![Vec::new(); function.blocks.len()];
+    for block in function.block_ids() {
+        let Some(terminator) = &function.block(block).terminator else { continue };
+        for succ in terminator.successors() {
+            let list: &mut Vec<Block> = &mut preds[succ.0 as usize];
+            if !list.contains(&block) {
+                list.push(block);
+            }
+        }
+    }
+    preds
+}
+
+/// Blocks reachable from the entry in reverse postorder
+pub fn reverse_postorder(function: &Function) -> Vec<Block> {
+    let mut order = Vec::new();
+    let mut visited = vec![false; function.blocks.len()];
+    // Explicit stack of blocks and the index of the next successor to visit
+    let mut stack = vec![(Block::ENTRY, 0)];
+    visited[0] = true;
+    while let Some((block, next)) = stack.last_mut() {
+        let succs = function.block(*block).terminator.as_ref().map(|t| t.successors()).unwrap_or_default();
+        if let Some(&succ) = succs.get(*next) {
+            *next += 1;
+            if !visited[succ.0 as usize] {
+                visited[succ.0 as usize] = true;
+                stack.push((succ, 0));
+            }
+        } else {
+            order.push(*block);
+            stack.pop();
+        }
+    }
+    order.reverse();
+    order
+}
+
+/// Immediate dominator of every reachable block, the entry being its own
+///
+/// Uses the iterative algorithm from Cooper, Harvey and Kennedy,
+/// "A Simple, Fast Dominance Algorithm".
+pub fn dominators(function: &Function) -> Vec<Option<Block>> {
+    let order = reverse_postorder(function);
+    let mut position = vec![usize::MAX; function.blocks.len()];
+    for (i, block) in order.iter().enumerate() {
+        position[block.0 as usize] = i;
+    }
+    let preds = predecessors(function);
+
+    let mut idom: Vec<Option<Block>> = vec![None; function.blocks.len()];
+    idom[0] = Some(Block::ENTRY);
+    let intersect = |idom: &[Option<Block>], mut a: Block, mut b: Block| {
+        while a != b {
+            while position[a.0 as usize] > position[b.0 as usize] {
+                a = idom[a.0 as usize].unwrap();
+            }
+            while position[b.0 as usize] > position[a.0 as usize] {
+                b = idom[b.0 as usize].unwrap();
+            }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in order.iter().skip(1) {
+            let mut new_idom = None;
+            for &pred in preds[block.0 as usize].iter() {
+                if idom[pred.0 as usize].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => pred,
+                    Some(current) => intersect(&idom, pred, current),
+                });
+            }
+            if new_idom.is_some() && idom[block.0 as usize] != new_idom {
+                idom[block.0 as usize] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    idom
+}
+
+/// Does `a` dominate `b`? Both must be reachable.
+pub fn dominates(idom: &[Option<Block>], a: Block, mut b: Block) -> bool {
+    loop {
+        if a == b {
+            return true;
+        }
+        match idom[b.0 as usize] {
+            Some(parent) if parent != b => b = parent,
+            _ => return false,
+        }
+    }
+}
+
+/// Deletes blocks the entry cannot reach and renumbers the rest
+///
+/// Phis drop the operands of deleted predecessors. Returns whether anything
+/// was removed.
+pub fn remove_unreachable_blocks(function: &mut Function) -> bool {
+    let mut reachable = vec![false; function.blocks.len()];
+    for block in reverse_postorder(function) {
+        reachable[block.0 as usize] = true;
+    }
+    if reachable.iter().all(|&r| r) {
+        return false;
+    }
+
+    let mut renumbered = vec![None; function.blocks.len()];
+    let mut next = 0;
+    for (i, &r) in reachable.iter().enumerate() {
+        if r {
+            renumbered[i] = Some(Block(next));
+            next += 1;
+        }
+    }
+    let remap = |block: Block| renumbered[block.0 as usize].expect("reachable blocks only jump to reachable blocks");
+
+    let blocks = std::mem::take(&mut function.blocks);
+    for (i, mut data) in blocks.into_iter().enumerate() {
+        if !reachable[i] {
+            continue;
+        }
+        if let Some(terminator) = &mut data.terminator {
+            terminator.map_targets(remap);
+        }
+        for value in data.insts.iter() {
+            if let Inst::Phi(incoming) = &mut function.values[value.0 as usize].inst {
+                incoming.retain(|(pred, _)| reachable[pred.0 as usize]);
+                incoming.iter_mut().for_each(|(pred, _)| *pred = remap(*pred));
+            }
+        }
+        function.blocks.push(data);
+    }
+    true
+}
+
+/// Replaces phis whose operands are all the same value, or the phi itself,
+/// by that value. Returns whether anything was removed.
+pub fn remove_trivial_phis(function: &mut Function) -> bool {
+    let mut replaced: HashMap<Value, Value> = HashMap::new();
+    let resolve = |replaced: &HashMap<Value, Value>, mut value: Value| {
+        while let Some(&next) = replaced.get(&value) {
+            value = next;
+        }
+        value
+    };
+
+    // Removing one phi can make the phis using it trivial
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in 0..function.blocks.len() {
+            let mut removed = HashSet::new();
+            for &phi in function.blocks[block].insts.iter() {
+                let Inst::Phi(incoming) = &function.values[phi.0 as usize].inst else { continue };
+                let mut same = None;
+                let mut trivial = true;
+                for &(_, operand) in incoming.iter() {
+                    let operand = resolve(&replaced, operand);
+                    if operand == phi || Some(operand) == same {
+                        continue;
+                    }
+                    if same.is_some() {
+                        trivial = false;
+                        break;
+                    }
+                    same = Some(operand);
+                }
+                // A phi without other operands is only read on paths that never run
+                if let (true, Some(same)) = (trivial, same) {
+                    replaced.insert(phi, same);
+                    removed.insert(phi);
+                }
+            }
+            if !removed.is_empty() {
+                function.blocks[block].insts.retain(|value| !removed.contains(value));
+                changed = true;
+            }
+        }
+    }
+
+    if replaced.is_empty() {
+        return false;
+    }
+    function.replace_uses(|value| resolve(&replaced, value));
+    true
+}
@@ -0,0 +1,459 @@
+//! Lowering of type checked trees to SSA form
+//!
+//! SSA values are built on the fly with the algorithm from Braun et al.,
+//! "Simple and Efficient Construction of Static Single Assignment Form":
+//! every write to a local records its current value per block, and reads
+//! search the predecessors, placing phis where paths merge. A block is sealed
+//! once all its predecessors are known; reads in unsealed blocks (loop
+//! headers) get a placeholder phi which is completed when the block is sealed.
+//!
+//! Code following `return`, `break` and `continue` goes to a fresh block
+//! without predecessors which is deleted once the function is finished.
+
+use hashbrown::HashMap;
+
+use crate::ast2::{AstToken, BlockExpr, Expr, IfAlt, IfExpr, Stmt, TopDeclList, TopLevelDecl};
+use crate::errors::Loc;
+use crate::token::Tag;
+use crate::typecheck::check::{expr_loc, TypeChecker};
+use crate::typecheck::Type;
+use crate::vm::bytecode::Builtin;
+use crate::vm::compiler::{field_index, unescape};
+
+use super::{cfg, BinOp, Block, Constant, Function, Inst, Module, Terminator, UnOp, Value};
+
+#[derive(Debug)]
+pub struct LowerError {
+    pub message: &'static str,
+    pub location: Loc,
+}
+
+/// Lowers a program which passed type checking
+///
+/// Constants become functions without parameters, just like in the VM.
+pub fn lower<'s, 'b>(decls: TopDeclList<'s, 'b>, types: &TypeChecker<'s>) -> Result<Module, Vec<LowerError>> {
+    let mut module = Module::default();
+    let mut errors = Vec::new();
+    for decl in decls.items() {
+        match decl {
+            TopLevelDecl::Fn(fn_def) => {
+                let Some(body) = fn_def.body() else { continue };
+                let name = fn_def.name().token().value;
+                let Some(Type::Fn(params, ret)) = types.global(name) else { continue };
+
+                let mut lowering = Lowering::new(types, name, (**ret).clone());
+                for ((i, param), typ) in fn_def.params().items().enumerate().zip(params.iter()) {
+                    let value = lowering.emit(Inst::Param(i as u32), typ.clone());
+                    lowering.function.params.push(value);
+                    let var = lowering.declare(param.ident().token().value, typ.clone());
+                    lowering.write(var, Block::ENTRY, value);
+                }
+                lowering.block(body);
+                module.functions.push(lowering.finish(&mut errors));
+            }
+            TopLevelDecl::Const(const_decl) => {
+                let name = const_decl.name().token().value;
+                let Some(Type::Const(typ)) = types.global(name) else { continue };
+                let mut lowering = Lowering::new(types, name, (**typ).clone());
+                let value = lowering.expr(const_decl.value());
+                lowering.terminate(Terminator::Return(value));
+                module.functions.push(lowering.finish(&mut errors));
+            }
+            TopLevelDecl::Enum(enum_decl) => {
+                errors.push(LowerError { message: "enums", location: Loc::from_token(*enum_decl.name().token()) });
+            }
+            _ => {}
+        }
+    }
+    if errors.is_empty() { Ok(module) } else { Err(errors) }
+}
+
+/// Index of a local variable declaration
+type Var = usize;
+
+struct Loop {
+    header: Block,
+    exit: Block,
+}
+
+struct Lowering<'s, 't> {
+    types: &'t TypeChecker<'s>,
+    function: Function,
+    current: Block,
+    unit: Option<Value>,
+
+    scopes: Vec<HashMap<&'s str, Var>>,
+    var_types: Vec<Type>,
+    loops: Vec<Loop>,
+
+    /// Value of each variable at the end of a block, as far as it is known
+    defs: HashMap<(Var, Block), Value>,
+    preds: Vec<Vec<Block>>,
+    sealed: Vec<bool>,
+    /// Placeholder phis of unsealed blocks
+    incomplete: HashMap<Block, Vec<(Var, Value)>>,
+    errors: Vec<LowerError>,
+}
+
+impl<'s, 'b, 't> Lowering<'s, 't> {
+    fn new(types: &'t TypeChecker<'s>, name: &str, return_type: Type) -> Self {
+        let mut lowering = Self {
+            types,
+            function: Function::new(name, return_type),
+            current: Block::ENTRY,
+            unit: None,
+            scopes: vec![HashMap::new()],
+            var_types: Vec::new(),
+            loops: Vec::new(),
+            defs: HashMap::new(),
+            preds: Vec::new(),
+            sealed: Vec::new(),
+            incomplete: HashMap::new(),
+            errors: Vec::new(),
+        };
+        let entry = lowering.new_block();
+        lowering.seal(entry);
+        lowering
+    }
+
+    fn finish(mut self, errors: &mut Vec<LowerError>) -> Function {
+        // Falling off the end returns unit, anything else was rejected by the type checker
+        if self.function.block(self.current).terminator.is_none() {
+            let terminator = if self.function.return_type == Type::Unit {
+                Terminator::Return(self.unit())
+            } else {
+                Terminator::Unreachable
+            };
+            self.terminate(terminator);
+        }
+        for block in self.function.block_ids().collect::<Vec<_>>() {
+            if !self.sealed[block.0 as usize] {
+                self.seal(block);
+            }
+        }
+        cfg::remove_unreachable_blocks(&mut self.function);
+        cfg::remove_trivial_phis(&mut self.function);
+        errors.append(&mut self.errors);
+        self.function
+    }
+
+    fn unsupported(&mut self, message: &'static str, location: Loc) -> Value {
+        self.errors.push(LowerError { message, location });
+        self.emit(Inst::Undef, Type::Error)
+    }
+
+    fn new_block(&mut self) -> Block {
+        self.preds.push(Vec::new());
+        self.sealed.push(false);
+        self.function.add_block()
+    }
+
+    fn emit(&mut self, inst: Inst, typ: Type) -> Value {
+        let value = self.function.add_value(inst, typ);
+        let current = self.current;
+        self.function.block_mut(current).insts.push(value);
+        value
+    }
+
+    fn constant(&mut self, constant: Constant, typ: Type) -> Value {
+        self.emit(Inst::Const(constant), typ)
+    }
+
+    /// The unit value, created once at the start of the function
+    fn unit(&mut self) -> Value {
+        if let Some(unit) = self.unit {
+            return unit;
+        }
+        let unit = self.function.add_value(Inst::Const(Constant::Unit), Type::Unit);
+        let params = self.function.params.len();
+        let entry = self.function.block_mut(Block::ENTRY);
+        entry.insts.insert(params.min(entry.insts.len()), unit);
+        self.unit = Some(unit);
+        unit
+    }
+
+    /// Ends the current block and continues in an unreachable one
+    fn terminate(&mut self, terminator: Terminator) {
+        for succ in terminator.successors() {
+            self.preds[succ.0 as usize].push(self.current);
+        }
+        let current = self.current;
+        self.function.block_mut(current).terminator = Some(terminator);
+        self.current = self.new_block();
+        self.seal(self.current);
+    }
+
+    fn declare(&mut self, name: &'s str, typ: Type) -> Var {
+        self.var_types.push(typ);
+        let var = self.var_types.len() - 1;
+        self.scopes.last_mut().expect("locals are declared inside a scope").insert(name, var);
+        var
+    }
+
+    fn local(&self, name: &str) -> Option<Var> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+    }
+
+    fn write(&mut self, var: Var, block: Block, value: Value) {
+        self.defs.insert((var, block), value);
+    }
+
+    fn read(&mut self, var: Var, block: Block) -> Value {
+        if let Some(&value) = self.defs.get(&(var, block)) {
+            return value;
+        }
+        let typ = self.var_types[var].clone();
+        let value = if !self.sealed[block.0 as usize] {
+            let phi = self.phi(block, typ);
+            self.incomplete.entry(block).or_default().push((var, phi));
+            phi
+        } else if self.preds[block.0 as usize].len() == 1 {
+            let pred = self.preds[block.0 as usize][0];
+            self.read(var, pred)
+        } else if self.preds[block.0 as usize].is_empty() {
+            // Only blocks following a jump have no predecessors
+            let undef = self.function.add_value(Inst::Undef, typ);
+            self.function.block_mut(block).insts.insert(0, undef);
+            undef
+        } else {
+            // Written before the operands are read to break cycles through loops
+            let phi = self.phi(block, typ);
+            self.write(var, block, phi);
+            self.add_phi_operands(var, phi, block);
+            phi
+        };
+        self.write(var, block, value);
+        value
+    }
+
+    fn phi(&mut self, block: Block, typ: Type) -> Value {
+        let phi = self.function.add_value(Inst::Phi(Vec::new()), typ);
+        self.function.block_mut(block).insts.insert(0, phi);
+        phi
+    }
+
+    fn add_phi_operands(&mut self, var: Var, phi: Value, block: Block) {
+        for pred in self.preds[block.0 as usize].clone() {
+            let value = self.read(var, pred);
+            let Inst::Phi(incoming) = self.function.inst_mut(phi) else { unreachable!() };
+            incoming.push((pred, value));
+        }
+    }
+
+    /// Marks that every predecessor of a block is known
+    fn seal(&mut self, block: Block) {
+        for (var, phi) in self.incomplete.remove(&block).unwrap_or_default() {
+            self.add_phi_operands(var, phi, block);
+        }
+        self.sealed[block.0 as usize] = true;
+    }
+
+    fn block(&mut self, block: BlockExpr<'s, 'b>) -> Value {
+        self.scopes.push(HashMap::new());
+        for stmt in block.body().items() {
+            match stmt {
+                Stmt::VarDecl(var_decl) => {
+                    let typ = self.types.type_of_var(&var_decl).cloned().unwrap_or(Type::Error);
+                    let value = match var_decl.value() {
+                        Some(value) => self.expr(value),
+                        None => self.emit(Inst::Undef, typ.clone()),
+                    };
+                    // Declared after the initializer so `let x = x;` reads the outer `x`
+                    let var = self.declare(var_decl.name().token().value, typ);
+                    self.write(var, self.current, value);
+                }
+                Stmt::ExprStmt(expr_stmt) => { self.expr(expr_stmt.expr()); }
+                Stmt::EmptyStmt(_) => {}
+            }
+        }
+        self.scopes.pop();
+        self.unit()
+    }
+
+    /// Value of an expression which diverged
+    fn diverged(&mut self) -> Value {
+        self.emit(Inst::Undef, Type::Never)
+    }
+
+    fn expr(&mut self, expr: Expr<'s, 'b>) -> Value {
+        let location = expr_loc(&expr);
+        let typ = self.types.type_of(&expr).cloned().unwrap_or(Type::Error);
+        match expr {
+            Expr::Int(int) => {
+                let text = int.token().value;
+                let constant = match typ {
+                    Type::F32 | Type::F64 => Constant::Float(text.parse().expect("the type checker keeps literals in range")),
+                    Type::U32 | Type::U64 => Constant::UInt(text.parse().expect("the type checker keeps literals in range")),
+                    _ => Constant::Int(text.parse().expect("the type checker keeps literals in range")),
+                };
+                self.constant(constant, typ)
+            }
+            Expr::Str(string) => self.constant(Constant::Str(unescape(string.token().value).into()), typ),
+            Expr::Bool(boolean) => self.constant(Constant::Bool(boolean.token().value == "true"), typ),
+            Expr::Ident(ident) => {
+                let name = ident.token().value;
+                if let Some(var) = self.local(name) {
+                    self.read(var, self.current)
+                } else if let Some(Type::Const(_)) = self.types.global(name) {
+                    self.emit(Inst::Call(name.into(), Vec::new()), typ)
+                } else {
+                    self.unsupported("function values", location)
+                }
+            }
+            Expr::Group(group) => self.expr(group.expr()),
+            Expr::Infix(infix) => {
+                let left = self.expr(infix.left());
+                let right = self.expr(infix.right());
+                let op = match infix.op().tag {
+                    Tag::Plus => BinOp::Add,
+                    Tag::Minus => BinOp::Sub,
+                    Tag::Asterisk => BinOp::Mul,
+                    Tag::Slash => BinOp::Div,
+                    Tag::EqualEqual => BinOp::Eq,
+                    Tag::BangEqual => BinOp::Ne,
+                    Tag::Less => BinOp::Lt,
+                    Tag::LessEqual => BinOp::Le,
+                    Tag::Greater => BinOp::Gt,
+                    Tag::GreaterEqual => BinOp::Ge,
+                    tag => unreachable!("{:?} is not a binary operator", tag),
+                };
+                self.emit(Inst::Binary(op, left, right), typ)
+            }
+            Expr::Prefix(prefix) => {
+                let operand = self.expr(prefix.right());
+                let op = if prefix.op().tag == Tag::Bang { UnOp::Not } else { UnOp::Neg };
+                self.emit(Inst::Unary(op, operand), typ)
+            }
+            Expr::CallExpr(call) => {
+                let name = call.name().token().value;
+                let args: Vec<Value> = call.args().args().map(|arg| self.expr(arg)).collect();
+                if self.local(name).is_some() {
+                    self.unsupported("function values", location)
+                } else if let Some(Type::Fn(..)) = self.types.global(name) {
+                    self.emit(Inst::Call(name.into(), args), typ)
+                } else {
+                    let builtin = Builtin::from_name(name).expect("type checker resolved the function");
+                    let arg = *args.first().expect("builtins take one argument");
+                    self.emit(Inst::Builtin(builtin, arg), typ)
+                }
+            }
+            Expr::AssignExpr(assign) => {
+                let value = self.expr(assign.value());
+                let Expr::Ident(name) = assign.target() else {
+                    return self.unsupported("assignment to a field or element", location);
+                };
+                match self.local(name.token().value) {
+                    Some(var) => {
+                        self.write(var, self.current, value);
+                        self.unit()
+                    }
+                    None => self.unsupported("assignment to a global", location),
+                }
+            }
+            Expr::ReturnExpr(return_expr) => {
+                let value = match return_expr.value() {
+                    Some(value) => self.expr(value),
+                    None => self.unit(),
+                };
+                self.terminate(Terminator::Return(value));
+                self.diverged()
+            }
+            Expr::BreakExpr(_) => {
+                let exit = self.loops.last().expect("type checker rejects break outside loops").exit;
+                self.terminate(Terminator::Jump(exit));
+                self.diverged()
+            }
+            Expr::ContinueExpr(_) => {
+                let header = self.loops.last().expect("type checker rejects continue outside loops").header;
+                self.terminate(Terminator::Jump(header));
+                self.diverged()
+            }
+            Expr::IfExpr(if_expr) => {
+                self.if_expr(if_expr);
+                self.unit()
+            }
+            Expr::WhileExpr(while_expr) => {
+                // The header stays unsealed until the back edges are known
+                let header = self.new_block();
+                self.terminate(Terminator::Jump(header));
+                self.current = header;
+                let condition = self.expr(while_expr.condition());
+
+                let body = self.new_block();
+                let exit = self.new_block();
+                self.terminate(Terminator::Branch(condition, body, exit));
+                self.seal(body);
+
+                self.current = body;
+                self.loops.push(Loop { header, exit });
+                self.block(while_expr.consequence());
+                self.terminate(Terminator::Jump(header));
+                self.loops.pop();
+
+                self.seal(header);
+                self.seal(exit);
+                self.current = exit;
+                self.unit()
+            }
+            Expr::BlockExpr(block) => self.block(block),
+            Expr::ArrayExpr(array) => {
+                let items = array.items().map(|item| self.expr(item)).collect();
+                self.emit(Inst::Array(items), typ)
+            }
+            Expr::IndexExpr(index) => {
+                let container = self.expr(index.container());
+                let index = self.expr(index.index());
+                self.emit(Inst::Index(container, index), typ)
+            }
+            Expr::FieldAccessExpr(access) => {
+                let parent = access.parent();
+                let field = self.types.type_of(&parent)
+                    .and_then(|typ| field_index(typ, access.field_name().token().value))
+                    .expect("type checker resolved the field");
+                let parent = self.expr(parent);
+                self.emit(Inst::Field(parent, field), typ)
+            }
+            Expr::StructExpr(struct_expr) => {
+                let Type::Struct(_, fields) = &typ else { unreachable!("type checker resolved the struct") };
+                let mut values = Vec::new();
+                for (field, _) in fields.iter() {
+                    let init = struct_expr.fields().items()
+                        .find(|init| init.name().token().value == &**field)
+                        .expect("type checker ensures every field is initialized");
+                    values.push(self.expr(init.value()));
+                }
+                self.emit(Inst::Struct(values), typ)
+            }
+            Expr::MethodCall(_) | Expr::TupleExpr(_) => self.unsupported("methods and tuples", location),
+        }
+    }
+
+    fn if_expr(&mut self, if_expr: IfExpr<'s, 'b>) {
+        let condition = self.expr(if_expr.condition());
+        let then = self.new_block();
+        let merge = self.new_block();
+        let otherwise = match if_expr.alternate() {
+            Some(_) => self.new_block(),
+            None => merge,
+        };
+        self.terminate(Terminator::Branch(condition, then, otherwise));
+        self.seal(then);
+
+        self.current = then;
+        self.block(if_expr.consequence());
+        self.terminate(Terminator::Jump(merge));
+
+        if otherwise != merge {
+            self.seal(otherwise);
+            self.current = otherwise;
+            match if_expr.alternate() {
+                Some(IfAlt::Else(block)) => { self.block(block); }
+                Some(IfAlt::ElseIf(else_if)) => self.if_expr(else_if),
+                None => {}
+            }
+            self.terminate(Terminator::Jump(merge));
+        }
+        self.seal(merge);
+        self.current = merge;
+    }
+}
@@ -0,0 +1,480 @@
+//! Mid-level intermediate representation
+//!
+//! Functions are control flow graphs of basic blocks. Each block holds a list
+//! of instructions followed by a single terminator, the only place control may
+//! leave the block. Every instruction defines one SSA value which is assigned
+//! exactly once; values flowing in from several predecessors are merged with
+//! `phi` instructions at the start of a block.
+//!
+//! The textual dump printed by the `Display` impls numbers values in block
+//! order so it stays stable when values are added or removed.
+
+pub mod cfg;
+pub mod lower;
+pub mod opt;
+pub mod verify;
+
+use std::fmt::{self, Display};
+use std::rc::Rc;
+
+use hashbrown::HashMap;
+
+use crate::typecheck::Type;
+use crate::vm::bytecode::Builtin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Value(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Block(pub u32);
+
+impl Block {
+    pub const ENTRY: Block = Block(0);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(Rc<str>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inst {
+    /// The nth argument of the function, only found in the entry block
+    Param(u32),
+    Const(Constant),
+    Binary(BinOp, Value, Value),
+    Unary(UnOp, Value),
+    /// Call of a function or constant of the module
+    Call(Box<str>, Vec<Value>),
+    Builtin(Builtin, Value),
+    Array(Vec<Value>),
+    /// Field values in declaration order
+    Struct(Vec<Value>),
+    Index(Value, Value),
+    Field(Value, u16),
+    /// One incoming value per predecessor
+    Phi(Vec<(Block, Value)>),
+    Copy(Value),
+    /// A value read on a path which never executes
+    Undef,
+}
+
+impl Inst {
+    pub fn operands(&self) -> Vec<Value> {
+        match self {
+            Inst::Param(_) | Inst::Const(_) | Inst::Undef => Vec::new(),
+            Inst::Binary(_, left, right) | Inst::Index(left, right) => vec![*left, *right],
+            Inst::Unary(_, value) | Inst::Builtin(_, value) | Inst::Field(value, _) | Inst::Copy(value) => vec![*value],
+            Inst::Call(_, args) | Inst::Array(args) | Inst::Struct(args) => args.clone(),
+            Inst::Phi(incoming) => incoming.iter().map(|(_, value)| *value).collect(),
+        }
+    }
+
+    pub fn map_operands(&mut self, mut f: impl FnMut(Value) -> Value) {
+        match self {
+            Inst::Param(_) | Inst::Const(_) | Inst::Undef => {}
+            Inst::Binary(_, left, right) | Inst::Index(left, right) => {
+                *left = f(*left);
+                *right = f(*right);
+            }
+            Inst::Unary(_, value) | Inst::Builtin(_, value) | Inst::Field(value, _) | Inst::Copy(value) => *value = f(*value),
+            Inst::Call(_, args) | Inst::Array(args) | Inst::Struct(args) => args.iter_mut().for_each(|arg| *arg = f(*arg)),
+            Inst::Phi(incoming) => incoming.iter_mut().for_each(|(_, value)| *value = f(*value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(Block),
+    Branch(Value, Block, Block),
+    Return(Value),
+    Unreachable,
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<Block> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch(_, then, otherwise) => vec![*then, *otherwise],
+            Terminator::Return(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    pub fn operands(&self) -> Vec<Value> {
+        match self {
+            Terminator::Branch(value, ..) | Terminator::Return(value) => vec![*value],
+            Terminator::Jump(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    pub fn map_operands(&mut self, mut f: impl FnMut(Value) -> Value) {
+        match self {
+            Terminator::Branch(value, ..) | Terminator::Return(value) => *value = f(*value),
+            Terminator::Jump(_) | Terminator::Unreachable => {}
+        }
+    }
+
+    pub fn map_targets(&mut self, mut f: impl FnMut(Block) -> Block) {
+        match self {
+            Terminator::Jump(target) => *target = f(*target),
+            Terminator::Branch(_, then, otherwise) => {
+                *then = f(*then);
+                *otherwise = f(*otherwise);
+            }
+            Terminator::Return(_) | Terminator::Unreachable => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValueData {
+    pub inst: Inst,
+    pub ty: Type,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BlockData {
+    /// Phis first, then the other instructions in execution order
+    pub insts: Vec<Value>,
+    /// Only missing while the block is being built
+    pub terminator: Option<Terminator>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: Box<str>,
+    pub params: Vec<Value>,
+    pub return_type: Type,
+    /// Every value ever created, including those no longer in a block
+    pub values: Vec<ValueData>,
+    pub blocks: Vec<BlockData>,
+}
+
+impl Function {
+    pub fn new(name: &str, return_type: Type) -> Self {
+        Self { name: name.into(), params: Vec::new(), return_type, values: Vec::new(), blocks: Vec::new() }
+    }
+
+    pub fn add_block(&mut self) -> Block {
+        self.blocks.push(BlockData::default());
+        Block(self.blocks.len() as u32 - 1)
+    }
+
+    /// Creates a value without placing it in a block
+    pub fn add_value(&mut self, inst: Inst, ty: Type) -> Value {
+        self.values.push(ValueData { inst, ty });
+        Value(self.values.len() as u32 - 1)
+    }
+
+    pub fn block(&self, block: Block) -> &BlockData {
+        &self.blocks[block.0 as usize]
+    }
+
+    pub fn block_mut(&mut self, block: Block) -> &mut BlockData {
+        &mut self.blocks[block.0 as usize]
+    }
+
+    pub fn inst(&self, value: Value) -> &Inst {
+        &self.values[value.0 as usize].inst
+    }
+
+    pub fn inst_mut(&mut self, value: Value) -> &mut Inst {
+        &mut self.values[value.0 as usize].inst
+    }
+
+    pub fn ty(&self, value: Value) -> &Type {
+        &self.values[value.0 as usize].ty
+    }
+
+    /// True if removing the value's instruction could change what the program does
+    pub fn has_effects(&self, value: Value) -> bool {
+        // Calls may print, indexing and integer division may trap
+        match self.inst(value) {
+            Inst::Call(..) | Inst::Builtin(..) | Inst::Index(..) => true,
+            Inst::Binary(BinOp::Div, ..) => !self.ty(value).is_float(),
+            _ => false,
+        }
+    }
+
+    pub fn block_ids(&self) -> impl Iterator<Item = Block> {
+        (0..self.blocks.len() as u32).map(Block)
+    }
+
+    /// Rewrites every use of a value, in instructions and terminators alike
+    pub fn replace_uses(&mut self, mut f: impl FnMut(Value) -> Value) {
+        for block in 0..self.blocks.len() {
+            for i in 0..self.blocks[block].insts.len() {
+                let value = self.blocks[block].insts[i];
+                self.values[value.0 as usize].inst.map_operands(&mut f);
+            }
+            if let Some(terminator) = &mut self.blocks[block].terminator {
+                terminator.map_operands(&mut f);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+    pub functions: Vec<Function>,
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Unit => write!(f, "()"),
+            Constant::Bool(value) => write!(f, "{}", value),
+            Constant::Int(value) => write!(f, "{}", value),
+            Constant::UInt(value) => write!(f, "{}", value),
+            Constant::Float(value) => write!(f, "{:?}", value),
+            Constant::Str(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, function) in self.functions.iter().enumerate() {
+            if i > 0 { writeln!(f)?; }
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Sequential names in block order
+        let mut names = HashMap::new();
+        for block in self.blocks.iter() {
+            for value in block.insts.iter() {
+                let next = names.len();
+                names.entry(*value).or_insert(next);
+            }
+        }
+        let name = |value: &Value| match names.get(value) {
+            Some(n) => format!("%{}", n),
+            None => format!("%?{}", value.0),
+        };
+        let list = |values: &[Value]| values.iter().map(name).collect::<Vec<_>>().join(", ");
+
+        let params: Vec<String> = self.params.iter().map(|p| format!("{}: {}", name(p), self.ty(*p))).collect();
+        writeln!(f, "fn {}({}) -> {} {{", self.name, params.join(", "), self.return_type)?;
+
+        let preds = cfg::predecessors(self);
+        for (id, block) in self.blocks.iter().enumerate() {
+            write!(f, "bb{}:", id)?;
+            if !preds[id].is_empty() {
+                let preds: Vec<String> = preds[id].iter().map(|pred| format!("bb{}", pred.0)).collect();
+                write!(f, "  ; preds: {}", preds.join(", "))?;
+            }
+            writeln!(f)?;
+
+            for value in block.insts.iter() {
+                write!(f, "    {}: {} = ", name(value), self.ty(*value))?;
+                match self.inst(*value) {
+                    Inst::Param(index) => write!(f, "param {}", index)?,
+                    Inst::Const(constant) => write!(f, "const {}", constant)?,
+                    Inst::Binary(op, left, right) => {
+                        write!(f, "{} {}, {}", format!("{:?}", op).to_lowercase(), name(left), name(right))?
+                    }
+                    Inst::Unary(op, operand) => write!(f, "{} {}", format!("{:?}", op).to_lowercase(), name(operand))?,
+                    Inst::Call(callee, args) => write!(f, "call {}({})", callee, list(args))?,
+                    Inst::Builtin(builtin, arg) => {
+                        write!(f, "builtin {}({})", format!("{:?}", builtin).to_lowercase(), name(arg))?
+                    }
+                    Inst::Array(items) => write!(f, "array [{}]", list(items))?,
+                    Inst::Struct(fields) => write!(f, "struct {{{}}}", list(fields))?,
+                    Inst::Index(container, index) => write!(f, "index {}, {}", name(container), name(index))?,
+                    Inst::Field(parent, field) => write!(f, "field {}.{}", name(parent), field)?,
+                    Inst::Phi(incoming) => {
+                        let incoming: Vec<String> = incoming.iter()
+                            .map(|(block, value)| format!("bb{}: {}", block.0, name(value)))
+                            .collect();
+                        write!(f, "phi [{}]", incoming.join(", "))?
+                    }
+                    Inst::Copy(value) => write!(f, "copy {}", name(value))?,
+                    Inst::Undef => write!(f, "undef")?,
+                }
+                writeln!(f)?;
+            }
+
+            match &block.terminator {
+                Some(Terminator::Jump(target)) => writeln!(f, "    jump bb{}", target.0)?,
+                Some(Terminator::Branch(condition, then, otherwise)) => {
+                    writeln!(f, "    branch {}, bb{}, bb{}", name(condition), then.0, otherwise.0)?
+                }
+                Some(Terminator::Return(value)) => writeln!(f, "    return {}", name(value))?,
+                Some(Terminator::Unreachable) => writeln!(f, "    unreachable")?,
+                None => writeln!(f, "    <no terminator>")?,
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::ast2::{AstNode, TopDeclList};
+    use crate::parser3::Parser;
+    use crate::typecheck::check::TypeChecker;
+
+    fn lower_source(source: &str) -> Module {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        assert!(checker.errors.is_empty(), "{:?}", checker.errors);
+        lower::lower(TopDeclList::cast(root), &checker).unwrap()
+    }
+
+    #[test]
+    fn test_lower_samples() {
+        for source in [
+            include_str!("../../samples/fib.hz"),
+            include_str!("../../samples/loops.hz"),
+            include_str!("../../samples/points.hz"),
+        ] {
+            let module = lower_source(source);
+            verify::verify_module(&module).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_dump() {
+        let module = lower_source("
+            fn count(n: i32) -> i32 {
+                let i = 0;
+                let total = 0;
+                while i < n {
+                    i = i + 1;
+                    if i == 3 { continue; }
+                    if total > 100 { break; } else { total = total + i; }
+                }
+                return total;
+            }
+        ");
+        verify::verify_module(&module).unwrap();
+        assert_eq!(module.to_string(), "\
+fn count(%0: i32) -> i32 {
+bb0:
+    %0: i32 = param 0
+    %1: () = const ()
+    %2: i32 = const 0
+    %3: i32 = const 0
+    jump bb1
+bb1:  ; preds: bb0, bb4, bb7
+    %4: i32 = phi [bb0: %3, bb4: %4, bb7: %13]
+    %5: i32 = phi [bb0: %2, bb4: %8, bb7: %8]
+    %6: bool = lt %5, %0
+    branch %6, bb2, bb3
+bb2:  ; preds: bb1
+    %7: i32 = const 1
+    %8: i32 = add %5, %7
+    %9: i32 = const 3
+    %10: bool = eq %8, %9
+    branch %10, bb4, bb5
+bb3:  ; preds: bb1, bb6
+    return %4
+bb4:  ; preds: bb2
+    jump bb1
+bb5:  ; preds: bb2
+    %11: i32 = const 100
+    %12: bool = gt %4, %11
+    branch %12, bb6, bb8
+bb6:  ; preds: bb5
+    jump bb3
+bb7:  ; preds: bb8
+    jump bb1
+bb8:  ; preds: bb5
+    %13: i32 = add %4, %8
+    jump bb7
+}
+");
+    }
+
+    fn messages(function: &Function) -> Vec<String> {
+        verify::verify(function).unwrap_err().into_iter().map(|err| err.message).collect()
+    }
+
+    #[test]
+    fn test_verify_errors() {
+        // Jumps back to the entry and returns a value defined later
+        let mut function = Function::new("f", Type::I32);
+        let entry = function.add_block();
+        let next = function.add_block();
+        let late = function.add_value(Inst::Const(Constant::Int(1)), Type::I32);
+        function.block_mut(entry).terminator = Some(Terminator::Return(late));
+        function.block_mut(next).insts.push(late);
+        function.block_mut(next).terminator = Some(Terminator::Jump(entry));
+        assert_eq!(messages(&function), [
+            "entry block has predecessors",
+            "use of value %?0 before its definition",
+        ]);
+
+        // Missing terminators are reported before anything else
+        function.block_mut(next).terminator = None;
+        assert_eq!(messages(&function), ["block has no terminator"]);
+
+        // Phi operands must match the predecessors
+        let mut function = Function::new("g", Type::I32);
+        let entry = function.add_block();
+        let merge = function.add_block();
+        let condition = function.add_value(Inst::Const(Constant::Int(0)), Type::I32);
+        let phi = function.add_value(Inst::Phi(vec![(entry, condition)]), Type::I32);
+        function.block_mut(entry).insts.push(condition);
+        function.block_mut(entry).terminator = Some(Terminator::Branch(condition, merge, merge));
+        function.block_mut(merge).insts.push(phi);
+        function.block_mut(merge).insts.insert(0, condition);
+        function.block_mut(merge).terminator = Some(Terminator::Return(phi));
+        assert_eq!(messages(&function), ["value %?0 is placed more than once"]);
+
+        function.block_mut(merge).insts.remove(0);
+        let other = function.add_value(Inst::Const(Constant::Bool(true)), Type::Bool);
+        *function.inst_mut(phi) = Inst::Phi(vec![(entry, condition), (merge, other)]);
+        assert_eq!(messages(&function), [
+            "branch on a value which is not a bool",
+            "phi operands do not match the predecessors",
+            "phi operand has a different type",
+        ]);
+
+        // Constants must fit in their type
+        let mut function = Function::new("h", Type::I32);
+        let entry = function.add_block();
+        let big = function.add_value(Inst::Const(Constant::Int(1 << 31)), Type::I32);
+        function.block_mut(entry).insts.push(big);
+        function.block_mut(entry).terminator = Some(Terminator::Return(big));
+        assert_eq!(messages(&function), ["constant 2147483648 out of range for i32"]);
+    }
+}
@@ -0,0 +1,36 @@
+//! Copy propagation
+//!
+//! Uses of `copy` instructions are redirected to the copied value and the
+//! copies deleted. Phis merging a single value are copies in disguise and are
+//! removed as well.
+
+use hashbrown::HashMap;
+
+use crate::ir::{cfg, Function, Inst, Value};
+
+pub fn run(function: &mut Function) -> bool {
+    let mut copies = HashMap::new();
+    for block in function.blocks.iter() {
+        for &value in block.insts.iter() {
+            if let Inst::Copy(source) = function.inst(value) {
+                copies.insert(value, *source);
+            }
+        }
+    }
+
+    let mut changed = false;
+    if !copies.is_empty() {
+        let resolve = |mut value: Value| {
+            while let Some(&source) = copies.get(&value) {
+                value = source;
+            }
+            value
+        };
+        function.replace_uses(resolve);
+        for block in function.blocks.iter_mut() {
+            block.insts.retain(|value| !copies.contains_key(value));
+        }
+        changed = true;
+    }
+    cfg::remove_trivial_phis(function) | changed
+}
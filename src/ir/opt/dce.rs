@@ -0,0 +1,37 @@
+//! Dead code elimination
+//!
+//! Marks the instructions with side effects, the operands of terminators and
+//! the parameters as live, then everything they use, transitively. The rest
+//! is deleted, including cycles of phis which only feed each other.
+
+use hashbrown::HashSet;
+
+use crate::ir::{Function, Inst};
+
+pub fn run(function: &mut Function) -> bool {
+    let mut live = HashSet::new();
+    let mut worklist = Vec::new();
+    for block in function.blocks.iter() {
+        for &value in block.insts.iter() {
+            if function.has_effects(value) || matches!(function.inst(value), Inst::Param(_)) {
+                worklist.push(value);
+            }
+        }
+        if let Some(terminator) = &block.terminator {
+            worklist.extend(terminator.operands());
+        }
+    }
+    while let Some(value) = worklist.pop() {
+        if live.insert(value) {
+            worklist.extend(function.inst(value).operands());
+        }
+    }
+
+    let mut changed = false;
+    for block in function.blocks.iter_mut() {
+        let len = block.insts.len();
+        block.insts.retain(|value| live.contains(value));
+        changed |= block.insts.len() != len;
+    }
+    changed
+}
@@ -0,0 +1,115 @@
+//! Constant folding and propagation
+//!
+//! Instructions whose operands are all constants are replaced in place by
+//! their result. Since every use refers to the value rather than the
+//! instruction, the constant reaches all uses at once. Arithmetic goes
+//! through the VM so folded results match what the program computes at run
+//! time, wrapped to the width of the value's type; operations which would
+//! fail, like division by zero, are kept.
+//!
+//! Reading a field of a struct built in the same function, or an element of
+//! an array at a constant index, becomes a copy of the stored value.
+
+use crate::ir::{cfg, BinOp, Constant, Function, Inst, UnOp, Value};
+use crate::typecheck::Type;
+use crate::vm::bytecode::Op;
+use crate::vm::{self, arithmetic};
+
+pub fn run(function: &mut Function) -> bool {
+    let mut changed = false;
+    // Definitions come before their uses in reverse postorder, except for phis
+    for block in cfg::reverse_postorder(function) {
+        for value in function.block(block).insts.clone() {
+            if let Some(folded) = fold(function, value) {
+                *function.inst_mut(value) = folded;
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+fn constant(function: &Function, value: Value) -> Option<&Constant> {
+    match function.inst(value) {
+        Inst::Const(constant) => Some(constant),
+        _ => None,
+    }
+}
+
+fn fold(function: &Function, value: Value) -> Option<Inst> {
+    match function.inst(value) {
+        Inst::Binary(op, left, right) => {
+            let left = to_vm(constant(function, *left)?);
+            let right = to_vm(constant(function, *right)?);
+            let op = match op {
+                BinOp::Add => Op::Add,
+                BinOp::Sub => Op::Sub,
+                BinOp::Mul => Op::Mul,
+                BinOp::Div => Op::Div,
+                BinOp::Eq => Op::Eq,
+                BinOp::Ne => Op::Ne,
+                BinOp::Lt => Op::Lt,
+                BinOp::Le => Op::Le,
+                BinOp::Gt => Op::Gt,
+                BinOp::Ge => Op::Ge,
+            };
+            let result = arithmetic(op, left, right).ok()?;
+            from_vm(fit(function.ty(value), result)).map(Inst::Const)
+        }
+        Inst::Unary(op, operand) => {
+            let folded = match (op, to_vm(constant(function, *operand)?)) {
+                (UnOp::Not, vm::Value::Bool(value)) => vm::Value::Bool(!value),
+                (UnOp::Neg, vm::Value::Int(value)) => vm::Value::Int(value.wrapping_neg()),
+                (UnOp::Neg, vm::Value::Float(value)) => vm::Value::Float(-value),
+                _ => return None,
+            };
+            from_vm(fit(function.ty(value), folded)).map(Inst::Const)
+        }
+        Inst::Field(parent, field) => match function.inst(*parent) {
+            Inst::Struct(fields) => Some(Inst::Copy(fields[*field as usize])),
+            _ => None,
+        },
+        Inst::Index(container, index) => {
+            let Inst::Array(items) = function.inst(*container) else { return None };
+            let index = match constant(function, *index)? {
+                Constant::Int(index) => usize::try_from(*index).ok()?,
+                Constant::UInt(index) => usize::try_from(*index).ok()?,
+                _ => return None,
+            };
+            // Out of bounds reads must still trap
+            items.get(index).map(|item| Inst::Copy(*item))
+        }
+        _ => None,
+    }
+}
+
+/// Wraps an integer computed in 64 bits to the width of its type
+fn fit(typ: &Type, value: vm::Value) -> vm::Value {
+    match typ {
+        Type::I32 | Type::U32 => vm::wrap32(value),
+        _ => value,
+    }
+}
+
+fn to_vm(constant: &Constant) -> vm::Value {
+    match constant {
+        Constant::Unit => vm::Value::Unit,
+        Constant::Bool(value) => vm::Value::Bool(*value),
+        Constant::Int(value) => vm::Value::Int(*value),
+        Constant::UInt(value) => vm::Value::UInt(*value),
+        Constant::Float(value) => vm::Value::Float(*value),
+        Constant::Str(value) => vm::Value::Str(value.clone()),
+    }
+}
+
+fn from_vm(value: vm::Value) -> Option<Constant> {
+    Some(match value {
+        vm::Value::Unit => Constant::Unit,
+        vm::Value::Bool(value) => Constant::Bool(value),
+        vm::Value::Int(value) => Constant::Int(value),
+        vm::Value::UInt(value) => Constant::UInt(value),
+        vm::Value::Float(value) => Constant::Float(value),
+        vm::Value::Str(value) => Constant::Str(value),
+        vm::Value::Array(_) | vm::Value::Struct(_) => return None,
+    })
+}
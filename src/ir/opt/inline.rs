@@ -0,0 +1,149 @@
+//! Inlining of small non-recursive functions
+//!
+//! A call is replaced by a copy of the callee's blocks. The calling block is
+//! split in two at the call: the first half jumps to the copied entry block,
+//! the copied returns jump to the second half, which starts with the call's
+//! value, now a phi of the returned values or a copy of the only one.
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::ir::{Block, Function, Inst, Module, Terminator, Value};
+
+/// Callees with more instructions than this are left alone
+const MAX_SIZE: usize = 24;
+
+pub fn run(module: &mut Module) -> bool {
+    let recursive = recursive_functions(module);
+    let inlinable: HashMap<Box<str>, Function> = module.functions.iter()
+        .filter(|function| !recursive.contains(&function.name) && size(function) <= MAX_SIZE)
+        .map(|function| (function.name.clone(), function.clone()))
+        .collect();
+
+    let mut changed = false;
+    for caller in module.functions.iter_mut() {
+        // Inlined bodies may contain more calls, which are inlined in turn.
+        // This ends because the callees do not call back into themselves.
+        while let Some((block, position, callee)) = find_call(caller, &inlinable) {
+            inline(caller, block, position, callee);
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn size(function: &Function) -> usize {
+    function.blocks.iter().map(|block| block.insts.len()).sum()
+}
+
+fn callees(function: &Function) -> impl Iterator<Item = &str> {
+    function.blocks.iter()
+        .flat_map(|block| block.insts.iter())
+        .filter_map(|&value| match function.inst(value) {
+            Inst::Call(callee, _) => Some(&**callee),
+            _ => None,
+        })
+}
+
+/// Functions which can end up calling themselves
+fn recursive_functions(module: &Module) -> HashSet<Box<str>> {
+    let graph: HashMap<&str, Vec<&str>> = module.functions.iter()
+        .map(|function| (&*function.name, callees(function).collect()))
+        .collect();
+
+    let mut recursive = HashSet::new();
+    for function in module.functions.iter() {
+        let mut seen = HashSet::new();
+        let mut stack = graph[&*function.name].clone();
+        while let Some(callee) = stack.pop() {
+            if callee == &*function.name {
+                recursive.insert(function.name.clone());
+                break;
+            }
+            if seen.insert(callee) {
+                stack.extend(graph.get(callee).into_iter().flatten());
+            }
+        }
+    }
+    recursive
+}
+
+fn find_call<'m>(caller: &Function, inlinable: &'m HashMap<Box<str>, Function>) -> Option<(Block, usize, &'m Function)> {
+    for block in caller.block_ids() {
+        for (position, &value) in caller.block(block).insts.iter().enumerate() {
+            if let Inst::Call(callee, _) = caller.inst(value) {
+                match inlinable.get(callee) {
+                    Some(callee) if callee.name != caller.name => return Some((block, position, callee)),
+                    _ => {}
+                }
+            }
+        }
+    }
+    None
+}
+
+fn inline(caller: &mut Function, block: Block, position: usize, callee: &Function) {
+    let call = caller.block(block).insts[position];
+    let Inst::Call(_, args) = caller.inst(call).clone() else { unreachable!() };
+
+    // Everything after the call moves to a new block
+    let rest = caller.add_block();
+    let mut tail = caller.block_mut(block).insts.split_off(position);
+    tail.remove(0);
+    let terminator = caller.block_mut(block).terminator.take().expect("finished blocks have terminators");
+    for succ in terminator.successors() {
+        let phis: Vec<Value> = caller.block(succ).insts.clone();
+        for phi in phis {
+            if let Inst::Phi(incoming) = caller.inst_mut(phi) {
+                incoming.iter_mut().for_each(|(pred, _)| if *pred == block { *pred = rest });
+            }
+        }
+    }
+    caller.block_mut(rest).insts = tail;
+    caller.block_mut(rest).terminator = Some(terminator);
+
+    // Fresh blocks and values for the callee's
+    let blocks: Vec<Block> = callee.block_ids().map(|_| caller.add_block()).collect();
+    let mut values = HashMap::new();
+    for data in callee.blocks.iter() {
+        for &value in data.insts.iter() {
+            values.insert(value, caller.add_value(Inst::Undef, callee.ty(value).clone()));
+        }
+    }
+    let map_value = |value: Value| values[&value];
+    let map_block = |block: Block| blocks[block.0 as usize];
+
+    let mut returns = Vec::new();
+    for (old, data) in callee.block_ids().zip(callee.blocks.iter()) {
+        let new = map_block(old);
+        for &value in data.insts.iter() {
+            let mut inst = match callee.inst(value) {
+                Inst::Param(index) => Inst::Copy(args[*index as usize]),
+                Inst::Phi(incoming) => Inst::Phi(incoming.iter().map(|&(pred, value)| (map_block(pred), value)).collect()),
+                inst => inst.clone(),
+            };
+            if !matches!(callee.inst(value), Inst::Param(_)) {
+                inst.map_operands(map_value);
+            }
+            *caller.inst_mut(values[&value]) = inst;
+            caller.block_mut(new).insts.push(values[&value]);
+        }
+
+        let mut terminator = data.terminator.clone().expect("finished blocks have terminators");
+        terminator.map_operands(map_value);
+        terminator.map_targets(map_block);
+        if let Terminator::Return(value) = terminator {
+            returns.push((new, value));
+            terminator = Terminator::Jump(rest);
+        }
+        caller.block_mut(new).terminator = Some(terminator);
+    }
+
+    // The call's value is whatever the callee returned
+    *caller.inst_mut(call) = match returns.as_slice() {
+        [] => Inst::Undef,
+        [(_, value)] => Inst::Copy(*value),
+        _ => Inst::Phi(returns),
+    };
+    caller.block_mut(rest).insts.insert(0, call);
+    caller.block_mut(block).terminator = Some(Terminator::Jump(map_block(Block::ENTRY)));
+}
@@ -0,0 +1,459 @@
+//! Optimization passes over the IR
+//!
+//! Each pass reports whether it changed anything. The pass manager runs the
+//! passes enabled at an optimization level over and over until none of them
+//! finds more to do, since one pass often exposes work for another: inlining
+//! a constant lets folding decide a branch, which lets the CFG simplification
+//! delete a block, which leaves dead instructions behind.
+
+pub mod copy_prop;
+pub mod dce;
+pub mod fold;
+pub mod inline;
+pub mod simplify;
+
+use super::{verify, Function, Module};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    Inline,
+    ConstFold,
+    SimplifyCfg,
+    CopyProp,
+    DeadCode,
+}
+
+impl Pass {
+    /// Every pass in the order the pass manager runs them
+    pub const ALL: [Pass; 5] = [Pass::Inline, Pass::ConstFold, Pass::SimplifyCfg, Pass::CopyProp, Pass::DeadCode];
+
+    /// Lowest `-O` level which enables the pass
+    pub fn level(self) -> u8 {
+        match self {
+            Pass::Inline => 2,
+            Pass::ConstFold | Pass::SimplifyCfg | Pass::CopyProp | Pass::DeadCode => 1,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::Inline => "inline",
+            Pass::ConstFold => "const-fold",
+            Pass::SimplifyCfg => "simplify-cfg",
+            Pass::CopyProp => "copy-prop",
+            Pass::DeadCode => "dce",
+        }
+    }
+
+    pub fn run(self, module: &mut Module) -> bool {
+        let per_function: fn(&mut Function) -> bool = match self {
+            Pass::Inline => return inline::run(module),
+            Pass::ConstFold => fold::run,
+            Pass::SimplifyCfg => simplify::run,
+            Pass::CopyProp => copy_prop::run,
+            Pass::DeadCode => dce::run,
+        };
+        module.functions.iter_mut().fold(false, |changed, function| per_function(function) | changed)
+    }
+}
+
+/// Gives up on reaching a fixed point after this many rounds
+const MAX_ROUNDS: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct PassManager {
+    passes: Vec<Pass>,
+}
+
+impl PassManager {
+    /// The passes enabled at an `-O` level, none at level 0
+    pub fn new(level: u8) -> Self {
+        Self::with_passes(Pass::ALL.into_iter().filter(|pass| pass.level() <= level).collect())
+    }
+
+    pub fn with_passes(passes: Vec<Pass>) -> Self {
+        Self { passes }
+    }
+
+    pub fn run(&self, module: &mut Module) {
+        for _ in 0..MAX_ROUNDS {
+            let mut changed = false;
+            for pass in self.passes.iter() {
+                changed |= pass.run(module);
+                if cfg!(debug_assertions) {
+                    if let Err(errors) = verify::verify_module(module) {
+                        panic!("{} produced invalid IR: {:?}\n{}", pass.name(), errors, module);
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::ast2::{AstNode, TopDeclList};
+    use crate::ir::lower;
+    use crate::parser3::Parser;
+    use crate::typecheck::check::TypeChecker;
+
+    fn lower_source(source: &str) -> Module {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        assert!(checker.errors.is_empty(), "{:?}", checker.errors);
+        lower::lower(TopDeclList::cast(root), &checker).unwrap()
+    }
+
+    /// Dumps of a program before and after running the given passes
+    fn snapshots(source: &str, passes: &[Pass]) -> (String, String) {
+        let mut module = lower_source(source);
+        let before = module.to_string();
+        PassManager::with_passes(passes.to_vec()).run(&mut module);
+        (before, module.to_string())
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_eq!(PassManager::new(0).passes, []);
+        assert_eq!(PassManager::new(1).passes, [Pass::ConstFold, Pass::SimplifyCfg, Pass::CopyProp, Pass::DeadCode]);
+        assert_eq!(PassManager::new(2).passes, Pass::ALL);
+    }
+
+    #[test]
+    fn test_const_fold() {
+        let (before, after) = snapshots("
+            fn f() -> i32 {
+                let x = 2 * 3;
+                let y = 10 / 0;
+                return x + -1;
+            }
+        ", &[Pass::ConstFold]);
+        assert_eq!(before, "\
+fn f() -> i32 {
+bb0:
+    %0: () = const ()
+    %1: i32 = const 2
+    %2: i32 = const 3
+    %3: i32 = mul %1, %2
+    %4: i32 = const 10
+    %5: i32 = const 0
+    %6: i32 = div %4, %5
+    %7: i32 = const 1
+    %8: i32 = neg %7
+    %9: i32 = add %3, %8
+    return %9
+}
+");
+        // Division by zero is left to trap at run time
+        assert_eq!(after, "\
+fn f() -> i32 {
+bb0:
+    %0: () = const ()
+    %1: i32 = const 2
+    %2: i32 = const 3
+    %3: i32 = const 6
+    %4: i32 = const 10
+    %5: i32 = const 0
+    %6: i32 = div %4, %5
+    %7: i32 = const 1
+    %8: i32 = const -1
+    %9: i32 = const 5
+    return %9
+}
+");
+    }
+
+    #[test]
+    fn test_const_fold_wraps() {
+        let (_, after) = snapshots("
+            fn f() -> i32 {
+                let x: i32 = 2147483647;
+                let y: u32 = 0;
+                let z: u32 = y - 1;
+                return x + 1;
+            }
+        ", &[Pass::ConstFold]);
+        assert_eq!(after, "\
+fn f() -> i32 {
+bb0:
+    %0: () = const ()
+    %1: i32 = const 2147483647
+    %2: u32 = const 0
+    %3: u32 = const 1
+    %4: u32 = const 4294967295
+    %5: i32 = const 1
+    %6: i32 = const -2147483648
+    return %6
+}
+");
+    }
+
+    #[test]
+    fn test_dead_code() {
+        let (before, after) = snapshots("
+            fn f(n: i32) -> i32 {
+                let unused = n * 2;
+                let i = 0;
+                while i < n { i = i + 1; }
+                print(n);
+                return n / 1;
+            }
+        ", &[Pass::DeadCode]);
+        assert_eq!(before, "\
+fn f(%0: i32) -> i32 {
+bb0:
+    %0: i32 = param 0
+    %1: () = const ()
+    %2: i32 = const 2
+    %3: i32 = mul %0, %2
+    %4: i32 = const 0
+    jump bb1
+bb1:  ; preds: bb0, bb2
+    %5: i32 = phi [bb0: %4, bb2: %8]
+    %6: bool = lt %5, %0
+    branch %6, bb2, bb3
+bb2:  ; preds: bb1
+    %7: i32 = const 1
+    %8: i32 = add %5, %7
+    jump bb1
+bb3:  ; preds: bb1
+    %9: () = builtin print(%0)
+    %10: i32 = const 1
+    %11: i32 = div %0, %10
+    return %11
+}
+");
+        // The loop stays as its condition decides where control goes
+        assert_eq!(after, "\
+fn f(%0: i32) -> i32 {
+bb0:
+    %0: i32 = param 0
+    %1: i32 = const 0
+    jump bb1
+bb1:  ; preds: bb0, bb2
+    %2: i32 = phi [bb0: %1, bb2: %5]
+    %3: bool = lt %2, %0
+    branch %3, bb2, bb3
+bb2:  ; preds: bb1
+    %4: i32 = const 1
+    %5: i32 = add %2, %4
+    jump bb1
+bb3:  ; preds: bb1
+    %6: () = builtin print(%0)
+    %7: i32 = const 1
+    %8: i32 = div %0, %7
+    return %8
+}
+");
+    }
+
+    #[test]
+    fn test_copy_prop() {
+        let (before, after) = snapshots("
+            struct Pair { a: i32, b: i32 }
+            fn f(x: i32) -> i32 {
+                let pair = .Pair { a: x, b: 1 };
+                return pair.a;
+            }
+        ", &[Pass::ConstFold, Pass::CopyProp]);
+        assert_eq!(before, "\
+fn f(%0: i32) -> i32 {
+bb0:
+    %0: i32 = param 0
+    %1: () = const ()
+    %2: i32 = const 1
+    %3: Pair = struct {%0, %2}
+    %4: i32 = field %3.0
+    return %4
+}
+");
+        // Folding turns the field read into a copy which is then propagated
+        assert_eq!(after, "\
+fn f(%0: i32) -> i32 {
+bb0:
+    %0: i32 = param 0
+    %1: () = const ()
+    %2: i32 = const 1
+    %3: Pair = struct {%0, %2}
+    return %0
+}
+");
+    }
+
+    #[test]
+    fn test_simplify_cfg() {
+        let (before, after) = snapshots("
+            fn f(n: i32) -> i32 {
+                let x = n;
+                if true { x = 1; } else { x = 2; }
+                while n < 10 {
+                    if n == 5 { continue; }
+                    n = n + 1;
+                }
+                return x;
+            }
+        ", &[Pass::SimplifyCfg]);
+        assert_eq!(before, "\
+fn f(%0: i32) -> i32 {
+bb0:
+    %0: i32 = param 0
+    %1: () = const ()
+    %2: bool = const true
+    branch %2, bb1, bb3
+bb1:  ; preds: bb0
+    %3: i32 = const 1
+    jump bb2
+bb2:  ; preds: bb1, bb3
+    %4: i32 = phi [bb1: %3, bb3: %5]
+    jump bb4
+bb3:  ; preds: bb0
+    %5: i32 = const 2
+    jump bb2
+bb4:  ; preds: bb2, bb7, bb8
+    %6: i32 = phi [bb2: %0, bb7: %6, bb8: %12]
+    %7: i32 = const 10
+    %8: bool = lt %6, %7
+    branch %8, bb5, bb6
+bb5:  ; preds: bb4
+    %9: i32 = const 5
+    %10: bool = eq %6, %9
+    branch %10, bb7, bb8
+bb6:  ; preds: bb4
+    return %4
+bb7:  ; preds: bb5
+    jump bb4
+bb8:  ; preds: bb5
+    %11: i32 = const 1
+    %12: i32 = add %6, %11
+    jump bb4
+}
+");
+        assert_eq!(after, "\
+fn f(%0: i32) -> i32 {
+bb0:
+    %0: i32 = param 0
+    %1: () = const ()
+    %2: bool = const true
+    %3: i32 = const 1
+    jump bb1
+bb1:  ; preds: bb0, bb2, bb4
+    %4: i32 = phi [bb0: %0, bb4: %10, bb2: %4]
+    %5: i32 = const 10
+    %6: bool = lt %4, %5
+    branch %6, bb2, bb3
+bb2:  ; preds: bb1
+    %7: i32 = const 5
+    %8: bool = eq %4, %7
+    branch %8, bb1, bb4
+bb3:  ; preds: bb1
+    return %3
+bb4:  ; preds: bb2
+    %9: i32 = const 1
+    %10: i32 = add %4, %9
+    jump bb1
+}
+");
+    }
+
+    #[test]
+    fn test_inline() {
+        let (before, after) = snapshots("
+            const LIMIT: i32 = 10;
+            fn clamp(x: i32) -> i32 {
+                if x > LIMIT { return LIMIT; }
+                return x;
+            }
+            fn fact(n: i32) -> i32 {
+                if n == 0 { return 1; }
+                return n * fact(n - 1);
+            }
+            fn main() {
+                print(clamp(fact(3)));
+            }
+        ", &[Pass::Inline]);
+        assert!(before.contains("call clamp(%2)"), "{}", before);
+        // `fact` is recursive, `clamp` and the constant it reads are not
+        let main = &after[after.find("fn main").unwrap()..];
+        assert_eq!(main, "\
+fn main() -> () {
+bb0:
+    %0: () = const ()
+    %1: i32 = const 3
+    %2: i32 = call fact(%1)
+    jump bb2
+bb1:  ; preds: bb4, bb7
+    %3: i32 = phi [bb7: %10, bb4: %5]
+    %4: () = builtin print(%3)
+    return %0
+bb2:  ; preds: bb0
+    %5: i32 = copy %2
+    %6: () = const ()
+    jump bb6
+bb3:  ; preds: bb5
+    jump bb8
+bb4:  ; preds: bb5
+    jump bb1
+bb5:  ; preds: bb6
+    %7: i32 = copy %9
+    %8: bool = gt %5, %7
+    branch %8, bb3, bb4
+bb6:  ; preds: bb2
+    %9: i32 = const 10
+    jump bb5
+bb7:  ; preds: bb8
+    %10: i32 = copy %11
+    jump bb1
+bb8:  ; preds: bb3
+    %11: i32 = const 10
+    jump bb7
+}
+");
+    }
+
+    #[test]
+    fn test_all_passes() {
+        let (_, after) = snapshots("
+            const LIMIT: i32 = 10;
+            fn clamp(x: i32) -> i32 {
+                if x > LIMIT { return LIMIT; }
+                return x;
+            }
+            fn main() {
+                print(clamp(3) + clamp(20));
+            }
+        ", &Pass::ALL);
+        let main = &after[after.find("fn main").unwrap()..];
+        assert_eq!(main, "\
+fn main() -> () {
+bb0:
+    %0: () = const ()
+    %1: i32 = const 13
+    %2: () = builtin print(%1)
+    return %0
+}
+");
+    }
+
+    #[test]
+    fn test_optimize_samples() {
+        for source in [
+            include_str!("../../../samples/fib.hz"),
+            include_str!("../../../samples/loops.hz"),
+            include_str!("../../../samples/points.hz"),
+        ] {
+            let mut module = lower_source(source);
+            PassManager::new(2).run(&mut module);
+            verify::verify_module(&module).unwrap();
+        }
+    }
+}
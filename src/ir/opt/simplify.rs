@@ -0,0 +1,131 @@
+//! Control flow graph simplification
+//!
+//! - branches on a constant, or with both targets equal, become jumps,
+//! - blocks which only jump elsewhere are bypassed,
+//! - a block jumping to a block with no other predecessor absorbs it,
+//! - blocks which can no longer be reached are deleted.
+
+use hashbrown::HashMap;
+
+use crate::ir::{cfg, Block, Constant, Function, Inst, Terminator, Value};
+
+pub fn run(function: &mut Function) -> bool {
+    let mut changed = fold_branches(function);
+    changed |= cfg::remove_unreachable_blocks(function);
+    changed |= bypass_empty_blocks(function);
+    changed |= merge_blocks(function);
+    changed |= cfg::remove_unreachable_blocks(function);
+    changed |= cfg::remove_trivial_phis(function);
+    changed
+}
+
+fn phis(function: &Function, block: Block) -> Vec<Value> {
+    function.block(block).insts.iter()
+        .copied()
+        .take_while(|&value| matches!(function.inst(value), Inst::Phi(_)))
+        .collect()
+}
+
+/// Edits the phi operands flowing from `pred` into `block`
+fn edit_incoming(function: &mut Function, block: Block, mut edit: impl FnMut(&mut Vec<(Block, Value)>)) {
+    for phi in phis(function, block) {
+        let Inst::Phi(incoming) = function.inst_mut(phi) else { unreachable!() };
+        edit(incoming);
+    }
+}
+
+fn fold_branches(function: &mut Function) -> bool {
+    let mut changed = false;
+    for block in function.block_ids().collect::<Vec<_>>() {
+        let Some(Terminator::Branch(condition, then, otherwise)) = function.block(block).terminator else { continue };
+        let (taken, dropped) = match function.inst(condition) {
+            _ if then == otherwise => (then, None),
+            Inst::Const(Constant::Bool(true)) => (then, Some(otherwise)),
+            Inst::Const(Constant::Bool(false)) => (otherwise, Some(then)),
+            _ => continue,
+        };
+        function.block_mut(block).terminator = Some(Terminator::Jump(taken));
+        if let Some(dropped) = dropped {
+            edit_incoming(function, dropped, |incoming| incoming.retain(|(pred, _)| *pred != block));
+        }
+        changed = true;
+    }
+    changed
+}
+
+/// Sends the predecessors of empty blocks ending in a jump straight to its target
+fn bypass_empty_blocks(function: &mut Function) -> bool {
+    let mut changed = false;
+    for block in function.block_ids().skip(1).collect::<Vec<_>>() {
+        let data = function.block(block);
+        let Some(Terminator::Jump(target)) = data.terminator else { continue };
+        if !data.insts.is_empty() || target == block {
+            continue;
+        }
+        let preds = cfg::predecessors(function);
+        let block_preds = preds[block.0 as usize].clone();
+        // A phi can only tell predecessors apart if they stay distinct
+        let has_phis = !phis(function, target).is_empty();
+        if block_preds.is_empty() || has_phis && block_preds.iter().any(|pred| preds[target.0 as usize].contains(pred)) {
+            continue;
+        }
+
+        for &pred in block_preds.iter() {
+            let terminator = function.block_mut(pred).terminator.as_mut().unwrap();
+            terminator.map_targets(|succ| if succ == block { target } else { succ });
+        }
+        // Whatever flowed in from the block now flows in from each of its predecessors
+        edit_incoming(function, target, |incoming| {
+            let Some(i) = incoming.iter().position(|(pred, _)| *pred == block) else { return };
+            let (_, value) = incoming.remove(i);
+            incoming.extend(block_preds.iter().map(|&pred| (pred, value)));
+        });
+        changed = true;
+    }
+    changed
+}
+
+/// Appends blocks to their only predecessor when it jumps straight to them
+fn merge_blocks(function: &mut Function) -> bool {
+    let mut changed = false;
+    let mut replaced: HashMap<Value, Value> = HashMap::new();
+    for block in function.block_ids().collect::<Vec<_>>() {
+        while let Some(Terminator::Jump(next)) = function.block(block).terminator {
+            let preds = cfg::predecessors(function);
+            if next == block || next == Block::ENTRY || preds[next.0 as usize] != [block] {
+                break;
+            }
+
+            let absorbed = std::mem::take(function.block_mut(next));
+            for &value in absorbed.insts.iter() {
+                // With a single predecessor a phi has a single operand
+                if let Inst::Phi(incoming) = function.inst(value) {
+                    replaced.insert(value, incoming[0].1);
+                } else {
+                    function.block_mut(block).insts.push(value);
+                }
+            }
+            let terminator = absorbed.terminator.expect("finished blocks have terminators");
+            for succ in terminator.successors() {
+                edit_incoming(function, succ, |incoming| {
+                    incoming.iter_mut().for_each(|(pred, _)| if *pred == next { *pred = block });
+                });
+            }
+            function.block_mut(block).terminator = Some(terminator);
+            // The absorbed block stays behind, unreachable, until it is deleted
+            function.block_mut(next).terminator = Some(Terminator::Unreachable);
+            changed = true;
+        }
+    }
+
+    if !replaced.is_empty() {
+        let resolve = |mut value: Value| {
+            while let Some(&next) = replaced.get(&value) {
+                value = next;
+            }
+            value
+        };
+        function.replace_uses(resolve);
+    }
+    changed
+}
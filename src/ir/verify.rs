@@ -0,0 +1,193 @@
+//! Well-formedness checks for IR functions
+//!
+//! Run after lowering and after every optimization pass in tests. A function
+//! is well formed when:
+//! - every block ends in a terminator whose targets exist,
+//! - the entry block has no predecessors,
+//! - every value is placed in exactly one block and is defined before it is
+//!   used, in the same block or in a dominating one,
+//! - phis come first in their block and have exactly one operand per
+//!   predecessor, each available at the end of that predecessor,
+//! - branch conditions are booleans and returned values have the return type,
+//! - integer constants fit in their type.
+
+use std::fmt::{self, Display};
+
+use hashbrown::HashMap;
+
+use crate::typecheck::Type;
+
+use super::{cfg, Block, Constant, Function, Inst, Module, Terminator, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyError {
+    pub function: Box<str>,
+    pub block: Block,
+    pub message: String,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "in `{}` bb{}: {}", self.function, self.block.0, self.message)
+    }
+}
+
+pub fn verify_module(module: &Module) -> Result<(), Vec<VerifyError>> {
+    let errors: Vec<VerifyError> = module.functions.iter()
+        .flat_map(|function| verify(function).err().unwrap_or_default())
+        .collect();
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+pub fn verify(function: &Function) -> Result<(), Vec<VerifyError>> {
+    let mut verifier = Verifier { function, errors: Vec::new() };
+    verifier.verify();
+    if verifier.errors.is_empty() { Ok(()) } else { Err(verifier.errors) }
+}
+
+struct Verifier<'f> {
+    function: &'f Function,
+    errors: Vec<VerifyError>,
+}
+
+impl<'f> Verifier<'f> {
+    fn error(&mut self, block: Block, message: String) {
+        self.errors.push(VerifyError { function: self.function.name.clone(), block, message });
+    }
+
+    fn verify(&mut self) {
+        let function = self.function;
+        if function.blocks.is_empty() {
+            self.error(Block::ENTRY, "function has no blocks".into());
+            return;
+        }
+
+        // Structure first, dominance is meaningless on a broken graph
+        for block in function.block_ids() {
+            match &function.block(block).terminator {
+                None => self.error(block, "block has no terminator".into()),
+                Some(terminator) => {
+                    for target in terminator.successors() {
+                        if target.0 as usize >= function.blocks.len() {
+                            self.error(block, format!("jump to missing block bb{}", target.0));
+                        }
+                    }
+                }
+            }
+        }
+        if !self.errors.is_empty() {
+            return;
+        }
+        let preds = cfg::predecessors(function);
+        if !preds[0].is_empty() {
+            self.error(Block::ENTRY, "entry block has predecessors".into());
+        }
+
+        // Where every value is defined
+        let reported = self.errors.len();
+        let mut defined_in: HashMap<Value, (Block, usize)> = HashMap::new();
+        for block in function.block_ids() {
+            for (position, &value) in function.block(block).insts.iter().enumerate() {
+                if value.0 as usize >= function.values.len() {
+                    self.error(block, format!("value %?{} does not exist", value.0));
+                } else if defined_in.insert(value, (block, position)).is_some() {
+                    self.error(block, format!("value %?{} is placed more than once", value.0));
+                }
+            }
+        }
+        if self.errors.len() > reported {
+            return;
+        }
+
+        let idom = cfg::dominators(function);
+        let reachable = |block: Block| idom[block.0 as usize].is_some();
+        // Is `value` available at the given position of a block?
+        let available = |value: Value, block: Block, position: usize| match defined_in.get(&value) {
+            None => false,
+            Some(&(def_block, def_position)) if def_block == block => def_position < position,
+            Some(&(def_block, _)) => !reachable(block) || cfg::dominates(&idom, def_block, block),
+        };
+
+        for (i, &param) in function.params.iter().enumerate() {
+            if function.inst(param) != &Inst::Param(i as u32) || defined_in.get(&param).map(|d| d.0) != Some(Block::ENTRY) {
+                self.error(Block::ENTRY, format!("parameter {} is not a param instruction of the entry block", i));
+            }
+        }
+
+        for block in function.block_ids() {
+            let data = function.block(block);
+            let mut phis_done = false;
+            for (position, &value) in data.insts.iter().enumerate() {
+                let inst = function.inst(value);
+                if let Inst::Phi(incoming) = inst {
+                    if phis_done {
+                        self.error(block, "phi after other instructions".into());
+                    }
+                    let mut sources: Vec<Block> = incoming.iter().map(|(pred, _)| *pred).collect();
+                    sources.sort();
+                    let mut expected = preds[block.0 as usize].clone();
+                    expected.sort();
+                    if sources != expected {
+                        self.error(block, "phi operands do not match the predecessors".into());
+                    }
+                    for &(pred, operand) in incoming.iter() {
+                        let end = function.block(pred).insts.len();
+                        if expected.contains(&pred) && !available(operand, pred, end + 1) {
+                            self.error(block, format!("phi operand from bb{} is not available there", pred.0));
+                        }
+                        if !self.same_type(function.ty(operand), function.ty(value)) {
+                            self.error(block, "phi operand has a different type".into());
+                        }
+                    }
+                    continue;
+                }
+                phis_done = true;
+
+                match inst {
+                    Inst::Param(_) if block != Block::ENTRY => {
+                        self.error(block, "param outside of the entry block".into());
+                    }
+                    Inst::Const(constant) if !fits(constant, function.ty(value)) => {
+                        self.error(block, format!("constant {} out of range for {}", constant, function.ty(value)));
+                    }
+                    _ => {}
+                }
+                for operand in inst.operands() {
+                    if !available(operand, block, position) {
+                        self.error(block, format!("use of value %?{} before its definition", operand.0));
+                    }
+                }
+            }
+
+            let terminator = data.terminator.as_ref().unwrap();
+            for operand in terminator.operands() {
+                if !available(operand, block, data.insts.len()) {
+                    self.error(block, format!("use of value %?{} before its definition", operand.0));
+                }
+            }
+            match terminator {
+                Terminator::Branch(condition, ..) if !self.same_type(function.ty(*condition), &Type::Bool) => {
+                    self.error(block, "branch on a value which is not a bool".into());
+                }
+                Terminator::Return(value) if !self.same_type(function.ty(*value), &function.return_type) => {
+                    self.error(block, format!("returned {} from a function returning {}", function.ty(*value), function.return_type));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Never values are only produced by unreachable code and fit anywhere
+    fn same_type(&self, found: &Type, expected: &Type) -> bool {
+        found == expected || matches!(found, Type::Never | Type::Error) || matches!(expected, Type::Error)
+    }
+}
+
+/// Whether an integer constant is in the range of its type
+fn fits(constant: &Constant, typ: &Type) -> bool {
+    match (constant, typ) {
+        (Constant::Int(value), Type::I32) => i32::try_from(*value).is_ok(),
+        (Constant::UInt(value), Type::U32) => u32::try_from(*value).is_ok(),
+        _ => true,
+    }
+}
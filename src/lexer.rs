@@ -1,5 +1,3 @@
-use std::{iter::Peekable, str::CharIndices, ops::Range, fmt, error::Error};
-
 use crate::token::{Tag, Token};
 
 /// A lexer that turns a string literal to a stream of tokens.
@@ -29,6 +27,8 @@ impl<'a> Lexer<'a> {
         self.skip_whitespace();
 
         if let Some((idx, ch)) = self.next_ch() {
+            // Tokens are on the line they start on, even multi-line strings
+            let line = self.line;
             let mut tag = Tag::Invalid;
             match ch {
                 '+' => tag = self.bi_tok('=', Tag::Plus, Tag::PlusEqual),
@@ -68,7 +68,7 @@ impl<'a> Lexer<'a> {
 
                 _ => {}
             }
-            Some(Token::new(tag, unsafe { self.slice(idx) }, idx, self.line))
+            Some(Token::new(tag, unsafe { self.slice(idx) }, idx, line))
         } else {
             None
         }
@@ -85,6 +85,12 @@ impl<'a> Lexer<'a> {
                 Some('\t' | '\r' | ' ') => {
                     self.bump();
                 }
+                // Line comments run up to the newline
+                Some('/') if self.src.as_bytes().get(self.offset as usize + 1) == Some(&b'/') => {
+                    while !matches!(self.peek_off(), Some('\n') | None) {
+                        self.next_ch();
+                    }
+                }
                 _ => break,
             }
         }
@@ -142,11 +148,8 @@ impl<'a> Lexer<'a> {
                 Some('0'..='9') => self.bump(),
                 Some('.') => {
                     self.bump();
-                    loop {
-                        match self.peek_off() {
-                            Some('0'..='9') => self.bump(),
-                            _ => break,
-                        }
+                    while let Some('0'..='9') = self.peek_off() {
+                        self.bump();
                     }
                     return Tag::Number;
                 }
@@ -186,13 +189,14 @@ impl<'a> Lexer<'a> {
             "true" => Tag::Bool,
             "false" => Tag::Bool,
             "break" => Tag::Break,
-            "contine" => Tag::Continue,
+            "continue" => Tag::Continue,
             "module" => Tag::Module,
             "import" => Tag::Import,
             "enum" => Tag::Enum,
             "struct" => Tag::Struct,
             "type" => Tag::Type,
             "const" => Tag::Const,
+            "pub" => Tag::Pub,
             _ => Tag::Ident,
         }
     }
@@ -343,13 +347,13 @@ mod tests {
 
     use super::Lexer;
 
-    fn make_lex<'a>(source: &'a str) -> Lexer {
+    fn make_lex(source: &str) -> Lexer<'_> {
         Lexer::from(source)
     }
 
     /// Transform a string slice into a vector of tokens
     /// This is a testing utility that provides an easier to compare representation of tokens
-    fn lex<'a>(source: &'a str) -> Vec<(Tag, &'a str)> {
+    fn lex(source: &str) -> Vec<(Tag, &str)> {
         make_lex(source).map(|tok| (tok.tag, tok.value)).collect()
     }
 
@@ -674,4 +678,23 @@ mod tests {
             ]
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_comments() {
+        let tokens = lex("let x = 1; // one\n// two / 2\nx / 2 //");
+        assert_eq!(
+            tokens,
+            vec![
+                (Let, "let"),
+                (Ident, "x"),
+                (Equal, "="),
+                (Number, "1"),
+                (Semicolon, ";"),
+                (Ident, "x"),
+                (Slash, "/"),
+                (Number, "2"),
+            ]
+        );
+        assert_eq!(make_lex("// a\n\n// b\nx").next().unwrap().line, 4);
+    }
+}
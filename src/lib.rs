@@ -0,0 +1,35 @@
+#![cfg_attr(test, feature(test))]
+
+pub mod lexer;
+pub mod token;
+pub mod utils;
+// The first parser and its tree, superseded by parser3 and ast2 and only
+// partly written, so much of them is never called
+#[allow(dead_code)]
+pub mod ast;
+mod ast2;
+// pub mod parser;
+pub mod bumping;
+pub mod errors;
+#[allow(dead_code)]
+pub mod parser2;
+pub mod parser3;
+pub mod incremental;
+pub mod grammar;
+mod typecheck;
+mod visitor;
+mod codegen;
+mod loader;
+mod vm;
+mod ir;
+mod wasm;
+mod lsp;
+mod highlight;
+mod names;
+mod repl;
+mod explain;
+mod query;
+pub mod tree_json;
+mod dump;
+pub mod generate;
+pub mod cli;
@@ -0,0 +1,519 @@
+//! Multi-file module loading
+//!
+//! A Haze project is a directory tree of `.hz` files. An import path maps onto
+//! that tree relative to the project root: `import geometry.shapes;` names the
+//! file `<root>/geometry/shapes.hz`. When no such file exists, the path may
+//! instead name an inline `module` declared in a shorter file, e.g.
+//! `module shapes { ... }` inside `<root>/geometry.hz`.
+//!
+//! Every file is read and parsed at most once. Imported items are brought into
+//! the importer's scope unqualified; only items declared `pub` are exported.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use bumpalo::Bump;
+use hashbrown::HashMap;
+use indexmap::IndexMap;
+
+use crate::ast2::{AstNode, AstToken, Ident, Node, Pub, TopDeclList, TopLevelDecl};
+use crate::errors::{Loc, ParseError};
+use crate::parser3::Parser;
+
+/// File extension of Haze source files
+pub const SOURCE_EXTENSION: &str = "hz";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModuleId(u32);
+
+/// A parsed source file
+pub struct SourceFile<'b> {
+    pub path: PathBuf,
+    pub source: &'b str,
+    pub errors: Vec<ParseError>,
+}
+
+/// A module is either the root of a file or an inline `module` declaration
+pub struct ModuleInfo<'b> {
+    /// Dotted import path, e.g. `geometry.shapes`
+    pub name: Box<str>,
+    pub file: FileId,
+    pub decls: TopDeclList<'b, 'b>,
+    /// Resolved imports along with the location of their import declaration
+    pub imports: Vec<(ModuleId, Loc)>,
+}
+
+/// An item declared at the top level of a module
+#[derive(Debug, Clone)]
+pub struct Item<'b> {
+    pub module: ModuleId,
+    pub decl: TopLevelDecl<'b, 'b>,
+}
+
+impl<'b> Item<'b> {
+    pub fn is_public(&self) -> bool {
+        decl_visibility(&self.decl).is_some()
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadErrorKind {
+    /// No file or inline module matches the import path
+    ModuleNotFound(Box<str>),
+    Io(PathBuf, std::io::Error),
+    /// Modules which import each other, in import order
+    ImportCycle(Box<[Box<str>]>),
+}
+
+impl fmt::Display for LoadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadErrorKind::ModuleNotFound(name) => write!(f, "no file or module `{}`", name),
+            LoadErrorKind::Io(path, err) => write!(f, "cannot read `{}`: {}", path.display(), err),
+            LoadErrorKind::ImportCycle(names) => {
+                write!(f, "modules import each other: ")?;
+                for name in names.iter() {
+                    write!(f, "`{}` -> ", name)?;
+                }
+                write!(f, "`{}`", names[0])
+            }
+        }
+    }
+}
+
+impl LoadErrorKind {
+    /// Stable code of the error, explained by `haze explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            LoadErrorKind::ModuleNotFound(_) => "E0300",
+            LoadErrorKind::Io(..) => "E0301",
+            LoadErrorKind::ImportCycle(_) => "E0302",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LoadError {
+    pub kind: LoadErrorKind,
+    /// Module containing the offending import, if any
+    pub module: Option<ModuleId>,
+    pub location: Option<Loc>,
+}
+
+/// All modules reachable from an entry file
+pub struct ModuleGraph<'b> {
+    root: PathBuf,
+    bump: &'b Bump,
+    files: Vec<SourceFile<'b>>,
+    modules: Vec<ModuleInfo<'b>>,
+    by_name: HashMap<Box<str>, ModuleId>,
+    loaded_paths: HashMap<PathBuf, FileId>,
+    /// Number of modules whose imports have been resolved
+    resolved: usize,
+    pub errors: Vec<LoadError>,
+}
+
+impl<'b> ModuleGraph<'b> {
+    /// Creates an empty graph for the project rooted at `root`
+    pub fn new(root: impl Into<PathBuf>, bump: &'b Bump) -> Self {
+        Self {
+            root: root.into(),
+            bump,
+            files: Vec::new(),
+            modules: Vec::new(),
+            by_name: HashMap::new(),
+            loaded_paths: HashMap::new(),
+            resolved: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Loads the entry file, given relative to the project root, and every module
+    /// it transitively imports.
+    ///
+    /// Import cycles are reported once all modules are loaded.
+    pub fn load_entry(&mut self, path: impl AsRef<Path>) -> Option<ModuleId> {
+        let path = path.as_ref();
+        let name = module_name_of(path);
+        let file = self.load_file(self.root.join(path), &name, None, None)?;
+        let entry = self.file_module(file);
+
+        self.resolve_imports();
+        self.detect_cycles();
+
+        Some(entry)
+    }
+
+    pub fn module(&self, id: ModuleId) -> &ModuleInfo<'b> {
+        &self.modules[id.0 as usize]
+    }
+
+    pub fn modules(&self) -> impl Iterator<Item = (ModuleId, &ModuleInfo<'b>)> {
+        self.modules.iter().enumerate().map(|(i, m)| (ModuleId(i as u32), m))
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile<'b> {
+        &self.files[id.0 as usize]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &SourceFile<'b>> {
+        self.files.iter()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<ModuleId> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Every named item declared in a module, public or not
+    pub fn items(&self, id: ModuleId) -> IndexMap<&'b str, Item<'b>> {
+        self.module(id)
+            .decls
+            .items()
+            .filter_map(|decl| {
+                let name = decl_name(&decl)?.token().value;
+                Some((name, Item { module: id, decl }))
+            })
+            .collect()
+    }
+
+    /// Items a module makes available to its importers
+    ///
+    /// Only `pub` items are exported. Import declarations are not re-exported.
+    pub fn exports(&self, id: ModuleId) -> IndexMap<&'b str, Item<'b>> {
+        let mut items = self.items(id);
+        items.retain(|_, item| item.is_public());
+        items
+    }
+
+    /// Items brought into scope by a module's imports, in import order.
+    ///
+    /// When several imports provide the same name, the first one wins.
+    pub fn imported_items(&self, id: ModuleId) -> IndexMap<&'b str, Item<'b>> {
+        let mut items = IndexMap::new();
+        for &(import, _) in self.module(id).imports.iter() {
+            for (name, item) in self.exports(import) {
+                items.entry(name).or_insert(item);
+            }
+        }
+        items
+    }
+
+    /// Every name visible at the top level of a module.
+    ///
+    /// A module's own items shadow imported ones.
+    pub fn scope(&self, id: ModuleId) -> IndexMap<&'b str, Item<'b>> {
+        let mut scope = self.items(id);
+        for (name, item) in self.imported_items(id) {
+            scope.entry(name).or_insert(item);
+        }
+        scope
+    }
+
+    fn file_module(&self, file: FileId) -> ModuleId {
+        self.modules
+            .iter()
+            .position(|m| m.file == file)
+            .map(|i| ModuleId(i as u32))
+            .expect("every loaded file has a root module")
+    }
+
+    /// Reads and parses a file, registering its root module and inline modules.
+    fn load_file(
+        &mut self,
+        path: PathBuf,
+        name: &str,
+        importer: Option<ModuleId>,
+        location: Option<Loc>,
+    ) -> Option<FileId> {
+        if let Some(&file) = self.loaded_paths.get(&path) {
+            return Some(file);
+        }
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                self.errors.push(LoadError {
+                    kind: LoadErrorKind::Io(path, err),
+                    module: importer,
+                    location,
+                });
+                return None;
+            }
+        };
+
+        let source: &'b str = self.bump.alloc_str(&contents);
+        let mut parser = Parser::new(source, self.bump);
+        let tree: &'b Node<'b, 'b> = self.bump.alloc(parser.parse().tree);
+
+        let file = FileId(self.files.len() as u32);
+        self.files.push(SourceFile {
+            path: path.clone(),
+            source,
+            errors: std::mem::take(&mut parser.errors),
+        });
+        self.loaded_paths.insert(path, file);
+
+        self.register_module(name.into(), file, TopDeclList::cast(tree));
+
+        Some(file)
+    }
+
+    fn register_module(&mut self, name: Box<str>, file: FileId, decls: TopDeclList<'b, 'b>) {
+        // A file and an inline module may claim the same name; the first one wins.
+        if !self.by_name.contains_key(&name) {
+            self.by_name.insert(name.clone(), ModuleId(self.modules.len() as u32));
+        }
+        self.modules.push(ModuleInfo {
+            name: name.clone(),
+            file,
+            decls: decls.clone(),
+            imports: Vec::new(),
+        });
+
+        for decl in decls.items() {
+            if let TopLevelDecl::Mod(module) = decl {
+                let inner = format!("{}.{}", name, module.name().token().value);
+                self.register_module(inner.into(), file, module.decls());
+            }
+        }
+    }
+
+    /// Finds the module named by an import path, loading files as needed.
+    ///
+    /// Longer file paths are preferred: `a.b.c` tries `a/b/c.hz`, then
+    /// `a/b.hz` (for an inline module `c`), then `a.hz`.
+    fn find_or_load(
+        &mut self,
+        segments: &[&str],
+        importer: Option<ModuleId>,
+        location: Option<Loc>,
+    ) -> Option<ModuleId> {
+        let name = segments.join(".");
+        if let Some(id) = self.lookup(&name) {
+            return Some(id);
+        }
+
+        for len in (1..=segments.len()).rev() {
+            let mut path = self.root.clone();
+            path.extend(&segments[..len]);
+            path.set_extension(SOURCE_EXTENSION);
+
+            if self.loaded_paths.contains_key(&path) || !path.is_file() {
+                continue;
+            }
+            self.load_file(path, &segments[..len].join("."), importer, location)?;
+
+            if let Some(id) = self.lookup(&name) {
+                return Some(id);
+            }
+        }
+
+        self.errors.push(LoadError {
+            kind: LoadErrorKind::ModuleNotFound(name.into()),
+            module: importer,
+            location,
+        });
+        None
+    }
+
+    /// Resolves the imports of every module, loading new modules along the way.
+    fn resolve_imports(&mut self) {
+        while self.resolved < self.modules.len() {
+            let id = ModuleId(self.resolved as u32);
+            self.resolved += 1;
+
+            let decls = self.module(id).decls.clone();
+            let mut imports = Vec::new();
+            for decl in decls.items() {
+                let TopLevelDecl::Import(import) = decl else { continue };
+
+                let path: Vec<Ident<'b, 'b>> = import.path().collect();
+                let (Some(first), Some(last)) = (path.first(), path.last()) else { continue };
+                let (first, last) = (first.token(), last.token());
+                let location = Loc::new(
+                    first.pos,
+                    last.pos + last.value.len() as u32 - first.pos,
+                    first.line,
+                );
+
+                let segments: Vec<&str> = path.iter().map(|ident| ident.token().value).collect();
+                if let Some(target) = self.find_or_load(&segments, Some(id), Some(location)) {
+                    imports.push((target, location));
+                }
+            }
+            self.modules[id.0 as usize].imports = imports;
+        }
+    }
+
+    /// Reports every import cycle once, at the import which closes it.
+    fn detect_cycles(&mut self) {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark { Unvisited, InProgress, Done }
+
+        let mut marks = vec![Mark::Unvisited; self.modules.len()];
+        let mut errors = Vec::new();
+
+        for start in 0..self.modules.len() {
+            if marks[start] != Mark::Unvisited { continue; }
+
+            // (module, index of the next import to follow)
+            let mut stack = vec![(start, 0)];
+            marks[start] = Mark::InProgress;
+
+            while let Some(&mut (module, ref mut edge)) = stack.last_mut() {
+                let Some(&(target, location)) = self.modules[module].imports.get(*edge) else {
+                    marks[module] = Mark::Done;
+                    stack.pop();
+                    continue;
+                };
+                *edge += 1;
+
+                let target = target.0 as usize;
+                match marks[target] {
+                    Mark::Unvisited => {
+                        marks[target] = Mark::InProgress;
+                        stack.push((target, 0));
+                    }
+                    Mark::InProgress => {
+                        let cycle_start = stack.iter().position(|&(m, _)| m == target).unwrap();
+                        let names = stack[cycle_start..]
+                            .iter()
+                            .map(|&(m, _)| self.modules[m].name.clone())
+                            .collect();
+                        errors.push(LoadError {
+                            kind: LoadErrorKind::ImportCycle(names),
+                            module: Some(ModuleId(module as u32)),
+                            location: Some(location),
+                        });
+                    }
+                    Mark::Done => {}
+                }
+            }
+        }
+
+        self.errors.extend(errors);
+    }
+}
+
+/// The name a declaration binds at the top level of its module
+pub fn decl_name<'b>(decl: &TopLevelDecl<'b, 'b>) -> Option<Ident<'b, 'b>> {
+    match decl {
+        TopLevelDecl::Mod(node) => Some(node.name()),
+        TopLevelDecl::Import(_) => None,
+        TopLevelDecl::Enum(node) => Some(node.name()),
+        TopLevelDecl::Fn(node) => Some(node.name()),
+        TopLevelDecl::Struct(node) => Some(node.name()),
+        TopLevelDecl::Type(node) => Some(node.name()),
+        TopLevelDecl::Const(node) => Some(node.name()),
+    }
+}
+
+/// The `pub` token of an exported declaration
+///
+/// Modules and imports are never exported.
+pub fn decl_visibility<'b>(decl: &TopLevelDecl<'b, 'b>) -> Option<Pub<'b, 'b>> {
+    match decl {
+        TopLevelDecl::Mod(_) | TopLevelDecl::Import(_) => None,
+        TopLevelDecl::Enum(node) => node.visibility(),
+        TopLevelDecl::Fn(node) => node.visibility(),
+        TopLevelDecl::Struct(node) => node.visibility(),
+        TopLevelDecl::Type(node) => node.visibility(),
+        TopLevelDecl::Const(node) => node.visibility(),
+    }
+}
+
+/// Derives a module name from a file path relative to the project root
+fn module_name_of(path: &Path) -> String {
+    path.with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp_project::TempProject;
+
+    #[test]
+    fn test_imported_items_in_scope() {
+        let project = TempProject::new(&[
+            ("main.hz", "import geometry.shapes;\nfn main() { }"),
+            ("geometry/shapes.hz", "pub struct Square { side: i32 }\npub fn area(s: Square) -> i32 { return 1; }\nfn helper() { }"),
+        ]);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        let main = graph.load_entry("main.hz").unwrap();
+
+        assert!(graph.errors.is_empty(), "{:?}", graph.errors);
+        let scope = graph.scope(main);
+        assert!(scope.keys().copied().eq(["main", "Square", "area"]));
+        assert_eq!(scope["area"].module, graph.lookup("geometry.shapes").unwrap());
+    }
+
+    #[test]
+    fn test_files_parsed_once() {
+        let project = TempProject::new(&[
+            ("main.hz", "import a;\nimport b;"),
+            ("a.hz", "import common;"),
+            ("b.hz", "import common;"),
+            ("common.hz", "const ZERO: i32 = 0;"),
+        ]);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        graph.load_entry("main.hz").unwrap();
+
+        assert!(graph.errors.is_empty(), "{:?}", graph.errors);
+        assert_eq!(graph.files().count(), 4);
+    }
+
+    #[test]
+    fn test_inline_module_import() {
+        let project = TempProject::new(&[
+            ("main.hz", "import util.math;"),
+            ("util.hz", "module math { pub fn square(x: i32) -> i32 { return x * x; } }"),
+        ]);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        let main = graph.load_entry("main.hz").unwrap();
+
+        assert!(graph.errors.is_empty(), "{:?}", graph.errors);
+        assert!(graph.imported_items(main).contains_key("square"));
+    }
+
+    #[test]
+    fn test_import_cycle() {
+        let project = TempProject::new(&[
+            ("main.hz", "import a;"),
+            ("a.hz", "import b;"),
+            ("b.hz", "import a;"),
+        ]);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        graph.load_entry("main.hz").unwrap();
+
+        let cycles: Vec<_> = graph.errors.iter().filter_map(|err| match &err.kind {
+            LoadErrorKind::ImportCycle(names) => Some(names.iter().map(|n| &**n).collect::<Vec<_>>()),
+            _ => None,
+        }).collect();
+        assert_eq!(cycles, vec![vec!["a", "b"]]);
+    }
+
+    #[test]
+    fn test_missing_module() {
+        let project = TempProject::new(&[("main.hz", "fn main() { }\nimport nowhere.thing;")]);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        graph.load_entry("main.hz").unwrap();
+
+        assert_eq!(graph.errors.len(), 1);
+        let err = &graph.errors[0];
+        assert!(matches!(&err.kind, LoadErrorKind::ModuleNotFound(name) if &**name == "nowhere.thing"));
+        let loc = err.location.unwrap();
+        assert_eq!((loc.line, loc.len), (2, "nowhere.thing".len() as u32));
+    }
+}
@@ -0,0 +1,196 @@
+//! What the server knows about a single document
+//!
+//! A document is parsed and, when it parses cleanly, type checked. The tree
+//! may be dropped right after, so everything requests need is copied out
+//! into owned data.
+
+use crate::ast2::{AstNode, AstToken, Ident, Node, TopDeclList, TopLevelDecl};
+use crate::errors::Loc;
+use crate::errors::{ParseError, TextEdit};
+use crate::loader::decl_name;
+use crate::names::{describe_global, extent, Name, Names, Scope};
+use crate::typecheck::check::TypeChecker;
+use crate::typecheck::resolve::BUILTIN_FUNCTIONS;
+
+/// Keywords offered by completion
+pub const KEYWORDS: &[&str] = &[
+    "fn", "if", "else", "return", "while", "let", "true", "false", "break", "continue",
+    "module", "import", "enum", "struct", "type", "const", "pub",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Field,
+    Const,
+    Enum,
+    Variant,
+    TypeAlias,
+    Module,
+    Variable,
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub location: Loc,
+    /// Error code, see `haze explain`
+    pub code: &'static str,
+    pub message: String,
+    pub fix: Option<TextEdit>,
+}
+
+/// A declaration at the top level of the document or nested in one
+#[derive(Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The whole declaration
+    pub location: Loc,
+    /// Just the name
+    pub name_location: Loc,
+    pub children: Vec<Symbol>,
+}
+
+/// A name which may be completed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub symbols: Vec<Symbol>,
+    /// Names in the order they appear
+    pub names: Vec<Name>,
+    globals: Vec<Completion>,
+    scopes: Vec<Scope>,
+}
+
+impl Analysis {
+    /// Analyses a tree parsed from `source`, with the errors parsing reported
+    pub fn of<'b, 'e>(source: &'b str, root: &'b Node<'b, 'b>, errors: impl Iterator<Item = &'e ParseError>) -> Analysis {
+        let decls = TopDeclList::cast(root);
+
+        let mut analysis = Analysis::default();
+        for err in errors {
+            analysis.diagnostics.push(Diagnostic { location: err.location, code: err.kind.code(), message: err.kind.to_string(), fix: err.fix.clone() });
+        }
+
+        // Checking assumes a well formed tree, like the command line does
+        let mut checker = TypeChecker::new();
+        let checked = analysis.diagnostics.is_empty();
+        if checked {
+            checker.check_program(decls.clone());
+            for err in checker.errors.iter() {
+                analysis.diagnostics.push(Diagnostic { location: err.location, code: err.kind.code(), message: err.kind.to_string(), fix: None });
+            }
+        }
+
+        let types = checked.then_some(&checker);
+        analysis.symbols = symbols(source, decls.clone());
+        globals(decls.clone(), types, &mut analysis.globals);
+        let names = Names::resolve(source, decls, types);
+        analysis.names = names.names;
+        analysis.scopes = names.scopes;
+        analysis
+    }
+
+    /// The name under an offset, including an offset just past its end
+    pub fn name_at(&self, offset: u32) -> Option<&Name> {
+        self.names.iter().find(|name| name.location.start <= offset && offset <= name.location.start + name.location.len)
+    }
+
+    /// Every name which may be used at an offset, innermost first
+    pub fn completions(&self, offset: u32) -> Vec<Completion> {
+        let mut completions: Vec<Completion> = Vec::new();
+        let mut scopes: Vec<&Scope> = self.scopes.iter().filter(|scope| scope.start <= offset && offset <= scope.end).collect();
+        scopes.sort_by_key(|scope| scope.end - scope.start);
+        for scope in scopes {
+            for (visible, local) in scope.locals.iter().rev() {
+                if *visible <= offset && !completions.iter().any(|other| other.label == local.name) {
+                    completions.push(Completion { label: local.name.clone(), kind: SymbolKind::Variable, detail: local.description.clone() });
+                }
+            }
+        }
+        for global in self.globals.iter() {
+            if !completions.iter().any(|other| other.label == global.label) {
+                completions.push(global.clone());
+            }
+        }
+        for builtin in BUILTIN_FUNCTIONS {
+            completions.push(Completion { label: builtin.to_string(), kind: SymbolKind::Function, detail: None });
+        }
+        completions
+    }
+}
+
+/// Every global of the document as a completion, including those of modules
+fn globals<'b>(decls: TopDeclList<'b, 'b>, types: Option<&TypeChecker<'b>>, out: &mut Vec<Completion>) {
+    for decl in decls.items() {
+        if let Some(name) = decl_name(&decl).filter(|name| !name.token().is_empty()) {
+            let kind = match &decl {
+                TopLevelDecl::Fn(_) => SymbolKind::Function,
+                TopLevelDecl::Struct(_) => SymbolKind::Struct,
+                TopLevelDecl::Const(_) => SymbolKind::Const,
+                TopLevelDecl::Type(_) => SymbolKind::TypeAlias,
+                TopLevelDecl::Enum(_) => SymbolKind::Enum,
+                _ => SymbolKind::Module,
+            };
+            let value = name.token().value;
+            let detail = types.and_then(|types| types.global(value)).map(|typ| describe_global(value, typ));
+            out.push(Completion { label: value.to_string(), kind, detail });
+        }
+        if let TopLevelDecl::Mod(module) = decl {
+            globals(module.decls(), types, out);
+        }
+    }
+}
+
+fn symbols<'b>(source: &str, decls: TopDeclList<'b, 'b>) -> Vec<Symbol> {
+    let mut list = Vec::new();
+    for decl in decls.items() {
+        let Some(name) = decl_name(&decl) else { continue };
+        if name.token().is_empty() { continue; }
+        let mut children = Vec::new();
+        let kind = match &decl {
+            TopLevelDecl::Fn(_) => SymbolKind::Function,
+            TopLevelDecl::Const(_) => SymbolKind::Const,
+            TopLevelDecl::Type(_) => SymbolKind::TypeAlias,
+            TopLevelDecl::Struct(struct_decl) => {
+                for field in struct_decl.fields().items() {
+                    children.push(leaf(field.name(), extent(source, field.node(), 0), SymbolKind::Field));
+                }
+                SymbolKind::Struct
+            }
+            TopLevelDecl::Enum(enum_decl) => {
+                for variant in enum_decl.variants().items() {
+                    children.push(leaf(variant.tag(), extent(source, variant.node(), 0), SymbolKind::Variant));
+                }
+                SymbolKind::Enum
+            }
+            TopLevelDecl::Mod(module) => {
+                children = symbols(source, module.decls());
+                SymbolKind::Module
+            }
+            TopLevelDecl::Import(_) => continue,
+        };
+        let mut symbol = leaf(name, extent(source, decl.node(), usize::MAX), kind);
+        symbol.children = children;
+        list.push(symbol);
+    }
+    list
+}
+
+fn leaf(name: Ident, location: Loc, kind: SymbolKind) -> Symbol {
+    Symbol {
+        name: name.token().value.to_string(),
+        kind,
+        location,
+        name_location: Loc::from_token(*name.token()),
+        children: Vec::new(),
+    }
+}
@@ -0,0 +1,731 @@
+//! Language server over stdio
+//!
+//! Messages are JSON-RPC framed with a `Content-Length` header. Documents are
+//! synced incrementally: the tree of each open document is kept and an edit
+//! only reparses around the changed range, see [`Document::edit`]. The
+//! analysis is redone after every change and serves requests until the next.
+//!
+//! Supported requests are hover, go to definition, document symbols,
+//! completion, semantic tokens and code actions applying the fixes of syntax
+//! errors. Diagnostics are published whenever a document changes.
+
+pub mod analysis;
+
+use std::io::{self, BufRead, Write};
+
+use bumpalo::Bump;
+use hashbrown::HashMap;
+use serde_json::{json, Value};
+
+use crate::errors::Loc;
+use crate::highlight::{self, Class, Highlight};
+use crate::errors::TextEdit;
+use crate::incremental::Document;
+
+use self::analysis::{Analysis, Diagnostic, Symbol, SymbolKind, KEYWORDS};
+
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Bytes the open documents may allocate before they move to a fresh arena
+const ARENA_LIMIT: usize = 64 << 20;
+
+/// Serves requests until `exit` or the end of the input
+///
+/// Every edit allocates the new source and what it reparsed in the arena of
+/// the documents. Once that grows past [`ARENA_LIMIT`], the open documents
+/// are parsed again into a fresh arena and the old one is dropped.
+pub fn run(input: impl BufRead, output: impl Write) -> io::Result<()> {
+    let mut input = input;
+    let mut output = output;
+    let mut shutdown = false;
+    let mut texts: Vec<(String, String)> = Vec::new();
+    loop {
+        let arena = Bump::new();
+        let mut server = Server { output: &mut output, arena: &arena, documents: HashMap::new(), shutdown };
+        for (uri, text) in texts.drain(..) {
+            server.open(uri, &text);
+        }
+        while arena.allocated_bytes() < ARENA_LIMIT {
+            let Some(message) = read_message(&mut input)? else { return Ok(()) };
+            if !server.handle(message)? {
+                return Ok(());
+            }
+        }
+        shutdown = server.shutdown;
+        texts = server.documents.into_iter()
+            .map(|(uri, open)| (uri, open.document.source().to_string()))
+            .collect();
+    }
+}
+
+/// Reads one message, none at the end of the input
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// Converts between byte offsets and LSP positions, which count UTF-16 units
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { source, line_starts }
+    }
+
+    pub fn position(&self, offset: usize) -> Value {
+        let (line, character) = self.line_character(offset);
+        json!({ "line": line, "character": character })
+    }
+
+    pub fn line_character(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        (line, self.source[start..offset].chars().map(char::len_utf16).sum())
+    }
+
+    pub fn offset(&self, position: &Value) -> Option<usize> {
+        let line = position["line"].as_u64()? as usize;
+        let character = position["character"].as_u64()? as usize;
+        let start = *self.line_starts.get(line)?;
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(self.source.len());
+        let mut units = 0;
+        for (i, c) in self.source[start..end].char_indices() {
+            if units >= character {
+                return Some(start + i);
+            }
+            units += c.len_utf16();
+        }
+        Some(end)
+    }
+
+    pub fn range(&self, location: Loc) -> Value {
+        // Missing tokens have no position, point at the end of the file
+        let start = (location.start as usize).min(self.source.len());
+        let end = start.saturating_add(location.len as usize).min(self.source.len());
+        json!({ "start": self.position(start), "end": self.position(end) })
+    }
+}
+
+struct Server<'a, W> {
+    output: W,
+    arena: &'a Bump,
+    documents: HashMap<String, Open<'a>>,
+    shutdown: bool,
+}
+
+/// An open document and what requests are answered from until it changes
+struct Open<'a> {
+    document: Document<'a, 'a>,
+    analysis: Analysis,
+}
+
+impl<'a, W: Write> Server<'a, W> {
+    fn open(&mut self, uri: String, text: &str) {
+        let document = Document::parse(self.arena.alloc_str(text), self.arena);
+        let analysis = analyze(&document, self.arena);
+        self.documents.insert(uri, Open { document, analysis });
+    }
+
+    /// Handles a request or notification, returning false once the server should exit
+    fn handle(&mut self, message: Value) -> io::Result<bool> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let Some(id) = message.get("id").cloned() else {
+            return self.notification(method, params);
+        };
+
+        let result = match method {
+            _ if self.shutdown => Err((INVALID_REQUEST, "the server is shutting down".to_string())),
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 2,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "documentSymbolProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                    "codeActionProvider": true,
+                    "semanticTokensProvider": {
+                        "legend": {
+                            "tokenTypes": Class::ALL.iter().map(|class| class.lsp_name()).collect::<Vec<_>>(),
+                            "tokenModifiers": [],
+                        },
+                        "full": true,
+                    },
+                },
+                "serverInfo": { "name": "haze" },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.with_position(params, |index, analysis, offset| {
+                let Some(name) = analysis.name_at(offset as u32) else { return Value::Null };
+                let Some(description) = &name.description else { return Value::Null };
+                json!({
+                    "contents": { "kind": "markdown", "value": format!("```haze\n{}\n```", description) },
+                    "range": index.range(name.location),
+                })
+            }),
+            "textDocument/definition" => {
+                let uri = params["textDocument"]["uri"].clone();
+                self.with_position(params, |index, analysis, offset| {
+                    match analysis.name_at(offset as u32).and_then(|name| name.definition) {
+                        Some(definition) => json!({ "uri": uri, "range": index.range(definition) }),
+                        None => Value::Null,
+                    }
+                })
+            }
+            "textDocument/documentSymbol" => self.with_document(params, |index, analysis| {
+                Value::Array(analysis.symbols.iter().map(|symbol| document_symbol(index, symbol)).collect())
+            }),
+            "textDocument/completion" => self.with_position(params, |_, analysis, offset| {
+                let mut items: Vec<Value> = analysis.completions(offset as u32).into_iter()
+                    .map(|completion| json!({
+                        "label": completion.label,
+                        "kind": completion_kind(completion.kind),
+                        "detail": completion.detail,
+                    }))
+                    .collect();
+                items.extend(KEYWORDS.iter().map(|keyword| json!({ "label": keyword, "kind": 14 })));
+                Value::Array(items)
+            }),
+            "textDocument/semanticTokens/full" => self.with_document(params, |index, analysis| {
+                json!({ "data": semantic_tokens(index, &highlight::classify(index.source, &analysis.names)) })
+            }),
+            "textDocument/codeAction" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+                self.with_document(params, |index, analysis| {
+                    let range = &params["range"];
+                    let (Some(start), Some(end)) = (index.offset(&range["start"]), index.offset(&range["end"])) else {
+                        return Value::Null;
+                    };
+                    let touches = |from: u32, to: u32| (from as usize).min(index.source.len()) <= end && start <= to as usize;
+                    // Fixes often go before the token the error points at, like a
+                    // semicolon missing at the end of the previous line
+                    Value::Array(analysis.diagnostics.iter()
+                        .filter_map(|diagnostic| Some((diagnostic, diagnostic.fix.as_ref()?)))
+                        .filter(|(diagnostic, fix)| {
+                            let location = diagnostic.location;
+                            touches(location.start, location.start + location.len) || touches(fix.range.start, fix.range.end)
+                        })
+                        .map(|(diagnostic, fix)| code_action(index, &uri, diagnostic, fix))
+                        .collect())
+                })
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+        };
+        write_message(&mut self.output, &response)?;
+        Ok(true)
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<bool> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.open(uri.clone(), text);
+                self.publish_diagnostics(&uri)?;
+            }
+            "textDocument/didChange" => {
+                let Some(open) = self.documents.get_mut(&uri) else { return Ok(true) };
+                for change in params["contentChanges"].as_array().into_iter().flatten() {
+                    let Some(text) = change["text"].as_str() else { continue };
+                    // A change without a range replaces the whole document
+                    if change.get("range").is_none() {
+                        open.document = Document::parse(self.arena.alloc_str(text), self.arena);
+                        continue;
+                    }
+                    let source = open.document.source();
+                    let index = LineIndex::new(source);
+                    let (Some(start), Some(end)) = (index.offset(&change["range"]["start"]), index.offset(&change["range"]["end"])) else {
+                        continue;
+                    };
+                    let edit = TextEdit::new(start as u32..end.max(start) as u32, text);
+                    open.document.edit(&edit, self.arena.alloc_str(&edit.apply(source)));
+                }
+                open.analysis = analyze(&open.document, self.arena);
+                self.publish_diagnostics(&uri)?;
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                let message = json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] },
+                });
+                write_message(&mut self.output, &message)?;
+            }
+            "exit" => return Ok(false),
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) -> io::Result<()> {
+        let Some(open) = self.documents.get(uri) else { return Ok(()) };
+        let index = LineIndex::new(open.document.source());
+        let diagnostics: Vec<Value> = open.analysis.diagnostics.iter().map(|diagnostic| diagnostic_json(&index, diagnostic)).collect();
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        });
+        write_message(&mut self.output, &message)
+    }
+
+    fn with_document(&self, params: &Value, f: impl FnOnce(&LineIndex, &Analysis) -> Value) -> Result<Value, (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let open = self.documents.get(uri).ok_or_else(|| (INVALID_PARAMS, format!("unknown document `{}`", uri)))?;
+        Ok(f(&LineIndex::new(open.document.source()), &open.analysis))
+    }
+
+    fn with_position(&self, params: &Value, f: impl FnOnce(&LineIndex, &Analysis, usize) -> Value) -> Result<Value, (i64, String)> {
+        self.with_document(params, |index, analysis| match index.offset(&params["position"]) {
+            Some(offset) => f(index, analysis, offset),
+            None => Value::Null,
+        })
+    }
+}
+
+fn analyze<'a>(document: &Document<'a, 'a>, arena: &'a Bump) -> Analysis {
+    // Checking borrows the tree for as long as the arena, which the document
+    // can't lend while it may still be edited
+    let tree = arena.alloc(document.tree().clone_in(arena));
+    Analysis::of(document.source(), tree, document.errors())
+}
+
+fn diagnostic_json(index: &LineIndex, diagnostic: &Diagnostic) -> Value {
+    json!({
+        "range": index.range(diagnostic.location),
+        "severity": 1,
+        "code": diagnostic.code,
+        "source": "haze",
+        "message": diagnostic.message,
+    })
+}
+
+/// A quick fix applying the suggested edit of a diagnostic
+fn code_action(index: &LineIndex, uri: &str, diagnostic: &Diagnostic, fix: &TextEdit) -> Value {
+    let title = match fix.range.is_empty() {
+        true => format!("Insert `{}`", fix.text),
+        false => format!("Replace with `{}`", fix.text),
+    };
+    let edit = json!({
+        "range": { "start": index.position(fix.range.start as usize), "end": index.position(fix.range.end as usize) },
+        "newText": fix.text,
+    });
+    let mut changes = serde_json::Map::new();
+    changes.insert(uri.to_string(), json!([edit]));
+    json!({
+        "title": title,
+        "kind": "quickfix",
+        "diagnostics": [diagnostic_json(index, diagnostic)],
+        "isPreferred": true,
+        "edit": { "changes": changes },
+    })
+}
+
+fn document_symbol(index: &LineIndex, symbol: &Symbol) -> Value {
+    let kind = match symbol.kind {
+        SymbolKind::Module => 2,
+        SymbolKind::Field => 8,
+        SymbolKind::Enum => 10,
+        SymbolKind::Function => 12,
+        SymbolKind::Variable => 13,
+        SymbolKind::Const => 14,
+        SymbolKind::Variant => 22,
+        SymbolKind::Struct => 23,
+        SymbolKind::TypeAlias => 26,
+    };
+    json!({
+        "name": symbol.name,
+        "kind": kind,
+        "range": index.range(symbol.location),
+        "selectionRange": index.range(symbol.name_location),
+        "children": symbol.children.iter().map(|child| document_symbol(index, child)).collect::<Vec<_>>(),
+    })
+}
+
+/// Encodes highlights as relative line, start, length, type and modifiers
+///
+/// Tokens may not span lines, so strings with newlines are split.
+fn semantic_tokens(index: &LineIndex, highlights: &[Highlight]) -> Vec<u32> {
+    let mut data = Vec::new();
+    let (mut last_line, mut last_start) = (0, 0);
+    for highlight in highlights {
+        let start = highlight.location.start as usize;
+        let text = &index.source[start..start + highlight.location.len as usize];
+        let mut offset = start;
+        for piece in text.split('\n') {
+            let (line, character) = index.line_character(offset);
+            offset += piece.len() + 1;
+            let length: usize = piece.chars().map(char::len_utf16).sum();
+            if length == 0 {
+                continue;
+            }
+            let delta_start = if line == last_line { character - last_start } else { character };
+            data.extend([(line - last_line) as u32, delta_start as u32, length as u32, highlight.class as u32, 0]);
+            (last_line, last_start) = (line, character);
+        }
+    }
+    data
+}
+
+fn completion_kind(kind: SymbolKind) -> u32 {
+    match kind {
+        SymbolKind::Function => 3,
+        SymbolKind::Field => 5,
+        SymbolKind::Variable => 6,
+        SymbolKind::Module => 9,
+        SymbolKind::Enum => 13,
+        SymbolKind::Variant => 20,
+        SymbolKind::Const => 21,
+        SymbolKind::Struct => 22,
+        SymbolKind::TypeAlias => 25,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///main.hz";
+
+    const SOURCE: &str = "\
+struct Point { x: i32, y: i32 }
+fn shift(p: Point, by: i32) -> i32 {
+    let moved = p.x + by;
+    if moved > 10 {
+        let big = moved;
+        return big;
+    }
+    return moved;
+}
+";
+
+    /// Feeds framed messages to a server, returning everything it wrote
+    fn session(messages: &[Value]) -> Vec<Value> {
+        let mut input = Vec::new();
+        for message in messages {
+            write_message(&mut input, message).unwrap();
+        }
+        let mut output = Vec::new();
+        run(&input[..], &mut output).unwrap();
+
+        let mut output = &output[..];
+        let mut replies = Vec::new();
+        while let Some(reply) = read_message(&mut output).unwrap() {
+            replies.push(reply);
+        }
+        replies
+    }
+
+    fn open(text: &str) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": URI, "languageId": "haze", "version": 1, "text": text } },
+        })
+    }
+
+    fn request(id: u32, method: &str, line: u32, character: u32) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": { "textDocument": { "uri": URI }, "position": { "line": line, "character": character } },
+        })
+    }
+
+    fn result(replies: &[Value], id: u32) -> &Value {
+        let reply = replies.iter().find(|reply| reply["id"] == id).expect("a reply to every request");
+        &reply["result"]
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+        json!({
+            "start": { "line": start.0, "character": start.1 },
+            "end": { "line": end.0, "character": end.1 },
+        })
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let replies = session(&[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "workspace/symbol", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+            json!({ "jsonrpc": "2.0", "id": 5, "method": "textDocument/hover", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "shutdown" }),
+        ]);
+
+        assert_eq!(replies.len(), 4);
+        let capabilities = &result(&replies, 1)["capabilities"];
+        assert_eq!(capabilities["hoverProvider"], true);
+        assert_eq!(capabilities["textDocumentSync"], 2);
+        assert_eq!(replies[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(result(&replies, 3), &Value::Null);
+        assert_eq!(replies[3]["error"]["code"], INVALID_REQUEST);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let replies = session(&[
+            open("fn main() {\n    let x: i32 = true;\n}\n"),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didChange",
+                "params": { "textDocument": { "uri": URI, "version": 2 }, "contentChanges": [{ "text": "fn main() { let = 1; }" }] },
+            }),
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didClose", "params": { "textDocument": { "uri": URI } } }),
+        ]);
+
+        assert_eq!(replies.len(), 3);
+        assert!(replies.iter().all(|reply| reply["method"] == "textDocument/publishDiagnostics"));
+        let type_error = &replies[0]["params"]["diagnostics"][0];
+        assert_eq!(type_error["message"], "expected `i32`, found `bool`");
+        assert_eq!(type_error["code"], "E0100");
+        assert_eq!(type_error["range"], range((1, 17), (1, 21)));
+        let parse_errors = replies[1]["params"]["diagnostics"].as_array().unwrap();
+        assert!(!parse_errors.is_empty());
+        assert_eq!(parse_errors[0]["message"], "expected a name");
+        assert_eq!(parse_errors[0]["code"], "E0006");
+        assert_eq!(replies[2]["params"]["diagnostics"], json!([]));
+    }
+
+    #[test]
+    fn test_incremental_changes() {
+        let change = |version: u32, changes: Value| json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": { "textDocument": { "uri": URI, "version": version }, "contentChanges": changes },
+        });
+        let replies = session(&[
+            open(SOURCE),
+            // `by` in `p.x + by`
+            change(2, json!([{ "range": range((2, 22), (2, 24)), "text": "true" }])),
+            // Changes apply in order, each to the text the one before left
+            change(3, json!([
+                { "range": range((2, 22), (2, 26)), "text": "by" },
+                { "range": range((0, 0), (0, 0)), "text": "const K: i32 = 1;\n" },
+            ])),
+            request(1, "textDocument/hover", 3, 9),
+            request(2, "textDocument/hover", 0, 7),
+        ]);
+
+        let diagnostics = |index: usize| replies[index]["params"]["diagnostics"].as_array().unwrap().len();
+        assert_eq!((diagnostics(0), diagnostics(1), diagnostics(2)), (0, 1, 0));
+        assert_eq!(replies[1]["params"]["diagnostics"][0]["range"], range((2, 22), (2, 26)));
+        assert_eq!(result(&replies, 1)["contents"]["value"], "```haze\nmoved: i32\n```");
+        assert_eq!(result(&replies, 1)["range"], range((3, 8), (3, 13)));
+        assert_eq!(result(&replies, 2)["contents"]["value"], "```haze\nconst K: i32\n```");
+    }
+
+    #[test]
+    fn test_hover() {
+        let replies = session(&[
+            open(SOURCE),
+            request(1, "textDocument/hover", 2, 9),
+            request(2, "textDocument/hover", 2, 19),
+            request(3, "textDocument/hover", 1, 4),
+            request(4, "textDocument/hover", 0, 16),
+            request(5, "textDocument/hover", 3, 2),
+        ]);
+
+        let hover = |id| result(&replies, id)["contents"]["value"].as_str().unwrap().to_string();
+        assert_eq!(hover(1), "```haze\nmoved: i32\n```");
+        assert_eq!(result(&replies, 1)["range"], range((2, 8), (2, 13)));
+        assert_eq!(hover(2), "```haze\nx: i32\n```");
+        assert_eq!(hover(3), "```haze\nshift: fn(Point, i32) -> i32\n```");
+        assert_eq!(hover(4), "```haze\nx: i32\n```");
+        assert_eq!(result(&replies, 5), &Value::Null);
+    }
+
+    #[test]
+    fn test_definition() {
+        let replies = session(&[
+            open(SOURCE),
+            // `moved` in `let big = moved;`
+            request(1, "textDocument/definition", 4, 19),
+            // The field in `p.x`
+            request(2, "textDocument/definition", 2, 18),
+            // The type of `p`
+            request(3, "textDocument/definition", 1, 13),
+            // A parameter
+            request(4, "textDocument/definition", 2, 23),
+            // Builtins are defined nowhere
+            request(5, "textDocument/definition", 7, 4),
+        ]);
+
+        let target = |id| {
+            let location = result(&replies, id);
+            assert_eq!(location["uri"], URI);
+            location["range"].clone()
+        };
+        assert_eq!(target(1), range((2, 8), (2, 13)));
+        assert_eq!(target(2), range((0, 15), (0, 16)));
+        assert_eq!(target(3), range((0, 7), (0, 12)));
+        assert_eq!(target(4), range((1, 19), (1, 21)));
+        assert_eq!(result(&replies, 5), &Value::Null);
+    }
+
+    #[test]
+    fn test_document_symbols() {
+        let replies = session(&[
+            open(SOURCE),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "textDocument/documentSymbol",
+                "params": { "textDocument": { "uri": URI } },
+            }),
+        ]);
+
+        let symbols = result(&replies, 1).as_array().unwrap();
+        let names: Vec<_> = symbols.iter().map(|symbol| (symbol["name"].as_str().unwrap(), symbol["kind"].as_u64().unwrap())).collect();
+        assert_eq!(names, [("Point", 23), ("shift", 12)]);
+        let fields: Vec<_> = symbols[0]["children"].as_array().unwrap().iter().map(|field| field["name"].clone()).collect();
+        assert_eq!(fields, ["x", "y"]);
+        assert_eq!(symbols[1]["range"], range((1, 3), (8, 1)));
+        assert_eq!(symbols[1]["selectionRange"], range((1, 3), (1, 8)));
+    }
+
+    #[test]
+    fn test_semantic_tokens() {
+        let replies = session(&[
+            open("fn f(n: int) {\n    let s = \"a\nb\"; // c\n}"),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "textDocument/semanticTokens/full",
+                "params": { "textDocument": { "uri": URI } },
+            }),
+        ]);
+
+        let data: Vec<u64> = result(&replies, 1)["data"].as_array().unwrap().iter().map(|n| n.as_u64().unwrap()).collect();
+        let tokens: Vec<&[u64]> = data.chunks(5).collect();
+        let (keyword, typ, function, parameter, local, string, comment, operator) = (0, 1, 2, 3, 4, 6, 8, 9);
+        assert_eq!(tokens, [
+            &[0, 0, 2, keyword, 0][..],
+            &[0, 3, 1, function, 0],
+            &[0, 2, 1, parameter, 0],
+            &[0, 3, 3, typ, 0],
+            &[1, 4, 3, keyword, 0],
+            &[0, 4, 1, local, 0],
+            &[0, 2, 1, operator, 0],
+            // The string is split at its newline
+            &[0, 2, 2, string, 0],
+            &[1, 0, 2, string, 0],
+            &[0, 4, 4, comment, 0],
+        ]);
+    }
+
+    #[test]
+    fn test_code_actions() {
+        let action = |id: u32, start: (u32, u32), end: (u32, u32)| json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "textDocument/codeAction",
+            "params": { "textDocument": { "uri": URI }, "range": range(start, end), "context": { "diagnostics": [] } },
+        });
+        let replies = session(&[
+            open("fn main() {\n    let x = 1\n    print(x);\n}\n"),
+            action(1, (1, 0), (1, 13)),
+            action(2, (3, 0), (3, 1)),
+        ]);
+
+        let actions = result(&replies, 1).as_array().unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0]["title"], "Insert `;`");
+        assert_eq!(actions[0]["kind"], "quickfix");
+        assert_eq!(actions[0]["edit"]["changes"][URI], json!([{ "range": range((1, 13), (1, 13)), "newText": ";" }]));
+        assert_eq!(result(&replies, 2), &json!([]));
+    }
+
+    #[test]
+    fn test_completion() {
+        let replies = session(&[
+            open(SOURCE),
+            // Before `let moved`
+            request(1, "textDocument/completion", 2, 4),
+            // Inside the `if` after `let big`
+            request(2, "textDocument/completion", 5, 8),
+            // After the `if`
+            request(3, "textDocument/completion", 7, 4),
+        ]);
+
+        let labels = |id| -> Vec<String> {
+            result(&replies, id).as_array().unwrap().iter()
+                .filter(|item| item["kind"] != 14)
+                .map(|item| item["label"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(labels(1), ["by", "p", "Point", "shift", "print", "input", "int"]);
+        assert_eq!(labels(2), ["big", "moved", "by", "p", "Point", "shift", "print", "input", "int"]);
+        assert_eq!(labels(3), ["moved", "by", "p", "Point", "shift", "print", "input", "int"]);
+        let moved = result(&replies, 3).as_array().unwrap().iter().find(|item| item["label"] == "moved").unwrap();
+        assert_eq!(moved["detail"], "moved: i32");
+        assert!(result(&replies, 3).as_array().unwrap().iter().any(|item| item["label"] == "while"));
+    }
+
+    #[test]
+    fn test_half_written_documents() {
+        // Requests come in between keystrokes, on whatever the parser recovered
+        for text in ["fn main() { let x = ", "fn main() { foo(1, ", "struct P { x: i64, y", "fn main() { .P { x: 1, ", "fn f() { p.x = "] {
+            let replies = session(&[open(text), request(1, "textDocument/hover", 0, 12), request(2, "textDocument/completion", 0, 12)]);
+            assert_eq!(replies.len(), 3, "{:?}", text);
+            assert!(!replies[0]["params"]["diagnostics"].as_array().unwrap().is_empty(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_line_index() {
+        let source = "let é = \"𝄞\";\nx";
+        let index = LineIndex::new(source);
+        assert_eq!(index.position(source.find('=').unwrap()), json!({ "line": 0, "character": 6 }));
+        assert_eq!(index.position(source.find(';').unwrap()), json!({ "line": 0, "character": 12 }));
+        assert_eq!(index.offset(&json!({ "line": 0, "character": 12 })), source.find(';'));
+        assert_eq!(index.offset(&json!({ "line": 1, "character": 0 })), source.find('x'));
+        assert_eq!(index.offset(&json!({ "line": 5, "character": 0 })), None);
+    }
+}
@@ -0,0 +1,416 @@
+//! Names of a program and what they refer to
+//!
+//! Every identifier is resolved to the local, global or builtin it names and
+//! classified for highlighting. Trees which didn't parse or check cleanly
+//! are resolved too, leaving out what can't be found.
+
+use bumpalo::Bump;
+use indexmap::IndexMap;
+
+use crate::ast2::{AstNode, AstToken, BlockExpr, Expr, Ident, IfAlt, Node, NodeChild, Stmt, TopDeclList, TopLevelDecl, TypeExpr};
+use crate::errors::Loc;
+use crate::highlight::Class;
+use crate::loader::decl_name;
+use crate::parser3::Parser;
+use crate::typecheck::check::TypeChecker;
+use crate::typecheck::resolve::{BUILTIN_FUNCTIONS, BUILTIN_TYPES};
+use crate::typecheck::Type;
+
+/// An occurrence of a name, either where it is defined or where it is used
+#[derive(Debug)]
+pub struct Name {
+    pub location: Loc,
+    pub definition: Option<Loc>,
+    /// Shown on hover
+    pub description: Option<String>,
+    /// What the name refers to, for highlighting
+    pub class: Class,
+}
+
+/// A local variable or parameter
+#[derive(Debug, Clone)]
+pub struct Local {
+    pub name: String,
+    /// Shown on hover
+    pub description: Option<String>,
+}
+
+/// Locals of a block or function, in scope between `start` and `end`
+#[derive(Debug)]
+pub struct Scope {
+    pub start: u32,
+    pub end: u32,
+    /// Each local becomes visible after its declaration ends
+    pub locals: Vec<(u32, Local)>,
+}
+
+#[derive(Debug, Default)]
+pub struct Names {
+    /// Names in the order they appear
+    pub names: Vec<Name>,
+    pub scopes: Vec<Scope>,
+}
+
+impl Names {
+    /// Resolves the names of a program, type checking it when it parses cleanly
+    pub fn new(source: &str) -> Names {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        let decls = TopDeclList::cast(root);
+
+        let mut checker = TypeChecker::new();
+        let checked = parser.errors.is_empty();
+        if checked {
+            checker.check_program(decls.clone());
+        }
+        Names::resolve(source, decls, checked.then_some(&checker))
+    }
+
+    /// Resolves the names of a parsed program, with the types of `types` if it checked
+    pub fn resolve<'b>(source: &str, decls: TopDeclList<'b, 'b>, types: Option<&TypeChecker<'b>>) -> Names {
+        let mut names = Names::default();
+        let mut indexer = Indexer { source, decls: decls.clone(), types, scopes: Vec::new(), names: &mut names };
+        indexer.index(decls);
+        names.names.sort_by_key(|name| name.location.start);
+        names
+    }
+}
+
+/// Span from the first to the last token of a node
+///
+/// The tree keeps no punctuation, so up to `braces` closing braces following
+/// the last token are taken from the source to cover whole blocks, along with
+/// any closing parentheses, brackets and semicolons in between.
+pub fn extent(source: &str, node: &Node, braces: usize) -> Loc {
+    fn tokens<'s>(node: &Node<'s, '_>, span: &mut Option<(u32, u32, u32)>) {
+        for child in node.children() {
+            match child {
+                NodeChild::Node(node) => tokens(node, span),
+                NodeChild::Token(token) if !token.is_empty() => {
+                    let end = token.pos + token.value.len() as u32;
+                    *span = Some(match *span {
+                        Some((start, line, old_end)) if start <= token.pos => (start, line, old_end.max(end)),
+                        Some((_, _, old_end)) => (token.pos, token.line, old_end.max(end)),
+                        None => (token.pos, token.line, end),
+                    });
+                }
+                NodeChild::Token(_) => {}
+            }
+        }
+    }
+
+    let mut span = None;
+    tokens(node, &mut span);
+    let Some((start, line, mut end)) = span else { return Loc::new(0, 0, 0) };
+    let last = end;
+    let mut closed = 0;
+    for (i, c) in source[last as usize..].char_indices() {
+        match c {
+            '}' if closed < braces => {
+                closed += 1;
+                end = last + i as u32 + 1;
+            }
+            c if c.is_whitespace() || matches!(c, ';' | ')' | ']') => {}
+            _ => break,
+        }
+    }
+    Loc::new(start, end - start, line)
+}
+
+/// How names of a declaration are highlighted. Constants read like locals
+/// and modules like types.
+fn decl_class(decl: &TopLevelDecl) -> Class {
+    match decl {
+        TopLevelDecl::Fn(_) => Class::Function,
+        TopLevelDecl::Const(_) => Class::Local,
+        _ => Class::Type,
+    }
+}
+
+/// How a global is described on hover and in completions
+pub fn describe_global(name: &str, typ: &Type) -> String {
+    match typ {
+        Type::Struct(..) => format!("struct {}", name),
+        Type::Alias(inner) => format!("type {} = {}", name, inner),
+        Type::Const(inner) => format!("const {}: {}", name, inner),
+        _ => format!("{}: {}", name, typ),
+    }
+}
+
+/// A local with its type, where it was defined and whether it is a parameter
+type Binding = (Loc, Option<Type>, Class);
+
+/// Walks the tree recording every name, in the same order as the resolver
+struct Indexer<'a, 'b> {
+    source: &'a str,
+    decls: TopDeclList<'b, 'b>,
+    /// Present when the program type checked
+    types: Option<&'a TypeChecker<'b>>,
+    scopes: Vec<IndexMap<&'b str, Binding>>,
+    names: &'a mut Names,
+}
+
+impl<'a, 'b> Indexer<'a, 'b> {
+    fn index(&mut self, decls: TopDeclList<'b, 'b>) {
+        for decl in decls.items() {
+            if let Some(name) = decl_name(&decl) {
+                let typ = self.global_type(name.token().value);
+                let description = typ.map(|typ| describe_global(name.token().value, &typ));
+                self.definition(name, description, decl_class(&decl));
+            }
+
+            match decl {
+                TopLevelDecl::Fn(fn_def) => {
+                    self.scopes.push(IndexMap::new());
+                    let location = extent(self.source, fn_def.node(), usize::MAX);
+                    let mut locals = Vec::new();
+                    for param in fn_def.params().items() {
+                        let param_type = param.param_type();
+                        self.type_expr(&param_type);
+                        let typ = self.resolve(Type::from(&param_type));
+                        locals.push((location.start, self.bind(param.ident(), Some(typ), Class::Parameter)));
+                    }
+                    if let Some(return_type) = fn_def.return_type() {
+                        self.type_expr(&return_type);
+                    }
+                    if let Some(body) = fn_def.body() {
+                        self.block(body);
+                    }
+                    self.scopes.pop();
+                    self.names.scopes.push(Scope { start: location.start, end: location.start + location.len, locals });
+                }
+                TopLevelDecl::Struct(struct_decl) => {
+                    for field in struct_decl.fields().items() {
+                        let field_type = field.field_type();
+                        self.type_expr(&field_type);
+                        let typ = self.resolve(Type::from(&field_type));
+                        self.definition(field.name(), Some(format!("{}: {}", field.name().token().value, typ)), Class::Field);
+                    }
+                }
+                TopLevelDecl::Enum(enum_decl) => {
+                    for variant in enum_decl.variants().items() {
+                        self.definition(variant.tag(), None, Class::Field);
+                        if let Some(variant_type) = variant.variant_type() {
+                            self.type_name(variant_type);
+                        }
+                    }
+                }
+                TopLevelDecl::Const(const_decl) => {
+                    self.type_expr(&const_decl.const_type());
+                    self.expr(const_decl.value());
+                }
+                TopLevelDecl::Type(type_alias) => self.type_expr(&type_alias.type_expr()),
+                TopLevelDecl::Mod(module) => self.index(module.decls()),
+                TopLevelDecl::Import(_) => {}
+            }
+        }
+    }
+
+    fn block(&mut self, block: BlockExpr<'b, 'b>) {
+        let location = extent(self.source, block.node(), 1);
+        let mut locals = Vec::new();
+        self.scopes.push(IndexMap::new());
+        for stmt in block.body().items() {
+            match stmt {
+                Stmt::VarDecl(var_decl) => {
+                    if let Some(var_type) = var_decl.var_type() {
+                        self.type_expr(&var_type);
+                    }
+                    if let Some(value) = var_decl.value() {
+                        self.expr(value);
+                    }
+                    let typ = self.types.and_then(|types| types.type_of_var(&var_decl)).cloned();
+                    let end = extent(self.source, var_decl.node(), 0);
+                    locals.push((end.start + end.len, self.bind(var_decl.name(), typ, Class::Local)));
+                }
+                Stmt::ExprStmt(expr_stmt) => self.expr(expr_stmt.expr()),
+                Stmt::EmptyStmt(_) => {}
+            }
+        }
+        self.scopes.pop();
+        self.names.scopes.push(Scope { start: location.start, end: location.start + location.len, locals });
+    }
+
+    fn expr(&mut self, expr: Expr<'b, 'b>) {
+        match expr.clone() {
+            Expr::Ident(ident) => {
+                let typ = self.types.and_then(|types| types.type_of(&expr)).cloned();
+                self.value_name(ident, typ);
+            }
+            Expr::Str(_) | Expr::Int(_) | Expr::Bool(_) | Expr::ContinueExpr(_) => {}
+            Expr::Infix(infix) => {
+                self.expr(infix.left());
+                self.expr(infix.right());
+            }
+            Expr::Prefix(prefix) => self.expr(prefix.right()),
+            Expr::BreakExpr(break_expr) => {
+                if let Some(value) = break_expr.value() { self.expr(value); }
+            }
+            Expr::ReturnExpr(return_expr) => {
+                if let Some(value) = return_expr.value() { self.expr(value); }
+            }
+            Expr::Group(group) => self.expr(group.expr()),
+            Expr::ArrayExpr(array) => array.items().for_each(|item| self.expr(item)),
+            Expr::TupleExpr(tuple) => tuple.items().for_each(|item| self.expr(item)),
+            Expr::CallExpr(call) => {
+                self.value_name(call.name(), None);
+                call.args().args().for_each(|arg| self.expr(arg));
+            }
+            Expr::MethodCall(call) => {
+                self.expr(call.receiver());
+                call.args().args().for_each(|arg| self.expr(arg));
+            }
+            Expr::IndexExpr(index) => {
+                self.expr(index.container());
+                self.expr(index.index());
+            }
+            Expr::FieldAccessExpr(access) => {
+                let parent = access.parent();
+                self.expr(parent.clone());
+                let parent_type = self.types.and_then(|types| types.type_of(&parent)).cloned();
+                let typ = self.types.and_then(|types| types.type_of(&expr)).cloned();
+                self.field(parent_type.as_ref(), access.field_name(), typ);
+            }
+            Expr::StructExpr(struct_expr) => {
+                self.type_name(struct_expr.name());
+                let struct_type = self.global_type(struct_expr.name().token().value);
+                for field_init in struct_expr.fields().items() {
+                    let field_type = match &struct_type {
+                        Some(Type::Struct(_, fields)) => fields.iter()
+                            .find(|(name, _)| **name == *field_init.name().token().value)
+                            .map(|(_, typ)| typ.clone()),
+                        _ => None,
+                    };
+                    self.field(struct_type.as_ref(), field_init.name(), field_type);
+                    self.expr(field_init.value());
+                }
+            }
+            Expr::AssignExpr(assign) => {
+                self.expr(assign.target());
+                self.expr(assign.value());
+            }
+            Expr::IfExpr(if_expr) => {
+                self.expr(if_expr.condition());
+                self.block(if_expr.consequence());
+                let mut alternate = if_expr.alternate();
+                while let Some(alt) = alternate {
+                    match alt {
+                        IfAlt::Else(block) => { self.block(block); break; }
+                        IfAlt::ElseIf(if_expr) => {
+                            self.expr(if_expr.condition());
+                            self.block(if_expr.consequence());
+                            alternate = if_expr.alternate();
+                        }
+                    }
+                }
+            }
+            Expr::WhileExpr(while_expr) => {
+                self.expr(while_expr.condition());
+                self.block(while_expr.consequence());
+            }
+            Expr::BlockExpr(block) => self.block(block),
+        }
+    }
+
+    fn type_expr(&mut self, type_expr: &TypeExpr<'b, 'b>) {
+        match type_expr {
+            TypeExpr::Ident(ident) => self.type_name(ident.clone()),
+            TypeExpr::ArrayType(array) => self.type_expr(&array.element_type()),
+            TypeExpr::GroupType(group) => self.type_expr(&group.inner_type()),
+            TypeExpr::TupleType(tuple) => tuple.items().for_each(|item| self.type_expr(&item)),
+            TypeExpr::FnType(fn_type) => fn_type.params().items().for_each(|param| self.type_expr(&param)),
+        }
+    }
+
+    /// Records a local, returning it for its scope
+    fn bind(&mut self, name: Ident<'b, 'b>, typ: Option<Type>, class: Class) -> Local {
+        let value = name.token().value;
+        let description = typ.as_ref().map(|typ| format!("{}: {}", value, typ));
+        self.definition(name.clone(), description.clone(), class);
+        if !name.token().is_empty() {
+            let scope = self.scopes.last_mut().expect("locals are bound inside a scope");
+            scope.insert(value, (Loc::from_token(*name.token()), typ, class));
+        }
+        Local { name: value.to_string(), description }
+    }
+
+    fn value_name(&mut self, ident: Ident<'b, 'b>, typ: Option<Type>) {
+        let name = ident.token().value;
+        if let Some((definition, local_type, class)) = self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned() {
+            let description = typ.or(local_type).map(|typ| format!("{}: {}", name, typ));
+            return self.reference(ident, Some(definition), description, class);
+        }
+        let description = self.global_type(name).map(|typ| describe_global(name, &typ));
+        let definition = self.global_decl(name);
+        let class = match self.decls.items().find(|decl| decl_name(decl).is_some_and(|ident| ident.token().value == name)) {
+            Some(decl) => decl_class(&decl),
+            None if BUILTIN_FUNCTIONS.contains(&name) => Class::Function,
+            None => Class::Local,
+        };
+        self.reference(ident, definition, description, class);
+    }
+
+    fn type_name(&mut self, ident: Ident<'b, 'b>) {
+        let name = ident.token().value;
+        if BUILTIN_TYPES.contains(&name) {
+            return self.reference(ident, None, None, Class::Type);
+        }
+        let description = self.global_type(name).map(|typ| describe_global(name, &typ));
+        let definition = self.global_decl(name);
+        self.reference(ident, definition, description, Class::Type);
+    }
+
+    /// A field of a struct, either accessed or initialized
+    fn field(&mut self, struct_type: Option<&Type>, field_name: Ident<'b, 'b>, typ: Option<Type>) {
+        let name = field_name.token().value;
+        let definition = match struct_type {
+            Some(Type::Struct(struct_name, _)) => self.decls.items().find_map(|decl| match decl {
+                TopLevelDecl::Struct(struct_decl) if struct_decl.name().token().value == &**struct_name => {
+                    struct_decl.fields().items()
+                        .find(|field| field.name().token().value == name)
+                        .map(|field| Loc::from_token(*field.name().token()))
+                }
+                _ => None,
+            }),
+            _ => None,
+        };
+        let description = typ.map(|typ| format!("{}: {}", name, typ));
+        self.reference(field_name, definition, description, Class::Field);
+    }
+
+    fn definition(&mut self, name: Ident<'b, 'b>, description: Option<String>, class: Class) {
+        let location = Loc::from_token(*name.token());
+        self.reference(name, Some(location), description, class);
+    }
+
+    fn reference(&mut self, name: Ident<'b, 'b>, definition: Option<Loc>, description: Option<String>, class: Class) {
+        if name.token().is_empty() { return; }
+        let location = Loc::from_token(*name.token());
+        self.names.names.push(Name { location, definition, description, class });
+    }
+
+    /// Where a global of the program is declared
+    fn global_decl(&self, name: &str) -> Option<Loc> {
+        self.decls.items()
+            .filter_map(|decl| decl_name(&decl))
+            .find(|ident| ident.token().value == name)
+            .map(|ident| Loc::from_token(*ident.token()))
+    }
+
+    fn global_type(&self, name: &str) -> Option<Type> {
+        self.types?.global(name).cloned()
+    }
+
+    /// Replaces the struct and alias names of a written type, when the program checked
+    fn resolve(&self, typ: Type) -> Type {
+        match (&typ, self.types) {
+            (Type::Unresolved(name), Some(types)) => match types.global(name) {
+                Some(Type::Alias(inner)) => (**inner).clone(),
+                Some(global @ Type::Struct(..)) => global.clone(),
+                _ => typ,
+            },
+            _ => typ,
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::lexer::Lexer;
-use crate::token::{Tag, Token, self};
+use crate::token::{Tag, Token};
 use crate::ast::*;
 use crate::bumping::{Vec, Box};
 use crate::errors::*;
@@ -16,12 +16,6 @@ macro_rules! next_and_return {
     }};
 }
 
-/// Advance the lexer without calling next
-macro_rules! commit {
-    ($parser:ident, $tok:ident) => {
-        $parser.tokens.offset += $tok.value.len() as u32
-    };
-}
 
 /// The Haze Parser
 /// 
@@ -39,13 +33,13 @@ type InfixParser = for<'a, 'bump> fn(&mut Parser<'a, 'bump>) -> Result<Expr<'a,
 pub type PResult<Node> = Result<Node, ParseError>;
 
 use ParseErrorKind::*;
-use serde::de::Expected;
 
 impl<'a, 'bump> Parser<'a, 'bump> {
     /// Constructs a new parser given a source string
     /// 
     /// # Examples
-    /// ```
+    /// ```no_run
+    /// # use haze_lang::parser2::Parser;
     /// let bump = bumpalo::Bump::new();
     /// let mut parser = Parser::new("
     /// let g = 1 * 2 + 5;
@@ -53,9 +47,9 @@ impl<'a, 'bump> Parser<'a, 'bump> {
     /// let tree = parser.parse();
     /// ```
     pub fn new(source: &'a str, allocator: &'bump Bump,) -> Self {
-        let mut tokens = Lexer::from(source);
+        let tokens = Lexer::from(source);
         Self {
-            tokens: tokens,
+            tokens,
             bump: allocator,
             errors: std::vec::Vec::with_capacity(50)
         }
@@ -65,12 +59,12 @@ impl<'a, 'bump> Parser<'a, 'bump> {
     pub fn parse(&mut self) -> PResult<Program<'a, 'bump>> {
         let mut program = Program::new_in(self.bump);
 
-        while let Some(_) = self.peek() {
+        while self.peek().is_some() {
             let item = self.parse_statement().map(Into::<Node>::into)?;
             program.0.push(item);
         }
 
-        return Ok(program);
+        Ok(program)
     }
 
     fn parse_statement(&mut self) -> PResult<Stmt<'a, 'bump>> {
@@ -94,21 +88,18 @@ impl<'a, 'bump> Parser<'a, 'bump> {
 
     fn parse_func_decl(&mut self) -> PResult<FuncDecl<'a, 'bump>> {
         let _ = self.next().expect("self.tokens shouldn't be consumed"); // consume`fn` keyword
-        let loc = self.loc(1);
+        let _loc = self.loc(1);
         let name = Ident(self.expect_token(Tag::Ident, Expected(Tag::Ident)));
         let mut params: Vec<'bump, Ident<'a>> = Vec::new_in(self.bump);
-        self.expect_token(Tag::LParen, ExpectedLBrace);
+        self.expect_token(Tag::LParen, Expected(Tag::LParen));
 
         // Early return for functions without parameters
-        match self.eat_token(Tag::RParen) {
-            Some(_) => {
-                return Ok(FuncDecl { 
-                    name, 
-                    params, 
-                    body: self.parse_block_stmt()?
-                })
-            }
-            None => {}
+        if self.eat_token(Tag::RParen).is_some() {
+            return Ok(FuncDecl { 
+                name, 
+                params, 
+                body: self.parse_block_stmt()?
+            })
         }
 
         // Parameter parsing
@@ -139,7 +130,7 @@ impl<'a, 'bump> Parser<'a, 'bump> {
     }
 
     fn parse_block_expr(&mut self) -> PResult<BlockExpr<'a, 'bump>> {
-        let lbrace = self.expect_token(Tag::LBrace, Expected(Tag::LBrace));
+        let _lbrace = self.expect_token(Tag::LBrace, Expected(Tag::LBrace));
 
         let mut stmts = Vec::new_in(self.bump);
 
@@ -149,7 +140,7 @@ impl<'a, 'bump> Parser<'a, 'bump> {
                     self.next();
                     break;
                 }
-                tok => {
+                Some(_) => {
                     stmts.0.push(self.parse_statement()?);
                 }
                 None => break self.add_error(Expected(Tag::RBrace), self.loc(1)),
@@ -193,7 +184,7 @@ impl<'a, 'bump> Parser<'a, 'bump> {
                 alternate = Some(Box::new_in(self.bump, IfAlt::Else(self.parse_block_expr()?)))
             }
             // Bro I have no clue what happens here
-            _ => return Err(ParseError { kind: Expected(Tag::LBrace), location: self.loc(1) })
+            _ => return Err(ParseError { kind: Expected(Tag::LBrace), location: self.loc(1), fix: None })
             // _ => return Err("Abort!!! brace missing - ifs lost"),
         };
 
@@ -222,7 +213,7 @@ impl<'a, 'bump> Parser<'a, 'bump> {
             Some(tok) if matches!(tok.tag, Tag::Semicolon | Tag::RBrace) => {
                 Ok(ReturnExpr { value: None })
             }
-            Some(tok) => Ok(ReturnExpr {
+            Some(_tok) => Ok(ReturnExpr {
                 value: Some(self.parse_expr()?),
             }),
             None => {
@@ -242,7 +233,7 @@ impl<'a, 'bump> Parser<'a, 'bump> {
             Some(tok) if matches!(tok.tag, Tag::Semicolon | Tag::RBrace) => {
                 Ok(BreakExpr { value: None })
             }
-            Some(tok) => Ok(BreakExpr {
+            Some(_tok) => Ok(BreakExpr {
                 value: Some(self.parse_expr()?),
             }),
             None => {
@@ -348,7 +339,7 @@ impl<'a, 'bump> Parser<'a, 'bump> {
             Tag::Break => Ok(Expr::Break(Box::new_in(self.bump, self.parse_break_expr()?))),
             _ => {
                 // We really need null nodes
-                return Err(ParseError { kind: ExpectedExpr, location: self.loc(1) })
+                Err(ParseError { kind: ExpectedExpr, location: self.loc(1), fix: None })
             }
         }
     }
@@ -423,7 +414,8 @@ impl<'a, 'bump> Parser<'a, 'bump> {
     fn add_error(&mut self, kind: ParseErrorKind, loc: Loc) {
         self.errors.push(ParseError {
             kind,
-            location: loc
+            location: loc,
+            fix: None,
         });
     }
 
@@ -498,38 +490,6 @@ mod expr {
 mod tests {
     extern crate test;
 
-    use super::{Parser, Bump};
-
-    const WHOLE_SOURCE: &str = r#"let PI = 3.14;
-
-    fn area_circle(radius) {
-        return PI * radius * radius;
-    }
-    
-    let radius = int(input("What is the radius"));
-    print(area_circle(radius));
-    
-    let students = [];
-    
-    while true {
-        print("Enter student record");
-        let name = input("Student Name: ");
-        let age = int(input("Student Age: "));
-        let class = input("Student's class");
-    
-    
-        if age > 18 {
-            return print("This person is too old");
-        }
-        if class.len() > 3 { return print("Invalid class name"); }
-    
-        students.push((name, age, class));
-    
-        if input("Exit? ") {
-            return print("Exiting record system")
-        }
-    }"#;
-
     // fn bench_parser(b: &mut test::Bencher) {
     //     let bump = Bump::new();
     //     let parser = Parser::new("", &bump);
@@ -0,0 +1,344 @@
+//! Selectors over the syntax tree
+//!
+//! A query is a list of steps, each separated from the previous one by `>`
+//! for a child of its match or by spaces for a descendant of it. A step is a
+//! node type from `language_nodes.txt` (`CallExpr`), a union of them
+//! (`Expr`) or `*`, followed by filters on its fields:
+//!
+//! - `[field=value]` holds if the field is a token with that value, or for a
+//!   list, if one of its items is. Values can be quoted, as in `[op="+"]`.
+//! - `[field]` holds if the field isn't a null node or an empty token.
+//!
+//! `name` also stands for the `ident` field of the nodes without a `name`,
+//! so `FnDef > ParamList > Param[name=radius]` finds the parameters called
+//! `radius`, and `WhileExpr[condition=true]` every `while true` loop.
+
+use std::fmt::Display;
+use std::ops::Range;
+
+use crate::ast2::{Node, NodeChild, NodeType, SpanTable};
+use crate::visitor::{Flow, Listener, Walker};
+
+#[derive(Debug)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug)]
+struct Step {
+    /// How the match relates to the one of the previous step
+    combinator: Combinator,
+    test: Test,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Child,
+    Descendant,
+}
+
+#[derive(Debug)]
+enum Test {
+    Any,
+    Type(NodeType),
+    Union(&'static [NodeType]),
+}
+
+#[derive(Debug)]
+struct Filter {
+    field: String,
+    value: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QueryError {
+    /// Byte offset into the pattern
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at offset {} of the query", self.message, self.offset)
+    }
+}
+
+/// A node found by a query
+#[derive(Debug)]
+pub struct Match<'s, 'b> {
+    pub node: &'b Node<'s, 'b>,
+    pub span: Range<u32>,
+}
+
+impl Query {
+    pub fn parse(pattern: &str) -> Result<Query, QueryError> {
+        let mut parser = PatternParser { pattern, pos: 0 };
+        let mut steps = Vec::new();
+        let mut combinator = Combinator::Descendant;
+        loop {
+            steps.push(parser.step(combinator)?);
+            let spaced = parser.skip_spaces();
+            if parser.eat('>') {
+                parser.skip_spaces();
+                combinator = Combinator::Child;
+            } else if parser.at_end() {
+                break;
+            } else if spaced {
+                combinator = Combinator::Descendant;
+            } else {
+                return Err(parser.error("expected `>`, a space or the end"));
+            }
+        }
+        Ok(Query { steps })
+    }
+
+    /// Matches in source order, outside of whatever the parser failed on
+    pub fn run<'s, 'b>(&self, tree: &'b Node<'s, 'b>, spans: &SpanTable) -> Vec<Match<'s, 'b>> {
+        let mut walker = Walker::new(tree, Matcher { query: self, spans, found: Vec::new() });
+        walker.walk();
+        walker.into_listener().found
+    }
+
+    /// Whether `node` matches the steps up to `step`, with its ancestors
+    /// matching the ones before
+    fn matches(&self, step: usize, node: &Node, ancestors: &[&Node]) -> bool {
+        if !self.steps[step].matches(node) {
+            return false;
+        }
+        if step == 0 {
+            return true;
+        }
+        match self.steps[step].combinator {
+            Combinator::Child => match ancestors.split_last() {
+                Some((parent, rest)) => self.matches(step - 1, parent, rest),
+                None => false,
+            },
+            Combinator::Descendant => (0..ancestors.len())
+                .rev()
+                .any(|i| self.matches(step - 1, ancestors[i], &ancestors[..i])),
+        }
+    }
+}
+
+impl Step {
+    fn matches(&self, node: &Node) -> bool {
+        let node_type = node.kind.0;
+        let test = match self.test {
+            Test::Any => node_type != NodeType::Any,
+            Test::Type(typ) => node_type == typ,
+            Test::Union(types) => types.contains(&node_type),
+        };
+        test && self.filters.iter().all(|filter| filter.matches(node))
+    }
+}
+
+impl Filter {
+    fn matches(&self, node: &Node) -> bool {
+        let Some(index) = field_index(node.kind.0, &self.field) else { return false };
+        let children = match node.kind.0.is_list() {
+            true => node.children(),
+            false => node.children().get(index..index + 1).unwrap_or(&[]),
+        };
+        children.iter().any(|child| match (child, &self.value) {
+            (NodeChild::Token(token), _) if token.is_empty() => false,
+            (NodeChild::Node(node), None) => !node.is_null(),
+            (NodeChild::Token(_), None) => true,
+            (NodeChild::Token(token), Some(value)) => token.value == value,
+            (NodeChild::Node(_), Some(_)) => false,
+        })
+    }
+}
+
+fn field_index(node_type: NodeType, field: &str) -> Option<usize> {
+    let fields = node_type.fields();
+    fields.iter().position(|name| *name == field).or_else(|| match field {
+        "name" => fields.iter().position(|name| *name == "ident"),
+        _ => None,
+    })
+}
+
+struct PatternParser<'p> {
+    pattern: &'p str,
+    pos: usize,
+}
+
+impl<'p> PatternParser<'p> {
+    fn step(&mut self, combinator: Combinator) -> Result<Step, QueryError> {
+        let start = self.pos;
+        let test = match self.eat('*') {
+            true => Test::Any,
+            false => {
+                let name = self.name();
+                match (NodeType::from_name(name), NodeType::union_members(name)) {
+                    _ if name.is_empty() => return Err(self.error("expected a node type or `*`")),
+                    (Some(typ), _) => Test::Type(typ),
+                    (None, Some(types)) => Test::Union(types),
+                    (None, None) => return Err(QueryError { offset: start, message: format!("unknown node type `{}`", name) }),
+                }
+            }
+        };
+
+        let mut filters = Vec::new();
+        while self.eat('[') {
+            self.skip_spaces();
+            let start = self.pos;
+            let field = self.name();
+            if field.is_empty() {
+                return Err(self.error("expected a field name"));
+            }
+            if let Test::Type(typ) = test {
+                if field_index(typ, field).is_none() {
+                    return Err(QueryError { offset: start, message: format!("`{:?}` has no field `{}`", typ, field) });
+                }
+            }
+            self.skip_spaces();
+            let value = match self.eat('=') {
+                true => Some(self.value()?),
+                false => None,
+            };
+            if !self.eat(']') {
+                return Err(self.error("expected `]`"));
+            }
+            filters.push(Filter { field: field.to_string(), value });
+        }
+        Ok(Step { combinator, test, filters })
+    }
+
+    fn name(&mut self) -> &'p str {
+        let rest = &self.pattern[self.pos..];
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// A quoted string, or the text up to `]` without surrounding spaces
+    fn value(&mut self) -> Result<String, QueryError> {
+        self.skip_spaces();
+        if !self.eat('"') {
+            let rest = &self.pattern[self.pos..];
+            let len = rest.find(']').unwrap_or(rest.len());
+            self.pos += len;
+            return Ok(rest[..len].trim().to_string());
+        }
+        let mut value = String::new();
+        let mut chars = self.pattern[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    self.skip_spaces();
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, c)) => value.push(c),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn skip_spaces(&mut self) -> bool {
+        let rest = &self.pattern[self.pos..];
+        let trimmed = rest.trim_start();
+        self.pos += rest.len() - trimmed.len();
+        rest.len() != trimmed.len()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.pattern[self.pos..].starts_with(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.pattern.len()
+    }
+
+    fn error(&self, message: &str) -> QueryError {
+        QueryError { offset: self.pos, message: message.to_string() }
+    }
+}
+
+struct Matcher<'q, 't, 's, 'b> {
+    query: &'q Query,
+    spans: &'q SpanTable<'t>,
+    found: Vec<Match<'s, 'b>>,
+}
+
+impl<'s, 'b> Listener<'s, 'b> for Matcher<'_, '_, 's, 'b> {
+    fn enter(&mut self, node: &'b Node<'s, 'b>, ancestors: &[&'b Node<'s, 'b>]) -> Flow {
+        // The children of invalid nodes don't line up with their fields
+        if node.is_invalid() {
+            return Flow::SkipChildren;
+        }
+        if self.query.matches(self.query.steps.len() - 1, node, ancestors) {
+            let span = self.spans.span(node.id()).unwrap_or(0..0);
+            self.found.push(Match { node, span });
+        }
+        Flow::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::{Query, QueryError};
+    use crate::parser3::Parser;
+
+    const SOURCE: &str = "fn area(radius: f64) -> f64 {
+    return 3.14 * radius * radius;
+}
+
+fn main() {
+    let r = 2.0;
+    while true {
+        print(area(r));
+        if r > 10.0 { break; }
+        r = r + 1.0;
+    }
+    print(r, \"done\");
+}";
+
+    /// Source text of the matches
+    fn query(pattern: &str) -> Vec<&'static str> {
+        let bump = Bump::new();
+        let mut parser = Parser::new(SOURCE, &bump);
+        let parsed = bump.alloc(parser.parse());
+        let query = Query::parse(pattern).unwrap();
+        query.run(&parsed.tree, parsed.spans)
+            .into_iter()
+            .map(|found| &SOURCE[found.span.start as usize..found.span.end as usize])
+            .collect()
+    }
+
+    #[test]
+    fn test_queries() {
+        assert_eq!(query("FnDef > ParamList > Param[name=radius]"), ["radius: f64"]);
+        assert_eq!(query("CallExpr[name=print]"), ["print(area(r))", "print(r, \"done\")"]);
+        assert_eq!(query("WhileExpr[condition=true] CallExpr"), ["print(area(r))", "area(r)"]);
+        assert_eq!(query("WhileExpr[condition=true] > * > * > ExprStmt > CallExpr"), ["print(area(r))"]);
+        assert_eq!(query("Infix[op=\"*\"] > Infix"), ["3.14 * radius"]);
+        assert_eq!(query("AssignExpr[target=r]"), ["r = r + 1.0"]);
+        assert_eq!(query("ArgList[args=\"\\\"done\\\"\"]"), ["(r, \"done\")"]);
+        assert_eq!(query("FnDef[return_type]"), ["fn area(radius: f64) -> f64 {\n    return 3.14 * radius * radius;\n}"]);
+        assert_eq!(query("ReturnExpr > Expr"), ["3.14 * radius * radius"]);
+        assert!(query("FnDef[name=missing]").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        let error = |pattern| Query::parse(pattern).unwrap_err();
+        assert_eq!(error("CallExpr >"), QueryError { offset: 10, message: "expected a node type or `*`".into() });
+        assert_eq!(error("Call"), QueryError { offset: 0, message: "unknown node type `Call`".into() });
+        assert_eq!(error("Param[type=f64]").message, "`Param` has no field `type`");
+        assert_eq!(error("Param[name=x").message, "expected `]`");
+        assert_eq!(error("Infix[op=\"+]").message, "unterminated string");
+        assert_eq!(error("FnDef,Param").message, "expected `>`, a space or the end");
+    }
+}
@@ -0,0 +1,408 @@
+//! Interactive sessions
+//!
+//! Every input is checked and run as part of a whole program. Declarations
+//! are kept as written, a new one replacing an earlier one of the same name,
+//! and statements become the body of a `main` function taking the variables
+//! of earlier inputs as parameters. Their values are kept from the run that
+//! computed them, so nothing runs twice. A variable is forgotten when its
+//! type can't be written as a parameter, or when its struct is redeclared.
+
+use std::io::{self, BufRead, Write};
+
+use bumpalo::Bump;
+
+use crate::ast2::{AstNode, AstToken, Stmt, TopDeclList, TopLevelDecl};
+use crate::lexer::Lexer;
+use crate::parser3::Parser;
+use crate::token::Tag;
+use crate::typecheck::check::TypeChecker;
+use crate::typecheck::Type;
+use crate::vm::{compiler, Io, Value, Vm};
+
+/// Name of the variable holding the value of an expression
+const VALUE: &str = "__value";
+
+const HELP: &str = "\
+:type <expr>     print the type of an expression
+:ast <expr>      print the tree of an expression
+:tokens <expr>   print the tokens of an expression
+:quit            end the session
+";
+
+/// Reads and runs inputs until `:quit` or the end of the input
+pub fn run(input: impl BufRead, output: impl Write) -> io::Result<()> {
+    let (mut input, mut output) = (input, output);
+    let mut repl = Repl::default();
+    while let Some(text) = read_input(&mut input, &mut output)? {
+        if !repl.eval(text.trim(), &mut input, &mut output)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads lines until every brace and string is closed, none at the end of the input
+fn read_input(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<Option<String>> {
+    let mut text = String::new();
+    loop {
+        write!(output, "{}", if text.is_empty() { "> " } else { ". " })?;
+        output.flush()?;
+        if input.read_line(&mut text)? == 0 {
+            return Ok((!text.trim().is_empty()).then_some(text));
+        }
+        if !incomplete(&text) {
+            return Ok(Some(text));
+        }
+    }
+}
+
+/// Whether more lines are needed to close a block, group or string
+pub fn incomplete(text: &str) -> bool {
+    let mut depth = 0;
+    for token in Lexer::from(text) {
+        match token.tag {
+            Tag::LBrace | Tag::LParen | Tag::LBracket => depth += 1,
+            Tag::RBrace | Tag::RParen | Tag::RBracket => depth -= 1,
+            // An unterminated string runs to the end of the input
+            Tag::UnexpectedEof => return true,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+/// A variable of the session, kept between inputs
+#[derive(Debug)]
+struct Var {
+    name: String,
+    typ: Type,
+    value: Value,
+}
+
+#[derive(Debug, Default)]
+struct Repl {
+    /// Declarations by name, in the order they were entered
+    decls: Vec<(String, String)>,
+    /// Variables of the inputs that ran successfully
+    vars: Vec<Var>,
+}
+
+impl Repl {
+    /// Evaluates one input, returning false once the session should end
+    fn eval(&mut self, text: &str, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<bool> {
+        if text.is_empty() {
+            return Ok(true);
+        }
+        if let Some(command) = text.strip_prefix(':') {
+            let (name, arg) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+            let arg = arg.trim();
+            match name {
+                "quit" | "q" => return Ok(false),
+                "help" => write!(output, "{}", HELP)?,
+                "type" => match self.type_of(arg) {
+                    Ok(typ) => writeln!(output, "{}", typ)?,
+                    Err(errors) => report(output, &errors)?,
+                },
+                "ast" => ast(arg, output)?,
+                "tokens" => {
+                    for token in Lexer::from(arg) {
+                        writeln!(output, "{:?} {:?} @ {}:{}", token.tag, token.value, token.line, token.pos)?;
+                    }
+                }
+                _ => writeln!(output, "error: unknown command `:{}`, try `:help`", name)?,
+            }
+            return Ok(true);
+        }
+
+        let first = Lexer::from(text).next().map(|token| token.tag);
+        match first {
+            Some(Tag::Fn | Tag::Struct | Tag::Enum | Tag::Type | Tag::Const | Tag::Pub | Tag::Module | Tag::Import) => {
+                self.declare(text, output)?
+            }
+            // Forgetting the semicolon of a variable is common enough to forgive
+            Some(Tag::Let) if !text.ends_with(';') => self.execute(&format!("{};", text), input, output)?,
+            _ => self.execute(text, input, output)?,
+        }
+        Ok(true)
+    }
+
+    fn declare(&mut self, text: &str, output: &mut impl Write) -> io::Result<()> {
+        let name = Lexer::from(text).find(|token| token.tag == Tag::Ident).map_or("", |token| token.value);
+        let mut decls = self.decls.clone();
+        match decls.iter_mut().find(|(other, _)| other == name) {
+            Some(decl) => decl.1 = text.to_string(),
+            None => decls.push((name.to_string(), text.to_string())),
+        }
+
+        // Variables don't take part, their values being computed already
+        let vars = &self.vars;
+        let changed = check(&program(&decls, &[], ""), |_, types| {
+            Ok(vars.iter().filter(|var| !same_type(&var.typ, types)).map(|var| var.name.clone()).collect::<Vec<_>>())
+        });
+        match changed {
+            Ok(changed) => {
+                self.decls = decls;
+                for name in changed {
+                    writeln!(output, "note: `{}` is forgotten, its type was redeclared", name)?;
+                    self.vars.retain(|var| var.name != name);
+                }
+                Ok(())
+            }
+            Err(errors) => report(output, &errors),
+        }
+    }
+
+    fn execute(&mut self, text: &str, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        // A bare expression is printed unless it has no value
+        let body = if text.ends_with(';') || text.ends_with('}') {
+            text.to_string()
+        } else {
+            match self.type_of(text) {
+                Ok(Type::Unit | Type::Never) => format!("{};", text),
+                Ok(typ) => {
+                    let print = format!("print({});", text);
+                    if check(&program(&self.decls, &self.vars, &print), |_, _| Ok(())).is_ok() {
+                        print
+                    } else {
+                        // Values `print` can't show are described by their type
+                        writeln!(output, "{}", typ)?;
+                        format!("{};", text)
+                    }
+                }
+                Err(errors) => return report(output, &errors),
+            }
+        };
+
+        let compiled = check(&program(&self.decls, &self.vars, &body), |decls, types| {
+            // Types of the variables the input declares, later ones shadowing earlier ones
+            let declared: Vec<(String, Type)> = main_body(decls.clone()).into_iter()
+                .filter_map(|stmt| match stmt {
+                    Stmt::VarDecl(var_decl) => {
+                        Some((var_decl.name().token().value.to_string(), types.type_of_var(&var_decl)?.clone()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            let program = compiler::compile(decls, types).map_err(|errors| {
+                errors.iter().map(|err| format!("{} are not supported by the VM", err.message)).collect::<Vec<_>>()
+            })?;
+            Ok((program, declared))
+        });
+        let (program, declared) = match compiled {
+            Ok(compiled) => compiled,
+            Err(errors) => return report(output, &errors),
+        };
+
+        let main = program.main.expect("the session declares `main`");
+        let args = self.vars.iter().map(|var| var.value.clone()).collect();
+        let mut vm = Vm::new(&program);
+        if let Err(err) = vm.call(main, args, &mut Io { input, output: &mut *output }) {
+            return writeln!(output, "error: {}", err.kind);
+        }
+
+        // Parameters come first, so a variable of the input replaces the one it shadows
+        let locals = vm.locals();
+        for (var, value) in self.vars.iter_mut().zip(locals) {
+            var.value = value.clone();
+        }
+        for (name, slot) in &program.functions[main as usize].body_locals {
+            let Some((_, typ)) = declared.iter().rev().find(|(other, _)| other == &**name) else { continue };
+            let var = Var { name: name.to_string(), typ: typ.clone(), value: locals[*slot as usize].clone() };
+            self.vars.retain(|other| other.name != var.name);
+            match spelling(&var.typ) {
+                Some(_) => self.vars.push(var),
+                None => writeln!(output, "note: `{}` is forgotten, values of type `{}` can't be kept", var.name, var.typ)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn type_of(&self, expr: &str) -> Result<Type, Vec<String>> {
+        let source = program(&self.decls, &self.vars, &format!("let {} = {};", VALUE, expr));
+        check(&source, |decls, types| {
+            let value = main_body(decls).last()
+                .and_then(|stmt| match stmt {
+                    Stmt::VarDecl(var_decl) => types.type_of_var(var_decl).cloned(),
+                    _ => None,
+                });
+            value.ok_or_else(|| vec!["error: expected an expression".to_string()])
+        })
+    }
+}
+
+/// The whole program of an input, `body` being the body of a `main` taking
+/// the variables of the session as parameters
+fn program(decls: &[(String, String)], vars: &[Var], body: &str) -> String {
+    let mut source = String::new();
+    for (_, decl) in decls {
+        source.push_str(decl);
+        source.push('\n');
+    }
+    let params: Vec<String> = vars.iter()
+        .map(|var| format!("{}: {}", var.name, spelling(&var.typ).expect("only spelled types are kept")))
+        .collect();
+    source.push_str(&format!("fn main({}) {{\n", params.join(", ")));
+    source.push_str(body);
+    source.push_str("\n}\n");
+    source
+}
+
+/// The statements of `main`
+fn main_body<'s, 'b>(decls: TopDeclList<'s, 'b>) -> Vec<Stmt<'s, 'b>> {
+    decls.items()
+        .find_map(|decl| match decl {
+            TopLevelDecl::Fn(fn_def) if fn_def.name().token().value == "main" => fn_def.body(),
+            _ => None,
+        })
+        .map_or(Vec::new(), |body| body.body().items().collect())
+}
+
+/// A type as written in a parameter, if it can be written
+fn spelling(typ: &Type) -> Option<String> {
+    match typ {
+        Type::Bool | Type::U32 | Type::U64 | Type::I32 | Type::I64 | Type::F32 | Type::F64 | Type::String
+        | Type::Struct(..) => Some(typ.to_string()),
+        Type::Array(elem, len) => Some(format!("[{}; {}]", spelling(elem)?, len)),
+        _ => None,
+    }
+}
+
+/// Whether the names in a type still mean what they did
+fn same_type(typ: &Type, types: &TypeChecker) -> bool {
+    match typ {
+        Type::Array(elem, _) => same_type(elem, types),
+        Type::Struct(name, _) => types.global(name) == Some(typ),
+        _ => true,
+    }
+}
+
+/// Parses and type checks a program, returning every error
+fn check<R>(
+    source: &str,
+    then: impl for<'s, 'b> FnOnce(TopDeclList<'s, 'b>, &TypeChecker<'s>) -> Result<R, Vec<String>>,
+) -> Result<R, Vec<String>> {
+    let bump = Bump::new();
+    let mut parser = Parser::new(source, &bump);
+    let root = parser.parse().tree;
+    if !parser.errors.is_empty() {
+        return Err(parser.errors.iter().map(|err| coded(err.kind.code(), &err.kind)).collect());
+    }
+
+    let root = bump.alloc(root);
+    let mut checker = TypeChecker::new();
+    checker.check_program(TopDeclList::cast(root));
+    if !checker.errors.is_empty() {
+        return Err(checker.errors.iter().map(|err| coded(err.kind.code(), &err.kind)).collect());
+    }
+    then(TopDeclList::cast(root), &checker)
+}
+
+fn ast(expr: &str, output: &mut impl Write) -> io::Result<()> {
+    let bump = Bump::new();
+    // Expressions end at a token that can't continue them
+    let source = format!("{};", expr);
+    let mut parser = Parser::new(&source, &bump);
+    let parsed = parser.expr().ok();
+    match parsed {
+        _ if !parser.errors.is_empty() => report(output, &parser.errors.iter().map(|err| coded(err.kind.code(), &err.kind)).collect::<Vec<_>>()),
+        Some(crate::ast2::NodeChild::Node(node)) => writeln!(output, "{:#}", node),
+        Some(crate::ast2::NodeChild::Token(token)) => writeln!(output, "({:?} {:?}) @ {}:{}", token.tag, token.value, token.line, token.pos),
+        _ => report(output, &["error: expected an expression".to_string()]),
+    }
+}
+
+/// An error message with the code `haze explain` knows it by
+fn coded(code: &str, message: impl std::fmt::Display) -> String {
+    format!("error[{}]: {}", code, message)
+}
+
+fn report(output: &mut impl Write, errors: &[String]) -> io::Result<()> {
+    for error in errors {
+        writeln!(output, "{}", error)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{incomplete, run};
+
+    fn session(input: &str) -> String {
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_incomplete() {
+        assert!(incomplete("fn f() {"));
+        assert!(incomplete("let s = \"abc"));
+        assert!(incomplete("if x { print(1); } else {\n"));
+        assert!(!incomplete("fn f() {}"));
+        assert!(!incomplete("let s = \"{\";"));
+    }
+
+    #[test]
+    fn test_state() {
+        let output = session("\
+fn double(n: i64) -> i64 { return n * 2; }
+let x = double(21)
+x
+print(x + 1);
+double(x)
+fn double(n: i64) -> i64 {
+    return n * 3;
+}
+double(x)
+x = x + 1;
+x
+[x, 1]
+");
+        assert_eq!(output, "> > > 42\n> 43\n> 84\n> . . > 126\n> > 43\n> [i64; 2]\n> ");
+    }
+
+    #[test]
+    fn test_nothing_runs_twice() {
+        let output = session("let x = 1;\nprint(\"once\");\nlet x = x == 1;\nx\nlet y = \"\u{1}haze repl\u{1}\";\ny\n");
+        assert_eq!(output, "> > once\n> > true\n> > \u{1}haze repl\u{1}\n> ");
+    }
+
+    #[test]
+    fn test_redeclared_struct_forgets_variables() {
+        let output = session("struct P { a: i64 }\nlet p = .P { a: 1 };\nlet n = p.a;\nstruct P { b: bool }\nn\np\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "> > > > note: `p` is forgotten, its type was redeclared", "{}", output);
+        assert_eq!(lines[1], "> 1", "{}", output);
+        assert!(lines[2].starts_with("> error[E0101]: undefined name"), "{}", output);
+    }
+
+    #[test]
+    fn test_input() {
+        let output = session("let name = input(\"name? \");\nhaze\nname\nlet s = \"two\nlines\";\ns\n");
+        assert_eq!(output, "> name? > haze\n> . > two\nlines\n> ");
+    }
+
+    #[test]
+    fn test_errors_are_forgotten() {
+        let output = session("let x = 1;\nlet y = x + true;\ny\nx\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("> > error[E0100]: "), "{}", output);
+        assert!(lines[1].starts_with("> error[E0101]: undefined name"), "{}", output);
+        assert_eq!(lines[2], "> 1", "{}", output);
+    }
+
+    #[test]
+    fn test_commands() {
+        let output = session("struct P { a: i64 }\n:type .P { a: 1 }\n:type 1 > 2\n:tokens x + 1\n:ast x + 1\n:quit\n1\n");
+        let expected = "\
+> > P
+> bool
+> Ident \"x\" @ 1:0
+Plus \"+\" @ 1:2
+Number \"1\" @ 1:4
+> ";
+        assert!(output.starts_with(expected), "{}", output);
+        assert!(output.contains("Infix"), "{}", output);
+        assert!(output.ends_with("> "), "{}", output);
+    }
+}
@@ -1,7 +1,6 @@
-use serde::Serialize;
-use std::{marker::PhantomData, ops::Range};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Tag {
     // Operators
     Plus,
@@ -58,37 +57,12 @@ pub enum Tag {
     Import,
     Type, // type keyword, not an actual type
     Const,
+    Pub,
 
     UnexpectedEof,
     Invalid,
 }
 
-pub struct Span<'a, T: 'a> {
-    start: u32,
-    len: u32,
-    _marker: PhantomData<&'a T>,
-}
-
-impl<'a> Span<'a, &'a str> {
-    fn new(start: u32, len: u32) -> Self {
-        Self {
-            start,
-            len,
-            _marker: PhantomData,
-        }
-    }
-}
-
-impl<'a> From<Range<u32>> for Span<'a, &'a str> {
-    fn from(range: Range<u32>) -> Self {
-        Self {
-            start: range.start,
-            len: range.len() as u32,
-            _marker: PhantomData,
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 pub struct Token<'s> {
     pub tag: Tag,
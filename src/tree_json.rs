@@ -0,0 +1,399 @@
+//! JSON form of the syntax tree, for tools written in other languages
+//!
+//! A node is an object
+//!
+//! ```json
+//! { "kind": "FnDef", "invalid": false, "span": [0, 42], "children": [...] }
+//! ```
+//!
+//! where `kind` is a node type of `language_nodes.txt` or `Any`, `span` is
+//! the byte range of the node as kept in the `SpanTable`, and `children` are
+//! nodes and tokens in the order of the fields, or `null` for a null node or
+//! an empty list. A token is
+//!
+//! ```json
+//! { "tag": "Ident", "value": "main", "pos": 3, "line": 1 }
+//! ```
+//!
+//! where `tag` is a variant of `Tag`, `pos` the byte offset of the token and
+//! `line` its line, counted from 1. An absent optional token, such as the
+//! visibility of a private function, is `{ "tag": null }`.
+//!
+//! `tree_json.schema.json` next to this file is the JSON Schema of the form.
+//!
+//! Imported trees may leave out `invalid`, which defaults to false, and
+//! `span`, which is then computed from the tokens of the node. Their
+//! children must fit the fields of `language_nodes.txt`: a token or node of
+//! the field's type for each field, any number of items for a list, and
+//! none for a null node, which is `Any`. Where the parser failed, an
+//! invalid node may take the place of a node and an absent token that of a
+//! token, and invalid nodes may hold anything, as the parser keeps whatever
+//! it got to in them.
+
+use std::fmt::Display;
+
+use bumpalo::Bump;
+use serde_json::{json, Value};
+
+use crate::ast2::{Node, NodeBuilder, NodeChild, NodeType, SpanTable};
+use crate::parser3::Parsed;
+use crate::token::{Tag, Token};
+
+pub fn to_json(node: &Node, spans: &SpanTable) -> Value {
+    let children = match node.children.as_ref() {
+        Some(children) => Value::Array(children.0.iter().map(|child| child_json(child, spans)).collect()),
+        None => Value::Null,
+    };
+    let span = spans.span(node.id()).map_or(Value::Null, |span| json!([span.start, span.end]));
+    json!({
+        "kind": format!("{:?}", node.kind.0),
+        "invalid": node.is_invalid(),
+        "span": span,
+        "children": children,
+    })
+}
+
+fn child_json(child: &NodeChild, spans: &SpanTable) -> Value {
+    match child {
+        NodeChild::Node(node) => to_json(node, spans),
+        NodeChild::Token(token) if token.is_empty() => json!({ "tag": null }),
+        NodeChild::Token(token) => json!({
+            "tag": token.tag,
+            "value": token.value,
+            "pos": token.pos,
+            "line": token.line,
+        }),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImportError {
+    /// JSON pointer to the offending value, e.g. `/children/0/kind`
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{} at `{}`", self.message, self.path),
+        }
+    }
+}
+
+pub fn from_json<'b>(json: &Value, bump: &'b Bump) -> Result<Parsed<'b, 'b>, ImportError> {
+    let spans = bump.alloc(SpanTable::new_in(bump));
+    let mut importer = Importer { bump, spans, path: String::new() };
+    let tree = importer.node(json)?;
+    Ok(Parsed { tree, spans })
+}
+
+struct Importer<'b> {
+    bump: &'b Bump,
+    spans: &'b SpanTable<'b>,
+    /// JSON pointer to the value being read
+    path: String,
+}
+
+impl<'b> Importer<'b> {
+    fn node(&mut self, json: &Value) -> Result<Node<'b, 'b>, ImportError> {
+        let kind = self.field(json, "kind", |kind| {
+            let name = kind.as_str()?;
+            match name {
+                "Any" => Some(NodeType::Any),
+                _ => NodeType::from_name(name),
+            }
+        }, "a node type")?;
+        let invalid = match json.get("invalid") {
+            None => false,
+            Some(_) => self.field(json, "invalid", Value::as_bool, "a boolean")?,
+        };
+        let span = match json.get("span") {
+            None | Some(Value::Null) => None,
+            Some(_) => Some(self.field(json, "span", |span| match span.as_array()?.as_slice() {
+                [start, end] => Some(as_u32(start)?..as_u32(end)?),
+                _ => None,
+            }, "a pair of offsets")?),
+        };
+
+        let mut children = Vec::new();
+        match json.get("children") {
+            None | Some(Value::Null) => {}
+            Some(Value::Array(items)) => {
+                for (i, child) in items.iter().enumerate() {
+                    let len = self.path.len();
+                    self.path += &format!("/children/{}", i);
+                    children.push(match child.get("kind") {
+                        Some(_) => NodeChild::Node(self.node(child)?),
+                        None => NodeChild::Token(self.token(child)?),
+                    });
+                    self.path.truncate(len);
+                }
+            }
+            Some(_) => return Err(self.error("children", "expected an array or null")),
+        }
+        // The parser keeps whatever an invalid node got to, in any shape
+        if !invalid {
+            self.check_children(kind, &children)?;
+        }
+
+        let mut builder = NodeBuilder::new(kind, self.bump, self.spans, span.as_ref().map_or(0, |span| span.start));
+        children.into_iter().for_each(|child| builder.add(child));
+
+        let node = match invalid {
+            true => builder.invalid(),
+            false => builder.finish(false),
+        };
+        if let Some(span) = span {
+            self.spans.set(node.id(), span);
+        }
+        Ok(node)
+    }
+
+    /// Checks the children against the fields of `kind`, which the accessors
+    /// of the typed tree index and cast without checking
+    fn check_children(&self, kind: NodeType, children: &[NodeChild]) -> Result<(), ImportError> {
+        let fields = kind.fields();
+        // A null node is an `Any` without children, and only lists may be empty
+        if !kind.is_list() && children.len() != fields.len() {
+            let names: Vec<String> = fields.iter().map(|field| format!("`{}`", field)).collect();
+            let message = match fields.len() {
+                0 => format!("expected no children for {:?}, found {}", kind, children.len()),
+                1 => format!("expected 1 child for the field {} of {:?}, found {}", names[0], kind, children.len()),
+                n => format!("expected {} children for the fields {} of {:?}, found {}", n, names.join(", "), kind, children.len()),
+            };
+            return Err(self.error("children", &message));
+        }
+        for (i, child) in children.iter().enumerate() {
+            if !kind.accepts(i, child) {
+                let field = fields[if kind.is_list() { 0 } else { i }];
+                return Err(self.error(&format!("children/{}", i), &format!("`{}` of {:?} can't be {}", field, kind, describe(child))));
+            }
+        }
+        Ok(())
+    }
+
+    fn token(&mut self, json: &Value) -> Result<Token<'b>, ImportError> {
+        if json.get("tag") == Some(&Value::Null) {
+            return Ok(Token::empty());
+        }
+        let tag = self.field(json, "tag", |tag| serde_json::from_value::<Tag>(tag.clone()).ok(), "a token tag")?;
+        let value = self.field(json, "value", Value::as_str, "a string")?;
+        let pos = self.field(json, "pos", as_u32, "an offset")?;
+        let line = self.field(json, "line", as_u32, "a line number")?;
+        Ok(Token::new(tag, self.bump.alloc_str(value), pos, line))
+    }
+
+    fn field<'j, T>(&self, json: &'j Value, name: &str, read: impl FnOnce(&'j Value) -> Option<T>, expected: &str) -> Result<T, ImportError> {
+        match json.get(name) {
+            Some(value) => read(value).ok_or_else(|| self.error(name, &format!("expected {}, found {}", expected, value))),
+            None if json.is_object() => Err(self.error(name, "missing")),
+            None => Err(ImportError { path: self.path.clone(), message: format!("expected an object, found {}", json) }),
+        }
+    }
+
+    fn error(&self, name: &str, message: &str) -> ImportError {
+        ImportError { path: format!("{}/{}", self.path, name), message: message.to_string() }
+    }
+}
+
+fn describe(child: &NodeChild) -> String {
+    match child {
+        NodeChild::Node(node) if node.is_invalid() => format!("an invalid {:?} node", node.kind.0),
+        NodeChild::Node(node) if node.kind.0 == NodeType::Any => match node.is_null() {
+            true => "a null node".to_string(),
+            false => "an Any node".to_string(),
+        },
+        NodeChild::Node(node) => format!("a {:?} node", node.kind.0),
+        NodeChild::Token(token) if token.is_empty() => "an absent token".to_string(),
+        NodeChild::Token(token) => format!("a {:?} token", token.tag),
+    }
+}
+
+fn as_u32(value: &Value) -> Option<u32> {
+    value.as_u64()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+    use serde_json::json;
+
+    use serde_json::Value;
+
+    use super::{from_json, to_json, ImportError};
+    use crate::parser3::Parser;
+
+    fn export(source: &str) -> Value {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let parsed = parser.parse();
+        to_json(&parsed.tree, parsed.spans)
+    }
+
+    /// Whether `value` matches `schema`, for the keywords the tree schema uses
+    fn validate(value: &Value, schema: &Value, root: &Value) -> Result<(), String> {
+        let object = schema.as_object().expect("a schema is an object");
+        for (keyword, rule) in object {
+            let ok = match keyword.as_str() {
+                "$schema" | "title" | "description" | "$defs" => true,
+                "$ref" => {
+                    let name = rule.as_str().unwrap().strip_prefix("#/$defs/").expect("a local definition");
+                    validate(value, &root["$defs"][name], root)?;
+                    true
+                }
+                "type" => match rule.as_str().unwrap() {
+                    "object" => value.is_object(),
+                    "array" => value.is_array(),
+                    "string" => value.is_string(),
+                    "integer" => value.is_u64() || value.is_i64(),
+                    "boolean" => value.is_boolean(),
+                    "null" => value.is_null(),
+                    other => panic!("unknown type `{}`", other),
+                },
+                "required" => rule.as_array().unwrap().iter().all(|name| value.get(name.as_str().unwrap()).is_some()),
+                "properties" => {
+                    for (name, property) in rule.as_object().unwrap() {
+                        if let Some(field) = value.get(name) {
+                            validate(field, property, root).map_err(|err| format!("/{}{}", name, err))?;
+                        }
+                    }
+                    true
+                }
+                "additionalProperties" => {
+                    assert_eq!(rule, false);
+                    let known = object["properties"].as_object().unwrap();
+                    value.as_object().is_none_or(|fields| fields.keys().all(|name| known.contains_key(name)))
+                }
+                "items" => {
+                    for (i, item) in value.as_array().into_iter().flatten().enumerate() {
+                        validate(item, rule, root).map_err(|err| format!("/{}{}", i, err))?;
+                    }
+                    true
+                }
+                "minItems" => value.as_array().is_none_or(|items| items.len() as u64 >= rule.as_u64().unwrap()),
+                "maxItems" => value.as_array().is_none_or(|items| items.len() as u64 <= rule.as_u64().unwrap()),
+                "minLength" => value.as_str().is_none_or(|text| text.chars().count() as u64 >= rule.as_u64().unwrap()),
+                "minimum" => value.as_f64().is_none_or(|number| number >= rule.as_f64().unwrap()),
+                "maximum" => value.as_f64().is_none_or(|number| number <= rule.as_f64().unwrap()),
+                "oneOf" => {
+                    let matching = rule.as_array().unwrap().iter().filter(|choice| validate(value, choice, root).is_ok()).count();
+                    matching == 1
+                }
+                other => panic!("the validator doesn't know `{}`", other),
+            };
+            if !ok {
+                return Err(format!(": {} fails `{}`", value, keyword));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = "pub struct P { x: f64 }\nfn f(a: i64) -> i64 {\n    let b = .P { x: 1.0 };\n    return a + 1;\n}\nfn g() { h(1 +); }\n} 3";
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let parsed = parser.parse();
+        let exported = to_json(&parsed.tree, parsed.spans);
+
+        let text = serde_json::to_string(&exported).unwrap();
+        let imported_bump = Bump::new();
+        let imported = from_json(&serde_json::from_str(&text).unwrap(), &imported_bump).unwrap();
+        assert_eq!(format!("{}", imported.tree), format!("{}", parsed.tree));
+        assert_eq!(to_json(&imported.tree, imported.spans), exported);
+    }
+
+    #[test]
+    fn test_parsed_trees_import() {
+        // Whatever the parser builds, broken input included, passes the checks
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/parser");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|extension| extension == "hz") {
+                let bump = Bump::new();
+                let exported = export(&std::fs::read_to_string(&path).unwrap());
+                if let Err(err) = from_json(&exported, &bump).map(drop) {
+                    panic!("{}: {}", path.display(), err);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema: Value = serde_json::from_str(include_str!("tree_json.schema.json")).unwrap();
+        let exported = export("pub fn f(a: i64) -> i64 { return a; }\nfn g() { h(1 +); }\n} 3");
+        assert_eq!(validate(&exported, &schema, &schema), Ok(()));
+
+        let mut broken = exported.clone();
+        broken["children"][0]["children"][0]["line"] = json!(0);
+        assert!(validate(&broken, &schema, &schema).unwrap_err().starts_with("/children"));
+        broken = exported.clone();
+        broken["span"] = json!([1]);
+        assert!(validate(&broken, &schema, &schema).unwrap_err().starts_with("/span"));
+        broken = exported;
+        broken["extra"] = json!(true);
+        assert!(validate(&broken, &schema, &schema).unwrap_err().contains("additionalProperties"));
+    }
+
+    #[test]
+    fn test_import_without_spans() {
+        let json = json!({
+            "kind": "TopDeclList",
+            "children": [{
+                "kind": "ConstDecl",
+                "children": [
+                    { "tag": "Ident", "value": "N", "pos": 6, "line": 1 },
+                    { "tag": "Ident", "value": "i64", "pos": 9, "line": 1 },
+                    { "tag": "Number", "value": "3", "pos": 15, "line": 1 },
+                    { "tag": null },
+                ],
+            }],
+        });
+        let bump = Bump::new();
+        let imported = from_json(&json, &bump).unwrap();
+        let exported = to_json(&imported.tree, imported.spans);
+        assert_eq!(exported["span"], json!([6, 16]));
+        assert_eq!(exported["children"][0]["invalid"], json!(false));
+        assert_eq!(exported["children"][0]["children"][3], json!({ "tag": null }));
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let bump = Bump::new();
+        let error = |json| from_json(&json, &bump).err().unwrap();
+        assert_eq!(error(json!({ "kind": "Fn" })), ImportError { path: "/kind".into(), message: "expected a node type, found \"Fn\"".into() });
+        assert_eq!(error(json!({ "children": [] })), ImportError { path: "/kind".into(), message: "missing".into() });
+        assert_eq!(
+            error(json!({ "kind": "TopDeclList", "children": [{ "kind": "FnDef", "children": [{ "tag": "Identifier" }] }] })),
+            ImportError { path: "/children/0/children/0/tag".into(), message: "expected a token tag, found \"Identifier\"".into() },
+        );
+        assert_eq!(error(json!({ "kind": "Any", "span": [1] })).message, "expected a pair of offsets, found [1]");
+        assert_eq!(error(json!({ "kind": "Any", "children": [3] })).to_string(), "expected an object, found 3 at `/children/0`");
+
+        // Children that don't fit the fields
+        assert_eq!(
+            error(json!({ "kind": "FnDef", "children": [] })),
+            ImportError {
+                path: "/children".into(),
+                message: "expected 5 children for the fields `name`, `params`, `body`, `return_type`, `visibility` of FnDef, found 0".into(),
+            },
+        );
+        assert_eq!(error(json!({ "kind": "Group" })).message, "expected 1 child for the field `expr` of Group, found 0");
+        assert_eq!(
+            error(json!({ "kind": "Group", "children": [{ "tag": "Comma", "value": ",", "pos": 0, "line": 1 }] })),
+            ImportError { path: "/children/0".into(), message: "`expr` of Group can't be a Comma token".into() },
+        );
+        assert_eq!(
+            error(json!({ "kind": "StmtList", "children": [{ "kind": "ParamList", "children": null }] })).message,
+            "`items` of StmtList can't be a ParamList node",
+        );
+        assert_eq!(
+            error(json!({ "kind": "Prefix", "children": [{ "tag": "Minus", "value": "-", "pos": 0, "line": 1 }, { "kind": "Any" }] })).message,
+            "`right` of Prefix can't be a null node",
+        );
+        assert_eq!(error(json!({ "kind": "Any", "children": [{ "tag": null }] })).message, "expected no children for Any, found 1");
+    }
+}
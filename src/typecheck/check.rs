@@ -0,0 +1,783 @@
+//! Type checking of a single program
+//!
+//! Every expression is assigned a type. Types are kept in a side table keyed by
+//! the address of the expression's node or token, so later stages such as code
+//! generation can look them up from the same tree.
+
+use std::fmt::{self, Display};
+
+use hashbrown::HashMap;
+use indexmap::IndexMap;
+
+use crate::ast2::{
+    AstNode, AstToken, BlockExpr, CallExpr, Expr, Ident, IfAlt, IfExpr, Node, Stmt, TopDeclList,
+    TopLevelDecl, TypeExpr, VarDecl,
+};
+use crate::errors::Loc;
+use crate::token::{Tag, Token};
+
+use super::{Env, Type};
+
+#[derive(Debug, PartialEq)]
+pub enum TypeErrorKind {
+    Mismatch { expected: Type, found: Type },
+    UndefinedName,
+    UnknownType,
+    /// A struct which contains itself
+    RecursiveType,
+    DuplicateDefinition,
+    /// A type or builtin used where a value is expected
+    NotAValue,
+    NotCallable,
+    ArgumentCount { expected: usize, found: usize },
+    /// Operator not defined for the operand type
+    InvalidOperand(Type),
+    NotIndexable(Type),
+    UnknownField,
+    MissingField(Box<str>),
+    NotAssignable,
+    BreakOutsideLoop,
+    /// A function with a return type whose body may finish without returning
+    MissingReturn,
+    /// A number literal too large for its type
+    LiteralOutOfRange(Type),
+    /// Constructs which parse but cannot be checked yet, e.g. enums, tuples and methods
+    Unsupported,
+}
+
+#[derive(Debug)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+    pub location: Loc,
+}
+
+impl Display for TypeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TypeErrorKind::*;
+        match self {
+            Mismatch { expected, found } => write!(f, "expected `{}`, found `{}`", expected, found),
+            UndefinedName => write!(f, "undefined name"),
+            UnknownType => write!(f, "unknown type"),
+            RecursiveType => write!(f, "struct contains itself"),
+            DuplicateDefinition => write!(f, "name is already defined"),
+            NotAValue => write!(f, "expected a value"),
+            NotCallable => write!(f, "not a function"),
+            ArgumentCount { expected, found } => {
+                write!(f, "expected {} argument(s), found {}", expected, found)
+            }
+            InvalidOperand(typ) => write!(f, "operator cannot be applied to `{}`", typ),
+            NotIndexable(typ) => write!(f, "`{}` cannot be indexed", typ),
+            UnknownField => write!(f, "no such field"),
+            MissingField(name) => write!(f, "missing field `{}`", name),
+            NotAssignable => write!(f, "only variables may be assigned to"),
+            BreakOutsideLoop => write!(f, "`break` or `continue` outside of a loop"),
+            MissingReturn => write!(f, "function may finish without returning a value"),
+            LiteralOutOfRange(typ) => write!(f, "literal out of range for `{}`", typ),
+            Unsupported => write!(f, "not supported by the type checker yet"),
+        }
+    }
+}
+
+impl TypeErrorKind {
+    /// Stable code of the error, explained by `haze explain`
+    pub fn code(&self) -> &'static str {
+        use TypeErrorKind::*;
+        match self {
+            Mismatch { .. } => "E0100",
+            UndefinedName => "E0101",
+            UnknownType => "E0102",
+            RecursiveType => "E0103",
+            DuplicateDefinition => "E0104",
+            NotAValue => "E0105",
+            NotCallable => "E0106",
+            ArgumentCount { .. } => "E0107",
+            InvalidOperand(_) => "E0108",
+            NotIndexable(_) => "E0109",
+            UnknownField => "E0110",
+            MissingField(_) => "E0111",
+            NotAssignable => "E0112",
+            BreakOutsideLoop => "E0113",
+            MissingReturn => "E0114",
+            Unsupported => "E0115",
+            LiteralOutOfRange(_) => "E0116",
+        }
+    }
+}
+
+/// Key of an expression in the type table
+fn expr_key(expr: &Expr) -> usize {
+    match expr {
+        Expr::Ident(ident) => ident.token() as *const Token as usize,
+        Expr::Str(string) => string.token() as *const Token as usize,
+        Expr::Int(int) => int.token() as *const Token as usize,
+        Expr::Bool(boolean) => boolean.token() as *const Token as usize,
+        _ => expr.node() as *const Node as usize,
+    }
+}
+
+pub fn expr_loc(expr: &Expr) -> Loc {
+    match expr {
+        Expr::Ident(ident) => Loc::from_token(*ident.token()),
+        Expr::Str(string) => Loc::from_token(*string.token()),
+        Expr::Int(int) => Loc::from_token(*int.token()),
+        Expr::Bool(boolean) => Loc::from_token(*boolean.token()),
+        _ => Loc::from_node(expr.node()),
+    }
+}
+
+fn type_expr_loc(type_expr: &TypeExpr) -> Loc {
+    match type_expr {
+        TypeExpr::Ident(ident) => Loc::from_token(*ident.token()),
+        _ => Loc::from_node(type_expr.node()),
+    }
+}
+
+/// Whether a number literal has a value of type `typ`, which the backends
+/// count on
+fn literal_fits(text: &str, typ: &Type) -> bool {
+    match typ {
+        Type::U32 => text.parse::<u32>().is_ok(),
+        Type::U64 => text.parse::<u64>().is_ok(),
+        Type::I32 => text.parse::<i32>().is_ok(),
+        Type::I64 => text.parse::<i64>().is_ok(),
+        Type::F32 => text.parse::<f32>().is_ok_and(f32::is_finite),
+        Type::F64 => text.parse::<f64>().is_ok_and(f64::is_finite),
+        _ => true,
+    }
+}
+
+/// Type of an integer literal without an expected type
+const DEFAULT_INT: Type = Type::I32;
+/// Type of a float literal without an expected type
+const DEFAULT_FLOAT: Type = Type::F64;
+
+#[derive(Debug, Default)]
+pub struct TypeChecker<'s> {
+    env: Env<'s>,
+    /// Types of expressions and variable declarations
+    types: HashMap<usize, Type>,
+    /// Return type of the function being checked
+    return_type: Option<Type>,
+    loop_depth: u32,
+    pub errors: Vec<TypeError>,
+}
+
+impl<'s, 'b> TypeChecker<'s> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Type of an expression of the checked program
+    pub fn type_of(&self, expr: &Expr<'s, 'b>) -> Option<&Type> {
+        self.types.get(&expr_key(expr))
+    }
+
+    /// Type of the variable introduced by a declaration
+    pub fn type_of_var(&self, var_decl: &VarDecl<'s, 'b>) -> Option<&Type> {
+        self.types.get(&(var_decl.node() as *const Node as usize))
+    }
+
+    /// Type of a function, constant, struct or type alias
+    pub fn global(&self, name: &str) -> Option<&Type> {
+        self.env.global_scope.get(name)
+    }
+
+    pub fn check_program(&mut self, decls: TopDeclList<'s, 'b>) {
+        for decl in decls.items() {
+            let (name, registered) = match &decl {
+                TopLevelDecl::Fn(fn_def) => (fn_def.name(), self.env.register_function(fn_def.clone())),
+                TopLevelDecl::Struct(struct_decl) => (struct_decl.name(), self.env.register_struct(struct_decl.clone())),
+                TopLevelDecl::Const(const_decl) => (const_decl.name(), self.env.register_const(const_decl.clone())),
+                TopLevelDecl::Type(type_alias) => (type_alias.name(), self.env.register_type_alias(type_alias.clone())),
+                TopLevelDecl::Enum(enum_decl) => {
+                    self.error(TypeErrorKind::Unsupported, Loc::from_token(*enum_decl.name().token()));
+                    continue;
+                }
+                // Modules are checked through the module graph
+                TopLevelDecl::Mod(_) | TopLevelDecl::Import(_) => continue,
+            };
+            if !registered {
+                self.error(TypeErrorKind::DuplicateDefinition, Loc::from_token(*name.token()));
+            }
+        }
+        self.env.resolve_globals();
+
+        for decl in decls.items() {
+            match decl {
+                TopLevelDecl::Fn(fn_def) => {
+                    let mut params = IndexMap::new();
+                    for param in fn_def.params().items() {
+                        let param_type = self.lower(&param.param_type());
+                        params.insert(param.ident().token().value, param_type);
+                    }
+                    let return_type = fn_def.return_type()
+                        .map(|ret| self.lower(&ret))
+                        .unwrap_or(Type::Unit);
+
+                    let Some(body) = fn_def.body() else { continue };
+                    self.env.local_scopes.push(params);
+                    self.return_type = Some(return_type.clone());
+                    let body_type = self.block(body);
+                    self.return_type = None;
+                    self.env.local_scopes.pop();
+
+                    if return_type != Type::Unit && !return_type.is_error() && body_type != Type::Never {
+                        self.error(TypeErrorKind::MissingReturn, Loc::from_token(*fn_def.name().token()));
+                    }
+                }
+                TopLevelDecl::Struct(struct_decl) => {
+                    let mut seen = Vec::new();
+                    for field in struct_decl.fields().items() {
+                        self.lower(&field.field_type());
+                        let name = field.name().token().value;
+                        if seen.contains(&name) {
+                            self.error(TypeErrorKind::DuplicateDefinition, Loc::from_token(*field.name().token()));
+                        }
+                        seen.push(name);
+                    }
+                }
+                TopLevelDecl::Const(const_decl) => {
+                    let const_type = self.lower(&const_decl.const_type());
+                    self.expect_expr(const_decl.value(), &const_type);
+                }
+                TopLevelDecl::Type(type_alias) => { self.lower(&type_alias.type_expr()); }
+                _ => {}
+            }
+        }
+    }
+
+    /// Converts a type annotation, reporting names which do not resolve
+    fn lower(&mut self, type_expr: &TypeExpr<'s, 'b>) -> Type {
+        let typ = self.env.resolve(&type_expr.into(), &mut Vec::new());
+        self.report_unresolved(&typ, type_expr);
+        match typ {
+            Type::Alias(inner) => *inner,
+            typ => typ,
+        }
+    }
+
+    fn report_unresolved(&mut self, typ: &Type, type_expr: &TypeExpr<'s, 'b>) {
+        match typ {
+            Type::Unresolved(name) => {
+                let kind = match self.env.global_scope.get(&**name) {
+                    Some(Type::Struct(..)) | Some(Type::Alias(_)) => TypeErrorKind::RecursiveType,
+                    _ => TypeErrorKind::UnknownType,
+                };
+                self.error(kind, type_expr_loc(type_expr));
+            }
+            Type::Error => self.error(TypeErrorKind::UnknownType, type_expr_loc(type_expr)),
+            Type::Array(elem, _) => self.report_unresolved(elem, type_expr),
+            Type::Struct(_, fields) => {
+                for (_, field) in fields.iter() {
+                    self.report_unresolved(field, type_expr);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn error(&mut self, kind: TypeErrorKind, location: Loc) {
+        self.errors.push(TypeError { kind, location });
+    }
+
+    /// Reports a mismatch unless `found` may be used where `expected` is required
+    fn coerce(&mut self, found: &Type, expected: &Type, location: Loc) {
+        let compatible = found == expected
+            || *found == Type::Never
+            || found.is_error()
+            || expected.is_error();
+        if !compatible {
+            self.error(TypeErrorKind::Mismatch { expected: expected.clone(), found: found.clone() }, location);
+        }
+    }
+
+    fn expect_expr(&mut self, expr: Expr<'s, 'b>, expected: &Type) -> Type {
+        let found = self.expr(expr.clone(), Some(expected));
+        self.coerce(&found, expected, expr_loc(&expr));
+        found
+    }
+
+    fn lookup_local(&self, name: &str) -> Option<&Type> {
+        self.env.local_scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn block(&mut self, block: BlockExpr<'s, 'b>) -> Type {
+        self.env.local_scopes.push(IndexMap::new());
+        let mut diverges = false;
+        for stmt in block.body().items() {
+            let stmt_type = match stmt {
+                Stmt::VarDecl(var_decl) => self.var_decl(var_decl),
+                Stmt::ExprStmt(expr_stmt) => self.expr(expr_stmt.expr(), None),
+                Stmt::EmptyStmt(_) => Type::Unit,
+            };
+            diverges |= stmt_type == Type::Never;
+        }
+        self.env.local_scopes.pop();
+        if diverges { Type::Never } else { Type::Unit }
+    }
+
+    fn var_decl(&mut self, var_decl: VarDecl<'s, 'b>) -> Type {
+        let annotation = var_decl.var_type().map(|var_type| self.lower(&var_type));
+        let value_type = var_decl.value().map(|value| match &annotation {
+            Some(annotation) => self.expect_expr(value, annotation),
+            None => self.expr(value, None),
+        });
+
+        let var_type = annotation.or(value_type.clone()).unwrap_or(Type::Error);
+        self.types.insert(var_decl.node() as *const Node as usize, var_type.clone());
+        // Bound after the initializer so `let x = x;` refers to an outer `x`
+        self.env.local_scopes.last_mut()
+            .expect("variables are declared inside a block")
+            .insert(var_decl.name().token().value, var_type);
+
+        match value_type {
+            Some(Type::Never) => Type::Never,
+            _ => Type::Unit,
+        }
+    }
+
+    /// Checks an expression and records its type.
+    ///
+    /// The expected type only guides literals, the caller compares the result.
+    fn expr(&mut self, expr: Expr<'s, 'b>, expected: Option<&Type>) -> Type {
+        let typ = self.expr_inner(expr.clone(), expected);
+        self.types.insert(expr_key(&expr), typ.clone());
+        typ
+    }
+
+    fn expr_inner(&mut self, expr: Expr<'s, 'b>, expected: Option<&Type>) -> Type {
+        let location = expr_loc(&expr);
+        match expr {
+            Expr::Ident(ident) => self.value(ident),
+            Expr::Str(_) => Type::String,
+            Expr::Bool(_) => Type::Bool,
+            Expr::Int(int) => {
+                let text = int.token().value;
+                let is_float = text.contains('.');
+                let typ = match expected {
+                    Some(typ) if typ.is_float() => typ.clone(),
+                    Some(typ) if typ.is_integer() && !is_float => typ.clone(),
+                    _ if is_float => DEFAULT_FLOAT,
+                    _ => DEFAULT_INT,
+                };
+                if !literal_fits(text, &typ) {
+                    self.error(TypeErrorKind::LiteralOutOfRange(typ.clone()), Loc::from_token(*int.token()));
+                }
+                typ
+            }
+            Expr::Group(group) => self.expr(group.expr(), expected),
+            Expr::Infix(infix) => {
+                let op = infix.op().tag;
+                let (left, right) = (infix.left(), infix.right());
+                let operand_hint = match op {
+                    Tag::Plus | Tag::Minus | Tag::Asterisk | Tag::Slash => expected,
+                    _ => None,
+                };
+
+                // A literal on the left takes the type of the other operand, e.g. `1 + x`
+                let (left_type, right_type) = if matches!(left, Expr::Int(_)) && !matches!(right, Expr::Int(_)) {
+                    let right_type = self.expr(right.clone(), operand_hint);
+                    (self.expr(left.clone(), Some(&right_type)), right_type)
+                } else {
+                    let left_type = self.expr(left.clone(), operand_hint);
+                    (left_type.clone(), self.expr(right.clone(), Some(&left_type)))
+                };
+                self.coerce(&right_type, &left_type, expr_loc(&right));
+
+                let operand = if left_type == Type::Never { right_type } else { left_type };
+                if operand.is_error() {
+                    return match op {
+                        Tag::Plus | Tag::Minus | Tag::Asterisk | Tag::Slash => Type::Error,
+                        _ => Type::Bool,
+                    };
+                }
+                let valid = match op {
+                    Tag::Plus | Tag::Minus | Tag::Asterisk | Tag::Slash => operand.is_numeric(),
+                    Tag::EqualEqual | Tag::BangEqual => operand.is_primitive(),
+                    Tag::Less | Tag::LessEqual | Tag::Greater | Tag::GreaterEqual => operand.is_numeric(),
+                    _ => false,
+                };
+                if !valid {
+                    self.error(TypeErrorKind::InvalidOperand(operand.clone()), Loc::from_token(*infix.op()));
+                }
+                match op {
+                    Tag::Plus | Tag::Minus | Tag::Asterisk | Tag::Slash if valid => operand,
+                    Tag::Plus | Tag::Minus | Tag::Asterisk | Tag::Slash => Type::Error,
+                    _ => Type::Bool,
+                }
+            }
+            Expr::Prefix(prefix) => {
+                let op = prefix.op().tag;
+                let hint = if op == Tag::Bang { Some(&Type::Bool) } else { expected };
+                let operand = self.expr(prefix.right(), hint);
+                let valid = operand.is_error() || match op {
+                    Tag::Minus => operand.is_signed(),
+                    Tag::Bang => operand == Type::Bool,
+                    _ => false,
+                };
+                if !valid {
+                    self.error(TypeErrorKind::InvalidOperand(operand.clone()), Loc::from_token(*prefix.op()));
+                    return Type::Error;
+                }
+                operand
+            }
+            Expr::CallExpr(call) => self.call(call),
+            Expr::AssignExpr(assign) => {
+                let Expr::Ident(name) = assign.target() else {
+                    self.expr(assign.target(), None);
+                    self.error(TypeErrorKind::NotAssignable, expr_loc(&assign.target()));
+                    self.expr(assign.value(), None);
+                    return Type::Unit;
+                };
+                match self.lookup_local(name.token().value).cloned() {
+                    Some(var_type) => { self.expect_expr(assign.value(), &var_type); }
+                    None => {
+                        let kind = if self.global(name.token().value).is_some() {
+                            TypeErrorKind::NotAssignable
+                        } else {
+                            TypeErrorKind::UndefinedName
+                        };
+                        self.error(kind, Loc::from_token(*name.token()));
+                        self.expr(assign.value(), None);
+                    }
+                }
+                Type::Unit
+            }
+            Expr::ReturnExpr(return_expr) => {
+                let return_type = self.return_type.clone().unwrap_or(Type::Unit);
+                match return_expr.value() {
+                    Some(value) => { self.expect_expr(value, &return_type); }
+                    None => self.coerce(&Type::Unit, &return_type, location),
+                }
+                Type::Never
+            }
+            Expr::BreakExpr(break_expr) => {
+                if self.loop_depth == 0 {
+                    self.error(TypeErrorKind::BreakOutsideLoop, location);
+                }
+                if let Some(value) = break_expr.value() {
+                    self.error(TypeErrorKind::Unsupported, expr_loc(&value));
+                    self.expr(value, None);
+                }
+                Type::Never
+            }
+            Expr::ContinueExpr(_) => {
+                if self.loop_depth == 0 {
+                    self.error(TypeErrorKind::BreakOutsideLoop, location);
+                }
+                Type::Never
+            }
+            Expr::ArrayExpr(array) => {
+                let mut elem = match expected {
+                    Some(Type::Array(elem, _)) => Some((**elem).clone()),
+                    _ => None,
+                };
+                let mut len = 0;
+                for item in array.items() {
+                    match &elem {
+                        Some(elem) => { self.expect_expr(item, elem); }
+                        None => elem = Some(self.expr(item, None)),
+                    }
+                    len += 1;
+                }
+                Type::Array(Box::new(elem.unwrap_or(Type::Error)), len)
+            }
+            Expr::IndexExpr(index) => {
+                let container = self.expr(index.container(), None);
+                let index_expr = index.index();
+                let index_type = self.expr(index_expr.clone(), Some(&Type::U64));
+                if !index_type.is_integer() && !index_type.is_error() {
+                    self.error(TypeErrorKind::InvalidOperand(index_type), expr_loc(&index_expr));
+                }
+                match container {
+                    Type::Array(elem, _) => *elem,
+                    typ if typ.is_error() => Type::Error,
+                    typ => {
+                        self.error(TypeErrorKind::NotIndexable(typ), expr_loc(&index.container()));
+                        Type::Error
+                    }
+                }
+            }
+            Expr::FieldAccessExpr(access) => {
+                let parent = self.expr(access.parent(), None);
+                let field_name = access.field_name();
+                match &parent {
+                    Type::Struct(_, fields) => {
+                        match fields.iter().find(|(name, _)| **name == *field_name.token().value) {
+                            Some((_, field_type)) => field_type.clone(),
+                            None => {
+                                self.error(TypeErrorKind::UnknownField, Loc::from_token(*field_name.token()));
+                                Type::Error
+                            }
+                        }
+                    }
+                    typ if typ.is_error() => Type::Error,
+                    _ => {
+                        self.error(TypeErrorKind::UnknownField, Loc::from_token(*field_name.token()));
+                        Type::Error
+                    }
+                }
+            }
+            Expr::StructExpr(struct_expr) => {
+                let name = struct_expr.name();
+                let struct_type = match self.global(name.token().value) {
+                    Some(typ @ Type::Struct(..)) => typ.clone(),
+                    Some(Type::Alias(inner)) if matches!(**inner, Type::Struct(..)) => (**inner).clone(),
+                    _ => {
+                        self.error(TypeErrorKind::UnknownType, Loc::from_token(*name.token()));
+                        for field_init in struct_expr.fields().items() {
+                            self.expr(field_init.value(), None);
+                        }
+                        return Type::Error;
+                    }
+                };
+                let Type::Struct(_, fields) = &struct_type else { unreachable!() };
+
+                let mut initialized = Vec::new();
+                for field_init in struct_expr.fields().items() {
+                    let field_name = field_init.name();
+                    let field_name_str = field_name.token().value;
+                    match fields.iter().find(|(name, _)| **name == *field_name_str) {
+                        Some((_, field_type)) => { self.expect_expr(field_init.value(), field_type); }
+                        None => {
+                            self.error(TypeErrorKind::UnknownField, Loc::from_token(*field_name.token()));
+                            self.expr(field_init.value(), None);
+                        }
+                    }
+                    if initialized.contains(&field_name_str) {
+                        self.error(TypeErrorKind::DuplicateDefinition, Loc::from_token(*field_name.token()));
+                    }
+                    initialized.push(field_name_str);
+                }
+                for (field, _) in fields.iter() {
+                    if !initialized.contains(&&**field) {
+                        self.error(TypeErrorKind::MissingField(field.clone()), Loc::from_token(*name.token()));
+                    }
+                }
+                struct_type
+            }
+            Expr::IfExpr(if_expr) => self.if_expr(if_expr),
+            Expr::WhileExpr(while_expr) => {
+                self.expect_expr(while_expr.condition(), &Type::Bool);
+                self.loop_depth += 1;
+                self.block(while_expr.consequence());
+                self.loop_depth -= 1;
+                Type::Unit
+            }
+            Expr::BlockExpr(block) => self.block(block),
+            Expr::MethodCall(_) | Expr::TupleExpr(_) => {
+                self.error(TypeErrorKind::Unsupported, location);
+                Type::Error
+            }
+        }
+    }
+
+    /// Type of a name used as a value
+    fn value(&mut self, ident: Ident<'s, 'b>) -> Type {
+        let name = ident.token().value;
+        let location = Loc::from_token(*ident.token());
+        if let Some(local) = self.lookup_local(name) {
+            return local.clone();
+        }
+        match self.global(name) {
+            Some(Type::Const(inner)) => (**inner).clone(),
+            Some(typ @ Type::Fn(..)) => typ.clone(),
+            Some(_) => {
+                self.error(TypeErrorKind::NotAValue, location);
+                Type::Error
+            }
+            None if BUILTINS.iter().any(|builtin| builtin.0 == name) => {
+                self.error(TypeErrorKind::NotAValue, location);
+                Type::Error
+            }
+            None => {
+                self.error(TypeErrorKind::UndefinedName, location);
+                Type::Error
+            }
+        }
+    }
+
+    fn call(&mut self, call: CallExpr<'s, 'b>) -> Type {
+        let name = call.name();
+        let location = Loc::from_token(*name.token());
+        let args: Vec<_> = call.args().args().collect();
+
+        let callee = match self.lookup_local(name.token().value).or_else(|| self.global(name.token().value)) {
+            Some(typ) => typ.clone(),
+            None => match BUILTINS.iter().find(|builtin| builtin.0 == name.token().value) {
+                Some((_, param, ret)) => {
+                    if args.len() != 1 {
+                        self.error(TypeErrorKind::ArgumentCount { expected: 1, found: args.len() }, location);
+                    }
+                    for arg in args {
+                        let arg_type = self.expr(arg.clone(), param.as_ref());
+                        match param {
+                            Some(param) => self.coerce(&arg_type, param, expr_loc(&arg)),
+                            // Any primitive may be printed
+                            None if !arg_type.is_primitive() && !arg_type.is_error() => {
+                                self.error(TypeErrorKind::InvalidOperand(arg_type), expr_loc(&arg));
+                            }
+                            None => {}
+                        }
+                    }
+                    return ret.clone();
+                }
+                None => {
+                    self.error(TypeErrorKind::UndefinedName, location);
+                    for arg in args { self.expr(arg, None); }
+                    return Type::Error;
+                }
+            },
+        };
+
+        match callee {
+            Type::Fn(params, ret) => {
+                if params.len() != args.len() {
+                    self.error(TypeErrorKind::ArgumentCount { expected: params.len(), found: args.len() }, location);
+                }
+                for (i, arg) in args.into_iter().enumerate() {
+                    match params.get(i) {
+                        Some(param) => { self.expect_expr(arg, param); }
+                        None => { self.expr(arg, None); }
+                    }
+                }
+                *ret
+            }
+            typ if typ.is_error() => Type::Error,
+            _ => {
+                self.error(TypeErrorKind::NotCallable, location);
+                for arg in args { self.expr(arg, None); }
+                Type::Error
+            }
+        }
+    }
+
+    fn if_expr(&mut self, if_expr: IfExpr<'s, 'b>) -> Type {
+        self.expect_expr(if_expr.condition(), &Type::Bool);
+        let consequence = self.block(if_expr.consequence());
+        let alternate = match if_expr.alternate() {
+            Some(IfAlt::Else(block)) => self.block(block),
+            Some(IfAlt::ElseIf(else_if)) => {
+                let typ = self.if_expr(else_if.clone());
+                self.types.insert(else_if.node() as *const Node as usize, typ.clone());
+                typ
+            }
+            None => Type::Unit,
+        };
+        if consequence == Type::Never && alternate == Type::Never { Type::Never } else { Type::Unit }
+    }
+}
+
+/// Builtin functions with their parameter and return types.
+///
+/// Each takes exactly one argument, `None` accepts any primitive.
+const BUILTINS: &[(&str, Option<Type>, Type)] = &[
+    ("print", None, Type::Unit),
+    ("input", Some(Type::String), Type::String),
+    ("int", Some(Type::String), Type::I64),
+];
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::parser3::Parser;
+
+    fn check(source: &str) -> Vec<TypeErrorKind> {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        checker.errors.into_iter().map(|err| err.kind).collect()
+    }
+
+    #[test]
+    fn test_well_typed() {
+        let errors = check(r#"
+struct Point { x: i64, y: i64 }
+type Grid = [Point; 2];
+const ORIGIN: i64 = 0;
+
+fn dist(p: Point) -> i64 {
+    return p.x + p.y - ORIGIN;
+}
+
+fn main() {
+    let grid: Grid = [.Point { x: 1, y: 2 }, .Point { x: 3, y: 4 }];
+    let total: i64 = 0;
+    let i: u64 = 0;
+    while i < 2 {
+        let p = grid[i];
+        total = total + dist(p);
+        i = i + 1;
+    }
+    if total > 5 { print("big"); } else if total == 5 { print(total); }
+}
+"#);
+        assert_eq!(errors, vec![]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let errors = check(r#"
+struct Node { next: Node }
+
+fn f(a: i32) -> i32 {
+    if a > 0 { return a; }
+}
+
+fn main() {
+    let s = "text";
+    let b = s + 1;
+    f(true, 2);
+    g();
+    break;
+    let p = .Missing { };
+}
+"#);
+        assert_eq!(errors, vec![
+            TypeErrorKind::RecursiveType,
+            TypeErrorKind::MissingReturn,
+            TypeErrorKind::Mismatch { expected: Type::String, found: Type::I32 },
+            TypeErrorKind::InvalidOperand(Type::String),
+            TypeErrorKind::ArgumentCount { expected: 1, found: 2 },
+            TypeErrorKind::Mismatch { expected: Type::I32, found: Type::Bool },
+            TypeErrorKind::UndefinedName,
+            TypeErrorKind::BreakOutsideLoop,
+            TypeErrorKind::UnknownType,
+        ]);
+
+        let errors = check("fn main() {\n    let a = 2147483648;\n    let b: i64 = 9223372036854775807;\n    let c: u32 = 4294967296;\n}");
+        assert_eq!(errors, vec![TypeErrorKind::LiteralOutOfRange(Type::I32), TypeErrorKind::LiteralOutOfRange(Type::U32)]);
+    }
+
+    #[test]
+    fn test_assignment_targets() {
+        let errors = check(r#"
+struct Point { x: i64, y: i64 }
+const LIMIT: i64 = 10;
+
+fn main() {
+    let p = .Point { x: 1, y: 2 };
+    let t = [1, 2];
+    let a: i64 = 0;
+    a = p.x;
+    p.x = 2;
+    t[0] = 3;
+    LIMIT = 4;
+    p.z = true;
+}
+"#);
+        assert_eq!(errors, vec![
+            TypeErrorKind::NotAssignable,
+            TypeErrorKind::NotAssignable,
+            TypeErrorKind::NotAssignable,
+            TypeErrorKind::UnknownField,
+            TypeErrorKind::NotAssignable,
+        ]);
+    }
+}
@@ -1,9 +1,15 @@
+
 // use std::collections::{HashMap, HashSet};
 
+pub mod check;
+pub mod resolve;
+
+use std::fmt::{self, Display};
+
 use indexmap::IndexMap;
 use hashbrown::HashMap;
 
-use crate::{ast2::{self, AstToken, Ident, TypeExpr}, visitor::{Visitor, Walker}};
+use crate::ast2::{self, AstToken, Ident, TypeExpr};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
@@ -16,7 +22,7 @@ pub enum Type {
     F64,
 
     String,
-    Array(Box<Type>, u8),
+    Array(Box<Type>, u32),
 
     Unit,
     Never, 
@@ -24,10 +30,15 @@ pub enum Type {
     Const(Box<Type>),
     Fn(Box<[Type]>, Box<Type>),
 
-    Struct(Box<[(Box<str>, Type)]>),
-    TypeAlias(Box<Type>),
+    /// Structs are nominal, two structs with the same fields are distinct types
+    Struct(Box<str>, Box<[(Box<str>, Type)]>),
+    Alias(Box<Type>),
 
-    Unresolved(Box<str>)
+    Unresolved(Box<str>),
+
+    /// Type of an expression that failed to check. 
+    /// It is compatible with every type so one mistake is reported once.
+    Error,
 }
 
 #[derive(Debug, Default)]
@@ -38,82 +49,172 @@ struct Env<'s> {
 }
 
 impl<'s, 'b> Env<'s> {
-    fn new() -> Self { 
-        Self::default()
+    /// Returns false if the name was already taken
+    fn register(&mut self, key: Ident<'s, 'b>, typ: Type) -> bool { 
+        self.global_scope.insert(key.token().value, typ).is_none()
     }
 
-    fn register(&mut self, key: &'b ast2::Ident<'s, 'b>, typ: Type) { self.global_scope.insert(key.token().value, typ); }
-
-    fn register_function(&mut self, node: ast2::FnDef<'s, 'b>) {
+    fn register_function(&mut self, node: ast2::FnDef<'s, 'b>) -> bool {
         let param_types: Box<[Type]> = node.params()
             .items()
             .map(|param| (&param.param_type()).into())
             .collect();
-        let mut return_type = Box::new(node.return_type().map(|ret_type| (&ret_type).into()).unwrap_or(Type::Unit));
+        let return_type = Box::new(node.return_type().map(|ret_type| (&ret_type).into()).unwrap_or(Type::Unit));
 
-        self.register(&node.name(), Type::Fn(param_types, return_type));
+        self.register(node.name(), Type::Fn(param_types, return_type))
     }
 
-    fn register_struct(&mut self, node: ast2::StructDecl<'s, 'b>) {
+    fn register_struct(&mut self, node: ast2::StructDecl<'s, 'b>) -> bool {
         let fields: Box<[(Box<str>, Type)]> = node.fields()
             .items()
             .map(|field| (field.name().token().value.into(), (&field.field_type()).into()))
             .collect();
-        self.register(&node.name(), Type::Struct(fields));
+        self.register(node.name(), Type::Struct(node.name().token().value.into(), fields))
     }
 
-    fn register_const(&mut self, node: ast2::ConstDecl<'s, 'b>) {
+    fn register_const(&mut self, node: ast2::ConstDecl<'s, 'b>) -> bool {
         let typ = (&node.const_type()).into();
-        self.register(&node.name(), Type::Const(Box::new(typ)));
+        self.register(node.name(), Type::Const(Box::new(typ)))
     }
 
-    fn register_type_alias(&mut self, node: ast2::TypeAlias<'s, 'b>) {
+    fn register_type_alias(&mut self, node: ast2::TypeAlias<'s, 'b>) -> bool {
         let typ = (&node.type_expr()).into();
-        self.register(&node.name(), Type::TypeAlias(Box::new(typ)));
+        self.register(node.name(), Type::Alias(Box::new(typ)))
     }
 
+    /// Replaces the struct and alias names in every global with the types they name.
+    /// 
+    /// Names which are unknown or part of a cycle are left unresolved.
     fn resolve_globals(&mut self) {
+        let names: Vec<&'s str> = self.global_scope.keys().copied().collect();
+        for name in names {
+            let typ = self.global_scope[name].clone();
+            let resolved = self.resolve(&typ, &mut Vec::new());
+            self.global_scope.insert(name, resolved);
+        }
+    }
 
+    fn resolve(&self, typ: &Type, visiting: &mut Vec<Box<str>>) -> Type {
+        match typ {
+            Type::Unresolved(name) => {
+                if visiting.contains(name) { return typ.clone(); }
+                let Some(global) = self.global_scope.get(&**name) else { return typ.clone() };
+                match global {
+                    Type::Struct(..) | Type::Alias(_) => {
+                        visiting.push(name.clone());
+                        let resolved = self.resolve(global, visiting);
+                        visiting.pop();
+                        match resolved {
+                            Type::Alias(inner) => *inner,
+                            resolved => resolved,
+                        }
+                    }
+                    _ => typ.clone(),
+                }
+            }
+            Type::Array(elem, len) => Type::Array(Box::new(self.resolve(elem, visiting)), *len),
+            Type::Const(inner) => Type::Const(Box::new(self.resolve(inner, visiting))),
+            Type::Alias(inner) => Type::Alias(Box::new(self.resolve(inner, visiting))),
+            Type::Fn(params, ret) => Type::Fn(
+                params.iter().map(|param| self.resolve(param, visiting)).collect(),
+                Box::new(self.resolve(ret, visiting)),
+            ),
+            Type::Struct(name, fields) => {
+                visiting.push(name.clone());
+                let fields = fields.iter()
+                    .map(|(field, typ)| (field.clone(), self.resolve(typ, visiting)))
+                    .collect();
+                visiting.pop();
+                Type::Struct(name.clone(), fields)
+            }
+            _ => typ.clone(),
+        }
     }
 }
 
+impl Type {
+    /// Name of the type in generated C
+    /// 
+    /// Structs and arrays must be defined by the generator before the name is used.
+    pub fn as_c_literal(&self) -> String {
+        match self {
+            Type::Bool => "bool".into(),
+            Type::U32 => "uint32_t".into(),
+            Type::U64 => "uint64_t".into(),
+            Type::I32 => "int32_t".into(),
+            Type::I64 => "int64_t".into(),
+            Type::F32 => "float".into(),
+            Type::F64 => "double".into(),
+            Type::String => "const char*".into(),
+            Type::Unit | Type::Never => "void".into(),
+            Type::Struct(name, _) => format!("hz_{}", name),
+            Type::Array(..) => format!("haze_array_{}", self.mangle()),
+            Type::Const(inner) | Type::Alias(inner) => inner.as_c_literal(),
+            Type::Fn(..) | Type::Unresolved(_) | Type::Error => unreachable!("no C representation for {}", self),
+        }
+    }
 
+    /// A name for the type made of identifier characters only
+    fn mangle(&self) -> String {
+        match self {
+            Type::Array(elem, len) => format!("{}_{}", len, elem.mangle()),
+            Type::Struct(name, _) => name.to_string(),
+            Type::Const(inner) | Type::Alias(inner) => inner.mangle(),
+            _ => self.to_string(),
+        }
+    }
 
-impl Type {
-    // pub fn as_c_literal(&self) -> String {
-    //     match self {
-    //         Bool => "bool".into(),
-    //         U32 => "uint32_t".into(),
-    //         F32 => "float".into(),
-    //         F64 => "double".into(),
-    //         String => 
-    //     }
-    // }
-
-    pub fn from_type_expr<'a, 's, 'b>(expr: &'a TypeExpr<'s, 'b>) -> Type {
-        match expr {
-            TypeExpr::Ident(ident) => {
-                match ident.token().value {
-                    "bool" => Type::Bool,
-                    "u32" => Type::U32,
-                    "u64" => Type::U64,
-                    "i32" => Type::I32,
-                    "i64" => Type::I64,
-                    "f32" => Type::F32,
-                    "f64" => Type::F64,
-                    "string" => Type::String,
-                    _ => Type::Unresolved(ident.token().value.into())
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::U32 | Type::U64 | Type::I32 | Type::I64)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::I32 | Type::I64) || self.is_float()
+    }
+
+    /// Bool, numbers and strings
+    pub fn is_primitive(&self) -> bool {
+        matches!(self, Type::Bool | Type::String) || self.is_numeric()
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Type::Error | Type::Unresolved(_))
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bool => write!(f, "bool"),
+            Type::U32 => write!(f, "u32"),
+            Type::U64 => write!(f, "u64"),
+            Type::I32 => write!(f, "i32"),
+            Type::I64 => write!(f, "i64"),
+            Type::F32 => write!(f, "f32"),
+            Type::F64 => write!(f, "f64"),
+            Type::String => write!(f, "string"),
+            Type::Array(elem, len) => write!(f, "[{}; {}]", elem, len),
+            Type::Unit => write!(f, "()"),
+            Type::Never => write!(f, "!"),
+            Type::Const(inner) | Type::Alias(inner) => write!(f, "{}", inner),
+            Type::Fn(params, ret) => {
+                write!(f, "fn(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}", param)?;
                 }
-            },
-            TypeExpr::ArrayType(array_type) => {
-                let elem = array_type.element_type();
-                let elem_type = Type::from_type_expr(&elem);
-                Type::Array(Box::new(elem_type), array_type.len().token().value.parse().unwrap())
+                write!(f, ") -> {}", ret)
             }
-            TypeExpr::GroupType(group_type) => {
-                Type::from_type_expr(&group_type.inner_type())
-            }
-            _ => todo!()
+            Type::Struct(name, _) | Type::Unresolved(name) => write!(f, "{}", name),
+            Type::Error => write!(f, "{{unknown}}"),
         }
     }
 }
@@ -137,12 +238,16 @@ impl<'a, 's, 'b> From<&'a TypeExpr<'s, 'b>> for Type {
             TypeExpr::ArrayType(array_type) => {
                 let elem = array_type.element_type();
                 let elem_type = (&elem).into();
-                Type::Array(Box::new(elem_type), array_type.len().token().value.parse().unwrap())
+                match array_type.len().token().value.parse() {
+                    Ok(len) => Type::Array(Box::new(elem_type), len),
+                    Err(_) => Type::Error,
+                }
             }
             TypeExpr::GroupType(group_type) => {
                 (&group_type.inner_type()).into()
             }
-            _ => todo!()
+            // Tuple and function types are not parsed yet
+            TypeExpr::TupleType(_) | TypeExpr::FnType(_) => Type::Error,
         }
     }
 }
@@ -0,0 +1,449 @@
+//! Name resolution across modules
+//!
+//! Every name used in a module must refer to a local, an item of the module,
+//! an item imported from another module or a builtin. Items and struct fields
+//! declared in another module may only be used when they are `pub`.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+
+use crate::ast2::{AstToken, BlockExpr, Expr, Ident, IfAlt, Stmt, TopLevelDecl, TypeExpr};
+use crate::errors::Loc;
+use crate::loader::{decl_name, Item, ModuleGraph, ModuleId};
+
+/// Functions available in every module without an import
+pub const BUILTIN_FUNCTIONS: &[&str] = &["print", "input", "int"];
+
+/// Primitive type names
+pub const BUILTIN_TYPES: &[&str] = &["bool", "u32", "u64", "i32", "i64", "f32", "f64", "string"];
+
+#[derive(Debug, PartialEq)]
+pub enum ResolveErrorKind {
+    UndefinedName,
+    /// An item of another module which is not `pub`
+    PrivateItem,
+    /// A field of another module's struct which is not `pub`
+    PrivateField,
+}
+
+impl fmt::Display for ResolveErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveErrorKind::UndefinedName => write!(f, "undefined name"),
+            ResolveErrorKind::PrivateItem => write!(f, "item is private to its module"),
+            ResolveErrorKind::PrivateField => write!(f, "field is private to its module"),
+        }
+    }
+}
+
+impl ResolveErrorKind {
+    /// Stable code of the error, explained by `haze explain`
+    ///
+    /// An undefined name is the same error the type checker reports.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ResolveErrorKind::UndefinedName => "E0101",
+            ResolveErrorKind::PrivateItem => "E0200",
+            ResolveErrorKind::PrivateField => "E0201",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResolveError {
+    pub kind: ResolveErrorKind,
+    /// Module in which the name is used
+    pub module: ModuleId,
+    pub location: Loc,
+    /// Declaration of the item or field which may not be accessed
+    pub declared_at: Option<(ModuleId, Loc)>,
+}
+
+/// Resolves the names used in every module of the graph
+pub fn resolve_graph<'b>(graph: &ModuleGraph<'b>) -> Vec<ResolveError> {
+    let mut errors = Vec::new();
+    for (id, _) in graph.modules() {
+        let mut resolver = Resolver::new(graph, id);
+        resolver.resolve_module();
+        errors.append(&mut resolver.errors);
+    }
+    errors
+}
+
+/// A local binding along with the struct it holds, when known
+type Scope<'b> = IndexMap<&'b str, Option<Item<'b>>>;
+
+struct Resolver<'g, 'b> {
+    graph: &'g ModuleGraph<'b>,
+    module: ModuleId,
+    /// Items of the module and public items of its imports
+    items: IndexMap<&'b str, Item<'b>>,
+    /// Private items of imported modules, kept for diagnostics
+    hidden: IndexMap<&'b str, Item<'b>>,
+    scopes: Vec<Scope<'b>>,
+    errors: Vec<ResolveError>,
+}
+
+impl<'g, 'b> Resolver<'g, 'b> {
+    fn new(graph: &'g ModuleGraph<'b>, module: ModuleId) -> Self {
+        let mut hidden = IndexMap::new();
+        for &(import, _) in graph.module(module).imports.iter() {
+            for (name, item) in graph.items(import) {
+                if !item.is_public() {
+                    hidden.entry(name).or_insert(item);
+                }
+            }
+        }
+
+        Self {
+            graph,
+            module,
+            items: graph.scope(module),
+            hidden,
+            scopes: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn resolve_module(&mut self) {
+        for decl in self.graph.module(self.module).decls.items() {
+            match decl {
+                TopLevelDecl::Fn(fn_def) => {
+                    self.scopes.push(Scope::new());
+                    for param in fn_def.params().items() {
+                        let param_type = param.param_type();
+                        self.type_expr(&param_type);
+                        let known = self.struct_of(&param_type);
+                        self.bind(param.ident(), known);
+                    }
+                    if let Some(return_type) = fn_def.return_type() {
+                        self.type_expr(&return_type);
+                    }
+                    if let Some(body) = fn_def.body() {
+                        self.block(body);
+                    }
+                    self.scopes.pop();
+                }
+                TopLevelDecl::Struct(struct_decl) => {
+                    for field in struct_decl.fields().items() {
+                        self.type_expr(&field.field_type());
+                    }
+                }
+                TopLevelDecl::Enum(enum_decl) => {
+                    for variant in enum_decl.variants().items() {
+                        if let Some(variant_type) = variant.variant_type() {
+                            self.type_name(variant_type);
+                        }
+                    }
+                }
+                TopLevelDecl::Const(const_decl) => {
+                    self.type_expr(&const_decl.const_type());
+                    self.expr(const_decl.value());
+                }
+                TopLevelDecl::Type(type_alias) => self.type_expr(&type_alias.type_expr()),
+                // Inline modules are resolved as modules of their own
+                TopLevelDecl::Mod(_) | TopLevelDecl::Import(_) => {}
+            }
+        }
+    }
+
+    fn block(&mut self, block: BlockExpr<'b, 'b>) {
+        self.scopes.push(Scope::new());
+        for stmt in block.body().items() {
+            match stmt {
+                Stmt::VarDecl(var_decl) => {
+                    let mut known = None;
+                    if let Some(var_type) = var_decl.var_type() {
+                        self.type_expr(&var_type);
+                        known = self.struct_of(&var_type);
+                    }
+                    if let Some(value) = var_decl.value() {
+                        known = self.expr(value).or(known);
+                    }
+                    // Bound after the initializer so `let x = x;` refers to an outer `x`
+                    self.bind(var_decl.name(), known);
+                }
+                Stmt::ExprStmt(expr_stmt) => { self.expr(expr_stmt.expr()); }
+                Stmt::EmptyStmt(_) => {}
+            }
+        }
+        self.scopes.pop();
+    }
+
+    /// Resolves the names in an expression, returning the struct it evaluates to when known.
+    fn expr(&mut self, expr: Expr<'b, 'b>) -> Option<Item<'b>> {
+        match expr {
+            Expr::Ident(ident) => self.value_name(ident),
+            Expr::Str(_) | Expr::Int(_) | Expr::Bool(_) | Expr::ContinueExpr(_) => None,
+            Expr::Infix(infix) => {
+                self.expr(infix.left());
+                self.expr(infix.right());
+                None
+            }
+            Expr::Prefix(prefix) => { self.expr(prefix.right()); None }
+            Expr::BreakExpr(break_expr) => {
+                if let Some(value) = break_expr.value() { self.expr(value); }
+                None
+            }
+            Expr::ReturnExpr(return_expr) => {
+                if let Some(value) = return_expr.value() { self.expr(value); }
+                None
+            }
+            Expr::Group(group) => self.expr(group.expr()),
+            Expr::ArrayExpr(array) => {
+                for item in array.items() { self.expr(item); }
+                None
+            }
+            Expr::TupleExpr(tuple) => {
+                for item in tuple.items() { self.expr(item); }
+                None
+            }
+            Expr::CallExpr(call) => {
+                self.value_name(call.name());
+                for arg in call.args().args() { self.expr(arg); }
+                self.returned_struct(call.name())
+            }
+            Expr::MethodCall(call) => {
+                self.expr(call.receiver());
+                for arg in call.args().args() { self.expr(arg); }
+                None
+            }
+            Expr::IndexExpr(index) => {
+                self.expr(index.container());
+                self.expr(index.index());
+                None
+            }
+            Expr::FieldAccessExpr(access) => {
+                if let Some(parent) = self.expr(access.parent()) {
+                    self.field(&parent, access.field_name());
+                }
+                None
+            }
+            Expr::StructExpr(struct_expr) => {
+                let item = self.type_name(struct_expr.name())
+                    .filter(|item| matches!(item.decl, TopLevelDecl::Struct(_)));
+                for field_init in struct_expr.fields().items() {
+                    if let Some(item) = &item {
+                        self.field(item, field_init.name());
+                    }
+                    self.expr(field_init.value());
+                }
+                item
+            }
+            Expr::AssignExpr(assign) => {
+                self.expr(assign.target());
+                self.expr(assign.value());
+                None
+            }
+            Expr::IfExpr(if_expr) => {
+                self.expr(if_expr.condition());
+                self.block(if_expr.consequence());
+                let mut alternate = if_expr.alternate();
+                while let Some(alt) = alternate {
+                    match alt {
+                        IfAlt::Else(block) => { self.block(block); break; }
+                        IfAlt::ElseIf(if_expr) => {
+                            self.expr(if_expr.condition());
+                            self.block(if_expr.consequence());
+                            alternate = if_expr.alternate();
+                        }
+                    }
+                }
+                None
+            }
+            Expr::WhileExpr(while_expr) => {
+                self.expr(while_expr.condition());
+                self.block(while_expr.consequence());
+                None
+            }
+            Expr::BlockExpr(block) => { self.block(block); None }
+        }
+    }
+
+    fn type_expr(&mut self, type_expr: &TypeExpr<'b, 'b>) {
+        match type_expr {
+            TypeExpr::Ident(ident) => {
+                if !BUILTIN_TYPES.contains(&ident.token().value) {
+                    self.type_name(ident.clone());
+                }
+            }
+            TypeExpr::ArrayType(array) => self.type_expr(&array.element_type()),
+            TypeExpr::GroupType(group) => self.type_expr(&group.inner_type()),
+            TypeExpr::TupleType(tuple) => tuple.items().for_each(|item| self.type_expr(&item)),
+            TypeExpr::FnType(fn_type) => fn_type.params().items().for_each(|param| self.type_expr(&param)),
+        }
+    }
+
+    fn bind(&mut self, name: Ident<'b, 'b>, known: Option<Item<'b>>) {
+        let scope = self.scopes.last_mut().expect("locals are bound inside a scope");
+        scope.insert(name.token().value, known);
+    }
+
+    /// Resolves a name used as a value
+    fn value_name(&mut self, ident: Ident<'b, 'b>) -> Option<Item<'b>> {
+        let name = ident.token().value;
+        if ident.token().is_empty() { return None; }
+
+        if let Some(known) = self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            return known.clone();
+        }
+        if BUILTIN_FUNCTIONS.contains(&name) {
+            return None;
+        }
+        self.item(ident);
+        None
+    }
+
+    /// Resolves a name used as a type, returning its item
+    fn type_name(&mut self, ident: Ident<'b, 'b>) -> Option<Item<'b>> {
+        let name = ident.token().value;
+        if ident.token().is_empty() || BUILTIN_TYPES.contains(&name) { return None; }
+        self.item(ident)
+    }
+
+    fn item(&mut self, ident: Ident<'b, 'b>) -> Option<Item<'b>> {
+        let name = ident.token().value;
+        let location = Loc::from_token(*ident.token());
+
+        if let Some(item) = self.items.get(name) {
+            return Some(item.clone());
+        }
+
+        let (kind, declared_at) = match self.hidden.get(name) {
+            Some(item) => (ResolveErrorKind::PrivateItem, Some(declaration_of(item))),
+            None => (ResolveErrorKind::UndefinedName, None),
+        };
+        self.errors.push(ResolveError { kind, module: self.module, location, declared_at });
+        None
+    }
+
+    /// Checks that a struct field may be accessed from this module
+    fn field(&mut self, item: &Item<'b>, field_name: Ident<'b, 'b>) {
+        let TopLevelDecl::Struct(struct_decl) = &item.decl else { return };
+        if item.module == self.module { return; }
+
+        let name = field_name.token().value;
+        let Some(field) = struct_decl.fields().items().find(|f| f.name().token().value == name) else {
+            return; // Unknown fields are left to the type checker
+        };
+
+        if field.visibility().is_none() {
+            self.errors.push(ResolveError {
+                kind: ResolveErrorKind::PrivateField,
+                module: self.module,
+                location: Loc::from_token(*field_name.token()),
+                declared_at: Some((item.module, Loc::from_token(*field.name().token()))),
+            });
+        }
+    }
+
+    /// The struct named by a type expression, if any
+    fn struct_of(&self, type_expr: &TypeExpr<'b, 'b>) -> Option<Item<'b>> {
+        let TypeExpr::Ident(ident) = type_expr else { return None };
+        let name = ident.token().value;
+        self.items.get(name)
+            .or_else(|| self.hidden.get(name))
+            .filter(|item| matches!(item.decl, TopLevelDecl::Struct(_)))
+            .cloned()
+    }
+
+    /// The struct returned by a call to the named function, if any
+    fn returned_struct(&self, callee: Ident<'b, 'b>) -> Option<Item<'b>> {
+        let name = callee.token().value;
+        let item = self.items.get(name).or_else(|| self.hidden.get(name))?;
+        let TopLevelDecl::Fn(fn_def) = &item.decl else { return None };
+        let TypeExpr::Ident(ident) = fn_def.return_type()? else { return None };
+
+        // The return type is named in the scope of the function's own module
+        let scope = self.graph.scope(item.module);
+        scope.get(ident.token().value)
+            .filter(|item| matches!(item.decl, TopLevelDecl::Struct(_)))
+            .cloned()
+    }
+}
+
+fn declaration_of(item: &Item<'_>) -> (ModuleId, Loc) {
+    let name = decl_name(&item.decl).expect("named items have a name");
+    (item.module, Loc::from_token(*name.token()))
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::utils::temp_project::TempProject;
+
+    const SHAPES: &str = "
+pub struct Square { pub side: i32, id: i32 }
+pub fn make(side: i32) -> Square { return .Square { side: side, id: 0 }; }
+fn secret() -> i32 { return 42; }
+";
+
+    #[test]
+    fn test_private_item() {
+        let project = TempProject::new(&[
+            ("main.hz", "import shapes;\nfn main() {\n    let x = secret();\n}"),
+            ("shapes.hz", SHAPES),
+        ]);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        graph.load_entry("main.hz").unwrap();
+        let errors = resolve_graph(&graph);
+
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        let err = &errors[0];
+        assert_eq!(err.kind, ResolveErrorKind::PrivateItem);
+        assert_eq!(err.location.line, 3);
+        let (module, decl) = err.declared_at.unwrap();
+        assert_eq!(module, graph.lookup("shapes").unwrap());
+        assert_eq!(decl.line, 4);
+    }
+
+    #[test]
+    fn test_private_fields() {
+        let project = TempProject::new(&[
+            ("main.hz", "import shapes;
+fn main() {
+    let a = .Square { side: 1, id: 2 };
+    let b = make(3);
+    let c = b.id + a.side;
+}
+fn area(s: Square) -> i32 { return s.side * s.id; }"),
+            ("shapes.hz", SHAPES),
+        ]);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        graph.load_entry("main.hz").unwrap();
+        let errors = resolve_graph(&graph);
+
+        let lines: Vec<_> = errors.iter().map(|err| (&err.kind, err.location.line)).collect();
+        assert_eq!(lines, [
+            (&ResolveErrorKind::PrivateField, 3),
+            (&ResolveErrorKind::PrivateField, 5),
+            (&ResolveErrorKind::PrivateField, 7),
+        ]);
+        assert!(errors.iter().all(|err| err.declared_at.unwrap().1.line == 2));
+    }
+
+    #[test]
+    fn test_same_module_and_undefined() {
+        let project = TempProject::new(&[("main.hz", "
+struct Point { x: i32, y: i32 }
+fn helper() -> i32 { return 1; }
+fn main() {
+    let p = .Point { x: helper(), y: 2 };
+    print(p.x + p.y);
+    missing = 3;
+}")]);
+        let bump = Bump::new();
+        let mut graph = ModuleGraph::new(project.root(), &bump);
+        graph.load_entry("main.hz").unwrap();
+        let errors = resolve_graph(&graph);
+
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].kind, ResolveErrorKind::UndefinedName);
+        assert_eq!(errors[0].location.line, 7);
+    }
+}
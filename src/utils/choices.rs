@@ -0,0 +1,73 @@
+//! Where generators take their decisions from
+//!
+//! Generators make every decision through [`Choices`], so the same code runs
+//! from a seeded [`Rng`] in tests and from the bytes of a fuzzer input, which
+//! the fuzzer then steers by mutating them.
+
+use super::rng::Rng;
+
+pub trait Choices {
+    /// A number in `0..bound`
+    fn below(&mut self, bound: usize) -> usize;
+
+    /// True with a probability of one in `n`
+    fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+impl Choices for Rng {
+    fn below(&mut self, bound: usize) -> usize {
+        Rng::below(self, bound)
+    }
+}
+
+/// Choices read from bytes, as many as a choice needs, after which every
+/// choice is the first one
+pub struct Bytes<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Bytes<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+}
+
+impl Choices for Bytes<'_> {
+    fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "empty range");
+        let mut value = 0usize;
+        let mut range = 1usize;
+        while range < bound {
+            let Some((&byte, rest)) = self.bytes.split_first() else { break };
+            self.bytes = rest;
+            value = value << 8 | byte as usize;
+            range = range.saturating_mul(256);
+        }
+        value % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bytes, Choices};
+
+    #[test]
+    fn test_bytes() {
+        let mut bytes = Bytes::new(&[7, 1, 2, 9]);
+        assert_eq!(bytes.below(5), 2);
+        // One choice of the range takes no byte
+        assert_eq!(bytes.below(1), 0);
+        // Two bytes for a range past 256, 1 and 2 making 258
+        assert_eq!(bytes.below(1000), 258);
+        assert_eq!(bytes.below(3), 0);
+        // Then the first choice, for good
+        assert_eq!(bytes.below(3), 0);
+        assert!(bytes.one_in(4));
+    }
+}
@@ -1,2 +1,5 @@
 pub mod peeking_take_while;
-
+pub mod rng;
+pub mod choices;
+#[cfg(test)]
+pub mod temp_project;
@@ -32,7 +32,7 @@ where
     }
 }
 
-impl<'s, I, P> Iterator for PeekingTakeWhile<&'s mut Peekable<I>, P>
+impl<I, P> Iterator for PeekingTakeWhile<&mut Peekable<I>, P>
 where
     I: Iterator,
     P: FnMut(&I::Item) -> bool
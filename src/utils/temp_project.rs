@@ -0,0 +1,42 @@
+//! Throwaway projects on disk for tests
+//!
+//! Each project lives in its own directory under the system temp directory,
+//! which is removed again when the project is dropped.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Tells apart the projects of tests running at the same time
+static NEXT: AtomicU32 = AtomicU32::new(0);
+
+/// A directory of source files, removed on drop
+pub struct TempProject {
+    root: PathBuf,
+}
+
+impl TempProject {
+    /// Writes `files`, given by their path relative to the project root, to a
+    /// fresh directory
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let id = NEXT.fetch_add(1, Ordering::Relaxed);
+        let root = std::env::temp_dir().join(format!("haze-{}-{}", std::process::id(), id));
+        let _ = std::fs::remove_dir_all(&root);
+        let project = Self { root };
+        for (path, contents) in files {
+            let path = project.root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        project
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,120 @@
+//! Instruction set of the virtual machine
+//!
+//! Every function is compiled to a flat list of instructions operating on a
+//! value stack. Locals live in the stack frame of their function and are
+//! addressed by slot, the arguments of a call occupying the first slots.
+
+use core::fmt::Write;
+
+use super::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    /// Push a value from the constant pool
+    Const(u32),
+    Unit,
+    True,
+    False,
+
+    GetLocal(u16),
+    SetLocal(u16),
+    Pop,
+
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Neg,
+    Not,
+    /// Wrap the integer on top of the stack to 32 bits, see [`super::wrap32`]
+    Wrap32,
+
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+
+    /// Jump to an absolute instruction index
+    Jump(u32),
+    /// Pop a bool and jump if it is false
+    JumpIfFalse(u32),
+
+    /// Call a function with its arguments on top of the stack
+    Call(u16),
+    Builtin(Builtin),
+    Return,
+
+    /// Collect values on top of the stack into an array
+    Array(u16),
+    /// Collect field values on top of the stack into a struct, in declaration order
+    Struct(u16),
+    /// Pop an index and an array, then push the element
+    Index,
+    /// Pop a struct and push one of its fields
+    Field(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    Print,
+    Input,
+    Int,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "print" => Some(Builtin::Print),
+            "input" => Some(Builtin::Input),
+            "int" => Some(Builtin::Int),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Function {
+    pub name: Box<str>,
+    pub arity: u16,
+    /// Slots needed for arguments and locals
+    pub locals: u16,
+    pub code: Vec<Op>,
+    /// Source line of each instruction
+    pub lines: Vec<u32>,
+    /// Slots of the variables declared directly in the body, the last one
+    /// of a shadowed name winning
+    pub body_locals: Vec<(Box<str>, u16)>,
+}
+
+#[derive(Debug, Default)]
+pub struct Program {
+    pub functions: Vec<Function>,
+    pub constants: Vec<Value>,
+    pub main: Option<u16>,
+}
+
+/// Lists every function of the program instruction by instruction
+pub fn disassemble(program: &Program) -> String {
+    let mut out = String::new();
+    for function in program.functions.iter() {
+        let _ = writeln!(out, "fn {} (arity {}, locals {})", function.name, function.arity, function.locals);
+        let mut last_line = None;
+        for (i, op) in function.code.iter().enumerate() {
+            let line = function.lines[i];
+            let line_col = if last_line == Some(line) { "   |".to_string() } else { format!("{:4}", line) };
+            last_line = Some(line);
+
+            let _ = write!(out, "  {:04} {} {:?}", i, line_col, op);
+            match op {
+                Op::Const(index) => { let _ = write!(out, "  ; {}", program.constants[*index as usize]); }
+                Op::Call(id) => { let _ = write!(out, "  ; {}", program.functions[*id as usize].name); }
+                _ => {}
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
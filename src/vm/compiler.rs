@@ -0,0 +1,404 @@
+//! Lowering of type checked trees to bytecode
+//!
+//! Every expression leaves exactly one value on the stack, `()` for those of
+//! unit type. Constants are compiled to functions without parameters which
+//! compute their value when called.
+
+use hashbrown::HashMap;
+
+use crate::ast2::{AstToken, BlockExpr, Expr, IfAlt, IfExpr, Stmt, TopDeclList, TopLevelDecl};
+use crate::errors::Loc;
+use crate::token::Tag;
+use crate::typecheck::check::{expr_loc, TypeChecker};
+use crate::typecheck::Type;
+
+use super::bytecode::{Builtin, Function, Op, Program};
+use super::Value;
+
+#[derive(Debug)]
+pub struct CompileError {
+    pub message: &'static str,
+    pub location: Loc,
+}
+
+/// Compiles a program which passed type checking
+pub fn compile<'s, 'b>(decls: TopDeclList<'s, 'b>, types: &TypeChecker<'s>) -> Result<Program, Vec<CompileError>> {
+    let mut compiler = Compiler::new(types);
+    compiler.compile_program(decls);
+    if compiler.errors.is_empty() { Ok(compiler.program) } else { Err(compiler.errors) }
+}
+
+/// Jumps to patch once the end of a loop is known
+struct Loop {
+    start: u32,
+    breaks: Vec<usize>,
+}
+
+pub struct Compiler<'s, 't> {
+    types: &'t TypeChecker<'s>,
+    program: Program,
+    /// Function ids of functions and constants
+    globals: HashMap<&'s str, u16>,
+    constants: HashMap<ConstKey, u32>,
+
+    function: Function,
+    scopes: Vec<HashMap<&'s str, u16>>,
+    loops: Vec<Loop>,
+    line: u32,
+    pub errors: Vec<CompileError>,
+}
+
+/// Literals are interned, floats by their bits
+#[derive(PartialEq, Eq, Hash)]
+enum ConstKey {
+    Int(i64),
+    UInt(u64),
+    Float(u64),
+    Str(Box<str>),
+}
+
+impl<'s, 'b, 't> Compiler<'s, 't> {
+    pub fn new(types: &'t TypeChecker<'s>) -> Self {
+        Self {
+            types,
+            program: Program::default(),
+            globals: HashMap::new(),
+            constants: HashMap::new(),
+            function: Function::default(),
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            line: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn compile_program(&mut self, decls: TopDeclList<'s, 'b>) {
+        // Functions may be called before they are declared, so ids are handed out first
+        for decl in decls.items() {
+            let name = match &decl {
+                TopLevelDecl::Fn(fn_def) => fn_def.name(),
+                TopLevelDecl::Const(const_decl) => const_decl.name(),
+                _ => continue,
+            };
+            let id = self.program.functions.len() as u16;
+            self.globals.insert(name.token().value, id);
+            self.program.functions.push(Function { name: name.token().value.into(), ..Function::default() });
+        }
+
+        for decl in decls.items() {
+            match decl {
+                TopLevelDecl::Fn(fn_def) => {
+                    let Some(body) = fn_def.body() else { continue };
+                    self.begin(fn_def.name().token().line);
+                    for param in fn_def.params().items() {
+                        self.declare(param.ident().token().value);
+                    }
+                    self.function.arity = self.function.locals;
+
+                    let locals = self.block(body);
+                    self.function.body_locals = locals.into_iter().map(|(name, slot)| (name.into(), slot)).collect();
+                    self.function.body_locals.sort_by_key(|&(_, slot)| slot);
+                    // Falling off the end returns the unit value left by the block
+                    self.emit(Op::Return);
+                    self.finish(fn_def.name().token().value);
+                }
+                TopLevelDecl::Const(const_decl) => {
+                    self.begin(const_decl.name().token().line);
+                    self.expr(const_decl.value());
+                    self.emit(Op::Return);
+                    self.finish(const_decl.name().token().value);
+                }
+                TopLevelDecl::Enum(enum_decl) => self.unsupported("enums", Loc::from_token(*enum_decl.name().token())),
+                _ => {}
+            }
+        }
+        self.program.main = self.globals.get("main").copied();
+    }
+
+    fn begin(&mut self, line: u32) {
+        self.function = Function::default();
+        self.scopes = vec![HashMap::new()];
+        self.line = line;
+    }
+
+    fn finish(&mut self, name: &str) {
+        let id = self.globals[name];
+        let mut function = std::mem::take(&mut self.function);
+        function.name = name.into();
+        self.program.functions[id as usize] = function;
+    }
+
+    fn unsupported(&mut self, message: &'static str, location: Loc) {
+        self.errors.push(CompileError { message, location });
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        self.function.code.push(op);
+        self.function.lines.push(self.line);
+        self.function.code.len() - 1
+    }
+
+    fn here(&self) -> u32 {
+        self.function.code.len() as u32
+    }
+
+    /// Points a forward jump at the next instruction
+    fn patch(&mut self, jump: usize) {
+        let target = self.here();
+        match &mut self.function.code[jump] {
+            Op::Jump(to) | Op::JumpIfFalse(to) => *to = target,
+            op => unreachable!("{:?} is not a jump", op),
+        }
+    }
+
+    fn constant(&mut self, key: ConstKey, value: Value) -> Op {
+        let pool = &mut self.program.constants;
+        let index = *self.constants.entry(key).or_insert_with(|| {
+            pool.push(value);
+            pool.len() as u32 - 1
+        });
+        Op::Const(index)
+    }
+
+    fn declare(&mut self, name: &'s str) -> u16 {
+        let slot = self.function.locals;
+        self.function.locals += 1;
+        self.scopes.last_mut().expect("locals are declared inside a scope").insert(name, slot);
+        slot
+    }
+
+    fn local(&self, name: &str) -> Option<u16> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Compiles a block, returning the slots of the variables declared directly in it
+    fn block(&mut self, block: BlockExpr<'s, 'b>) -> HashMap<&'s str, u16> {
+        self.scopes.push(HashMap::new());
+        for stmt in block.body().items() {
+            match stmt {
+                Stmt::VarDecl(var_decl) => {
+                    self.line = var_decl.name().token().line;
+                    match var_decl.value() {
+                        Some(value) => self.expr(value),
+                        None => { self.emit(Op::Unit); }
+                    }
+                    // Declared after the initializer so `let x = x;` reads the outer `x`
+                    let slot = self.declare(var_decl.name().token().value);
+                    self.emit(Op::SetLocal(slot));
+                }
+                Stmt::ExprStmt(expr_stmt) => {
+                    let expr = expr_stmt.expr();
+                    self.line = expr_loc(&expr).line;
+                    self.expr(expr);
+                    self.emit(Op::Pop);
+                }
+                Stmt::EmptyStmt(_) => {}
+            }
+        }
+        self.emit(Op::Unit);
+        self.scopes.pop().expect("the scope pushed above")
+    }
+
+    fn expr(&mut self, expr: Expr<'s, 'b>) {
+        let location = expr_loc(&expr);
+        let typ = self.types.type_of(&expr).cloned().unwrap_or(Type::Error);
+        match expr {
+            Expr::Int(int) => {
+                let text = int.token().value;
+                let op = match typ {
+                    Type::F32 | Type::F64 => {
+                        let value: f64 = text.parse().expect("the type checker keeps literals in range");
+                        self.constant(ConstKey::Float(value.to_bits()), Value::Float(value))
+                    }
+                    Type::U32 | Type::U64 => {
+                        let value: u64 = text.parse().expect("the type checker keeps literals in range");
+                        self.constant(ConstKey::UInt(value), Value::UInt(value))
+                    }
+                    _ => {
+                        let value: i64 = text.parse().expect("the type checker keeps literals in range");
+                        self.constant(ConstKey::Int(value), Value::Int(value))
+                    }
+                };
+                self.emit(op);
+            }
+            Expr::Str(string) => {
+                let text = unescape(string.token().value);
+                let op = self.constant(ConstKey::Str(text.clone().into()), Value::Str(text.into()));
+                self.emit(op);
+            }
+            Expr::Bool(boolean) => {
+                self.emit(if boolean.token().value == "true" { Op::True } else { Op::False });
+            }
+            Expr::Ident(ident) => {
+                let name = ident.token().value;
+                if let Some(slot) = self.local(name) {
+                    self.emit(Op::GetLocal(slot));
+                } else if let (Some(Type::Const(_)), Some(&id)) = (self.types.global(name), self.globals.get(name)) {
+                    self.emit(Op::Call(id));
+                } else {
+                    self.unsupported("function values", location);
+                }
+            }
+            Expr::Group(group) => self.expr(group.expr()),
+            Expr::Infix(infix) => {
+                self.expr(infix.left());
+                self.expr(infix.right());
+                let op = match infix.op().tag {
+                    Tag::Plus => Op::Add,
+                    Tag::Minus => Op::Sub,
+                    Tag::Asterisk => Op::Mul,
+                    Tag::Slash => Op::Div,
+                    Tag::EqualEqual => Op::Eq,
+                    Tag::BangEqual => Op::Ne,
+                    Tag::Less => Op::Lt,
+                    Tag::LessEqual => Op::Le,
+                    Tag::Greater => Op::Gt,
+                    Tag::GreaterEqual => Op::Ge,
+                    tag => unreachable!("{:?} is not a binary operator", tag),
+                };
+                self.emit(op);
+                if matches!(typ, Type::I32 | Type::U32) {
+                    self.emit(Op::Wrap32);
+                }
+            }
+            Expr::Prefix(prefix) => {
+                self.expr(prefix.right());
+                self.emit(if prefix.op().tag == Tag::Bang { Op::Not } else { Op::Neg });
+                if matches!(typ, Type::I32 | Type::U32) {
+                    self.emit(Op::Wrap32);
+                }
+            }
+            Expr::CallExpr(call) => {
+                let name = call.name().token().value;
+                for arg in call.args().args() {
+                    self.expr(arg);
+                }
+                if self.local(name).is_some() {
+                    self.unsupported("function values", location);
+                } else if let Some(&id) = self.globals.get(name) {
+                    self.emit(Op::Call(id));
+                } else if let Some(builtin) = Builtin::from_name(name) {
+                    self.emit(Op::Builtin(builtin));
+                }
+            }
+            Expr::AssignExpr(assign) => {
+                self.expr(assign.value());
+                match assign.target() {
+                    Expr::Ident(name) => match self.local(name.token().value) {
+                        Some(slot) => { self.emit(Op::SetLocal(slot)); }
+                        None => self.unsupported("assignment to a global", location),
+                    },
+                    _ => self.unsupported("assignment to a field or element", location),
+                }
+                self.emit(Op::Unit);
+            }
+            Expr::ReturnExpr(return_expr) => {
+                match return_expr.value() {
+                    Some(value) => self.expr(value),
+                    None => { self.emit(Op::Unit); }
+                }
+                self.emit(Op::Return);
+            }
+            Expr::BreakExpr(_) => {
+                let jump = self.emit(Op::Jump(0));
+                self.loops.last_mut().expect("type checker rejects break outside loops").breaks.push(jump);
+            }
+            Expr::ContinueExpr(_) => {
+                let start = self.loops.last().expect("type checker rejects continue outside loops").start;
+                self.emit(Op::Jump(start));
+            }
+            Expr::IfExpr(if_expr) => self.if_expr(if_expr),
+            Expr::WhileExpr(while_expr) => {
+                let start = self.here();
+                self.expr(while_expr.condition());
+                let exit = self.emit(Op::JumpIfFalse(0));
+
+                self.loops.push(Loop { start, breaks: Vec::new() });
+                self.block(while_expr.consequence());
+                self.emit(Op::Pop);
+                self.emit(Op::Jump(start));
+                let finished = self.loops.pop().unwrap();
+
+                self.patch(exit);
+                for jump in finished.breaks {
+                    self.patch(jump);
+                }
+                self.emit(Op::Unit);
+            }
+            Expr::BlockExpr(block) => { self.block(block); }
+            Expr::ArrayExpr(array) => {
+                let mut len = 0;
+                for item in array.items() {
+                    self.expr(item);
+                    len += 1;
+                }
+                self.emit(Op::Array(len));
+            }
+            Expr::IndexExpr(index) => {
+                self.expr(index.container());
+                self.expr(index.index());
+                self.emit(Op::Index);
+            }
+            Expr::FieldAccessExpr(access) => {
+                let parent = access.parent();
+                let field = self.types.type_of(&parent)
+                    .and_then(|typ| field_index(typ, access.field_name().token().value))
+                    .expect("type checker resolved the field");
+                self.expr(parent);
+                self.emit(Op::Field(field));
+            }
+            Expr::StructExpr(struct_expr) => {
+                let Type::Struct(_, fields) = &typ else { unreachable!("type checker resolved the struct") };
+                for (field, _) in fields.iter() {
+                    let init = struct_expr.fields().items()
+                        .find(|init| init.name().token().value == &**field)
+                        .expect("type checker ensures every field is initialized");
+                    self.expr(init.value());
+                }
+                self.emit(Op::Struct(fields.len() as u16));
+            }
+            Expr::MethodCall(_) | Expr::TupleExpr(_) => self.unsupported("methods and tuples", location),
+        }
+    }
+
+    fn if_expr(&mut self, if_expr: IfExpr<'s, 'b>) {
+        self.expr(if_expr.condition());
+        let skip_consequence = self.emit(Op::JumpIfFalse(0));
+        self.block(if_expr.consequence());
+        let skip_alternate = self.emit(Op::Jump(0));
+        self.patch(skip_consequence);
+        match if_expr.alternate() {
+            Some(IfAlt::Else(block)) => { self.block(block); }
+            Some(IfAlt::ElseIf(else_if)) => self.if_expr(else_if),
+            None => { self.emit(Op::Unit); }
+        }
+        self.patch(skip_alternate);
+    }
+}
+
+pub(crate) fn field_index(typ: &Type, name: &str) -> Option<u16> {
+    let Type::Struct(_, fields) = typ else { return None };
+    fields.iter().position(|(field, _)| &**field == name).map(|i| i as u16)
+}
+
+/// Contents of a string literal
+pub(crate) fn unescape(literal: &str) -> String {
+    let inner = &literal[1..literal.len() - 1];
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some('0') => text.push('\0'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
@@ -0,0 +1,254 @@
+//! Direct evaluation of type checked trees
+//!
+//! Slower than the bytecode VM but simple enough to serve as its reference.
+
+use hashbrown::HashMap;
+
+use crate::ast2::{AstToken, BlockExpr, ConstDecl, Expr, FnDef, IfAlt, IfExpr, Stmt, TopDeclList, TopLevelDecl};
+use crate::token::Tag;
+use crate::typecheck::check::{expr_loc, TypeChecker};
+use crate::typecheck::Type;
+
+use super::bytecode::{Builtin, Op};
+use super::compiler::{field_index, unescape};
+use super::{arithmetic, call_builtin, index, wrap32, Io, RuntimeError, RuntimeErrorKind, Value, MAX_FRAMES};
+
+/// Ways evaluation leaves an expression early
+enum Unwind {
+    Return(Value),
+    Break,
+    Continue,
+    Error(RuntimeError),
+}
+
+type Eval = Result<Value, Unwind>;
+
+pub struct Evaluator<'s, 'b, 't> {
+    types: &'t TypeChecker<'s>,
+    functions: HashMap<&'s str, FnDef<'s, 'b>>,
+    consts: HashMap<&'s str, ConstDecl<'s, 'b>>,
+    scopes: Vec<HashMap<&'s str, Value>>,
+    depth: usize,
+}
+
+impl<'s, 'b, 't> Evaluator<'s, 'b, 't> {
+    pub fn new(decls: TopDeclList<'s, 'b>, types: &'t TypeChecker<'s>) -> Self {
+        let mut functions = HashMap::new();
+        let mut consts = HashMap::new();
+        for decl in decls.items() {
+            match decl {
+                TopLevelDecl::Fn(fn_def) => { functions.insert(fn_def.name().token().value, fn_def); }
+                TopLevelDecl::Const(const_decl) => { consts.insert(const_decl.name().token().value, const_decl); }
+                _ => {}
+            }
+        }
+        Self { types, functions, consts, scopes: Vec::new(), depth: 0 }
+    }
+
+    pub fn run_main(&mut self, io: &mut Io) -> Result<Value, RuntimeError> {
+        if !self.functions.contains_key("main") {
+            return Err(RuntimeError { kind: RuntimeErrorKind::NoMain, line: 0 });
+        }
+        self.call("main", Vec::new(), io, 0)
+    }
+
+    /// Calls a function of the program by name
+    pub fn call(&mut self, name: &str, args: Vec<Value>, io: &mut Io, line: u32) -> Result<Value, RuntimeError> {
+        if self.depth == MAX_FRAMES {
+            return Err(RuntimeError { kind: RuntimeErrorKind::StackOverflow, line });
+        }
+        let fn_def = self.functions[name].clone();
+        let Some(body) = fn_def.body() else { return Ok(Value::Unit) };
+
+        let params = fn_def.params().items().map(|param| param.ident().token().value).zip(args).collect();
+        let outer = std::mem::replace(&mut self.scopes, vec![params]);
+        self.depth += 1;
+        let result = self.block(body, io);
+        self.depth -= 1;
+        self.scopes = outer;
+
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(err)) => Err(err),
+            Err(Unwind::Break | Unwind::Continue) => unreachable!("type checker rejects break outside loops"),
+        }
+    }
+
+    fn block(&mut self, block: BlockExpr<'s, 'b>, io: &mut Io) -> Eval {
+        self.scopes.push(HashMap::new());
+        let result = self.statements(block, io);
+        self.scopes.pop();
+        result
+    }
+
+    fn statements(&mut self, block: BlockExpr<'s, 'b>, io: &mut Io) -> Eval {
+        for stmt in block.body().items() {
+            match stmt {
+                Stmt::VarDecl(var_decl) => {
+                    let value = match var_decl.value() {
+                        Some(value) => self.expr(value, io)?,
+                        None => Value::Unit,
+                    };
+                    self.scopes.last_mut().unwrap().insert(var_decl.name().token().value, value);
+                }
+                Stmt::ExprStmt(expr_stmt) => { self.expr(expr_stmt.expr(), io)?; }
+                Stmt::EmptyStmt(_) => {}
+            }
+        }
+        Ok(Value::Unit)
+    }
+
+    fn local(&mut self, name: &str) -> Option<&mut Value> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name))
+    }
+
+    fn expr(&mut self, expr: Expr<'s, 'b>, io: &mut Io) -> Eval {
+        let location = expr_loc(&expr);
+        let error = |kind| Unwind::Error(RuntimeError { kind, line: location.line });
+        let types = self.types;
+        let typ = types.type_of(&expr);
+        match expr {
+            Expr::Int(int) => {
+                let text = int.token().value;
+                Ok(match typ {
+                    Some(Type::F32 | Type::F64) => Value::Float(text.parse().expect("the type checker keeps literals in range")),
+                    Some(Type::U32 | Type::U64) => Value::UInt(text.parse().expect("the type checker keeps literals in range")),
+                    _ => Value::Int(text.parse().expect("the type checker keeps literals in range")),
+                })
+            }
+            Expr::Str(string) => Ok(Value::Str(unescape(string.token().value).into())),
+            Expr::Bool(boolean) => Ok(Value::Bool(boolean.token().value == "true")),
+            Expr::Ident(ident) => {
+                let name = ident.token().value;
+                if let Some(value) = self.local(name) {
+                    return Ok(value.clone());
+                }
+                // The other globals are functions, which aren't values
+                let Some(const_decl) = self.consts.get(name).cloned() else {
+                    return Err(error(RuntimeErrorKind::Unsupported("function values")));
+                };
+                // Constants are evaluated on every use, just like in the VM
+                let outer = std::mem::take(&mut self.scopes);
+                let value = self.expr(const_decl.value(), io);
+                self.scopes = outer;
+                value
+            }
+            Expr::Group(group) => self.expr(group.expr(), io),
+            Expr::Infix(infix) => {
+                let left = self.expr(infix.left(), io)?;
+                let right = self.expr(infix.right(), io)?;
+                let op = match infix.op().tag {
+                    Tag::Plus => Op::Add,
+                    Tag::Minus => Op::Sub,
+                    Tag::Asterisk => Op::Mul,
+                    Tag::Slash => Op::Div,
+                    Tag::EqualEqual => Op::Eq,
+                    Tag::BangEqual => Op::Ne,
+                    Tag::Less => Op::Lt,
+                    Tag::LessEqual => Op::Le,
+                    Tag::Greater => Op::Gt,
+                    Tag::GreaterEqual => Op::Ge,
+                    tag => unreachable!("{:?} is not a binary operator", tag),
+                };
+                let result = arithmetic(op, left, right).map_err(error)?;
+                Ok(if matches!(typ, Some(Type::I32 | Type::U32)) { wrap32(result) } else { result })
+            }
+            Expr::Prefix(prefix) => {
+                let value = self.expr(prefix.right(), io)?;
+                let result = match (prefix.op().tag, value) {
+                    (Tag::Bang, Value::Bool(value)) => Value::Bool(!value),
+                    (_, Value::Int(value)) => Value::Int(value.wrapping_neg()),
+                    (_, Value::Float(value)) => Value::Float(-value),
+                    (_, value) => unreachable!("type checker allowed -{}", value),
+                };
+                Ok(if matches!(typ, Some(Type::I32 | Type::U32)) { wrap32(result) } else { result })
+            }
+            Expr::CallExpr(call) => {
+                let name = call.name().token().value;
+                let mut args = Vec::new();
+                for arg in call.args().args() {
+                    args.push(self.expr(arg, io)?);
+                }
+                match Builtin::from_name(name) {
+                    Some(builtin) if !self.functions.contains_key(name) => {
+                        let arg = args.pop().expect("builtins take one argument");
+                        call_builtin(builtin, arg, io).map_err(error)
+                    }
+                    _ => self.call(name, args, io, location.line).map_err(Unwind::Error),
+                }
+            }
+            Expr::AssignExpr(assign) => {
+                let value = self.expr(assign.value(), io)?;
+                let Expr::Ident(name) = assign.target() else { unreachable!("the type checker only allows variables to be assigned to") };
+                *self.local(name.token().value).expect("type checker resolved the variable") = value;
+                Ok(Value::Unit)
+            }
+            Expr::ReturnExpr(return_expr) => {
+                let value = match return_expr.value() {
+                    Some(value) => self.expr(value, io)?,
+                    None => Value::Unit,
+                };
+                Err(Unwind::Return(value))
+            }
+            Expr::BreakExpr(_) => Err(Unwind::Break),
+            Expr::ContinueExpr(_) => Err(Unwind::Continue),
+            Expr::IfExpr(if_expr) => self.if_expr(if_expr, io),
+            Expr::WhileExpr(while_expr) => {
+                while self.expr(while_expr.condition(), io)? == Value::Bool(true) {
+                    match self.block(while_expr.consequence(), io) {
+                        Ok(_) | Err(Unwind::Continue) => {}
+                        Err(Unwind::Break) => break,
+                        Err(unwind) => return Err(unwind),
+                    }
+                }
+                Ok(Value::Unit)
+            }
+            Expr::BlockExpr(block) => self.block(block, io),
+            Expr::ArrayExpr(array) => {
+                let mut items = Vec::new();
+                for item in array.items() {
+                    items.push(self.expr(item, io)?);
+                }
+                Ok(Value::Array(items.into()))
+            }
+            Expr::IndexExpr(index_expr) => {
+                let container = self.expr(index_expr.container(), io)?;
+                let index_value = self.expr(index_expr.index(), io)?;
+                index(container, index_value).map_err(error)
+            }
+            Expr::FieldAccessExpr(access) => {
+                let parent = access.parent();
+                let field = self.types.type_of(&parent)
+                    .and_then(|typ| field_index(typ, access.field_name().token().value))
+                    .expect("type checker resolved the field");
+                let Value::Struct(fields) = self.expr(parent, io)? else { unreachable!() };
+                Ok(fields[field as usize].clone())
+            }
+            Expr::StructExpr(struct_expr) => {
+                let Some(Type::Struct(_, fields)) = typ else {
+                    unreachable!("type checker resolved the struct")
+                };
+                let mut values = Vec::new();
+                for (field, _) in fields.iter() {
+                    let init = struct_expr.fields().items()
+                        .find(|init| init.name().token().value == &**field)
+                        .expect("type checker ensures every field is initialized");
+                    values.push(self.expr(init.value(), io)?);
+                }
+                Ok(Value::Struct(values.into()))
+            }
+            Expr::MethodCall(_) | Expr::TupleExpr(_) => unreachable!("type checker rejects methods and tuples"),
+        }
+    }
+
+    fn if_expr(&mut self, if_expr: IfExpr<'s, 'b>, io: &mut Io) -> Eval {
+        if self.expr(if_expr.condition(), io)? == Value::Bool(true) {
+            return self.block(if_expr.consequence(), io);
+        }
+        match if_expr.alternate() {
+            Some(IfAlt::Else(block)) => self.block(block, io),
+            Some(IfAlt::ElseIf(else_if)) => self.if_expr(else_if, io),
+            None => Ok(Value::Unit),
+        }
+    }
+}
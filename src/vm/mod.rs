@@ -0,0 +1,520 @@
+//! Script execution
+//!
+//! Type checked programs are lowered to bytecode by the [`compiler`] and run on
+//! a stack based [`Vm`]. The `eval` module runs the tree directly and serves
+//! as a reference for the VM, so it is only built for the tests and benchmarks.
+//!
+//! Signed integers are kept as `i64` and unsigned integers as `u64` whatever
+//! their declared width. Arithmetic wraps at 64 bits and results of `i32` and
+//! `u32` type are then wrapped again to 32 bits by [`wrap32`].
+
+pub mod bytecode;
+pub mod compiler;
+#[cfg(test)]
+pub mod eval;
+
+use std::fmt::{self, Display};
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+use bytecode::{Builtin, Op, Program};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(Rc<str>),
+    Array(Rc<[Value]>),
+    /// Field values in declaration order
+    Struct(Rc<[Value]>),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Unit => write!(f, "()"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::UInt(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{:?}", value),
+            Value::Array(items) | Value::Struct(items) => {
+                let (open, close) = if matches!(self, Value::Array(_)) { ('[', ']') } else { ('{', '}') };
+                write!(f, "{}", open)?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "{}", close)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RuntimeErrorKind {
+    DivisionByZero,
+    IndexOutOfBounds { index: i64, len: usize },
+    StackOverflow,
+    NoMain,
+    Io(String),
+    /// A construct the evaluator has no value for, as the VM refuses to compile
+    #[cfg(test)]
+    Unsupported(&'static str),
+}
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub line: u32,
+}
+
+impl Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::IndexOutOfBounds { index, len } => {
+                write!(f, "index out of bounds: the len is {} but the index is {}", len, index)
+            }
+            RuntimeErrorKind::StackOverflow => write!(f, "stack overflow"),
+            RuntimeErrorKind::NoMain => write!(f, "program has no `main` function"),
+            RuntimeErrorKind::Io(err) => write!(f, "{}", err),
+            #[cfg(test)]
+            RuntimeErrorKind::Unsupported(what) => write!(f, "{} are not supported by the evaluator", what),
+        }
+    }
+}
+
+/// Where `print` writes and `input` reads
+pub struct Io<'a> {
+    pub input: &'a mut dyn BufRead,
+    pub output: &'a mut dyn Write,
+}
+
+/// Maximum depth of nested calls
+pub const MAX_FRAMES: usize = 1024;
+
+/// Result of a builtin call
+pub(crate) fn call_builtin(builtin: Builtin, arg: Value, io: &mut Io) -> Result<Value, RuntimeErrorKind> {
+    let io_error = |err: std::io::Error| RuntimeErrorKind::Io(err.to_string());
+    match (builtin, arg) {
+        (Builtin::Print, Value::Str(text)) => writeln!(io.output, "{}", text).map_err(io_error)?,
+        (Builtin::Print, value) => writeln!(io.output, "{}", value).map_err(io_error)?,
+        (Builtin::Input, Value::Str(prompt)) => {
+            write!(io.output, "{}", prompt).map_err(io_error)?;
+            io.output.flush().map_err(io_error)?;
+            let mut line = String::new();
+            io.input.read_line(&mut line).map_err(io_error)?;
+            return Ok(Value::Str(line.trim_end_matches(['\n', '\r']).into()));
+        }
+        (Builtin::Int, Value::Str(text)) => {
+            // Like `strtoll`, text which is not a number reads as zero
+            return Ok(Value::Int(text.trim().parse().unwrap_or(0)));
+        }
+        (builtin, arg) => unreachable!("type checker allowed {:?}({})", builtin, arg),
+    }
+    Ok(Value::Unit)
+}
+
+pub(crate) fn arithmetic(op: Op, left: Value, right: Value) -> Result<Value, RuntimeErrorKind> {
+    use Value::*;
+    Ok(match (op, left, right) {
+        (Op::Add, Int(a), Int(b)) => Int(a.wrapping_add(b)),
+        (Op::Sub, Int(a), Int(b)) => Int(a.wrapping_sub(b)),
+        (Op::Mul, Int(a), Int(b)) => Int(a.wrapping_mul(b)),
+        (Op::Div, Int(_), Int(0)) | (Op::Div, UInt(_), UInt(0)) => return Err(RuntimeErrorKind::DivisionByZero),
+        (Op::Div, Int(a), Int(b)) => Int(a.wrapping_div(b)),
+        (Op::Add, UInt(a), UInt(b)) => UInt(a.wrapping_add(b)),
+        (Op::Sub, UInt(a), UInt(b)) => UInt(a.wrapping_sub(b)),
+        (Op::Mul, UInt(a), UInt(b)) => UInt(a.wrapping_mul(b)),
+        (Op::Div, UInt(a), UInt(b)) => UInt(a / b),
+        (Op::Add, Float(a), Float(b)) => Float(a + b),
+        (Op::Sub, Float(a), Float(b)) => Float(a - b),
+        (Op::Mul, Float(a), Float(b)) => Float(a * b),
+        (Op::Div, Float(a), Float(b)) => Float(a / b),
+        (Op::Eq, a, b) => Bool(a == b),
+        (Op::Ne, a, b) => Bool(a != b),
+        (op, a, b) => {
+            let ordering = match (&a, &b) {
+                (Int(a), Int(b)) => a.partial_cmp(b),
+                (UInt(a), UInt(b)) => a.partial_cmp(b),
+                (Float(a), Float(b)) => a.partial_cmp(b),
+                _ => unreachable!("type checker allowed {} {:?} {}", a, op, b),
+            };
+            Bool(match op {
+                Op::Lt => ordering.is_some_and(|o| o.is_lt()),
+                Op::Le => ordering.is_some_and(|o| o.is_le()),
+                Op::Gt => ordering.is_some_and(|o| o.is_gt()),
+                Op::Ge => ordering.is_some_and(|o| o.is_ge()),
+                _ => unreachable!("{:?} is not a binary operator", op),
+            })
+        }
+    })
+}
+
+/// Wraps an integer of `i32` or `u32` type, computed in 64 bits, to 32 bits
+pub(crate) fn wrap32(value: Value) -> Value {
+    match value {
+        Value::Int(value) => Value::Int(value as i32 as i64),
+        Value::UInt(value) => Value::UInt(value as u32 as u64),
+        value => unreachable!("type checker allowed a 32 bit {}", value),
+    }
+}
+
+pub(crate) fn index(container: Value, index: Value) -> Result<Value, RuntimeErrorKind> {
+    let Value::Array(items) = container else { unreachable!("type checker allowed indexing {}", container) };
+    let index = match index {
+        Value::Int(index) => index,
+        Value::UInt(index) => i64::try_from(index).unwrap_or(i64::MAX),
+        index => unreachable!("type checker allowed index {}", index),
+    };
+    usize::try_from(index).ok()
+        .and_then(|i| items.get(i))
+        .cloned()
+        .ok_or(RuntimeErrorKind::IndexOutOfBounds { index, len: items.len() })
+}
+
+struct Frame {
+    function: u16,
+    ip: usize,
+    /// Stack index of the first local
+    base: usize,
+}
+
+pub struct Vm<'p> {
+    program: &'p Program,
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+}
+
+impl<'p> Vm<'p> {
+    pub fn new(program: &'p Program) -> Self {
+        Self { program, stack: Vec::new(), frames: Vec::new() }
+    }
+
+    pub fn run_main(&mut self, io: &mut Io) -> Result<Value, RuntimeError> {
+        let main = self.program.main.ok_or(RuntimeError { kind: RuntimeErrorKind::NoMain, line: 0 })?;
+        self.call(main, Vec::new(), io)
+    }
+
+    /// Calls a function and runs it to completion
+    pub fn call(&mut self, function: u16, args: Vec<Value>, io: &mut Io) -> Result<Value, RuntimeError> {
+        self.stack.clear();
+        self.frames.clear();
+        self.stack.extend(args);
+        self.push_frame(function).map_err(|kind| RuntimeError { kind, line: 0 })?;
+        self.execute(io)
+    }
+
+    /// Arguments and locals of the function `call` ran, by slot, as it left them
+    pub fn locals(&self) -> &[Value] {
+        &self.stack
+    }
+
+    fn push_frame(&mut self, function: u16) -> Result<(), RuntimeErrorKind> {
+        if self.frames.len() == MAX_FRAMES {
+            return Err(RuntimeErrorKind::StackOverflow);
+        }
+        let callee = &self.program.functions[function as usize];
+        let base = self.stack.len() - callee.arity as usize;
+        self.stack.resize(base + callee.locals as usize, Value::Unit);
+        self.frames.push(Frame { function, ip: 0, base });
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("compiler keeps the stack balanced")
+    }
+
+    fn execute(&mut self, io: &mut Io) -> Result<Value, RuntimeError> {
+        let program = self.program;
+        loop {
+            let frame = self.frames.last_mut().expect("a function is running");
+            let function = &program.functions[frame.function as usize];
+            let op = function.code[frame.ip];
+            let line = function.lines[frame.ip];
+            let base = frame.base;
+            frame.ip += 1;
+
+            let error = |kind| RuntimeError { kind, line };
+            match op {
+                Op::Const(index) => self.stack.push(program.constants[index as usize].clone()),
+                Op::Unit => self.stack.push(Value::Unit),
+                Op::True => self.stack.push(Value::Bool(true)),
+                Op::False => self.stack.push(Value::Bool(false)),
+                Op::GetLocal(slot) => {
+                    let value = self.stack[base + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                Op::SetLocal(slot) => {
+                    let value = self.pop();
+                    self.stack[base + slot as usize] = value;
+                }
+                Op::Pop => { self.pop(); }
+                Op::Add | Op::Sub | Op::Mul | Op::Div
+                | Op::Eq | Op::Ne | Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let result = arithmetic(op, left, right).map_err(error)?;
+                    self.stack.push(result);
+                }
+                Op::Neg => {
+                    let value = match self.pop() {
+                        Value::Int(value) => Value::Int(value.wrapping_neg()),
+                        Value::Float(value) => Value::Float(-value),
+                        value => unreachable!("type checker allowed -{}", value),
+                    };
+                    self.stack.push(value);
+                }
+                Op::Wrap32 => {
+                    let value = self.pop();
+                    self.stack.push(wrap32(value));
+                }
+                Op::Not => {
+                    let Value::Bool(value) = self.pop() else { unreachable!("type checker allowed !") };
+                    self.stack.push(Value::Bool(!value));
+                }
+                Op::Jump(target) => self.frames.last_mut().unwrap().ip = target as usize,
+                Op::JumpIfFalse(target) => {
+                    if self.pop() == Value::Bool(false) {
+                        self.frames.last_mut().unwrap().ip = target as usize;
+                    }
+                }
+                Op::Call(callee) => self.push_frame(callee).map_err(error)?,
+                Op::Builtin(builtin) => {
+                    let arg = self.pop();
+                    let result = call_builtin(builtin, arg, io).map_err(error)?;
+                    self.stack.push(result);
+                }
+                Op::Return => {
+                    let result = self.pop();
+                    self.frames.pop();
+                    if self.frames.is_empty() {
+                        // The locals of the outermost call stay for `locals`
+                        self.stack.truncate(base + function.locals as usize);
+                        return Ok(result);
+                    }
+                    self.stack.truncate(base);
+                    self.stack.push(result);
+                }
+                Op::Array(len) | Op::Struct(len) => {
+                    let items: Rc<[Value]> = self.stack.drain(self.stack.len() - len as usize..).collect();
+                    self.stack.push(if matches!(op, Op::Array(_)) { Value::Array(items) } else { Value::Struct(items) });
+                }
+                Op::Index => {
+                    let index_value = self.pop();
+                    let container = self.pop();
+                    let item = index(container, index_value).map_err(error)?;
+                    self.stack.push(item);
+                }
+                Op::Field(field) => {
+                    let Value::Struct(fields) = self.pop() else { unreachable!("type checker allowed field access") };
+                    self.stack.push(fields[field as usize].clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::ast2::{AstNode, TopDeclList};
+    use crate::generate::{generate, Config};
+    use crate::parser3::Parser;
+    use crate::typecheck::check::TypeChecker;
+
+    const SAMPLES: &[(&str, &str)] = &[
+        ("fib", include_str!("../../samples/fib.hz")),
+        ("loops", include_str!("../../samples/loops.hz")),
+        ("points", include_str!("../../samples/points.hz")),
+    ];
+
+    fn with_program<R>(source: &str, f: impl for<'s, 'b> FnOnce(TopDeclList<'s, 'b>, &TypeChecker<'s>) -> R) -> R {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        assert!(checker.errors.is_empty(), "{:?}", checker.errors);
+        f(TopDeclList::cast(root), &checker)
+    }
+
+    fn run_vm(source: &str) -> String {
+        with_program(source, |decls, types| {
+            let program = compiler::compile(decls, types).unwrap();
+            let mut output = Vec::new();
+            let mut io = Io { input: &mut std::io::empty(), output: &mut output };
+            Vm::new(&program).run_main(&mut io).unwrap();
+            String::from_utf8(output).unwrap()
+        })
+    }
+
+    fn run_tree(source: &str) -> String {
+        with_program(source, |decls, types| {
+            let mut output = Vec::new();
+            let mut io = Io { input: &mut std::io::empty(), output: &mut output };
+            eval::Evaluator::new(decls, types).run_main(&mut io).unwrap();
+            String::from_utf8(output).unwrap()
+        })
+    }
+
+    #[test]
+    fn test_samples_agree() {
+        for (name, source) in SAMPLES {
+            let vm = run_vm(source);
+            assert!(!vm.is_empty(), "{} printed nothing", name);
+            assert_eq!(vm, run_tree(source), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_execution() {
+        let source = r#"
+struct Pair { a: u64, b: u64 }
+const BASE: u64 = 10;
+
+fn pick(p: Pair, first: bool) -> u64 {
+    if first { return p.a; } else { return p.b; }
+}
+
+fn main() {
+    let i = 0;
+    let found = -1;
+    while true {
+        i = i + 1;
+        if i == 3 { continue; }
+        if i > 5 { break; }
+        found = i;
+    }
+    print(found);
+    print(pick(.Pair { a: BASE, b: 2 }, false) * BASE);
+    let words = ["a", "b"];
+    print(words[1] == "b");
+    print(1.5 * 2.0);
+}
+"#;
+        let expected = "5\n20\ntrue\n3\n";
+        assert_eq!(run_vm(source), expected);
+        assert_eq!(run_tree(source), expected);
+    }
+
+    #[test]
+    fn test_integer_widths() {
+        let source = "
+fn main() {
+    let x: i32 = 2147483647;
+    let y: i32 = x + 1;
+    print(y);
+    print(-y);
+    let z: u32 = 0;
+    print(z - 1);
+    let w: i64 = 2147483647;
+    print(w + 1);
+}
+";
+        let expected = "-2147483648\n-2147483648\n4294967295\n2147483648\n";
+        assert_eq!(run_vm(source), expected);
+        assert_eq!(run_tree(source), expected);
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        let source = "fn main() {\n let items = [1, 2];\n let i = 2;\n print(items[i]);\n}";
+        with_program(source, |decls, types| {
+            let program = compiler::compile(decls, types).unwrap();
+            let mut io = Io { input: &mut std::io::empty(), output: &mut std::io::sink() };
+            let err = Vm::new(&program).run_main(&mut io).unwrap_err();
+            assert_eq!(err.kind, RuntimeErrorKind::IndexOutOfBounds { index: 2, len: 2 });
+            assert_eq!(err.line, 4);
+        });
+
+        let source = "fn f(n: i32) -> i32 { return f(n + 1); }\nfn main() { f(0); }";
+        with_program(source, |decls, types| {
+            let program = compiler::compile(decls, types).unwrap();
+            let mut io = Io { input: &mut std::io::empty(), output: &mut std::io::sink() };
+            let err = Vm::new(&program).run_main(&mut io).unwrap_err();
+            assert_eq!(err.kind, RuntimeErrorKind::StackOverflow);
+        });
+
+        let source = "fn main() {\n let f = main;\n}";
+        with_program(source, |decls, types| {
+            assert!(compiler::compile(decls.clone(), types).is_err());
+            let mut io = Io { input: &mut std::io::empty(), output: &mut std::io::sink() };
+            let err = eval::Evaluator::new(decls, types).run_main(&mut io).unwrap_err();
+            assert_eq!(err.kind, RuntimeErrorKind::Unsupported("function values"));
+            assert_eq!(err.line, 2);
+        });
+    }
+
+    #[test]
+    fn test_disassemble() {
+        with_program("fn main() { let x = 1 + 2; print(x); }", |decls, types| {
+            let program = compiler::compile(decls, types).unwrap();
+            let listing = bytecode::disassemble(&program);
+            assert_eq!(listing, "\
+fn main (arity 0, locals 1)
+  0000    1 Const(0)  ; 1
+  0001    | Const(1)  ; 2
+  0002    | Add
+  0003    | Wrap32
+  0004    | SetLocal(0)
+  0005    | GetLocal(0)
+  0006    | Builtin(Print)
+  0007    | Pop
+  0008    | Unit
+  0009    | Return
+
+");
+        });
+    }
+
+    fn bench_sample(b: &mut test::Bencher, name: &str, use_vm: bool) {
+        let source = SAMPLES.iter().find(|sample| sample.0 == name).unwrap().1;
+        bench_source(b, source, use_vm)
+    }
+
+    fn bench_generated(b: &mut test::Bencher, use_vm: bool) {
+        let config = Config { seed: 1, functions: 6, statements: 8, depth: 4, ..Config::default() };
+        bench_source(b, &generate(&config), use_vm)
+    }
+
+    fn bench_source(b: &mut test::Bencher, source: &str, use_vm: bool) {
+        with_program(source, |decls, types| {
+            let program = compiler::compile(decls.clone(), types).unwrap();
+            b.iter(|| {
+                let mut io = Io { input: &mut std::io::empty(), output: &mut std::io::sink() };
+                if use_vm {
+                    Vm::new(&program).run_main(&mut io).unwrap()
+                } else {
+                    eval::Evaluator::new(decls.clone(), types).run_main(&mut io).unwrap()
+                }
+            });
+        });
+    }
+
+    #[bench]
+    fn bench_vm_fib(b: &mut test::Bencher) { bench_sample(b, "fib", true) }
+    #[bench]
+    fn bench_tree_fib(b: &mut test::Bencher) { bench_sample(b, "fib", false) }
+    #[bench]
+    fn bench_vm_loops(b: &mut test::Bencher) { bench_sample(b, "loops", true) }
+    #[bench]
+    fn bench_tree_loops(b: &mut test::Bencher) { bench_sample(b, "loops", false) }
+    #[bench]
+    fn bench_vm_points(b: &mut test::Bencher) { bench_sample(b, "points", true) }
+    #[bench]
+    fn bench_tree_points(b: &mut test::Bencher) { bench_sample(b, "points", false) }
+    #[bench]
+    fn bench_vm_generated(b: &mut test::Bencher) { bench_generated(b, true) }
+    #[bench]
+    fn bench_tree_generated(b: &mut test::Bencher) { bench_generated(b, false) }
+}
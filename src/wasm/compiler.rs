@@ -0,0 +1,623 @@
+//! Lowering of type checked trees to WebAssembly
+//!
+//! Haze control flow is already structured, so `if` and `while` map directly
+//! to wasm blocks: a `while` is a `loop` inside a `block`, `break` branches
+//! out of the block and `continue` back to the start of the loop.
+
+use hashbrown::HashMap;
+
+use crate::ast2::{AstToken, BlockExpr, Expr, IfAlt, IfExpr, Stmt, TopDeclList, TopLevelDecl};
+use crate::errors::Loc;
+use crate::token::Tag;
+use crate::typecheck::check::{expr_loc, TypeChecker};
+use crate::typecheck::Type;
+use crate::vm::compiler::{field_index, unescape};
+
+use super::{FuncType, Function, Import, Instr, Module, Op, ValType};
+
+#[derive(Debug)]
+pub struct CompileError {
+    pub message: &'static str,
+    pub location: Loc,
+}
+
+/// Host functions, the builtins of the language
+const IMPORTS: &[(&str, &[ValType], &[ValType])] = &[
+    ("print_i32", &[ValType::I32], &[]),
+    ("print_u32", &[ValType::I32], &[]),
+    ("print_i64", &[ValType::I64], &[]),
+    ("print_u64", &[ValType::I64], &[]),
+    ("print_f32", &[ValType::F32], &[]),
+    ("print_f64", &[ValType::F64], &[]),
+    ("print_bool", &[ValType::I32], &[]),
+    ("print_str", &[ValType::I32], &[]),
+    ("input", &[], &[ValType::I32]),
+    ("int", &[ValType::I32], &[ValType::I64]),
+];
+
+/// Index of the heap pointer global
+const HEAP: u32 = 0;
+
+/// Compiles a program which passed type checking
+///
+/// Constants become functions without parameters, just like in the VM.
+pub fn compile<'s, 'b>(decls: TopDeclList<'s, 'b>, types: &TypeChecker<'s>) -> Result<Module, Vec<CompileError>> {
+    let mut compiler = Compiler::new(types);
+    compiler.compile_program(decls);
+    if compiler.errors.is_empty() { Ok(compiler.module) } else { Err(compiler.errors) }
+}
+
+/// Offsets of the fields of a struct, each aligned to its own size
+pub fn struct_layout(fields: &[(Box<str>, Type)]) -> (Vec<u32>, u32) {
+    let mut offsets = Vec::new();
+    let mut size: u32 = 0;
+    for (_, typ) in fields.iter() {
+        let field_size = ValType::of(typ).map_or(0, ValType::size);
+        size = size.next_multiple_of(field_size.max(1));
+        offsets.push(size);
+        size += field_size;
+    }
+    (offsets, size)
+}
+
+fn strip(typ: &Type) -> &Type {
+    match typ {
+        Type::Alias(typ) | Type::Const(typ) => strip(typ),
+        typ => typ,
+    }
+}
+
+struct Loop {
+    /// Nesting levels of the labels
+    exit: usize,
+    start: usize,
+}
+
+pub struct Compiler<'s, 't> {
+    types: &'t TypeChecker<'s>,
+    module: Module,
+    /// Function indices of functions and constants
+    globals: HashMap<&'s str, u32>,
+    alloc: u32,
+    str_eq: u32,
+    /// Addresses of string literals
+    strings: HashMap<String, u32>,
+
+    function: Function,
+    /// Local index of every variable, none for those of unit type
+    scopes: Vec<HashMap<&'s str, Option<u32>>>,
+    /// Blocks, loops and ifs the current instruction is nested in
+    depth: usize,
+    loops: Vec<Loop>,
+    pub errors: Vec<CompileError>,
+}
+
+impl<'s, 'b, 't> Compiler<'s, 't> {
+    pub fn new(types: &'t TypeChecker<'s>) -> Self {
+        let mut module = Module::default();
+        for (name, params, results) in IMPORTS {
+            let typ = FuncType { params: params.to_vec(), results: results.to_vec() };
+            module.imports.push(Import { module: "env", name, typ });
+        }
+        let imports = module.imports.len() as u32;
+        module.functions.push(alloc_function());
+        module.functions.push(str_eq_function());
+
+        Self {
+            types,
+            module,
+            globals: HashMap::new(),
+            alloc: imports,
+            str_eq: imports + 1,
+            strings: HashMap::new(),
+            function: empty_function(""),
+            scopes: Vec::new(),
+            depth: 0,
+            loops: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn compile_program(&mut self, decls: TopDeclList<'s, 'b>) {
+        // Functions may be called before they are declared, so indices are handed out first
+        for decl in decls.items() {
+            let (name, export) = match &decl {
+                TopLevelDecl::Fn(fn_def) if fn_def.body().is_some() => (fn_def.name(), true),
+                TopLevelDecl::Const(const_decl) => (const_decl.name(), false),
+                _ => continue,
+            };
+            let name = name.token();
+            if export && matches!(name.value, "memory" | "haze_alloc") {
+                self.unsupported("functions named like the module's own exports", Loc::from_token(*name));
+            }
+            let index = (self.module.imports.len() + self.module.functions.len()) as u32;
+            self.globals.insert(name.value, index);
+            self.module.functions.push(Function { export, ..empty_function(name.value) });
+        }
+
+        for decl in decls.items() {
+            match decl {
+                TopLevelDecl::Fn(fn_def) => {
+                    let Some(body) = fn_def.body() else { continue };
+                    let name = fn_def.name().token().value;
+                    let Some(Type::Fn(params, ret)) = self.types.global(name) else { continue };
+                    let (params, ret) = (params.clone(), ret.clone());
+
+                    self.begin(name);
+                    for (param, typ) in fn_def.params().items().zip(params.iter()) {
+                        let local = ValType::of(typ).map(|valtype| {
+                            self.function.typ.params.push(valtype);
+                            self.function.typ.params.len() as u32 - 1
+                        });
+                        self.scopes[0].insert(param.ident().token().value, local);
+                    }
+                    self.function.typ.results.extend(ValType::of(&ret));
+
+                    self.block(body);
+                    // Only reached if the type checker saw the body diverge
+                    if !self.function.typ.results.is_empty() {
+                        self.emit(Instr::Op(Op::Unreachable));
+                    }
+                    self.finish(name);
+                }
+                TopLevelDecl::Const(const_decl) => {
+                    let name = const_decl.name().token().value;
+                    let Some(Type::Const(typ)) = self.types.global(name) else { continue };
+                    let valtype = ValType::of(typ);
+
+                    self.begin(name);
+                    self.function.typ.results.extend(valtype);
+                    self.expr(const_decl.value());
+                    self.finish(name);
+                }
+                TopLevelDecl::Enum(enum_decl) => self.unsupported("enums", Loc::from_token(*enum_decl.name().token())),
+                _ => {}
+            }
+        }
+    }
+
+    fn begin(&mut self, name: &str) {
+        let export = self.function_mut(name).export;
+        self.function = Function { export, ..empty_function(name) };
+        self.scopes = vec![HashMap::new()];
+        self.depth = 0;
+    }
+
+    fn finish(&mut self, name: &str) {
+        let function = std::mem::replace(&mut self.function, empty_function(""));
+        *self.function_mut(name) = function;
+    }
+
+    fn function_mut(&mut self, name: &str) -> &mut Function {
+        let index = self.globals[name] as usize - self.module.imports.len();
+        &mut self.module.functions[index]
+    }
+
+    fn unsupported(&mut self, message: &'static str, location: Loc) {
+        self.errors.push(CompileError { message, location });
+    }
+
+    fn emit(&mut self, instr: Instr) {
+        self.function.body.push(instr);
+    }
+
+    fn op(&mut self, op: Op) {
+        self.emit(Instr::Op(op));
+    }
+
+    fn new_local(&mut self, valtype: ValType) -> u32 {
+        self.function.locals.push(valtype);
+        (self.function.typ.params.len() + self.function.locals.len()) as u32 - 1
+    }
+
+    fn local(&self, name: &str) -> Option<Option<u32>> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Relative depth of a label for `br`
+    fn label(&self, level: usize) -> u32 {
+        (self.depth - 1 - level) as u32
+    }
+
+    /// Opens a block, loop or if and returns its nesting level
+    fn enter(&mut self, instr: Instr) -> usize {
+        self.emit(instr);
+        self.depth += 1;
+        self.depth - 1
+    }
+
+    fn leave(&mut self) {
+        self.op(Op::End);
+        self.depth -= 1;
+    }
+
+    /// Address of a string literal in the data segment
+    fn string(&mut self, text: String) -> u32 {
+        if let Some(&address) = self.strings.get(&text) {
+            return address;
+        }
+        let data = &mut self.module.data;
+        while !data.len().is_multiple_of(4) {
+            data.push(0);
+        }
+        let address = super::DATA_START + data.len() as u32;
+        data.extend((text.len() as u32).to_le_bytes());
+        data.extend(text.as_bytes());
+        self.strings.insert(text, address);
+        address
+    }
+
+    fn block(&mut self, block: BlockExpr<'s, 'b>) {
+        self.scopes.push(HashMap::new());
+        for stmt in block.body().items() {
+            match stmt {
+                Stmt::VarDecl(var_decl) => {
+                    let typ = self.types.type_of_var(&var_decl).cloned().unwrap_or(Type::Error);
+                    if let Some(value) = var_decl.value() {
+                        self.expr(value);
+                    }
+                    let local = ValType::of(&typ).map(|valtype| self.new_local(valtype));
+                    if let (Some(local), Some(_)) = (local, var_decl.value()) {
+                        self.emit(Instr::LocalSet(local));
+                    }
+                    // Declared after the initializer so `let x = x;` reads the outer `x`
+                    self.scopes.last_mut().unwrap().insert(var_decl.name().token().value, local);
+                }
+                Stmt::ExprStmt(expr_stmt) => {
+                    let expr = expr_stmt.expr();
+                    let typ = self.types.type_of(&expr).cloned().unwrap_or(Type::Error);
+                    self.expr(expr);
+                    if ValType::of(&typ).is_some() {
+                        self.op(Op::Drop);
+                    }
+                }
+                Stmt::EmptyStmt(_) => {}
+            }
+        }
+        self.scopes.pop();
+    }
+
+    /// Leaves the value of the expression on the stack, nothing for `()` and `!`
+    fn expr(&mut self, expr: Expr<'s, 'b>) {
+        let location = expr_loc(&expr);
+        let typ = self.types.type_of(&expr).cloned().unwrap_or(Type::Error);
+        match expr {
+            Expr::Int(int) => {
+                let text = int.token().value;
+                let instr = match strip(&typ) {
+                    Type::F32 => Instr::F32Const(text.parse().expect("the type checker keeps literals in range")),
+                    Type::F64 => Instr::F64Const(text.parse().expect("the type checker keeps literals in range")),
+                    Type::U32 => Instr::I32Const(text.parse::<u32>().expect("the type checker keeps literals in range") as i32),
+                    Type::U64 => Instr::I64Const(text.parse::<u64>().expect("the type checker keeps literals in range") as i64),
+                    Type::I64 => Instr::I64Const(text.parse().expect("the type checker keeps literals in range")),
+                    _ => Instr::I32Const(text.parse().expect("the type checker keeps literals in range")),
+                };
+                self.emit(instr);
+            }
+            Expr::Str(string) => {
+                let address = self.string(unescape(string.token().value));
+                self.emit(Instr::I32Const(address as i32));
+            }
+            Expr::Bool(boolean) => self.emit(Instr::I32Const((boolean.token().value == "true") as i32)),
+            Expr::Ident(ident) => {
+                let name = ident.token().value;
+                match self.local(name) {
+                    Some(Some(local)) => self.emit(Instr::LocalGet(local)),
+                    Some(None) => {}
+                    None => match (self.types.global(name), self.globals.get(name)) {
+                        (Some(Type::Const(_)), Some(&index)) => self.emit(Instr::Call(index)),
+                        _ => self.unsupported("function values", location),
+                    },
+                }
+            }
+            Expr::Group(group) => self.expr(group.expr()),
+            Expr::Infix(infix) => {
+                let operand = self.types.type_of(&infix.left()).cloned().unwrap_or(Type::Error);
+                let op = infix.op().tag;
+                self.expr(infix.left());
+                self.expr(infix.right());
+                match binary_op(op, strip(&operand)) {
+                    Some(op) => self.op(op),
+                    None if *strip(&operand) == Type::String && matches!(op, Tag::EqualEqual | Tag::BangEqual) => {
+                        self.emit(Instr::Call(self.str_eq));
+                        if op == Tag::BangEqual {
+                            self.op(Op::I32Eqz);
+                        }
+                    }
+                    None => self.unsupported("comparisons of strings, structs and arrays", location),
+                }
+            }
+            Expr::Prefix(prefix) => {
+                if prefix.op().tag == Tag::Bang {
+                    self.expr(prefix.right());
+                    self.op(Op::I32Eqz);
+                    return;
+                }
+                match strip(&typ) {
+                    Type::F32 => {
+                        self.expr(prefix.right());
+                        self.op(Op::F32Neg);
+                    }
+                    Type::F64 => {
+                        self.expr(prefix.right());
+                        self.op(Op::F64Neg);
+                    }
+                    typ => {
+                        let wide = ValType::of(typ) == Some(ValType::I64);
+                        self.emit(if wide { Instr::I64Const(0) } else { Instr::I32Const(0) });
+                        self.expr(prefix.right());
+                        self.op(if wide { Op::I64Sub } else { Op::I32Sub });
+                    }
+                }
+            }
+            Expr::CallExpr(call) => {
+                let name = call.name().token().value;
+                let mut arg_types = Vec::new();
+                for arg in call.args().args() {
+                    arg_types.push(self.types.type_of(&arg).cloned().unwrap_or(Type::Error));
+                    self.expr(arg);
+                }
+                if self.local(name).is_some() {
+                    self.unsupported("function values", location);
+                } else if let Some(&index) = self.globals.get(name) {
+                    self.emit(Instr::Call(index));
+                } else {
+                    let import = match (name, arg_types.first().map(strip)) {
+                        ("print", Some(Type::I32)) => "print_i32",
+                        ("print", Some(Type::U32)) => "print_u32",
+                        ("print", Some(Type::I64)) => "print_i64",
+                        ("print", Some(Type::U64)) => "print_u64",
+                        ("print", Some(Type::F32)) => "print_f32",
+                        ("print", Some(Type::F64)) => "print_f64",
+                        ("print", Some(Type::Bool)) => "print_bool",
+                        ("print", Some(Type::String)) => "print_str",
+                        ("input", _) => "input",
+                        ("int", _) => "int",
+                        _ => return self.unsupported("printing values of this type", location),
+                    };
+                    let index = IMPORTS.iter().position(|(other, ..)| *other == import).unwrap();
+                    self.emit(Instr::Call(index as u32));
+                }
+            }
+            Expr::AssignExpr(assign) => {
+                self.expr(assign.value());
+                let Expr::Ident(name) = assign.target() else {
+                    return self.unsupported("assignment to a field or element", location);
+                };
+                match self.local(name.token().value) {
+                    Some(Some(local)) => self.emit(Instr::LocalSet(local)),
+                    Some(None) => {}
+                    None => self.unsupported("assignment to a global", location),
+                }
+            }
+            Expr::ReturnExpr(return_expr) => {
+                if let Some(value) = return_expr.value() {
+                    self.expr(value);
+                }
+                self.op(Op::Return);
+            }
+            Expr::BreakExpr(_) => {
+                let exit = self.loops.last().expect("type checker rejects break outside loops").exit;
+                self.emit(Instr::Br(self.label(exit)));
+            }
+            Expr::ContinueExpr(_) => {
+                let start = self.loops.last().expect("type checker rejects continue outside loops").start;
+                self.emit(Instr::Br(self.label(start)));
+            }
+            Expr::IfExpr(if_expr) => self.if_expr(if_expr),
+            Expr::WhileExpr(while_expr) => {
+                let exit = self.enter(Instr::Block);
+                let start = self.enter(Instr::Loop);
+                self.expr(while_expr.condition());
+                self.op(Op::I32Eqz);
+                self.emit(Instr::BrIf(self.label(exit)));
+
+                self.loops.push(Loop { exit, start });
+                self.block(while_expr.consequence());
+                self.loops.pop();
+                self.emit(Instr::Br(self.label(start)));
+                self.leave();
+                self.leave();
+            }
+            Expr::BlockExpr(block) => self.block(block),
+            Expr::ArrayExpr(array) => {
+                let Type::Array(item, len) = strip(&typ) else { unreachable!("type checker resolved the array") };
+                let valtype = ValType::of(item);
+                let size = valtype.map_or(0, ValType::size);
+                let address = self.allocate(size * len);
+                for (i, item) in array.items().enumerate() {
+                    self.store(address, valtype, i as u32 * size, item);
+                }
+                self.emit(Instr::LocalGet(address));
+            }
+            Expr::IndexExpr(index) => {
+                let Type::Array(_, len) = strip(self.types.type_of(&index.container()).unwrap_or(&Type::Error)).clone() else {
+                    return self.unsupported("indexing this value", location);
+                };
+                let index_type = ValType::of(self.types.type_of(&index.index()).unwrap_or(&Type::I32)).unwrap_or(ValType::I32);
+                let valtype = ValType::of(&typ);
+
+                self.expr(index.container());
+                let address = self.new_local(ValType::I32);
+                self.emit(Instr::LocalSet(address));
+                self.expr(index.index());
+                let position = self.new_local(index_type);
+                self.emit(Instr::LocalSet(position));
+
+                // Negative indices look huge when compared unsigned
+                self.emit(Instr::LocalGet(position));
+                if index_type == ValType::I64 {
+                    self.emit(Instr::I64Const(len as i64));
+                    self.op(Op::I64GeU);
+                } else {
+                    self.emit(Instr::I32Const(len as i32));
+                    self.op(Op::I32GeU);
+                }
+                self.enter(Instr::If);
+                self.op(Op::Unreachable);
+                self.leave();
+
+                let Some(valtype) = valtype else { return };
+                self.emit(Instr::LocalGet(address));
+                self.emit(Instr::LocalGet(position));
+                if index_type == ValType::I64 {
+                    self.op(Op::I32WrapI64);
+                }
+                self.emit(Instr::I32Const(valtype.size() as i32));
+                self.op(Op::I32Mul);
+                self.op(Op::I32Add);
+                self.emit(Instr::Load(valtype, 0));
+            }
+            Expr::FieldAccessExpr(access) => {
+                let parent = access.parent();
+                let parent_type = strip(self.types.type_of(&parent).unwrap_or(&Type::Error)).clone();
+                let Type::Struct(_, fields) = &parent_type else { unreachable!("type checker resolved the struct") };
+                let field = field_index(&parent_type, access.field_name().token().value).expect("type checker resolved the field");
+                let (offsets, _) = struct_layout(fields);
+
+                self.expr(parent);
+                match ValType::of(&typ) {
+                    Some(valtype) => self.emit(Instr::Load(valtype, offsets[field as usize])),
+                    None => self.op(Op::Drop),
+                }
+            }
+            Expr::StructExpr(struct_expr) => {
+                let Type::Struct(_, fields) = strip(&typ) else { unreachable!("type checker resolved the struct") };
+                let (offsets, size) = struct_layout(fields);
+                let address = self.allocate(size);
+                for ((field, field_type), offset) in fields.iter().zip(offsets) {
+                    let init = struct_expr.fields().items()
+                        .find(|init| init.name().token().value == &**field)
+                        .expect("type checker ensures every field is initialized");
+                    self.store(address, ValType::of(field_type), offset, init.value());
+                }
+                self.emit(Instr::LocalGet(address));
+            }
+            Expr::MethodCall(_) | Expr::TupleExpr(_) => self.unsupported("methods and tuples", location),
+        }
+    }
+
+    /// Allocates memory and returns the local holding its address
+    fn allocate(&mut self, size: u32) -> u32 {
+        self.emit(Instr::I32Const(size as i32));
+        self.emit(Instr::Call(self.alloc));
+        let address = self.new_local(ValType::I32);
+        self.emit(Instr::LocalSet(address));
+        address
+    }
+
+    fn store(&mut self, address: u32, valtype: Option<ValType>, offset: u32, value: Expr<'s, 'b>) {
+        match valtype {
+            Some(valtype) => {
+                self.emit(Instr::LocalGet(address));
+                self.expr(value);
+                self.emit(Instr::Store(valtype, offset));
+            }
+            None => self.expr(value),
+        }
+    }
+
+    fn if_expr(&mut self, if_expr: IfExpr<'s, 'b>) {
+        self.expr(if_expr.condition());
+        self.enter(Instr::If);
+        self.block(if_expr.consequence());
+        if let Some(alternate) = if_expr.alternate() {
+            self.op(Op::Else);
+            match alternate {
+                IfAlt::Else(block) => self.block(block),
+                IfAlt::ElseIf(else_if) => self.if_expr(else_if),
+            }
+        }
+        self.leave();
+    }
+}
+
+fn binary_op(tag: Tag, operand: &Type) -> Option<Op> {
+    use Op::*;
+    let ops = match operand {
+        Type::I32 => [I32Add, I32Sub, I32Mul, I32DivS, I32Eq, I32Ne, I32LtS, I32LeS, I32GtS, I32GeS],
+        Type::U32 => [I32Add, I32Sub, I32Mul, I32DivU, I32Eq, I32Ne, I32LtU, I32LeU, I32GtU, I32GeU],
+        Type::I64 => [I64Add, I64Sub, I64Mul, I64DivS, I64Eq, I64Ne, I64LtS, I64LeS, I64GtS, I64GeS],
+        Type::U64 => [I64Add, I64Sub, I64Mul, I64DivU, I64Eq, I64Ne, I64LtU, I64LeU, I64GtU, I64GeU],
+        Type::F32 => [F32Add, F32Sub, F32Mul, F32Div, F32Eq, F32Ne, F32Lt, F32Le, F32Gt, F32Ge],
+        Type::F64 => [F64Add, F64Sub, F64Mul, F64Div, F64Eq, F64Ne, F64Lt, F64Le, F64Gt, F64Ge],
+        Type::Bool => [Unreachable, Unreachable, Unreachable, Unreachable, I32Eq, I32Ne, I32LtU, I32LeU, I32GtU, I32GeU],
+        _ => return None,
+    };
+    let position = match tag {
+        Tag::Plus => 0,
+        Tag::Minus => 1,
+        Tag::Asterisk => 2,
+        Tag::Slash => 3,
+        Tag::EqualEqual => 4,
+        Tag::BangEqual => 5,
+        Tag::Less => 6,
+        Tag::LessEqual => 7,
+        Tag::Greater => 8,
+        Tag::GreaterEqual => 9,
+        tag => unreachable!("{:?} is not a binary operator", tag),
+    };
+    Some(ops[position])
+}
+
+fn empty_function(name: &str) -> Function {
+    Function {
+        name: name.into(),
+        typ: FuncType { params: Vec::new(), results: Vec::new() },
+        locals: Vec::new(),
+        body: Vec::new(),
+        export: false,
+    }
+}
+
+/// `haze_alloc(size) -> address`, growing memory as needed
+fn alloc_function() -> Function {
+    use Instr::*;
+    let (size, address) = (0, 1);
+    Function {
+        name: "haze_alloc".into(),
+        typ: FuncType { params: vec![ValType::I32], results: vec![ValType::I32] },
+        locals: vec![ValType::I32],
+        body: vec![
+            GlobalGet(HEAP), LocalSet(address),
+            // Keep every allocation 8 byte aligned
+            GlobalGet(HEAP), LocalGet(size), Op(super::Op::I32Add), I32Const(7), Op(super::Op::I32Add),
+            I32Const(-8), Op(super::Op::I32And), GlobalSet(HEAP),
+            Block, Loop,
+            GlobalGet(HEAP), Op(super::Op::MemorySize), I32Const(65536), Op(super::Op::I32Mul), Op(super::Op::I32LeU), BrIf(1),
+            I32Const(1), Op(super::Op::MemoryGrow), I32Const(-1), Op(super::Op::I32Eq),
+            If, Op(super::Op::Unreachable), Op(super::Op::End),
+            Br(0),
+            Op(super::Op::End), Op(super::Op::End),
+            LocalGet(address),
+        ],
+        export: true,
+    }
+}
+
+/// `haze_str_eq(a, b) -> bool` comparing lengths, then bytes
+fn str_eq_function() -> Function {
+    use Instr::*;
+    let (a, b, i, len) = (0, 1, 2, 3);
+    Function {
+        name: "haze_str_eq".into(),
+        typ: FuncType { params: vec![ValType::I32, ValType::I32], results: vec![ValType::I32] },
+        locals: vec![ValType::I32, ValType::I32],
+        body: vec![
+            LocalGet(a), Load(ValType::I32, 0), LocalTee(len), LocalGet(b), Load(ValType::I32, 0), Op(super::Op::I32Ne),
+            If, I32Const(0), Op(super::Op::Return), Op(super::Op::End),
+            I32Const(0), LocalSet(i),
+            Block, Loop,
+            LocalGet(i), LocalGet(len), Op(super::Op::I32GeU), BrIf(1),
+            LocalGet(a), LocalGet(i), Op(super::Op::I32Add), Load8U(4),
+            LocalGet(b), LocalGet(i), Op(super::Op::I32Add), Load8U(4),
+            Op(super::Op::I32Ne),
+            If, I32Const(0), Op(super::Op::Return), Op(super::Op::End),
+            LocalGet(i), I32Const(1), Op(super::Op::I32Add), LocalSet(i),
+            Br(0),
+            Op(super::Op::End), Op(super::Op::End),
+            I32Const(1),
+        ],
+        export: false,
+    }
+}
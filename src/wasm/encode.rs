@@ -0,0 +1,197 @@
+//! Binary encoding of modules, following the WebAssembly core specification
+
+use super::{FuncType, Instr, Module, Op, ValType, DATA_START};
+
+const MAGIC: &[u8] = b"\0asm";
+const VERSION: &[u8] = &[1, 0, 0, 0];
+
+pub fn encode(module: &Module) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(MAGIC);
+    bytes.extend(VERSION);
+
+    let types = module.types();
+    section(&mut bytes, 1, |out| {
+        vec(out, &types, |out, typ| func_type(out, typ));
+    });
+    section(&mut bytes, 2, |out| {
+        vec(out, &module.imports, |out, import| {
+            name(out, import.module);
+            name(out, import.name);
+            out.push(0x00);
+            unsigned(out, module.type_index(&import.typ) as u64);
+        });
+    });
+    section(&mut bytes, 3, |out| {
+        vec(out, &module.functions, |out, function| unsigned(out, module.type_index(&function.typ) as u64));
+    });
+    // One memory of at least a page, grown by `haze_alloc`
+    section(&mut bytes, 5, |out| out.extend([1, 0x00, 1]));
+    section(&mut bytes, 6, |out| {
+        out.extend([1, 0x7f, 0x01]);
+        instr(out, &Instr::I32Const(module.heap_start() as i32));
+        out.push(Op::End.byte());
+    });
+    section(&mut bytes, 7, |out| {
+        let imports = module.imports.len();
+        let exports: Vec<(usize, &str)> = module.functions.iter().enumerate()
+            .filter(|(_, function)| function.export)
+            .map(|(i, function)| (imports + i, &*function.name))
+            .collect();
+        unsigned(out, exports.len() as u64 + 1);
+        for (index, export) in exports {
+            name(out, export);
+            out.push(0x00);
+            unsigned(out, index as u64);
+        }
+        name(out, "memory");
+        out.extend([0x02, 0]);
+    });
+    section(&mut bytes, 10, |out| {
+        vec(out, &module.functions, |out, function| {
+            let mut body = Vec::new();
+            // Locals are declared in runs of the same type
+            let mut runs: Vec<(u32, ValType)> = Vec::new();
+            for &local in function.locals.iter() {
+                match runs.last_mut() {
+                    Some((count, valtype)) if *valtype == local => *count += 1,
+                    _ => runs.push((1, local)),
+                }
+            }
+            vec(&mut body, &runs, |out, &(count, valtype)| {
+                unsigned(out, count as u64);
+                out.push(val_type(valtype));
+            });
+            for instruction in function.body.iter() {
+                instr(&mut body, instruction);
+            }
+            body.push(Op::End.byte());
+            unsigned(out, body.len() as u64);
+            out.extend(body);
+        });
+    });
+    section(&mut bytes, 11, |out| {
+        out.extend([1, 0x00]);
+        instr(out, &Instr::I32Const(DATA_START as i32));
+        out.push(Op::End.byte());
+        unsigned(out, module.data.len() as u64);
+        out.extend(&module.data);
+    });
+    bytes
+}
+
+fn section(bytes: &mut Vec<u8>, id: u8, contents: impl FnOnce(&mut Vec<u8>)) {
+    let mut out = Vec::new();
+    contents(&mut out);
+    bytes.push(id);
+    unsigned(bytes, out.len() as u64);
+    bytes.extend(out);
+}
+
+fn vec<T>(out: &mut Vec<u8>, items: &[T], mut item: impl FnMut(&mut Vec<u8>, &T)) {
+    unsigned(out, items.len() as u64);
+    for it in items {
+        item(out, it);
+    }
+}
+
+fn name(out: &mut Vec<u8>, name: &str) {
+    unsigned(out, name.len() as u64);
+    out.extend(name.as_bytes());
+}
+
+fn val_type(valtype: ValType) -> u8 {
+    match valtype {
+        ValType::I32 => 0x7f,
+        ValType::I64 => 0x7e,
+        ValType::F32 => 0x7d,
+        ValType::F64 => 0x7c,
+    }
+}
+
+fn func_type(out: &mut Vec<u8>, typ: &FuncType) {
+    out.push(0x60);
+    vec(out, &typ.params, |out, &valtype| out.push(val_type(valtype)));
+    vec(out, &typ.results, |out, &valtype| out.push(val_type(valtype)));
+}
+
+/// Alignment as a power of two, then the offset
+fn memarg(out: &mut Vec<u8>, size: u32, offset: u32) {
+    unsigned(out, size.trailing_zeros() as u64);
+    unsigned(out, offset as u64);
+}
+
+fn instr(out: &mut Vec<u8>, instr: &Instr) {
+    /// Block type of blocks without results
+    const EMPTY: u8 = 0x40;
+    match *instr {
+        Instr::Op(op) => {
+            out.push(op.byte());
+            // The memory index
+            if let Op::MemorySize | Op::MemoryGrow = op {
+                out.push(0x00);
+            }
+        }
+        Instr::Block => out.extend([0x02, EMPTY]),
+        Instr::Loop => out.extend([0x03, EMPTY]),
+        Instr::If => out.extend([0x04, EMPTY]),
+        Instr::Br(depth) => { out.push(0x0c); unsigned(out, depth as u64); }
+        Instr::BrIf(depth) => { out.push(0x0d); unsigned(out, depth as u64); }
+        Instr::Call(index) => { out.push(0x10); unsigned(out, index as u64); }
+        Instr::LocalGet(index) => { out.push(0x20); unsigned(out, index as u64); }
+        Instr::LocalSet(index) => { out.push(0x21); unsigned(out, index as u64); }
+        Instr::LocalTee(index) => { out.push(0x22); unsigned(out, index as u64); }
+        Instr::GlobalGet(index) => { out.push(0x23); unsigned(out, index as u64); }
+        Instr::GlobalSet(index) => { out.push(0x24); unsigned(out, index as u64); }
+        Instr::Load(valtype, offset) => {
+            out.push(match valtype {
+                ValType::I32 => 0x28,
+                ValType::I64 => 0x29,
+                ValType::F32 => 0x2a,
+                ValType::F64 => 0x2b,
+            });
+            memarg(out, valtype.size(), offset);
+        }
+        Instr::Store(valtype, offset) => {
+            out.push(match valtype {
+                ValType::I32 => 0x36,
+                ValType::I64 => 0x37,
+                ValType::F32 => 0x38,
+                ValType::F64 => 0x39,
+            });
+            memarg(out, valtype.size(), offset);
+        }
+        Instr::Load8U(offset) => { out.push(0x2d); memarg(out, 1, offset); }
+        Instr::I32Const(value) => { out.push(0x41); signed(out, value as i64); }
+        Instr::I64Const(value) => { out.push(0x42); signed(out, value); }
+        Instr::F32Const(value) => { out.push(0x43); out.extend(value.to_le_bytes()); }
+        Instr::F64Const(value) => { out.push(0x44); out.extend(value.to_le_bytes()); }
+    }
+}
+
+/// Unsigned LEB128
+pub fn unsigned(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Signed LEB128
+pub fn signed(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
@@ -0,0 +1,379 @@
+//! WebAssembly backend
+//!
+//! Type checked programs are compiled to a small in-memory module which is
+//! then written out either as a binary (`encode`) or as `.wat` text (the
+//! `Display` impl in `wat`), so both always describe the same code.
+//!
+//! Values map to wasm types as follows:
+//! - `bool`, `i32` and `u32` are `i32`, `i64` and `u64` are `i64`,
+//!   `f32` and `f64` are themselves,
+//! - strings, structs and arrays live in linear memory and are passed around
+//!   as `i32` addresses. Strings start with their length in bytes, structs and
+//!   arrays store their fields and elements at fixed offsets,
+//! - `()` and `!` have no value at all.
+//!
+//! Memory is never freed: `haze_alloc` hands out bytes from a heap pointer
+//! which only grows. Builtins are imported from the `env` module, every
+//! function of the program is exported under its own name along with
+//! `memory` and `haze_alloc`, so a host can read and build strings.
+
+pub mod compiler;
+pub mod encode;
+pub mod wat;
+
+use crate::typecheck::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl ValType {
+    /// The wasm type of a Haze value, none for `()` and `!`
+    pub fn of(typ: &Type) -> Option<ValType> {
+        match typ {
+            Type::Bool | Type::I32 | Type::U32 => Some(ValType::I32),
+            Type::I64 | Type::U64 => Some(ValType::I64),
+            Type::F32 => Some(ValType::F32),
+            Type::F64 => Some(ValType::F64),
+            Type::String | Type::Array(..) | Type::Struct(..) => Some(ValType::I32),
+            Type::Alias(typ) | Type::Const(typ) => ValType::of(typ),
+            Type::Unit | Type::Never | Type::Fn(..) | Type::Unresolved(_) | Type::Error => None,
+        }
+    }
+
+    /// Bytes taken in memory
+    pub fn size(self) -> u32 {
+        match self {
+            ValType::I32 | ValType::F32 => 4,
+            ValType::I64 | ValType::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+macro_rules! ops {
+    ($($name:ident = $byte:literal $text:literal,)*) => {
+        /// Instructions without immediates
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Op {
+            $($name,)*
+        }
+
+        impl Op {
+            pub fn byte(self) -> u8 {
+                match self {
+                    $(Op::$name => $byte,)*
+                }
+            }
+
+            pub fn text(self) -> &'static str {
+                match self {
+                    $(Op::$name => $text,)*
+                }
+            }
+        }
+    };
+}
+
+ops! {
+    Unreachable = 0x00 "unreachable",
+    Else = 0x05 "else",
+    End = 0x0b "end",
+    Return = 0x0f "return",
+    Drop = 0x1a "drop",
+    MemorySize = 0x3f "memory.size",
+    MemoryGrow = 0x40 "memory.grow",
+
+    I32Eqz = 0x45 "i32.eqz",
+    I32Eq = 0x46 "i32.eq",
+    I32Ne = 0x47 "i32.ne",
+    I32LtS = 0x48 "i32.lt_s",
+    I32LtU = 0x49 "i32.lt_u",
+    I32GtS = 0x4a "i32.gt_s",
+    I32GtU = 0x4b "i32.gt_u",
+    I32LeS = 0x4c "i32.le_s",
+    I32LeU = 0x4d "i32.le_u",
+    I32GeS = 0x4e "i32.ge_s",
+    I32GeU = 0x4f "i32.ge_u",
+    I64Eq = 0x51 "i64.eq",
+    I64Ne = 0x52 "i64.ne",
+    I64LtS = 0x53 "i64.lt_s",
+    I64LtU = 0x54 "i64.lt_u",
+    I64GtS = 0x55 "i64.gt_s",
+    I64GtU = 0x56 "i64.gt_u",
+    I64LeS = 0x57 "i64.le_s",
+    I64LeU = 0x58 "i64.le_u",
+    I64GeS = 0x59 "i64.ge_s",
+    I64GeU = 0x5a "i64.ge_u",
+    F32Eq = 0x5b "f32.eq",
+    F32Ne = 0x5c "f32.ne",
+    F32Lt = 0x5d "f32.lt",
+    F32Gt = 0x5e "f32.gt",
+    F32Le = 0x5f "f32.le",
+    F32Ge = 0x60 "f32.ge",
+    F64Eq = 0x61 "f64.eq",
+    F64Ne = 0x62 "f64.ne",
+    F64Lt = 0x63 "f64.lt",
+    F64Gt = 0x64 "f64.gt",
+    F64Le = 0x65 "f64.le",
+    F64Ge = 0x66 "f64.ge",
+
+    I32Add = 0x6a "i32.add",
+    I32Sub = 0x6b "i32.sub",
+    I32Mul = 0x6c "i32.mul",
+    I32DivS = 0x6d "i32.div_s",
+    I32DivU = 0x6e "i32.div_u",
+    I32And = 0x71 "i32.and",
+    I64Add = 0x7c "i64.add",
+    I64Sub = 0x7d "i64.sub",
+    I64Mul = 0x7e "i64.mul",
+    I64DivS = 0x7f "i64.div_s",
+    I64DivU = 0x80 "i64.div_u",
+    F32Neg = 0x8c "f32.neg",
+    F32Add = 0x92 "f32.add",
+    F32Sub = 0x93 "f32.sub",
+    F32Mul = 0x94 "f32.mul",
+    F32Div = 0x95 "f32.div",
+    F64Neg = 0x9a "f64.neg",
+    F64Add = 0xa0 "f64.add",
+    F64Sub = 0xa1 "f64.sub",
+    F64Mul = 0xa2 "f64.mul",
+    F64Div = 0xa3 "f64.div",
+    I32WrapI64 = 0xa7 "i32.wrap_i64",
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    Op(Op),
+    /// Blocks, loops and ifs never produce values, Haze blocks have none
+    Block,
+    Loop,
+    If,
+    Br(u32),
+    BrIf(u32),
+    Call(u32),
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    Load(ValType, u32),
+    Store(ValType, u32),
+    Load8U(u32),
+    I32Const(i32),
+    I64Const(i64),
+    F32Const(f32),
+    F64Const(f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub module: &'static str,
+    pub name: &'static str,
+    pub typ: FuncType,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: Box<str>,
+    pub typ: FuncType,
+    /// Locals after the parameters
+    pub locals: Vec<ValType>,
+    pub body: Vec<Instr>,
+    pub export: bool,
+}
+
+/// Where the data segment starts, address 0 is left unused
+pub const DATA_START: u32 = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+    /// Imported functions come first in the function index space
+    pub imports: Vec<Import>,
+    pub functions: Vec<Function>,
+    /// Bytes placed at `DATA_START`
+    pub data: Vec<u8>,
+}
+
+impl Module {
+    /// Name of a function by index, imports included
+    pub fn function_name(&self, index: u32) -> &str {
+        let index = index as usize;
+        match self.imports.get(index) {
+            Some(import) => import.name,
+            None => &self.functions[index - self.imports.len()].name,
+        }
+    }
+
+    /// First address handed out by `haze_alloc`
+    pub fn heap_start(&self) -> u32 {
+        (DATA_START + self.data.len() as u32 + 7) & !7
+    }
+
+    /// Distinct function types in order of first use
+    pub fn types(&self) -> Vec<&FuncType> {
+        let mut types: Vec<&FuncType> = Vec::new();
+        let all = self.imports.iter().map(|import| &import.typ).chain(self.functions.iter().map(|function| &function.typ));
+        for typ in all {
+            if !types.contains(&typ) {
+                types.push(typ);
+            }
+        }
+        types
+    }
+
+    pub fn type_index(&self, typ: &FuncType) -> u32 {
+        self.types().iter().position(|other| *other == typ).expect("every type is listed") as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::ast2::{AstNode, TopDeclList};
+    use crate::parser3::Parser;
+    use crate::typecheck::check::TypeChecker;
+
+    fn compile_source(source: &str) -> Module {
+        let bump = Bump::new();
+        let mut parser = Parser::new(source, &bump);
+        let root = bump.alloc(parser.parse().tree);
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        let mut checker = TypeChecker::new();
+        checker.check_program(TopDeclList::cast(root));
+        assert!(checker.errors.is_empty(), "{:?}", checker.errors);
+        compiler::compile(TopDeclList::cast(root), &checker).unwrap()
+    }
+
+    fn validate(module: &Module) {
+        let bytes = encode::encode(module);
+        if let Err(err) = wasmparser::Validator::new().validate_all(&bytes) {
+            panic!("invalid module: {}\n{}", err, module);
+        }
+    }
+
+    #[test]
+    fn test_compile_samples() {
+        for source in [
+            include_str!("../../samples/fib.hz"),
+            include_str!("../../samples/loops.hz"),
+            include_str!("../../samples/points.hz"),
+        ] {
+            validate(&compile_source(source));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let module = compile_source(r#"
+            struct Pair { a: i64, b: f64, ok: bool }
+
+            const GREETING: string = "hello";
+
+            fn sum(xs: [i32; 4]) -> i32 {
+                let i = 0;
+                let total = 0;
+                while i < 4 {
+                    if i == 2 {
+                        i = i + 1;
+                        continue;
+                    }
+                    if total > 100 { break; }
+                    total = total + xs[i];
+                    i = i + 1;
+                }
+                return total;
+            }
+
+            fn main() {
+                print(sum([1, 2, 3, 4]));
+                let p = .Pair { a: 40, b: 2.5, ok: true };
+                print(p.a + 2);
+                print(-p.b * 2.0);
+                print(!p.ok);
+                print(GREETING == "hello");
+                print(GREETING != "bye");
+                let n: u32 = 7;
+                print(n / 2 >= 3);
+                let big: u64 = 9;
+                print(big);
+                let f: f32 = 1.5;
+                print(f < 2.0);
+            }
+        "#);
+        validate(&module);
+        let exports: Vec<&str> = module.functions.iter().filter(|f| f.export).map(|f| &*f.name).collect();
+        assert_eq!(exports, ["haze_alloc", "sum", "main"]);
+    }
+
+    #[test]
+    fn test_wat() {
+        let module = compile_source("
+            struct Pair { a: i64, b: f64 }
+
+            fn second(p: Pair) -> f64 {
+                return p.b;
+            }
+
+            fn main() {
+                print(second(.Pair { a: 1, b: 2.5 }));
+            }
+        ");
+        let wat = module.to_string();
+        let start = wat.find("  (func $second").unwrap();
+        assert_eq!(&wat[start..], r#"  (func $second (export "second") (param i32) (result f64)
+    local.get 0
+    f64.load offset=8
+    return
+    unreachable
+  )
+  (func $main (export "main")
+    (local i32)
+    i32.const 16
+    call $haze_alloc
+    local.set 0
+    local.get 0
+    i64.const 1
+    i64.store offset=0
+    local.get 0
+    f64.const 2.5
+    f64.store offset=8
+    local.get 0
+    call $second
+    call $print_f64
+  )
+  (data (i32.const 8) "")
+)
+"#);
+    }
+
+    #[test]
+    fn test_struct_layout() {
+        let fields = [("ok".into(), Type::Bool), ("a".into(), Type::I64), ("b".into(), Type::F32), ("c".into(), Type::Unit)];
+        assert_eq!(compiler::struct_layout(&fields), (vec![0, 8, 16, 20], 20));
+    }
+
+    #[test]
+    fn test_leb128() {
+        let mut out = Vec::new();
+        encode::unsigned(&mut out, 624485);
+        assert_eq!(out, [0xe5, 0x8e, 0x26]);
+        out.clear();
+        encode::signed(&mut out, -123456);
+        assert_eq!(out, [0xc0, 0xbb, 0x78]);
+        out.clear();
+        encode::signed(&mut out, 64);
+        assert_eq!(out, [0xc0, 0x00]);
+    }
+}
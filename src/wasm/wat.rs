@@ -0,0 +1,113 @@
+//! WebAssembly text format
+//!
+//! Functions are named after their Haze names, locals and labels are
+//! referred to by index, so the text reads the same as the binary.
+
+use std::fmt::{self, Display, Write};
+
+use super::{FuncType, Instr, Module, Op, ValType, DATA_START};
+
+impl Display for ValType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+            ValType::F32 => "f32",
+            ValType::F64 => "f64",
+        })
+    }
+}
+
+impl Display for FuncType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for param in self.params.iter() {
+            write!(f, " (param {})", param)?;
+        }
+        for result in self.results.iter() {
+            write!(f, " (result {})", result)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "(module")?;
+        for import in self.imports.iter() {
+            writeln!(f, "  (import \"{}\" \"{}\" (func ${}{}))", import.module, import.name, import.name, import.typ)?;
+        }
+        writeln!(f, "  (memory (export \"memory\") 1)")?;
+        writeln!(f, "  (global $heap (mut i32) (i32.const {}))", self.heap_start())?;
+
+        for function in self.functions.iter() {
+            write!(f, "  (func ${}", function.name)?;
+            if function.export {
+                write!(f, " (export \"{}\")", function.name)?;
+            }
+            writeln!(f, "{}", function.typ)?;
+            for local in function.locals.iter() {
+                writeln!(f, "    (local {})", local)?;
+            }
+            let mut indent = 2;
+            for instr in function.body.iter() {
+                if let Instr::Op(Op::End | Op::Else) = instr {
+                    indent -= 1;
+                }
+                writeln!(f, "{:width$}{}", "", self.instr(instr), width = indent * 2)?;
+                if let Instr::Block | Instr::Loop | Instr::If | Instr::Op(Op::Else) = instr {
+                    indent += 1;
+                }
+            }
+            writeln!(f, "  )")?;
+        }
+
+        write!(f, "  (data (i32.const {}) \"", DATA_START)?;
+        for &byte in self.data.iter() {
+            match byte {
+                b'"' | b'\\' => write!(f, "\\{}", byte as char)?,
+                0x20..=0x7e => write!(f, "{}", byte as char)?,
+                _ => write!(f, "\\{:02x}", byte)?,
+            }
+        }
+        writeln!(f, "\")")?;
+        writeln!(f, ")")
+    }
+}
+
+impl Module {
+    fn instr(&self, instr: &Instr) -> String {
+        let mut text = String::new();
+        let _ = match instr {
+            Instr::Op(op) => write!(text, "{}", op.text()),
+            Instr::Block => write!(text, "block"),
+            Instr::Loop => write!(text, "loop"),
+            Instr::If => write!(text, "if"),
+            Instr::Br(depth) => write!(text, "br {}", depth),
+            Instr::BrIf(depth) => write!(text, "br_if {}", depth),
+            Instr::Call(index) => write!(text, "call ${}", self.function_name(*index)),
+            Instr::LocalGet(index) => write!(text, "local.get {}", index),
+            Instr::LocalSet(index) => write!(text, "local.set {}", index),
+            Instr::LocalTee(index) => write!(text, "local.tee {}", index),
+            Instr::GlobalGet(_) => write!(text, "global.get $heap"),
+            Instr::GlobalSet(_) => write!(text, "global.set $heap"),
+            Instr::Load(valtype, offset) => write!(text, "{}.load offset={}", valtype, offset),
+            Instr::Store(valtype, offset) => write!(text, "{}.store offset={}", valtype, offset),
+            Instr::Load8U(offset) => write!(text, "i32.load8_u offset={}", offset),
+            Instr::I32Const(value) => write!(text, "i32.const {}", value),
+            Instr::I64Const(value) => write!(text, "i64.const {}", value),
+            Instr::F32Const(value) => write!(text, "f32.const {}", float(*value as f64, value.is_nan())),
+            Instr::F64Const(value) => write!(text, "f64.const {}", float(*value, value.is_nan())),
+        };
+        text
+    }
+}
+
+fn float(value: f64, nan: bool) -> String {
+    if nan {
+        "nan".into()
+    } else if value.is_infinite() {
+        if value > 0.0 { "inf".into() } else { "-inf".into() }
+    } else {
+        format!("{:?}", value)
+    }
+}